@@ -15,6 +15,7 @@ use tokio::sync::mpsc;
 
 use crate::{
     block_store::BlockStore,
+    commit_sink::CommitSink,
     committee::{Committee, ProcessedTransactionHandler, QuorumThreshold, TransactionAggregator},
     consensus::linearizer::{CommittedSubDag, Linearizer},
     data::Data,
@@ -346,6 +347,7 @@ pub struct TestCommitHandler<H = HashSet<TransactionLocator>> {
 
     metrics: Arc<Metrics>,
     consensus_only: bool,
+    commit_sink: Option<CommitSink>,
 }
 
 impl<H: ProcessedTransactionHandler<TransactionLocator> + Default> TestCommitHandler<H> {
@@ -377,9 +379,16 @@ impl<H: ProcessedTransactionHandler<TransactionLocator>> TestCommitHandler<H> {
 
             metrics,
             consensus_only,
+            commit_sink: None,
         }
     }
 
+    /// Write the sequence of committed leaders to the specified sink.
+    pub fn with_commit_sink(mut self, commit_sink: CommitSink) -> Self {
+        self.commit_sink = Some(commit_sink);
+        self
+    }
+
     pub fn committed_leaders(&self) -> &Vec<BlockReference> {
         &self.committed_leaders
     }
@@ -464,6 +473,9 @@ impl<H: ProcessedTransactionHandler<TransactionLocator> + Send + Sync> CommitObs
             }
             // self.committed_dags.push(commit);
         }
+        if let Some(commit_sink) = &mut self.commit_sink {
+            commit_sink.record(committed.iter().map(|x| &x.anchor));
+        }
         self.metrics
             .commit_handler_pending_certificates
             .set(self.transaction_votes.len() as i64);
@@ -10,7 +10,7 @@ use crate::{
     block_store::{BlockStore, BlockWriter},
     committee::Committee,
     data::Data,
-    types::{BlockReference, StatementBlock},
+    types::{BlockReference, RoundNumber, StatementBlock},
     wal::WalPosition,
 };
 
@@ -24,6 +24,9 @@ pub struct BlockManager {
     /// Keeps all blocks that need to be synced in order to unblock the processing of other pending
     /// blocks. The indices of the vector correspond the authority indices.
     missing: Vec<HashSet<BlockReference>>,
+    /// The gc round of the block store when the pending blocks were last released (see
+    /// [`BlockStore::gc_round`]).
+    gc_round: RoundNumber,
     block_store: BlockStore,
 }
 
@@ -33,6 +36,7 @@ impl BlockManager {
             blocks_pending: Default::default(),
             block_references_waiting: Default::default(),
             missing: (0..committee.len()).map(|_| HashSet::new()).collect(),
+            gc_round: 0,
             block_store,
         }
     }
@@ -44,12 +48,16 @@ impl BlockManager {
     ) -> Vec<(WalPosition, Data<StatementBlock>)> {
         let mut blocks: VecDeque<Data<StatementBlock>> = blocks.into();
         let mut newly_blocks_processed: Vec<(WalPosition, Data<StatementBlock>)> = vec![];
+        let gc_round = self.block_store.gc_round();
+        self.release_pruned(gc_round, &mut blocks);
         while let Some(block) = blocks.pop_front() {
             // Update the highest known round number.
 
-            // check whether we have already processed this block and skip it if so.
+            // check whether we have already processed this block and skip it if so. The blocks
+            // below the gc round were either processed and pruned, or will never be committed.
             let block_reference = block.reference();
-            if self.block_store.block_exists(*block_reference)
+            if block_reference.round < gc_round
+                || self.block_store.block_exists(*block_reference)
                 || self.blocks_pending.contains_key(block_reference)
             {
                 continue;
@@ -58,7 +66,9 @@ impl BlockManager {
             let mut processed = true;
             for included_reference in block.includes() {
                 // If we are missing a reference then we insert into pending and update the waiting index
-                if !self.block_store.block_exists(*included_reference) {
+                if included_reference.round >= gc_round
+                    && !self.block_store.block_exists(*included_reference)
+                {
                     processed = false;
                     self.block_references_waiting
                         .entry(*included_reference)
@@ -82,29 +92,66 @@ impl BlockManager {
                 newly_blocks_processed.push((position, block.clone()));
 
                 // Now unlock any pending blocks, and process them if ready.
-                if let Some(waiting_references) =
-                    self.block_references_waiting.remove(&block_reference)
+                self.unlock_waiting(&block_reference, &mut blocks);
+            }
+        }
+
+        newly_blocks_processed
+    }
+
+    /// Stop waiting for the blocks that are no longer needed, and queue the pending blocks that
+    /// are unlocked as a result.
+    fn unlock_waiting(
+        &mut self,
+        block_reference: &BlockReference,
+        blocks: &mut VecDeque<Data<StatementBlock>>,
+    ) {
+        if let Some(waiting_references) = self.block_references_waiting.remove(block_reference) {
+            // For each reference see if its unblocked.
+            for waiting_block_reference in waiting_references {
+                let block_pointer = self.blocks_pending.get(&waiting_block_reference).expect(
+                    "Safe since we ensure the block waiting reference has a valid primary key.",
+                );
+
+                if block_pointer
+                    .includes()
+                    .iter()
+                    .all(|item_ref| !self.block_references_waiting.contains_key(item_ref))
                 {
-                    // For each reference see if its unblocked.
-                    for waiting_block_reference in waiting_references {
-                        let block_pointer = self.blocks_pending.get(&waiting_block_reference).expect("Safe since we ensure the block waiting reference has a valid primary key.");
-
-                        if block_pointer
-                            .includes()
-                            .iter()
-                            .all(|item_ref| !self.block_references_waiting.contains_key(item_ref))
-                        {
-                            // No dependencies are left unprocessed, so remove from unprocessed list, and add to the
-                            // blocks we are processing now.
-                            let block = self.blocks_pending.remove(&waiting_block_reference).expect("Safe since we ensure the block waiting reference has a valid primary key.");
-                            blocks.push_front(block);
-                        }
-                    }
+                    // No dependencies are left unprocessed, so remove from unprocessed list, and add to the
+                    // blocks we are processing now.
+                    let block = self.blocks_pending.remove(&waiting_block_reference).expect(
+                        "Safe since we ensure the block waiting reference has a valid primary key.",
+                    );
+                    blocks.push_front(block);
                 }
             }
         }
+    }
 
-        newly_blocks_processed
+    /// Stop waiting for (and syncing) the blocks below the specified gc round: the pending
+    /// blocks including them are processed without them.
+    fn release_pruned(
+        &mut self,
+        gc_round: RoundNumber,
+        blocks: &mut VecDeque<Data<StatementBlock>>,
+    ) {
+        if gc_round <= self.gc_round {
+            return;
+        }
+        self.gc_round = gc_round;
+        for missing in &mut self.missing {
+            missing.retain(|reference| reference.round >= gc_round);
+        }
+        let pruned: Vec<_> = self
+            .block_references_waiting
+            .keys()
+            .filter(|reference| reference.round < gc_round)
+            .copied()
+            .collect();
+        for reference in pruned {
+            self.unlock_waiting(&reference, blocks);
+        }
     }
 
     pub fn missing_blocks(&self) -> &[HashSet<BlockReference>] {
@@ -117,7 +164,11 @@ mod tests {
     use rand::{prelude::StdRng, SeedableRng};
 
     use super::*;
-    use crate::{test_util::TestBlockWriter, types::Dag};
+    use crate::{
+        consensus::GC_DEPTH,
+        test_util::{build_dag_layer, committee, TestBlockWriter},
+        types::Dag,
+    };
 
     #[test]
     fn test_block_manager_add_block() {
@@ -149,6 +200,75 @@ mod tests {
         }
     }
 
+    /// Blocks arriving after their ancestors were pruned are processed rather than suspended,
+    /// and pruned blocks are not inserted again.
+    #[test]
+    fn test_block_manager_pruned_ancestors() {
+        let committee = committee(4);
+        let mut block_writer = TestBlockWriter::new(&committee);
+        let block_store = block_writer.block_store();
+        let mut bm = BlockManager::new(block_store.clone(), &committee);
+
+        // Authority 3 is partitioned away while the others build the dag up to round 10.
+        let genesis: Vec<_> = committee
+            .authorities()
+            .map(StatementBlock::new_genesis)
+            .collect();
+        let mut includes: Vec<_> = genesis.iter().map(|x| *x.reference()).collect();
+        block_writer.add_blocks(genesis);
+        let mut rounds = vec![includes.clone()];
+        for _ in 1..=10 {
+            let connections = (0..3)
+                .map(|authority| (authority, includes.clone()))
+                .collect();
+            includes = build_dag_layer(connections, &mut block_writer);
+            rounds.push(includes.clone());
+        }
+        let block = |authority, round, includes| {
+            Data::new(StatementBlock::new(
+                authority,
+                round,
+                includes,
+                vec![],
+                0,
+                false,
+                Default::default(),
+            ))
+        };
+
+        // The block of authority 3 at round 8 waits for its own block of round 6.
+        let mut includes = rounds[7].clone();
+        includes.push(BlockReference::new_test(3, 6));
+        let suspended = block(3, 8, includes);
+        assert!(bm
+            .add_blocks(vec![suspended.clone()], &mut block_writer)
+            .is_empty());
+        assert!(bm.missing_blocks()[3].contains(&BlockReference::new_test(3, 6)));
+
+        // Prune all rounds below 7.
+        block_store.set_committed_round(7 + GC_DEPTH);
+        block_store.prune_below(7);
+        assert_eq!(block_store.gc_round(), 7);
+
+        // A late block whose ancestors were pruned is processed, along with the suspended one.
+        let late = block(3, 7, rounds[6].clone());
+        let processed = bm.add_blocks(vec![late.clone()], &mut block_writer);
+        let processed: HashSet<_> = processed.iter().map(|(_, x)| *x.reference()).collect();
+        assert_eq!(
+            processed,
+            HashSet::from([*late.reference(), *suspended.reference()])
+        );
+        assert!(bm.missing_blocks()[3].is_empty());
+        assert!(bm.blocks_pending.is_empty());
+        assert!(bm.block_references_waiting.is_empty());
+
+        // Pruned blocks are not inserted again.
+        let pruned = block(0, 5, rounds[4].clone());
+        assert_eq!(*pruned.reference(), rounds[5][0]);
+        assert!(bm.add_blocks(vec![pruned], &mut block_writer).is_empty());
+        assert!(block_store.get_blocks_by_round(5).is_empty());
+    }
+
     fn rng(s: u8) -> StdRng {
         let mut seed = [0; 32];
         seed[0] = s;
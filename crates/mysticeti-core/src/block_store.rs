@@ -4,7 +4,9 @@
 use std::{
     cmp::max,
     collections::{BTreeMap, HashMap},
-    io::IoSlice,
+    fmt::Display,
+    io::{self, IoSlice},
+    path::Path,
     sync::Arc,
     time::Instant,
 };
@@ -15,7 +17,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     committee::Committee,
-    consensus::linearizer::CommittedSubDag,
+    consensus::{linearizer::CommittedSubDag, GC_DEPTH},
     data::Data,
     metrics::{Metrics, UtilizationTimerExt},
     state::{RecoveredState, RecoveredStateBuilder},
@@ -29,7 +31,7 @@ use crate::{
         Transaction,
         TransactionLocator,
     },
-    wal::{Tag, WalPosition, WalReader, WalWriter},
+    wal::{open_file_for_wal, walf, Tag, WalPosition, WalReader, WalWriter},
 };
 
 #[derive(Clone)]
@@ -45,8 +47,46 @@ struct BlockStoreInner {
     own_blocks: BTreeMap<RoundNumber, BlockDigest>,
     highest_round: RoundNumber,
     authority: AuthorityIndex,
-    last_seen_by_authority: Vec<RoundNumber>,
+    last_seen_by_authority: Vec<Option<BlockReference>>,
     last_own_block: Option<BlockReference>,
+    /// All blocks strictly below this round have been pruned.
+    gc_round: RoundNumber,
+    /// All leaders up to this round are decided. Blocks at or above it may still belong to the
+    /// causal history of an undecided leader.
+    committed_round: RoundNumber,
+    /// The last decided leader journaled in the wal when the store was opened.
+    last_decided: Option<BlockReference>,
+    /// The maximum number of blocks kept in memory, unbounded if `None`. Evicted blocks are
+    /// read back from the wal on demand.
+    cache_size: Option<usize>,
+    /// The loaded blocks ordered from the least to the most recently used (only tracked if the
+    /// cache is bounded), along with the last access of each of them.
+    lru: BTreeMap<u64, BlockReference>,
+    last_access: HashMap<BlockReference, u64>,
+    access_counter: u64,
+    /// The evidence of all equivocations detected (at or above the gc round), in insertion order.
+    equivocations: Vec<EquivocationDetected>,
+}
+
+/// Evidence that an authority proposed two different blocks at the same round.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct EquivocationDetected {
+    pub authority: AuthorityIndex,
+    pub round: RoundNumber,
+    /// The digest of a block already held by the store, followed by the digest of the
+    /// conflicting block.
+    pub digests: [BlockDigest; 2],
+}
+
+impl Display for EquivocationDetected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [first, second] = self.digests;
+        write!(
+            f,
+            "Authority {} equivocated at round {} ({first} and {second})",
+            self.authority, self.round
+        )
+    }
 }
 
 pub trait BlockWriter {
@@ -61,6 +101,26 @@ enum IndexEntry {
 }
 
 impl BlockStore {
+    /// Open (or create) the wal at the specified path and rebuild the block store from the
+    /// blocks and commits it holds. Returns the writer appending to the wal along with the
+    /// recovered state.
+    pub fn open_persisted(
+        authority: AuthorityIndex,
+        path: impl AsRef<Path>,
+        metrics: Arc<Metrics>,
+        committee: &Committee,
+    ) -> io::Result<(WalWriter, RecoveredState)> {
+        let (wal_writer, wal_reader) = walf(open_file_for_wal(path)?)?;
+        let recovered = Self::open(
+            authority,
+            Arc::new(wal_reader),
+            &wal_writer,
+            metrics,
+            committee,
+        );
+        Ok((wal_writer, recovered))
+    }
+
     pub fn open(
         authority: AuthorityIndex,
         block_wal_reader: Arc<WalReader>,
@@ -68,7 +128,7 @@ impl BlockStore {
         metrics: Arc<Metrics>,
         committee: &Committee,
     ) -> RecoveredState {
-        let last_seen_by_authority = committee.authorities().map(|_| 0).collect();
+        let last_seen_by_authority = committee.authorities().map(|_| None).collect();
         let mut inner = BlockStoreInner {
             authority,
             last_seen_by_authority,
@@ -104,8 +164,12 @@ impl BlockStore {
                     continue;
                 }
                 WAL_ENTRY_COMMIT => {
-                    let (commit_data, state) = bincode::deserialize(&data)
+                    let (commit_data, state): (Vec<CommitData>, _) = bincode::deserialize(&data)
                         .expect("Failed to deserialized commit data from wal");
+                    if let Some(last) = commit_data.last() {
+                        inner.last_decided = Some(last.leader);
+                        inner.committed_round = last.leader.round();
+                    }
                     builder.commit_data(commit_data, state);
                     continue;
                 }
@@ -113,7 +177,9 @@ impl BlockStore {
             };
             // todo - we want to keep some last blocks in the cache
             block_count += 1;
-            inner.add_unloaded(block.reference(), pos);
+            if let Some(equivocation) = inner.add_unloaded(block.reference(), pos) {
+                tracing::warn!("{equivocation} (replayed from wal)");
+            }
         }
         metrics.block_store_entries.inc_by(block_count);
         if let Some(replay_started) = replay_started {
@@ -129,22 +195,49 @@ impl BlockStore {
         builder.build(this)
     }
 
-    pub fn insert_block(&self, block: Data<StatementBlock>, position: WalPosition) {
+    /// Insert a block in the store. A block conflicting with a block of the same author and
+    /// round already in the store is still inserted (so that the blocks referencing it can be
+    /// processed), but it is recorded as evidence and the equivocation is returned as an error.
+    pub fn insert_block(
+        &self,
+        block: Data<StatementBlock>,
+        position: WalPosition,
+    ) -> Result<(), EquivocationDetected> {
         self.metrics.block_store_entries.inc();
-        self.inner.write().add_loaded(position, block);
+        let (equivocation, evicted) = {
+            let mut inner = self.inner.write();
+            let equivocation = inner.add_loaded(position, block);
+            (equivocation, inner.evict())
+        };
+        self.metrics
+            .block_store_evicted_blocks
+            .inc_by(evicted as u64);
+        match equivocation {
+            Some(equivocation) => {
+                self.metrics.block_store_equivocations.inc();
+                Err(equivocation)
+            }
+            None => Ok(()),
+        }
     }
 
     pub fn get_block(&self, reference: BlockReference) -> Option<Data<StatementBlock>> {
-        let entry = self.inner.read().get_block(reference);
-        // todo - consider adding loaded entries back to cache
-        entry.map(|pos| self.read_index(pos))
+        let entry = self.inner.read().get_block(reference)?;
+        let block = self.read_index(entry);
+        self.cache_blocks(std::slice::from_ref(&block));
+        Some(block)
     }
 
+    /// Return the blocks of the specified round sorted by reference (that is, by authority and
+    /// then by digest), so that all replicas holding them list them in the same order.
     pub fn get_blocks_by_round(&self, round: RoundNumber) -> Vec<Data<StatementBlock>> {
         let entries = self.inner.read().get_blocks_by_round(round);
-        self.read_index_vec(entries)
+        let blocks = self.read_index_vec(entries);
+        self.cache_blocks(&blocks);
+        blocks
     }
 
+    /// Return the blocks of the specified authority at the specified round, sorted by digest.
     pub fn get_blocks_at_authority_round(
         &self,
         authority: AuthorityIndex,
@@ -157,6 +250,47 @@ impl BlockStore {
         self.read_index_vec(entries)
     }
 
+    /// Return the block proposed by the authority at the specified round. Fails with the
+    /// (sorted) conflicting references if the authority equivocated at that round, that is,
+    /// if the store holds more than one of its blocks for this slot.
+    pub fn get_own_blocks_by_round_authority(
+        &self,
+        round: RoundNumber,
+        authority: AuthorityIndex,
+    ) -> Result<Option<Data<StatementBlock>>, Vec<BlockReference>> {
+        let entry = {
+            let inner = self.inner.read();
+            let references = inner.references_at_authority_round(authority, round);
+            match references.as_slice() {
+                [] => return Ok(None),
+                [reference] => inner.get_block(*reference),
+                _ => return Err(references),
+            }
+        };
+        Ok(entry.map(|entry| self.read_index(entry)))
+    }
+
+    /// Whether the authority proposed more than one block at the specified round.
+    pub fn is_equivocation(&self, authority: AuthorityIndex, round: RoundNumber) -> bool {
+        let inner = self.inner.read();
+        let Some(blocks) = inner.index.get(&round) else {
+            return false;
+        };
+        blocks.keys().filter(|(a, _)| *a == authority).count() > 1
+    }
+
+    /// Report the evidence of every equivocation detected upon insertion (and not yet pruned),
+    /// in the order they were detected. An authority proposing `n` blocks at the same round is
+    /// reported `n - 1` times.
+    pub fn equivocations(&self) -> Vec<(AuthorityIndex, RoundNumber, [BlockDigest; 2])> {
+        self.inner
+            .read()
+            .equivocations
+            .iter()
+            .map(|x| (x.authority, x.round, x.digests))
+            .collect()
+    }
+
     pub fn block_exists_at_authority_round(
         &self,
         authority: AuthorityIndex,
@@ -231,6 +365,83 @@ impl BlockStore {
         self.metrics.wal_mappings.set(retained_maps as i64);
     }
 
+    /// Drop all blocks below the specified round from the block store. Pruned blocks can no
+    /// longer be queried. The round is capped to the committed round (see
+    /// [`Self::set_committed_round`]) minus [`GC_DEPTH`], so that blocks that may still be
+    /// committed along with an undecided leader are never pruned. Returns the number of pruned
+    /// blocks.
+    pub fn prune_below(&self, round: RoundNumber) -> usize {
+        let _timer = self.metrics.block_store_cleanup_util.utilization_timer();
+        let pruned = {
+            let mut inner = self.inner.write();
+            let watermark = inner.committed_round.saturating_sub(GC_DEPTH);
+            let round = round.min(watermark);
+            if round <= inner.gc_round {
+                return 0;
+            }
+            inner.prune_below(round)
+        };
+        self.metrics.block_store_pruned_blocks.inc_by(pruned as u64);
+        let retained_maps = self.block_wal_reader.cleanup();
+        self.metrics.wal_mappings.set(retained_maps as i64);
+        pruned
+    }
+
+    /// Record that all leaders up to the specified round are decided.
+    pub fn set_committed_round(&self, round: RoundNumber) {
+        let mut inner = self.inner.write();
+        inner.committed_round = max(inner.committed_round, round);
+    }
+
+    /// The highest round up to which all leaders are decided.
+    pub fn committed_round(&self) -> RoundNumber {
+        self.inner.read().committed_round
+    }
+
+    /// The last decided leader journaled in the wal when the store was opened, from which the
+    /// committer resumes after a restart.
+    pub fn recover_last_decided(&self) -> Option<BlockReference> {
+        self.inner.read().last_decided
+    }
+
+    /// Keep at most the specified number of blocks in memory (or all blocks if `None`). The least
+    /// recently used blocks are evicted first and transparently read back from the wal when
+    /// queried. Blocks at or above the committed round are never evicted since the committer
+    /// still needs them, so the cache may temporarily hold more blocks than its size.
+    pub fn set_cache_size(&self, cache_size: Option<usize>) {
+        let evicted = {
+            let mut inner = self.inner.write();
+            inner.cache_size = cache_size;
+            inner.evict()
+        };
+        self.metrics
+            .block_store_evicted_blocks
+            .inc_by(evicted as u64);
+    }
+
+    /// Mark the specified blocks as recently used, loading them back in the cache if they were
+    /// evicted. Does nothing if the cache is unbounded.
+    fn cache_blocks(&self, blocks: &[Data<StatementBlock>]) {
+        if blocks.is_empty() || self.inner.read().cache_size.is_none() {
+            return;
+        }
+        let evicted = {
+            let mut inner = self.inner.write();
+            for block in blocks {
+                inner.reload(block);
+            }
+            inner.evict()
+        };
+        self.metrics
+            .block_store_evicted_blocks
+            .inc_by(evicted as u64);
+    }
+
+    /// The highest round below which all blocks have been pruned.
+    pub fn gc_round(&self) -> RoundNumber {
+        self.inner.read().gc_round
+    }
+
     pub fn get_own_blocks(
         &self,
         from_excluded: RoundNumber,
@@ -257,6 +468,38 @@ impl BlockStore {
         self.inner.read().last_seen_by_authority(authority)
     }
 
+    /// The highest round received from the slowest peer (excluding ourselves). The peer is
+    /// known to hold all the blocks up to that round, but may still need to sync any block
+    /// above it.
+    pub fn slowest_peer_round(&self) -> RoundNumber {
+        let inner = self.inner.read();
+        (0..inner.last_seen_by_authority.len() as AuthorityIndex)
+            .filter(|authority| *authority != inner.authority)
+            .map(|authority| inner.last_seen_by_authority(authority))
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// The highest round of the blocks of the specified authority, or 0 if there are none.
+    pub fn highest_round_for_authority(&self, authority: AuthorityIndex) -> RoundNumber {
+        self.last_seen_by_authority(authority)
+    }
+
+    /// The block of the specified authority with the highest round. If the authority equivocated
+    /// at that round, this is the first of its blocks received by the store. Returns `None` if
+    /// the block store holds no block of the authority (or if this block was pruned).
+    pub fn last_block_by_authority(
+        &self,
+        authority: AuthorityIndex,
+    ) -> Option<Data<StatementBlock>> {
+        let entry = {
+            let inner = self.inner.read();
+            let reference = inner.last_block_by_authority(authority)?;
+            inner.get_block(reference)?
+        };
+        Some(self.read_index(entry))
+    }
+
     pub fn last_own_block_ref(&self) -> Option<BlockReference> {
         self.inner.read().last_own_block()
     }
@@ -264,6 +507,7 @@ impl BlockStore {
     fn read_index(&self, entry: IndexEntry) -> Data<StatementBlock> {
         match entry {
             IndexEntry::WalPosition(position) => {
+                self.metrics.block_store_cache_misses.inc();
                 self.metrics.block_store_loaded_blocks.inc();
                 let (tag, data) = self
                     .block_wal_reader
@@ -283,7 +527,10 @@ impl BlockStore {
                     }
                 }
             }
-            IndexEntry::Loaded(_, block) => block,
+            IndexEntry::Loaded(_, block) => {
+                self.metrics.block_store_cache_hits.inc();
+                block
+            }
         }
     }
 
@@ -332,23 +579,48 @@ impl BlockStoreInner {
         let Some(blocks) = self.index.get(&round) else {
             return vec![];
         };
-        blocks
+        let mut entries: Vec<_> = blocks
             .iter()
-            .filter_map(|((a, _), entry)| {
-                if *a == authority {
-                    Some(entry.clone())
-                } else {
-                    None
-                }
-            })
+            .filter(|((a, _), _)| *a == authority)
+            .collect();
+        entries.sort_by_key(|(key, _)| *key);
+        entries
+            .into_iter()
+            .map(|(_, entry)| entry.clone())
             .collect()
     }
 
+    pub fn references_at_authority_round(
+        &self,
+        authority: AuthorityIndex,
+        round: RoundNumber,
+    ) -> Vec<BlockReference> {
+        let Some(blocks) = self.index.get(&round) else {
+            return vec![];
+        };
+        let mut references: Vec<_> = blocks
+            .keys()
+            .filter(|(a, _)| *a == authority)
+            .map(|(authority, digest)| BlockReference {
+                authority: *authority,
+                round,
+                digest: *digest,
+            })
+            .collect();
+        references.sort();
+        references
+    }
+
     pub fn get_blocks_by_round(&self, round: RoundNumber) -> Vec<IndexEntry> {
         let Some(blocks) = self.index.get(&round) else {
             return vec![];
         };
-        blocks.values().cloned().collect()
+        let mut entries: Vec<_> = blocks.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+        entries
+            .into_iter()
+            .map(|(_, entry)| entry.clone())
+            .collect()
     }
 
     pub fn get_block(&self, reference: BlockReference) -> Option<IndexEntry> {
@@ -358,7 +630,6 @@ impl BlockStoreInner {
             .cloned()
     }
 
-    // todo - also specify LRU criteria
     /// Unload all entries from below or equal threshold_round
     pub fn unload_below_round(&mut self, threshold_round: RoundNumber) -> usize {
         let mut unloaded = 0usize;
@@ -378,32 +649,158 @@ impl BlockStoreInner {
                 }
             }
         }
+        self.forget_below(threshold_round + 1);
         if unloaded > 0 {
             tracing::debug!("Unloaded {unloaded} entries from block store cache");
         }
         unloaded
     }
 
-    pub fn add_unloaded(&mut self, reference: &BlockReference, position: WalPosition) {
+    /// Mark the specified loaded block as the most recently used one.
+    fn touch(&mut self, reference: &BlockReference) {
+        if self.cache_size.is_none() {
+            return;
+        }
+        self.access_counter += 1;
+        if let Some(previous) = self.last_access.insert(*reference, self.access_counter) {
+            self.lru.remove(&previous);
+        }
+        self.lru.insert(self.access_counter, *reference);
+    }
+
+    /// Load the specified block back in the cache (if it is still in the store) and mark it as
+    /// the most recently used one.
+    fn reload(&mut self, block: &Data<StatementBlock>) {
+        let Some(entry) = self
+            .index
+            .get_mut(&block.round())
+            .and_then(|map| map.get_mut(&block.reference().author_digest()))
+        else {
+            return;
+        };
+        if let IndexEntry::WalPosition(position) = entry {
+            *entry = IndexEntry::Loaded(*position, block.clone());
+        }
+        self.touch(block.reference());
+    }
+
+    /// Stop tracking the accesses to the blocks strictly below the specified round.
+    fn forget_below(&mut self, round: RoundNumber) {
+        self.last_access
+            .retain(|reference, _| reference.round >= round);
+        self.lru.retain(|_, reference| reference.round >= round);
+    }
+
+    /// Unload the least recently used blocks below the committed round until the cache holds
+    /// at most `cache_size` blocks. Returns the number of evicted blocks.
+    fn evict(&mut self) -> usize {
+        let Some(cache_size) = self.cache_size else {
+            return 0;
+        };
+        let excess = self.last_access.len().saturating_sub(cache_size);
+        if excess == 0 {
+            return 0;
+        }
+        let evicted: Vec<_> = self
+            .lru
+            .iter()
+            .filter(|(_, reference)| reference.round < self.committed_round)
+            .take(excess)
+            .map(|(access, reference)| (*access, *reference))
+            .collect();
+        for (access, reference) in &evicted {
+            self.lru.remove(access);
+            self.last_access.remove(reference);
+            let entry = self
+                .index
+                .get_mut(&reference.round)
+                .and_then(|map| map.get_mut(&reference.author_digest()));
+            if let Some(entry) = entry {
+                if let IndexEntry::Loaded(position, _) = entry {
+                    *entry = IndexEntry::WalPosition(*position);
+                }
+            }
+        }
+        evicted.len()
+    }
+
+    /// Remove all entries strictly below the specified round.
+    pub fn prune_below(&mut self, round: RoundNumber) -> usize {
+        let retained = self.index.split_off(&round);
+        let pruned = std::mem::replace(&mut self.index, retained)
+            .values()
+            .map(HashMap::len)
+            .sum();
+        self.own_blocks = self.own_blocks.split_off(&round);
+        self.equivocations.retain(|x| x.round >= round);
+        self.forget_below(round);
+        self.gc_round = round;
+        if pruned > 0 {
+            tracing::debug!("Pruned {pruned} entries below round {round} from block store");
+        }
+        pruned
+    }
+
+    pub fn add_unloaded(
+        &mut self,
+        reference: &BlockReference,
+        position: WalPosition,
+    ) -> Option<EquivocationDetected> {
+        let equivocation = self.record_equivocation(reference);
         self.highest_round = max(self.highest_round, reference.round());
         let map = self.index.entry(reference.round()).or_default();
         map.insert(reference.author_digest(), IndexEntry::WalPosition(position));
         self.add_own_index(reference);
         self.update_last_seen_by_authority(reference);
+        equivocation
     }
 
-    pub fn add_loaded(&mut self, position: WalPosition, block: Data<StatementBlock>) {
+    pub fn add_loaded(
+        &mut self,
+        position: WalPosition,
+        block: Data<StatementBlock>,
+    ) -> Option<EquivocationDetected> {
+        let equivocation = self.record_equivocation(block.reference());
         self.highest_round = max(self.highest_round, block.round());
         self.add_own_index(block.reference());
         self.update_last_seen_by_authority(block.reference());
+        self.touch(block.reference());
         let map = self.index.entry(block.round()).or_default();
         map.insert(
             (block.author(), block.digest()),
             IndexEntry::Loaded(position, block),
         );
+        equivocation
+    }
+
+    /// Record the evidence if the specified block (not yet inserted) conflicts with a block of
+    /// the same author and round. Inserting the same block twice is not an equivocation.
+    fn record_equivocation(&mut self, reference: &BlockReference) -> Option<EquivocationDetected> {
+        let blocks = self.index.get(&reference.round)?;
+        if blocks.contains_key(&reference.author_digest()) {
+            return None;
+        }
+        let existing = blocks
+            .keys()
+            .filter(|(authority, _)| *authority == reference.authority)
+            .map(|(_, digest)| *digest)
+            .min()?;
+        let equivocation = EquivocationDetected {
+            authority: reference.authority,
+            round: reference.round,
+            digests: [existing, reference.digest],
+        };
+        self.equivocations.push(equivocation);
+        Some(equivocation)
     }
 
     pub fn last_seen_by_authority(&self, authority: AuthorityIndex) -> RoundNumber {
+        self.last_block_by_authority(authority)
+            .map(|reference| reference.round())
+            .unwrap_or_default()
+    }
+
+    pub fn last_block_by_authority(&self, authority: AuthorityIndex) -> Option<BlockReference> {
         *self
             .last_seen_by_authority
             .get(authority as usize)
@@ -415,8 +812,8 @@ impl BlockStoreInner {
             .last_seen_by_authority
             .get_mut(reference.authority as usize)
             .expect("last_seen_by_authority not found");
-        if reference.round() > *last_seen {
-            *last_seen = reference.round();
+        if last_seen.map_or(true, |last_seen| reference.round() > last_seen.round()) {
+            *last_seen = Some(*reference);
         }
     }
 
@@ -496,13 +893,17 @@ impl BlockWriter for (&mut WalWriter, &BlockStore) {
             .0
             .write(WAL_ENTRY_BLOCK, block.serialized_bytes())
             .expect("Writing to wal failed");
-        self.1.insert_block(block, pos);
+        if let Err(equivocation) = self.1.insert_block(block, pos) {
+            tracing::warn!("{equivocation}");
+        }
         pos
     }
 
     fn insert_own_block(&mut self, data: &OwnBlockData) {
         let block_pos = data.write_to_wal(self.0);
-        self.1.insert_block(data.block.clone(), block_pos);
+        self.1
+            .insert_block(data.block.clone(), block_pos)
+            .expect("Own blocks never equivocate");
     }
 }
 
@@ -558,6 +959,10 @@ impl From<&CommittedSubDag> for CommitData {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{
+        crypto::compute_test_digests,
+        test_util::{build_dag, committee, test_metrics, TestBlockWriter},
+    };
 
     #[test]
     fn own_block_serialization_test() {
@@ -565,4 +970,383 @@ mod test {
         let serialized = bincode::serialize(&next_entry).unwrap();
         assert_eq!(serialized.len(), OWN_BLOCK_HEADER_SIZE);
     }
+
+    #[test]
+    fn prune_below() {
+        let committee = committee(4);
+        let mut block_writer = TestBlockWriter::new(&committee);
+        build_dag(&committee, &mut block_writer, None, 1000);
+        let block_store = block_writer.into_block_store();
+        let old_block = block_store.get_blocks_by_round(10).pop().unwrap();
+        assert_eq!(block_store.len_expensive(), 4 * 1001);
+
+        // Nothing is pruned above the gc round of the committed round.
+        block_store.set_committed_round(500 + GC_DEPTH);
+        assert_eq!(block_store.prune_below(900), 4 * 500);
+        assert_eq!(block_store.gc_round(), 500);
+        assert_eq!(block_store.get_blocks_by_round(500).len(), 4);
+
+        block_store.set_committed_round(900 + GC_DEPTH);
+        let pruned = block_store.prune_below(900);
+        assert_eq!(pruned, 4 * 400);
+        assert_eq!(block_store.gc_round(), 900);
+        // Pruning is idempotent.
+        assert_eq!(block_store.prune_below(900), 0);
+        assert_eq!(block_store.prune_below(800), 0);
+        assert_eq!(block_store.len_expensive(), 4 * 101);
+        assert_eq!(block_store.inner.read().index.keys().next(), Some(&900));
+
+        // Old rounds are released.
+        assert!(block_store.get_block(*old_block.reference()).is_none());
+        assert!(block_store.get_blocks_by_round(899).is_empty());
+
+        // Recent rounds remain queryable.
+        for round in 900..=1000 {
+            let blocks = block_store.get_blocks_by_round(round);
+            assert_eq!(blocks.len(), 4);
+            for block in blocks {
+                assert!(block_store.get_block(*block.reference()).is_some());
+            }
+        }
+        assert_eq!(block_store.highest_round(), 1000);
+    }
+
+    #[test]
+    fn bounded_cache() {
+        let committee = committee(4);
+        let mut block_writer = TestBlockWriter::new(&committee);
+        let block_store = block_writer.block_store();
+        block_store.set_cache_size(Some(16));
+        block_store.set_committed_round(18);
+        build_dag(&committee, &mut block_writer, None, 20);
+        let metrics = block_store.metrics.clone();
+        let evicted = metrics.block_store_evicted_blocks.get();
+        assert_eq!(evicted, 4 * 17);
+
+        // The blocks at or above the committed round are never evicted.
+        let loaded_rounds = || {
+            let inner = block_store.inner.read();
+            let mut rounds: Vec<_> = inner
+                .index
+                .iter()
+                .flat_map(|(round, map)| {
+                    map.values()
+                        .filter(|entry| matches!(entry, IndexEntry::Loaded(..)))
+                        .map(move |_| *round)
+                })
+                .collect();
+            rounds.dedup();
+            rounds
+        };
+        assert_eq!(loaded_rounds(), vec![17, 18, 19, 20]);
+
+        // Evicted blocks are read back from the wal.
+        let misses = metrics.block_store_cache_misses.get();
+        for round in 0..=20 {
+            let blocks = block_store.get_blocks_by_round(round);
+            assert_eq!(blocks.len(), 4);
+            for (authority, block) in blocks.iter().enumerate() {
+                assert_eq!(block.round(), round);
+                assert_eq!(block.author(), authority as AuthorityIndex);
+                let reference = *block.reference();
+                assert_eq!(block_store.get_block(reference).as_ref(), Some(block));
+            }
+        }
+        assert_eq!(metrics.block_store_cache_misses.get(), misses + 4 * 18);
+        assert_eq!(metrics.block_store_evicted_blocks.get(), evicted + 4 * 18);
+
+        // The least recently used blocks are evicted first.
+        assert_eq!(loaded_rounds(), vec![17, 18, 19, 20]);
+        let hits = metrics.block_store_cache_hits.get();
+        block_store.get_blocks_by_round(17);
+        assert_eq!(metrics.block_store_cache_hits.get(), hits + 4);
+        block_store.get_blocks_by_round(3);
+        assert_eq!(loaded_rounds(), vec![3, 18, 19, 20]);
+    }
+
+    #[test]
+    fn recover_from_wal() {
+        let committee = committee(4);
+        let dir = tempdir::TempDir::new("recover_from_wal").unwrap();
+        let path = dir.path().join("wal");
+
+        let (mut block_writer, _) = TestBlockWriter::new_persisted(&committee, &path);
+        build_dag(&committee, &mut block_writer, None, 10);
+        let block_store = block_writer.block_store();
+        let leader = block_store
+            .get_blocks_at_authority_round(1, 9)
+            .pop()
+            .unwrap();
+        let commit = CommitData {
+            leader: *leader.reference(),
+            sub_dag: vec![*leader.reference()],
+        };
+        block_writer.add_commits(&[commit]);
+        let rounds: Vec<_> = (0..=10)
+            .map(|round| {
+                let mut references: Vec<_> = block_store
+                    .get_blocks_by_round(round)
+                    .iter()
+                    .map(|block| *block.reference())
+                    .collect();
+                references.sort();
+                references
+            })
+            .collect();
+        drop(block_store);
+        drop(block_writer);
+
+        // Replay the wal into a fresh block store.
+        let (block_writer, recovered) = TestBlockWriter::new_persisted(&committee, &path);
+        let block_store = block_writer.into_block_store();
+        assert_eq!(block_store.highest_round(), 10);
+        assert_eq!(block_store.len_expensive(), 4 * 11);
+        for (round, references) in rounds.iter().enumerate() {
+            let mut recovered: Vec<_> = block_store
+                .get_blocks_by_round(round as RoundNumber)
+                .iter()
+                .map(|block| *block.reference())
+                .collect();
+            recovered.sort();
+            assert_eq!(&recovered, references);
+            for reference in references {
+                let block = block_store.get_block(*reference).unwrap();
+                assert_eq!(block.reference(), reference);
+            }
+        }
+
+        // The journaled commits are recovered as well.
+        assert_eq!(recovered.last_committed_leader, Some(*leader.reference()));
+        assert!(recovered.committed_blocks.contains(leader.reference()));
+    }
+
+    #[test]
+    fn reopen_persisted_store() {
+        let committee = committee(4);
+        let dir = tempdir::TempDir::new("reopen_persisted_store").unwrap();
+        let path = dir.path().join("wal");
+
+        let (mut wal_writer, recovered) =
+            BlockStore::open_persisted(0, &path, test_metrics(), &committee).unwrap();
+        let block_store = recovered.block_store;
+        assert_eq!(block_store.recover_last_decided(), None);
+
+        // Persist a few rounds of blocks along with two batches of commits.
+        let mut includes: Vec<_> = committee
+            .authorities()
+            .map(|authority| {
+                let block = StatementBlock::new_genesis(authority);
+                (&mut wal_writer, &block_store).insert_block(block.clone());
+                *block.reference()
+            })
+            .collect();
+        let mut leaders = Vec::new();
+        for round in 1..=6 {
+            includes = committee
+                .authorities()
+                .map(|authority| {
+                    let block = Data::new(StatementBlock::new(
+                        authority,
+                        round,
+                        includes.clone(),
+                        vec![],
+                        0,
+                        false,
+                        Default::default(),
+                    ));
+                    (&mut wal_writer, &block_store).insert_block(block.clone());
+                    *block.reference()
+                })
+                .collect();
+            leaders.push(includes[round as usize % 4]);
+        }
+        for leader in [leaders[2], leaders[5]] {
+            let commits = vec![CommitData {
+                leader,
+                sub_dag: vec![leader],
+            }];
+            let commits = bincode::serialize(&(commits, Bytes::new())).unwrap();
+            wal_writer.write(WAL_ENTRY_COMMIT, &commits).unwrap();
+        }
+        wal_writer.sync().unwrap();
+        drop(block_store);
+        drop(wal_writer);
+
+        // Reopen the store from the same path.
+        let (_, recovered) =
+            BlockStore::open_persisted(0, &path, test_metrics(), &committee).unwrap();
+        let block_store = recovered.block_store;
+        assert_eq!(block_store.len_expensive(), 4 * 7);
+        assert_eq!(block_store.highest_round(), 6);
+        for reference in &includes {
+            let block = block_store.get_block(*reference).unwrap();
+            assert_eq!(block.reference(), reference);
+        }
+        assert_eq!(block_store.recover_last_decided(), Some(leaders[5]));
+        assert_eq!(block_store.committed_round(), 6);
+        assert_eq!(recovered.last_committed_leader, Some(leaders[5]));
+    }
+
+    #[test]
+    fn last_block_by_authority() {
+        let committee = committee(4);
+        let mut block_writer = TestBlockWriter::new(&committee);
+
+        // Authority 1 proposes blocks with gaps in its round sequence.
+        let mut includes = vec![];
+        for round in [1, 2, 5, 9] {
+            let block = Data::new(StatementBlock::new(
+                1,
+                round,
+                includes,
+                vec![],
+                0,
+                false,
+                Default::default(),
+            ));
+            includes = vec![*block.reference()];
+            block_writer.add_block(block);
+        }
+        let block_store = block_writer.into_block_store();
+
+        // Authority 0 has no blocks.
+        assert_eq!(block_store.highest_round_for_authority(0), 0);
+        assert!(block_store.last_block_by_authority(0).is_none());
+
+        assert_eq!(block_store.highest_round_for_authority(1), 9);
+        let last_block = block_store.last_block_by_authority(1).unwrap();
+        assert_eq!(last_block.reference(), &includes[0]);
+        assert_eq!(last_block.round(), 9);
+    }
+
+    #[test]
+    fn equivocations() {
+        compute_test_digests();
+        let committee = committee(4);
+        let mut block_writer = TestBlockWriter::new(&committee);
+        let references = build_dag(&committee, &mut block_writer, None, 2);
+        assert!(block_writer.block_store().equivocations().is_empty());
+
+        // Authority 2 proposes two conflicting blocks at round 3.
+        let blocks: Vec<_> = (0..2)
+            .map(|time| {
+                Data::new(StatementBlock::new(
+                    2,
+                    3,
+                    references.clone(),
+                    vec![],
+                    time,
+                    false,
+                    Default::default(),
+                ))
+            })
+            .collect();
+        assert!(block_writer.try_add_block(blocks[0].clone()).is_ok());
+        // Inserting the same block again is not an equivocation.
+        assert!(block_writer.try_add_block(blocks[0].clone()).is_ok());
+        let (_, equivocation) = block_writer.try_add_block(blocks[1].clone()).unwrap_err();
+        assert_eq!(
+            equivocation,
+            EquivocationDetected {
+                authority: 2,
+                round: 3,
+                digests: [blocks[0].digest(), blocks[1].digest()],
+            }
+        );
+        let block_store = block_writer.into_block_store();
+        let mut conflicts: Vec<_> = blocks.iter().map(|block| *block.reference()).collect();
+        conflicts.sort();
+
+        // The conflicting block is stored as evidence.
+        assert!(block_store.block_exists(*blocks[1].reference()));
+        assert_eq!(
+            block_store.equivocations(),
+            vec![(2, 3, [blocks[0].digest(), blocks[1].digest()])]
+        );
+        assert!(block_store.is_equivocation(2, 3));
+        assert!(!block_store.is_equivocation(2, 2));
+        assert_eq!(
+            block_store.get_own_blocks_by_round_authority(3, 2).err(),
+            Some(conflicts)
+        );
+        let block = block_store.get_own_blocks_by_round_authority(2, 2).unwrap();
+        assert_eq!(block.unwrap().author_round(), (2, 2));
+        assert!(block_store
+            .get_own_blocks_by_round_authority(3, 0)
+            .unwrap()
+            .is_none());
+
+        // The evidence is released along with the pruned rounds.
+        block_store.set_committed_round(4 + GC_DEPTH);
+        block_store.prune_below(3);
+        assert_eq!(block_store.equivocations().len(), 1);
+        block_store.prune_below(4);
+        assert!(block_store.equivocations().is_empty());
+    }
+
+    #[test]
+    fn blocks_by_round_sorted() {
+        let committee = committee(10);
+        let mut block_writer = TestBlockWriter::new(&committee);
+        let references = build_dag(&committee, &mut block_writer, None, 2);
+
+        // The blocks of round 3 are inserted in the reverse order of their authorities.
+        for authority in committee.authorities().rev() {
+            block_writer.add_block(Data::new(StatementBlock::new(
+                authority,
+                3,
+                references.clone(),
+                vec![],
+                0,
+                false,
+                Default::default(),
+            )));
+        }
+        let block_store = block_writer.into_block_store();
+
+        for round in 0..=3 {
+            let references: Vec<_> = block_store
+                .get_blocks_by_round(round)
+                .iter()
+                .map(|block| *block.reference())
+                .collect();
+            assert_eq!(references.len(), 10);
+            let mut sorted = references.clone();
+            sorted.sort();
+            assert_eq!(references, sorted);
+        }
+    }
+
+    #[test]
+    fn slowest_peer_round() {
+        let committee = committee(4);
+        let mut block_writer = TestBlockWriter::new(&committee);
+        let references = build_dag(&committee, &mut block_writer, None, 5);
+        assert_eq!(block_writer.block_store().slowest_peer_round(), 5);
+
+        // Authorities 0 to 2 keep making progress while authority 3 lags behind.
+        let mut includes = references;
+        for round in 6..=20 {
+            let blocks: Vec<_> = (0..3)
+                .map(|authority| {
+                    Data::new(StatementBlock::new(
+                        authority,
+                        round,
+                        includes.clone(),
+                        vec![],
+                        0,
+                        false,
+                        Default::default(),
+                    ))
+                })
+                .collect();
+            includes = blocks.iter().map(|block| *block.reference()).collect();
+            block_writer.add_blocks(blocks);
+        }
+        let block_store = block_writer.into_block_store();
+        assert_eq!(block_store.slowest_peer_round(), 5);
+
+        // Our own progress is not taken into account.
+        assert_eq!(block_store.highest_round_for_authority(0), 20);
+    }
 }
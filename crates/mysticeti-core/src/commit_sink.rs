@@ -0,0 +1,140 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{crypto::BlockDigest, types::BlockReference};
+
+/// Writes the sequence of committed leaders to a file, one block reference per line formatted as
+/// `<authority> <round> <digest (hex)>`. The files of different nodes can be compared to check
+/// that they committed the same sequence, and replayed with [`read_commit_sequence`].
+pub struct CommitSink {
+    writer: BufWriter<File>,
+}
+
+impl CommitSink {
+    pub fn start(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    /// Append the committed leaders to the file. The file is flushed after every batch so that
+    /// it reflects all commits even if the node crashes.
+    pub fn record<'a>(&mut self, leaders: impl IntoIterator<Item = &'a BlockReference>) {
+        for leader in leaders {
+            writeln!(self.writer, "{}", format_entry(leader))
+                .expect("Failed to write to commit sink");
+        }
+        self.writer.flush().expect("Failed to flush commit sink");
+    }
+}
+
+/// Format a committed leader as a line of the commit sink file.
+pub fn format_entry(reference: &BlockReference) -> String {
+    format!(
+        "{} {} {}",
+        reference.authority,
+        reference.round,
+        hex::encode(reference.digest)
+    )
+}
+
+fn parse_entry(line: &str) -> Option<BlockReference> {
+    let mut parts = line.split_whitespace();
+    let authority = parts.next()?.parse().ok()?;
+    let round = parts.next()?.parse().ok()?;
+    let digest = BlockDigest::from_hex(parts.next()?)?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(BlockReference {
+        authority,
+        round,
+        digest,
+    })
+}
+
+/// Read back the sequence of committed leaders written by a [`CommitSink`].
+pub fn read_commit_sequence(path: impl AsRef<Path>) -> io::Result<Vec<BlockReference>> {
+    let file = File::open(path)?;
+    let mut sequence = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let reference = parse_entry(&line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Malformed commit at line {}: '{line}'", i + 1),
+            )
+        })?;
+        sequence.push(reference);
+    }
+    Ok(sequence)
+}
+
+/// Return the position of the first commit on which two sequences disagree. Nodes may lag
+/// behind each other, so a sequence that is a prefix of the other does not diverge.
+pub fn first_divergence(a: &[BlockReference], b: &[BlockReference]) -> Option<usize> {
+    a.iter().zip(b).position(|(x, y)| x != y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(authority: u64, round: u64, byte: u8) -> BlockReference {
+        BlockReference {
+            authority,
+            round,
+            digest: BlockDigest::from_hex(&hex::encode([byte; 32])).unwrap(),
+        }
+    }
+
+    #[test]
+    fn commit_sink_round_trip() {
+        let temp = tempdir::TempDir::new("test_commit_sink").unwrap();
+        let path = temp.path().join("commits.txt");
+        let sequence = vec![
+            reference(0, 3, 1),
+            reference(1, 3, 2),
+            reference(2, 6, 3),
+            reference(30, 9, 255),
+        ];
+
+        let mut sink = CommitSink::start(&path).unwrap();
+        sink.record(&sequence[..2]);
+        sink.record(&sequence[2..]);
+        drop(sink);
+        assert_eq!(read_commit_sequence(&path).unwrap(), sequence);
+
+        // Restarting the node appends to the existing sequence.
+        let mut sink = CommitSink::start(&path).unwrap();
+        sink.record(&[reference(3, 12, 4)]);
+        let replayed = read_commit_sequence(&path).unwrap();
+        assert_eq!(replayed.len(), 5);
+        assert_eq!(replayed[..4], sequence);
+    }
+
+    #[test]
+    fn commit_sink_malformed() {
+        let temp = tempdir::TempDir::new("test_commit_sink").unwrap();
+        let path = temp.path().join("commits.txt");
+        std::fs::write(&path, "0 3 not-a-digest\n").unwrap();
+        assert!(read_commit_sequence(&path).is_err());
+    }
+
+    #[test]
+    fn divergence() {
+        let a = vec![reference(0, 3, 1), reference(1, 6, 2), reference(2, 9, 3)];
+        let mut b = a[..2].to_vec();
+        assert_eq!(first_divergence(&a, &b), None);
+
+        b.push(reference(3, 9, 4));
+        assert_eq!(first_divergence(&a, &b), Some(2));
+    }
+}
@@ -25,6 +25,7 @@ use crate::{
         AuthoritySet,
         BaseStatement,
         BlockReference,
+        EpochNumber,
         Stake,
         StatementBlock,
         TransactionLocator,
@@ -38,6 +39,8 @@ pub struct Committee {
     authorities: Vec<Authority>,
     validity_threshold: Stake, // The minimum stake required for validity
     quorum_threshold: Stake,   // The minimum stake required for quorum
+    #[serde(default)]
+    epoch: EpochNumber,
 }
 
 impl Committee {
@@ -49,6 +52,37 @@ impl Committee {
     }
 
     pub fn new(authorities: Vec<Authority>) -> Arc<Self> {
+        Arc::new(Self::build(authorities, 0))
+    }
+
+    /// Make the committee of the next epoch. Authorities are identified by their index; the
+    /// indices of the new authorities must span `0..n` (in any order).
+    pub fn reconfigure(
+        &self,
+        mut new_authorities: Vec<(AuthorityIndex, Stake, PublicKey)>,
+        epoch: EpochNumber,
+    ) -> Committee {
+        assert!(
+            epoch > self.epoch,
+            "Cannot reconfigure epoch {} into epoch {epoch}",
+            self.epoch
+        );
+        new_authorities.sort_by_key(|(authority, _, _)| *authority);
+        let authorities = new_authorities
+            .into_iter()
+            .enumerate()
+            .map(|(i, (authority, stake, public_key))| {
+                assert_eq!(
+                    authority, i as AuthorityIndex,
+                    "Authority indices must be contiguous"
+                );
+                Authority { stake, public_key }
+            })
+            .collect();
+        Self::build(authorities, epoch)
+    }
+
+    fn build(authorities: Vec<Authority>, epoch: EpochNumber) -> Self {
         // todo - check duplicate public keys
         // Ensure the list is not empty
         assert!(!authorities.is_empty());
@@ -65,11 +99,16 @@ impl Committee {
         }
         let validity_threshold = total_stake / 3;
         let quorum_threshold = 2 * total_stake / 3;
-        Arc::new(Committee {
+        Committee {
             authorities,
             validity_threshold,
             quorum_threshold,
-        })
+            epoch,
+        }
+    }
+
+    pub fn epoch(&self) -> EpochNumber {
+        self.epoch
     }
 
     pub fn get_stake(&self, authority: AuthorityIndex) -> Option<Stake> {
@@ -149,11 +188,19 @@ impl Committee {
     }
 
     pub fn new_for_benchmarks(committee_size: usize) -> Arc<Self> {
+        Self::new_with_stakes(vec![1; committee_size])
+    }
+
+    /// Make a committee whose i-th authority holds the i-th stake (with deterministic keys).
+    /// The thresholds are computed over the total stake rather than the number of authorities.
+    pub fn new_with_stakes(stakes: Vec<Stake>) -> Arc<Self> {
+        let signers = Signer::new_for_test(stakes.len());
         Self::new(
-            Signer::new_for_test(committee_size)
+            stakes
                 .into_iter()
-                .map(|keypair| Authority {
-                    stake: 1,
+                .zip(signers)
+                .map(|(stake, keypair)| Authority {
+                    stake,
                     public_key: keypair.public_key(),
                 })
                 .collect(),
@@ -492,4 +539,47 @@ mod test {
         assert_eq!(Some(4..5), b.add(6));
         assert_eq!(Some(6..7), b.finish());
     }
+
+    #[test]
+    fn reconfigure() {
+        let committee = Committee::new_test(vec![1, 1, 1, 1]);
+        assert_eq!(committee.epoch(), 0);
+
+        let new_authorities = vec![
+            (2, 3, dummy_public_key()),
+            (0, 1, dummy_public_key()),
+            (1, 2, dummy_public_key()),
+        ];
+        let next = committee.reconfigure(new_authorities, 1);
+        assert_eq!(next.epoch(), 1);
+        assert_eq!(next.len(), 3);
+        assert_eq!(next.get_stake(0), Some(1));
+        assert_eq!(next.get_stake(2), Some(3));
+        assert_eq!(next.quorum_threshold(), 5);
+    }
+
+    #[test]
+    fn uneven_stakes() {
+        let committee = Committee::new_with_stakes(vec![1, 1, 1, 7]);
+        assert_eq!(committee.quorum_threshold(), 7);
+        assert_eq!(committee.validity_threshold(), 4);
+
+        // A majority of the authorities holding a minority of the stake is not a quorum.
+        let majority: HashSet<AuthorityIndex> = [0, 1, 2].into_iter().collect();
+        let stake = committee.get_total_stake(&majority);
+        assert_eq!(stake, 3);
+        assert!(!committee.is_quorum(stake));
+        assert!(!committee.is_valid(stake));
+
+        // A single authority holding most of the stake is.
+        let heavy: HashSet<AuthorityIndex> = [3].into_iter().collect();
+        assert!(committee.is_quorum(committee.get_total_stake(&heavy)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn reconfigure_same_epoch() {
+        let committee = Committee::new_test(vec![1, 1, 1, 1]);
+        committee.reconfigure(vec![(0, 1, dummy_public_key())], 0);
+    }
 }
@@ -9,6 +9,7 @@ use std::{
     time::Duration,
 };
 
+use schemars::JsonSchema;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
@@ -16,22 +17,33 @@ use crate::{
     types::{AuthorityIndex, PublicKey, RoundNumber},
 };
 
+/// Load and print objects as JSON if the file has a `.json` extension, and as YAML otherwise.
 pub trait ImportExport: Serialize + DeserializeOwned {
     fn load<P: AsRef<Path>>(path: P) -> Result<Self, io::Error> {
         let content = fs::read_to_string(&path)?;
-        let object =
-            serde_yaml::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let object = if is_json(path.as_ref()) {
+            serde_json::from_str(&content)?
+        } else {
+            serde_yaml::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+        };
         Ok(object)
     }
 
     fn print<P: AsRef<Path>>(&self, path: P) -> Result<(), io::Error> {
-        let content =
-            serde_yaml::to_string(self).expect("Failed to serialize object to YAML string");
+        let content = if is_json(path.as_ref()) {
+            serde_json::to_string_pretty(self).expect("Failed to serialize object to JSON string")
+        } else {
+            serde_yaml::to_string(self).expect("Failed to serialize object to YAML string")
+        };
         fs::write(&path, content)
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+fn is_json(path: &Path) -> bool {
+    path.extension().map_or(false, |x| x == "json")
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct NodeParameters {
     #[serde(default = "node_defaults::default_wave_length")]
     pub wave_length: RoundNumber,
@@ -51,6 +63,44 @@ pub struct NodeParameters {
     pub consensus_only: bool,
     #[serde(default = "node_defaults::default_enable_synchronizer")]
     pub enable_synchronizer: bool,
+    /// The number of rounds below the highest round to keep in the block store. Older blocks
+    /// are pruned, but never the ones that may still be committed (see
+    /// [`crate::consensus::GC_DEPTH`]). Pruning is disabled if `None`.
+    #[serde(default = "node_defaults::default_retention_rounds")]
+    pub retention_rounds: Option<RoundNumber>,
+    /// Never prune the blocks that the slowest peer may still need to sync, i.e., the blocks
+    /// above the highest round we received from that peer. Only used if pruning is enabled. This
+    /// only keeps more blocks in the local store: the committed sub-dags never depend on it (see
+    /// [`crate::consensus::GC_DEPTH`]).
+    #[serde(default = "node_defaults::default_protect_lagging_peers")]
+    pub protect_lagging_peers: bool,
+    /// The maximum number of blocks the block store keeps in memory. The least recently used
+    /// blocks below the last committed round are read back from the wal when needed. The cache
+    /// is unbounded if `None`.
+    #[serde(default = "node_defaults::default_block_cache_size")]
+    pub block_cache_size: Option<usize>,
+    /// Write the sequence of committed leaders to a file in the storage directory (to compare
+    /// the commits of different nodes or replay them).
+    #[serde(default = "node_defaults::default_enable_commit_sink")]
+    pub enable_commit_sink: bool,
+    /// Record every commit decision in a tamper-evident audit log in the storage directory. The
+    /// log survives restarts and can be verified offline (`mysticeti verify-audit-log`).
+    #[serde(default = "node_defaults::default_enable_audit_log")]
+    pub enable_audit_log: bool,
+    /// The authorities that deliberately propose two conflicting blocks at every round (see
+    /// [`crate::core::CoreOptions::with_equivocation`]). Only use this to test the safety of the
+    /// protocol against Byzantine validators.
+    #[serde(default = "node_defaults::default_equivocating_authorities")]
+    pub equivocating_authorities: Vec<AuthorityIndex>,
+    /// The offset between the network port and the metrics port of each node (in benchmarks).
+    /// Defaults to the committee size if `None`.
+    #[serde(default = "node_defaults::default_metrics_port_offset")]
+    pub metrics_port_offset: Option<u16>,
+    /// The network and metrics ports of each node in the order of the authority index (in
+    /// benchmarks), e.g., to run several nodes on the same machine. Overrides the default ports
+    /// and the metrics port offset if set.
+    #[serde(default = "node_defaults::default_benchmark_ports")]
+    pub benchmark_ports: Option<Vec<(u16, u16)>>,
 }
 
 pub mod node_defaults {
@@ -89,6 +139,38 @@ pub mod node_defaults {
     pub fn default_enable_synchronizer() -> bool {
         false
     }
+
+    pub fn default_retention_rounds() -> Option<super::RoundNumber> {
+        None
+    }
+
+    pub fn default_protect_lagging_peers() -> bool {
+        false
+    }
+
+    pub fn default_block_cache_size() -> Option<usize> {
+        None
+    }
+
+    pub fn default_enable_commit_sink() -> bool {
+        false
+    }
+
+    pub fn default_enable_audit_log() -> bool {
+        false
+    }
+
+    pub fn default_equivocating_authorities() -> Vec<super::AuthorityIndex> {
+        Vec::new()
+    }
+
+    pub fn default_metrics_port_offset() -> Option<u16> {
+        None
+    }
+
+    pub fn default_benchmark_ports() -> Option<Vec<(u16, u16)>> {
+        None
+    }
 }
 
 impl Default for NodeParameters {
@@ -103,6 +185,14 @@ impl Default for NodeParameters {
             enable_pipelining: node_defaults::default_enable_pipelining(),
             consensus_only: node_defaults::default_consensus_only(),
             enable_synchronizer: node_defaults::default_enable_synchronizer(),
+            retention_rounds: node_defaults::default_retention_rounds(),
+            protect_lagging_peers: node_defaults::default_protect_lagging_peers(),
+            block_cache_size: node_defaults::default_block_cache_size(),
+            enable_commit_sink: node_defaults::default_enable_commit_sink(),
+            enable_audit_log: node_defaults::default_enable_audit_log(),
+            equivocating_authorities: node_defaults::default_equivocating_authorities(),
+            metrics_port_offset: node_defaults::default_metrics_port_offset(),
+            benchmark_ports: node_defaults::default_benchmark_ports(),
         }
     }
 }
@@ -151,10 +241,23 @@ impl NodePublicConfig {
     }
 
     pub fn new_for_benchmarks(ips: Vec<IpAddr>, node_parameters: Option<NodeParameters>) -> Self {
-        let default_with_ips = Self::new_for_tests(ips.len()).with_ips(ips);
+        let parameters = node_parameters.unwrap_or_default();
+        let mut identifiers = Self::new_for_tests(ips.len()).with_ips(ips).identifiers;
+        if let Some(offset) = parameters.metrics_port_offset {
+            for id in identifiers.iter_mut() {
+                id.metrics_address
+                    .set_port(id.network_address.port() + offset);
+            }
+        }
+        if let Some(ports) = &parameters.benchmark_ports {
+            for (id, (network_port, metrics_port)) in identifiers.iter_mut().zip(ports) {
+                id.network_address.set_port(*network_port);
+                id.metrics_address.set_port(*metrics_port);
+            }
+        }
         Self {
-            identifiers: default_with_ips.identifiers,
-            parameters: node_parameters.unwrap_or_default(),
+            identifiers,
+            parameters,
         }
     }
 
@@ -237,6 +340,10 @@ impl NodePrivateConfig {
         format!("private-config-{authority}.yaml").into()
     }
 
+    pub const COMMITTED_LEADERS_FILENAME: &'static str = "commits.txt";
+
+    pub const AUDIT_LOG_FILENAME: &'static str = "audit.log";
+
     pub fn default_storage_path(authority: AuthorityIndex) -> PathBuf {
         format!("storage-{authority}").into()
     }
@@ -252,11 +359,19 @@ impl NodePrivateConfig {
     pub fn wal(&self) -> PathBuf {
         self.storage_path.join("wal")
     }
+
+    pub fn committed_leaders_log(&self) -> PathBuf {
+        self.storage_path.join(Self::COMMITTED_LEADERS_FILENAME)
+    }
+
+    pub fn audit_log(&self) -> PathBuf {
+        self.storage_path.join(Self::AUDIT_LOG_FILENAME)
+    }
 }
 
 impl ImportExport for NodePrivateConfig {}
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct ClientParameters {
     /// The number of transactions to send to the network per second.
     #[serde(default = "client_defaults::default_load")]
@@ -267,6 +382,15 @@ pub struct ClientParameters {
     /// The initial delay before starting to send transactions.
     #[serde(default = "client_defaults::default_initial_delay")]
     pub initial_delay: Duration,
+    /// An optional schedule of (time since the end of the initial delay, transactions per second)
+    /// points overriding the constant `load`. Each point holds until the next one.
+    #[serde(default)]
+    pub load_schedule: Vec<(Duration, usize)>,
+    /// The commit throughput (tx/s) of the whole system to target. When set, the clients adjust
+    /// their load (starting from their initial load) based on the observed commit throughput,
+    /// and the load schedule is ignored.
+    #[serde(default)]
+    pub target_commit_tps: Option<usize>,
 }
 
 mod client_defaults {
@@ -291,8 +415,22 @@ impl Default for ClientParameters {
             load: client_defaults::default_load(),
             transaction_size: client_defaults::default_transaction_size(),
             initial_delay: client_defaults::default_initial_delay(),
+            load_schedule: Vec::new(),
+            target_commit_tps: None,
         }
     }
 }
 
+impl ClientParameters {
+    /// The number of transactions to send per second at the specified time (since the end of the
+    /// initial delay). The constant `load` applies before the first point of the schedule.
+    pub fn load_at(&self, elapsed: Duration) -> usize {
+        self.load_schedule
+            .iter()
+            .take_while(|(time, _)| *time <= elapsed)
+            .last()
+            .map_or(self.load, |(_, load)| *load)
+    }
+}
+
 impl ImportExport for ClientParameters {}
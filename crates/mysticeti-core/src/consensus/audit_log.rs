@@ -0,0 +1,305 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use digest::Digest;
+use serde::{Deserialize, Serialize};
+
+use super::LeaderStatus;
+use crate::{
+    crypto::CryptoHash,
+    types::{format_authority_round, AuthorityIndex, BlockReference, RoundNumber},
+};
+
+type AuditHasher = blake2::Blake2b<digest::consts::U32>;
+
+/// The digest of an entry of the audit log. Each entry commits to the digest of its predecessor,
+/// so altering any entry breaks the link with all the entries that follow.
+pub type AuditDigest = [u8; 32];
+
+/// The digest preceding the first entry of the audit log.
+pub const GENESIS_AUDIT_DIGEST: AuditDigest = [0; 32];
+
+/// A commit decision recorded in the audit log.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum AuditDecision {
+    Commit(BlockReference),
+    Skip(AuthorityIndex, RoundNumber),
+}
+
+impl AuditDecision {
+    pub fn round(&self) -> RoundNumber {
+        match self {
+            Self::Commit(reference) => reference.round,
+            Self::Skip(_, round) => *round,
+        }
+    }
+
+    pub fn authority(&self) -> AuthorityIndex {
+        match self {
+            Self::Commit(reference) => reference.authority,
+            Self::Skip(authority, _) => *authority,
+        }
+    }
+
+    /// Convert a decided leader into an audit decision. Returns `None` for undecided leaders.
+    pub fn from_status(status: &LeaderStatus) -> Option<Self> {
+        match status {
+            LeaderStatus::Commit(block) => Some(Self::Commit(*block.reference())),
+            LeaderStatus::Skip(authority, round) => Some(Self::Skip(*authority, *round)),
+            LeaderStatus::Undecided(..) => None,
+        }
+    }
+}
+
+impl CryptoHash for AuditDecision {
+    fn crypto_hash(&self, state: &mut impl Digest) {
+        match self {
+            Self::Commit(reference) => {
+                [0].crypto_hash(state);
+                reference.crypto_hash(state);
+            }
+            Self::Skip(authority, round) => {
+                [1].crypto_hash(state);
+                authority.crypto_hash(state);
+                round.crypto_hash(state);
+            }
+        }
+    }
+}
+
+impl Display for AuditDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Commit(reference) => write!(f, "Commit({reference})"),
+            Self::Skip(a, r) => write!(f, "Skip({})", format_authority_round(*a, *r)),
+        }
+    }
+}
+
+/// An entry of the audit log: a commit decision, the blocks supporting it (the certificates of a
+/// committed leader or the blames of a skipped leader), and the link to the previous entry.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// The position of the entry in the log.
+    pub height: u64,
+    pub decision: AuditDecision,
+    pub support: Vec<BlockReference>,
+    /// The digest of the previous entry (or the genesis digest for the first entry).
+    pub previous: AuditDigest,
+    /// The digest of all the fields above.
+    pub digest: AuditDigest,
+}
+
+impl AuditEntry {
+    fn new(
+        height: u64,
+        decision: AuditDecision,
+        support: Vec<BlockReference>,
+        previous: AuditDigest,
+    ) -> Self {
+        let digest = Self::compute_digest(height, &decision, &support, &previous);
+        Self {
+            height,
+            decision,
+            support,
+            previous,
+            digest,
+        }
+    }
+
+    fn compute_digest(
+        height: u64,
+        decision: &AuditDecision,
+        support: &[BlockReference],
+        previous: &AuditDigest,
+    ) -> AuditDigest {
+        let mut hasher = AuditHasher::default();
+        height.crypto_hash(&mut hasher);
+        decision.crypto_hash(&mut hasher);
+        (support.len() as u64).crypto_hash(&mut hasher);
+        for reference in support {
+            reference.crypto_hash(&mut hasher);
+        }
+        hasher.update(previous);
+        hasher.finalize().into()
+    }
+}
+
+/// The reason why an audit log failed verification.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AuditError {
+    /// The entry is not at its expected position in the log.
+    InvalidHeight(u64),
+    /// The entry does not point to the digest of the previous entry.
+    BrokenLink(u64),
+    /// The content of the entry does not match its digest.
+    InvalidDigest(u64),
+}
+
+impl Display for AuditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidHeight(height) => write!(f, "Entry {height} is out of place"),
+            Self::BrokenLink(height) => write!(f, "Entry {height} breaks the chain"),
+            Self::InvalidDigest(height) => write!(f, "Entry {height} does not match its digest"),
+        }
+    }
+}
+
+/// A tamper-evident log of all the commit decisions of the committer. Consecutive decisions are
+/// linked through a hash chain so the log can be exported and verified offline. A persisted log
+/// appends every new entry to its file, written as a sequence of length-prefixed (bincode)
+/// entries.
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+    /// The file to which new entries are appended (if the log is persisted).
+    file: Option<BufWriter<File>>,
+}
+
+impl AuditLog {
+    /// Rebuild a log from its entries (for instance to verify an exported log offline).
+    pub fn from_entries(entries: Vec<AuditEntry>) -> Self {
+        Self {
+            entries,
+            file: None,
+        }
+    }
+
+    /// Open the log persisted at the specified path (creating it if needed), and append all new
+    /// entries to it. An entry partially written when the node crashed is discarded. Fails if
+    /// the existing entries do not verify, since extending them would hide the tampering.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let (entries, length) = read_entries(BufReader::new(&file))?;
+        file.set_len(length)?;
+        let log = Self {
+            entries,
+            file: Some(BufWriter::new(file)),
+        };
+        log.verify()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        Ok(log)
+    }
+
+    /// Read back a log persisted with [`Self::open`] or written with [`Self::export`]. The log
+    /// is not verified, see [`Self::verify`].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let (entries, _) = read_entries(BufReader::new(File::open(path)?))?;
+        Ok(Self::from_entries(entries))
+    }
+
+    /// Write all the entries of the log to the specified file, to verify them offline.
+    pub fn export(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for entry in &self.entries {
+            write_entry(&mut writer, entry)?;
+        }
+        writer.flush()
+    }
+
+    /// Record a decision along with its supporting blocks. The entry is flushed to the file of
+    /// persisted logs right away so that the log reflects all decisions even if the node crashes.
+    pub fn append(&mut self, decision: AuditDecision, support: Vec<BlockReference>) {
+        let height = self.entries.len() as u64;
+        tracing::debug!("Recording decision {decision} at height {height}");
+        let entry = AuditEntry::new(height, decision, support, self.head());
+        if let Some(file) = &mut self.file {
+            write_entry(file, &entry)
+                .and_then(|_| file.flush())
+                .expect("Failed to write to the audit log");
+        }
+        self.entries.push(entry);
+    }
+
+    /// The last decision recorded in the log.
+    pub fn last_decision(&self) -> Option<&AuditDecision> {
+        self.entries.last().map(|x| &x.decision)
+    }
+
+    /// The digest of the last entry of the log.
+    pub fn head(&self) -> AuditDigest {
+        self.entries
+            .last()
+            .map(|x| x.digest)
+            .unwrap_or(GENESIS_AUDIT_DIGEST)
+    }
+
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Verify the integrity of the log: every entry must match its digest and point to the
+    /// digest of its predecessor.
+    pub fn verify(&self) -> Result<(), AuditError> {
+        let mut previous = GENESIS_AUDIT_DIGEST;
+        for (height, entry) in self.entries.iter().enumerate() {
+            if entry.height != height as u64 {
+                return Err(AuditError::InvalidHeight(height as u64));
+            }
+            if entry.previous != previous {
+                return Err(AuditError::BrokenLink(entry.height));
+            }
+            let digest = AuditEntry::compute_digest(
+                entry.height,
+                &entry.decision,
+                &entry.support,
+                &entry.previous,
+            );
+            if entry.digest != digest {
+                return Err(AuditError::InvalidDigest(entry.height));
+            }
+            previous = entry.digest;
+        }
+        Ok(())
+    }
+}
+
+fn write_entry(writer: &mut impl Write, entry: &AuditEntry) -> io::Result<()> {
+    let bytes = bincode::serialize(entry).expect("Failed to serialize audit entry");
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&bytes)
+}
+
+/// Read all the entries written with [`write_entry`], along with the length of the data they
+/// span. Reading stops at a truncated entry.
+fn read_entries(mut reader: impl Read) -> io::Result<(Vec<AuditEntry>, u64)> {
+    let (mut entries, mut length) = (Vec::new(), 0);
+    loop {
+        let mut prefix = [0; 4];
+        let mut bytes = Vec::new();
+        match reader.read_exact(&mut prefix) {
+            Ok(()) => (),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+        let size = u32::from_le_bytes(prefix) as u64;
+        if (&mut reader).take(size).read_to_end(&mut bytes)? as u64 != size {
+            break;
+        }
+        let entry = bincode::deserialize(&bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        entries.push(entry);
+        length += 4 + size;
+    }
+    Ok((entries, length))
+}
@@ -1,13 +1,16 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{fmt::Display, sync::Arc};
+use std::{collections::HashMap, fmt::Display, ops::Range, sync::Arc};
 
 use super::{LeaderStatus, DEFAULT_WAVE_LENGTH};
 use crate::{
     block_store::BlockStore,
     committee::{Committee, QuorumThreshold, StakeAggregator},
-    consensus::MINIMUM_WAVE_LENGTH,
+    consensus::{
+        leader_schedule::{LeaderSchedule, RoundRobinSchedule},
+        MINIMUM_WAVE_LENGTH,
+    },
     data::Data,
     types::{format_authority_round, AuthorityIndex, BlockReference, RoundNumber, StatementBlock},
 };
@@ -16,6 +19,7 @@ use crate::{
 /// voting round, and one decision round.
 type WaveNumber = u64;
 
+#[derive(Clone)]
 pub struct BaseCommitterOptions {
     /// The length of a wave (minimum 3)
     pub wave_length: u64,
@@ -47,17 +51,25 @@ pub struct BaseCommitter {
     block_store: BlockStore,
     /// The options used by this committer
     options: BaseCommitterOptions,
+    /// The schedule electing the leader of each round
+    leader_schedule: Arc<dyn LeaderSchedule>,
 }
 
 impl BaseCommitter {
     pub fn new(committee: Arc<Committee>, block_store: BlockStore) -> Self {
         Self {
+            leader_schedule: Arc::new(RoundRobinSchedule::new(committee.clone())),
             committee,
             block_store,
             options: BaseCommitterOptions::default(),
         }
     }
 
+    pub fn with_leader_schedule(mut self, leader_schedule: Arc<dyn LeaderSchedule>) -> Self {
+        self.leader_schedule = leader_schedule;
+        self
+    }
+
     pub fn with_options(mut self, options: BaseCommitterOptions) -> Self {
         assert!(options.wave_length >= MINIMUM_WAVE_LENGTH);
         self.options = options;
@@ -92,7 +104,14 @@ impl BaseCommitter {
         }
 
         let offset = self.options.leader_offset as RoundNumber;
-        Some(self.committee.elect_leader(round + offset))
+        Some(self.leader_schedule.leader(round + offset))
+    }
+
+    /// Return the leaders elected for all leader rounds within the specified range.
+    pub fn leader_schedule(&self, rounds: Range<RoundNumber>) -> Vec<AuthorityIndex> {
+        rounds
+            .filter_map(|round| self.elect_leader(round))
+            .collect()
     }
 
     /// Find which block is supported at (author, round) by the given block.
@@ -145,8 +164,21 @@ impl BaseCommitter {
         potential_certificate: &Data<StatementBlock>,
         leader_block: &Data<StatementBlock>,
     ) -> bool {
+        // Ignore the votes of authorities equivocating within the includes of the certificate.
+        // This only depends on the certificate itself, so all validators agree on its outcome.
+        let mut slots = HashMap::new();
+        for reference in potential_certificate.includes() {
+            *slots
+                .entry((reference.authority, reference.round))
+                .or_insert(0usize) += 1;
+        }
+
         let mut votes_stake_aggregator = StakeAggregator::<QuorumThreshold>::new();
         for reference in potential_certificate.includes() {
+            if slots[&(reference.authority, reference.round)] > 1 {
+                tracing::trace!("[{self}] Ignoring equivocating vote {reference}");
+                continue;
+            }
             let potential_vote = self
                 .block_store
                 .get_block(*reference)
@@ -246,6 +278,8 @@ impl BaseCommitter {
 
         let mut certificate_stake_aggregator = StakeAggregator::<QuorumThreshold>::new();
         for decision_block in &decision_blocks {
+            // The stake of an equivocating authority is counted once, however many of its blocks
+            // are certificates.
             let authority = decision_block.reference().authority;
             if self.is_certificate(decision_block, leader_block) {
                 tracing::trace!(
@@ -259,6 +293,31 @@ impl BaseCommitter {
         false
     }
 
+    /// Return the blocks supporting the specified decision: the certificates of a committed
+    /// leader, or the blames of a skipped leader.
+    pub fn supporting_blocks(&self, status: &LeaderStatus) -> Vec<BlockReference> {
+        match status {
+            LeaderStatus::Commit(leader_block) => {
+                let wave = self.wave_number(leader_block.round());
+                let decision_round = self.decision_round(wave);
+                self.block_store
+                    .get_blocks_by_round(decision_round)
+                    .iter()
+                    .filter(|block| self.is_certificate(block, leader_block))
+                    .map(|block| *block.reference())
+                    .collect()
+            }
+            LeaderStatus::Skip(leader, leader_round) => self
+                .block_store
+                .get_blocks_by_round(leader_round + 1)
+                .iter()
+                .filter(|block| block.includes().iter().all(|x| x.authority != *leader))
+                .map(|block| *block.reference())
+                .collect(),
+            LeaderStatus::Undecided(..) => Vec::new(),
+        }
+    }
+
     /// Apply the indirect decision rule to the specified leader to see whether we can indirect-commit
     /// or indirect-skip it.
     #[tracing::instrument(skip_all, fields(leader = %format_authority_round(leader, leader_round)))]
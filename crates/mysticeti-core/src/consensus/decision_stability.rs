@@ -0,0 +1,39 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{hash_map::Entry, HashMap};
+
+use parking_lot::Mutex;
+
+use super::LeaderStatus;
+use crate::types::{format_authority_round, AuthorityIndex, RoundNumber};
+
+/// Debug invariant ensuring that commit decisions never change as the dag grows. Every decided
+/// leader output by the committer is recorded; later outputs may only extend these decisions.
+#[derive(Default)]
+pub struct DecisionStability {
+    decisions: Mutex<HashMap<(RoundNumber, AuthorityIndex), LeaderStatus>>,
+}
+
+impl DecisionStability {
+    /// Record the specified decided leaders and panic if any of them contradicts a decision
+    /// previously recorded for the same leader.
+    pub fn check<'a>(&self, decided: impl IntoIterator<Item = &'a LeaderStatus>) {
+        let mut decisions = self.decisions.lock();
+        for status in decided {
+            assert!(status.is_decided(), "Only decided leaders can be checked");
+            let (round, authority) = (status.round(), status.authority());
+            match decisions.entry((round, authority)) {
+                Entry::Occupied(previous) => assert_eq!(
+                    previous.get(),
+                    status,
+                    "Decision for leader {} changed",
+                    format_authority_round(authority, round)
+                ),
+                Entry::Vacant(entry) => {
+                    entry.insert(status.clone());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{Arc, RwLock};
+
+use super::LeaderStatus;
+use crate::{
+    committee::Committee,
+    types::{AuthorityIndex, RoundNumber, Stake},
+};
+
+/// Elects the leader of each round. The committers only query the schedule for leader rounds;
+/// implementations must be deterministic so that all validators elect the same leaders.
+pub trait LeaderSchedule: Send + Sync {
+    fn leader(&self, round: RoundNumber) -> AuthorityIndex;
+
+    /// Notify the schedule of a newly decided leader, in commit order. Returns whether the
+    /// schedule changed for the rounds above the one of this leader, in which case the committer
+    /// must re-evaluate the following leaders.
+    fn on_decided(&self, _leader: &LeaderStatus) -> bool {
+        false
+    }
+
+    /// Notify the schedule that all leaders up to the specified round (the commit watermark) are
+    /// decided. The committer does not query the leaders of lower rounds anymore.
+    fn on_committed(&self, _round: RoundNumber) {}
+
+    /// Whether the schedule depends on the decided leaders (see [`Self::on_decided`]), in which
+    /// case it must be rebuilt from the committed leaders after a restart.
+    fn is_adaptive(&self) -> bool {
+        false
+    }
+
+    /// Make a schedule of the same kind for the committee of the next epoch.
+    fn for_committee(&self, committee: Arc<Committee>) -> Arc<dyn LeaderSchedule>;
+}
+
+/// The default schedule, rotating the leader through all authorities of the committee regardless
+/// of their stake.
+pub struct RoundRobinSchedule {
+    committee: Arc<Committee>,
+}
+
+impl RoundRobinSchedule {
+    pub fn new(committee: Arc<Committee>) -> Self {
+        Self { committee }
+    }
+}
+
+impl LeaderSchedule for RoundRobinSchedule {
+    fn leader(&self, round: RoundNumber) -> AuthorityIndex {
+        self.committee.elect_leader(round)
+    }
+
+    fn for_committee(&self, committee: Arc<Committee>) -> Arc<dyn LeaderSchedule> {
+        Arc::new(Self::new(committee))
+    }
+}
+
+/// A schedule electing leaders proportionally to their stake. The leader of a round is the
+/// authority owning the position of that round on the cumulative stake of the committee, where
+/// the positions follow a golden-ratio (low-discrepancy) sequence: the turns of each authority are
+/// spread over the rounds and their frequency quickly converges to its share of the stake. The
+/// schedule only depends on the committee and elects a leader in O(log n).
+pub struct StakeWeightedSchedule {
+    /// The cumulative stake of the authorities, in the order of their index.
+    cumulative_stakes: Vec<Stake>,
+}
+
+impl StakeWeightedSchedule {
+    /// The fractional part of the golden ratio, in units of 2^-64.
+    const GOLDEN_RATIO: u64 = 0x9e37_79b9_7f4a_7c15;
+
+    pub fn new(committee: Arc<Committee>) -> Self {
+        let cumulative_stakes = committee
+            .authorities()
+            .scan(0, |total, authority| {
+                *total += committee.get_stake(authority).unwrap();
+                Some(*total)
+            })
+            .collect();
+        Self { cumulative_stakes }
+    }
+}
+
+impl LeaderSchedule for StakeWeightedSchedule {
+    fn leader(&self, round: RoundNumber) -> AuthorityIndex {
+        let total_stake = *self.cumulative_stakes.last().unwrap();
+        let fraction = round.wrapping_mul(Self::GOLDEN_RATIO);
+        let position = ((fraction as u128 * total_stake as u128) >> 64) as Stake;
+        self.cumulative_stakes
+            .partition_point(|stake| *stake <= position) as AuthorityIndex
+    }
+
+    fn for_committee(&self, committee: Arc<Committee>) -> Arc<dyn LeaderSchedule> {
+        Arc::new(Self::new(committee))
+    }
+}
+
+/// The number of committed and skipped leader slots of each authority.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReputationScores {
+    commits: Vec<u64>,
+    skips: Vec<u64>,
+}
+
+impl ReputationScores {
+    pub fn new(committee_size: usize) -> Self {
+        Self {
+            commits: vec![0; committee_size],
+            skips: vec![0; committee_size],
+        }
+    }
+
+    /// Record the outcome of a decided leader.
+    pub fn update(&mut self, leader: &LeaderStatus) {
+        let authority = leader.authority() as usize;
+        match leader {
+            LeaderStatus::Commit(..) => self.commits[authority] += 1,
+            LeaderStatus::Skip(..) => self.skips[authority] += 1,
+            LeaderStatus::Undecided(..) => (),
+        }
+    }
+
+    pub fn commits(&self, authority: AuthorityIndex) -> u64 {
+        self.commits[authority as usize]
+    }
+
+    pub fn skips(&self, authority: AuthorityIndex) -> u64 {
+        self.skips[authority as usize]
+    }
+
+    /// The reputation of an authority: its number of committed minus skipped leader slots.
+    pub fn score(&self, authority: AuthorityIndex) -> i64 {
+        self.commits(authority) as i64 - self.skips(authority) as i64
+    }
+}
+
+/// A round-robin schedule in which the authorities with the lowest reputation are periodically
+/// replaced by the ones with the highest reputation. The scores are only derived from the
+/// sequence of decided leaders, so all validators compute the same schedule.
+pub struct ReputationSchedule {
+    committee: Arc<Committee>,
+    /// The number of decided leaders between two updates of the schedule.
+    update_interval: usize,
+    inner: RwLock<ReputationScheduleInner>,
+}
+
+struct ReputationScheduleInner {
+    scores: ReputationScores,
+    /// The round of the last leader notified to the schedule, and all the leaders notified for
+    /// that round (to ignore duplicate notifications).
+    last_decided_round: RoundNumber,
+    last_decided_leaders: Vec<AuthorityIndex>,
+    /// The number of leaders decided since the last update.
+    decided_since_update: usize,
+    /// Each entry holds the first round from which it applies and the sequence of authorities
+    /// to rotate through from that round, ordered by first round. The tables only applying below
+    /// the commit watermark are dropped.
+    tables: Vec<(RoundNumber, Vec<AuthorityIndex>)>,
+}
+
+impl ReputationSchedule {
+    pub fn new(committee: Arc<Committee>, update_interval: usize) -> Self {
+        assert!(update_interval > 0);
+        let inner = ReputationScheduleInner {
+            scores: ReputationScores::new(committee.len()),
+            last_decided_round: 0,
+            last_decided_leaders: Vec::new(),
+            decided_since_update: 0,
+            tables: Vec::new(),
+        };
+        Self {
+            committee,
+            update_interval,
+            inner: RwLock::new(inner),
+        }
+    }
+
+    /// The current reputation scores.
+    pub fn scores(&self) -> ReputationScores {
+        self.inner.read().unwrap().scores.clone()
+    }
+
+    /// Build the sequence of leaders following the specified scores: each of the (at most f)
+    /// worst authorities is replaced by one of the best authorities, if it has a strictly lower
+    /// score.
+    fn table(&self, scores: &ReputationScores) -> Vec<AuthorityIndex> {
+        let mut table: Vec<_> = self.committee.authorities().collect();
+        let mut ranked = table.clone();
+        ranked.sort_by_key(|authority| (-scores.score(*authority), *authority));
+
+        let max_swaps = (self.committee.len() - 1) / 3;
+        let best = ranked.iter().take(max_swaps);
+        let worst = ranked.iter().rev().take(max_swaps);
+        for (good, bad) in best.zip(worst) {
+            if scores.score(*bad) < scores.score(*good) {
+                table[*bad as usize] = *good;
+            }
+        }
+        table
+    }
+}
+
+impl LeaderSchedule for ReputationSchedule {
+    fn leader(&self, round: RoundNumber) -> AuthorityIndex {
+        let inner = self.inner.read().unwrap();
+        // The last table applying from this round or below.
+        let applying = inner.tables.partition_point(|(first, _)| *first <= round);
+        match applying.checked_sub(1) {
+            Some(index) => {
+                let table = &inner.tables[index].1;
+                table[(round % table.len() as RoundNumber) as usize]
+            }
+            None => self.committee.elect_leader(round),
+        }
+    }
+
+    fn on_decided(&self, leader: &LeaderStatus) -> bool {
+        let mut inner = self.inner.write().unwrap();
+        let (round, authority) = (leader.round(), leader.authority());
+        if round < inner.last_decided_round
+            || (round == inner.last_decided_round
+                && inner.last_decided_leaders.contains(&authority))
+        {
+            return false;
+        }
+        if round > inner.last_decided_round {
+            inner.last_decided_round = round;
+            inner.last_decided_leaders.clear();
+        }
+        inner.last_decided_leaders.push(authority);
+        inner.scores.update(leader);
+
+        inner.decided_since_update += 1;
+        if inner.decided_since_update < self.update_interval {
+            return false;
+        }
+        inner.decided_since_update = 0;
+
+        let table = self.table(&inner.scores);
+        let changed = match inner.tables.last() {
+            Some((_, last)) => last != &table,
+            None => table != self.committee.authorities().collect::<Vec<_>>(),
+        };
+        if changed {
+            inner.tables.push((leader.round() + 1, table));
+        }
+        changed
+    }
+
+    fn on_committed(&self, round: RoundNumber) {
+        let mut inner = self.inner.write().unwrap();
+        // Keep the table applying to the commit watermark and the ones above it.
+        let applying = inner.tables.partition_point(|(first, _)| *first <= round);
+        inner.tables.drain(..applying.saturating_sub(1));
+    }
+
+    fn is_adaptive(&self) -> bool {
+        true
+    }
+
+    /// The reputation scores only cover the authorities of the current committee: the schedule
+    /// of the next epoch starts afresh.
+    fn for_committee(&self, committee: Arc<Committee>) -> Arc<dyn LeaderSchedule> {
+        Arc::new(Self::new(committee, self.update_interval))
+    }
+}
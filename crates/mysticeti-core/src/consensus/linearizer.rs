@@ -5,14 +5,15 @@ use std::{collections::HashSet, fmt};
 
 use crate::{
     block_store::BlockStore,
+    consensus::GC_DEPTH,
     data::Data,
-    types::{BlockReference, StatementBlock},
+    types::{BlockReference, RoundNumber, StatementBlock},
 };
 
 /// The output of consensus is an ordered list of [`CommittedSubDag`]. The application can arbitrarily
 /// sort the blocks within each sub-dag (but using a deterministic algorithm).
 pub struct CommittedSubDag {
-    /// A reference to the anchor of the sub-dag
+    /// A reference to the anchor of the sub-dag (the committed leader)
     pub anchor: BlockReference,
     /// All the committed blocks that are part of this sub-dag
     pub blocks: Vec<Data<StatementBlock>>,
@@ -24,9 +25,11 @@ impl CommittedSubDag {
         Self { anchor, blocks }
     }
 
-    /// Sort the blocks of the sub-dag by round number. Any deterministic algorithm works.
+    /// Sort the blocks of the sub-dag by round number, breaking ties by authority and digest.
+    /// Any deterministic algorithm works, but it should not depend on the traversal order.
     pub fn sort(&mut self) {
-        self.blocks.sort_by_key(|x| x.round());
+        self.blocks
+            .sort_by_key(|x| (x.round(), x.author(), x.digest()));
     }
 }
 
@@ -35,6 +38,8 @@ impl CommittedSubDag {
 pub struct Linearizer {
     /// Keep track of all committed blocks to avoid committing the same block twice.
     pub committed: HashSet<BlockReference>,
+    /// The gc round of the last committed leader (see [`Linearizer::gc_round`]).
+    gc_round: RoundNumber,
 }
 
 impl Linearizer {
@@ -42,6 +47,13 @@ impl Linearizer {
         Self::default()
     }
 
+    /// The round below which the blocks are not committed along with the specified leader. It
+    /// only depends on the round of the leader, so that all validators linearize the same
+    /// sub-dag regardless of the blocks they pruned locally.
+    pub fn gc_round(leader: &BlockReference) -> RoundNumber {
+        leader.round.saturating_sub(GC_DEPTH)
+    }
+
     /// Collect the sub-dag from a specific anchor excluding any duplicates or blocks that
     /// have already been committed (within previous sub-dags).
     fn collect_sub_dag(
@@ -52,19 +64,22 @@ impl Linearizer {
         let mut to_commit = Vec::new();
 
         let leader_block_ref = *leader_block.reference();
+        let gc_round = Self::gc_round(&leader_block_ref);
         let mut buffer = vec![leader_block];
         assert!(self.committed.insert(leader_block_ref));
         while let Some(x) = buffer.pop() {
             to_commit.push(x.clone());
             for reference in x.includes() {
-                // The block manager may have cleaned up blocks passed the latest committed rounds.
-                let block = block_store
-                    .get_block(*reference)
-                    .expect("We should have the whole sub-dag by now");
-
+                // Never traverse into garbage collected (and possibly pruned) history.
+                if reference.round < gc_round {
+                    continue;
+                }
                 // Skip the block if we already committed it (either as part of this sub-dag or
                 // a previous one).
                 if self.committed.insert(*reference) {
+                    let block = block_store
+                        .get_block(*reference)
+                        .expect("We should have the whole sub-dag by now");
                     buffer.push(block);
                 }
             }
@@ -79,6 +94,15 @@ impl Linearizer {
     ) -> Vec<CommittedSubDag> {
         let mut committed = vec![];
         for leader_block in committed_leaders {
+            // Blocks below the gc round are never traversed again (leaders are committed in
+            // round order), so there is no need to remember them.
+            let gc_round = Self::gc_round(leader_block.reference());
+            if gc_round > self.gc_round {
+                self.committed
+                    .retain(|reference| reference.round >= gc_round);
+                self.gc_round = gc_round;
+            }
+
             // Collect the sub-dag generated using each of these leaders as anchor.
             let mut sub_dag = self.collect_sub_dag(block_store, leader_block);
 
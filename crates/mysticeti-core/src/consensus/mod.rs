@@ -8,7 +8,11 @@ use crate::{
     types::{format_authority_round, AuthorityIndex, RoundNumber, StatementBlock},
 };
 
+pub mod audit_log;
 pub mod base_committer;
+#[cfg(any(test, feature = "decision-stability"))]
+pub mod decision_stability;
+pub mod leader_schedule;
 pub mod linearizer;
 pub mod universal_committer;
 
@@ -22,6 +26,12 @@ pub const DEFAULT_WAVE_LENGTH: RoundNumber = MINIMUM_WAVE_LENGTH;
 /// We need at least one leader round, one voting round, and one decision round.
 pub const MINIMUM_WAVE_LENGTH: RoundNumber = 3;
 
+/// The number of rounds below a committed leader whose blocks can still be committed along with
+/// it. Older blocks are garbage collected: they are never committed, so they can be pruned from
+/// the block store. This must be the same for all validators (it decides which blocks each
+/// sub-dag contains), so it is a protocol constant rather than a node parameter.
+pub const GC_DEPTH: RoundNumber = 50;
+
 /// The status of every leader output by the committers. While the core only cares about committed
 /// leaders, providing a richer status allows for easier debugging, testing, and composition with
 /// advanced commit strategies.
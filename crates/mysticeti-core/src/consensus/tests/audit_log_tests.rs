@@ -0,0 +1,150 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fs::OpenOptions, io::Write};
+
+use crate::{
+    block_store::BlockStore,
+    committee::Committee,
+    consensus::{
+        audit_log::{AuditDecision, AuditError, AuditLog},
+        universal_committer::{UniversalCommitter, UniversalCommitterBuilder},
+        DEFAULT_WAVE_LENGTH,
+    },
+    test_util::{build_dag, committee, test_metrics, TestBlockWriter},
+    types::BlockReference,
+};
+
+/// Make a committer recording its decisions in the specified audit log.
+fn audit_committer(
+    committee: &std::sync::Arc<Committee>,
+    block_store: BlockStore,
+    audit_log: AuditLog,
+) -> UniversalCommitter {
+    UniversalCommitterBuilder::new(committee.clone(), block_store, test_metrics())
+        .with_wave_length(DEFAULT_WAVE_LENGTH)
+        .with_audit_log(audit_log)
+        .build()
+}
+
+/// Commit a few leaders and return the resulting audit log.
+fn audit_log() -> AuditLog {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let decision_round_3 = 4 * wave_length - 1;
+    build_dag(&committee, &mut block_writer, None, decision_round_3);
+
+    let block_store = block_writer.into_block_store();
+    let committer = audit_committer(&committee, block_store, AuditLog::default());
+
+    // Decisions output more than once are only recorded once.
+    let last_committed = BlockReference::new_test(0, 0);
+    let sequence = committer.try_commit(last_committed);
+    assert_eq!(sequence.len(), 3);
+    committer.try_commit(last_committed);
+
+    let audit_log = committer.audit_log().unwrap();
+    AuditLog::from_entries(audit_log.entries().to_vec())
+}
+
+/// The decisions of the committer are recorded in a valid hash chain.
+#[test]
+#[tracing_test::traced_test]
+fn audit_log_verifies() {
+    let audit_log = audit_log();
+    assert_eq!(audit_log.len(), 3);
+    assert_eq!(audit_log.verify(), Ok(()));
+
+    for (entry, round) in audit_log.entries().iter().zip([3, 6, 9]) {
+        assert!(matches!(entry.decision, AuditDecision::Commit(..)));
+        assert_eq!(entry.decision.round(), round);
+        // All the blocks of the decision round certify the leader.
+        assert_eq!(entry.support.len(), 4);
+        assert!(entry.support.iter().all(|x| x.round == round + 2));
+    }
+    for pair in audit_log.entries().windows(2) {
+        assert_eq!(pair[1].previous, pair[0].digest);
+    }
+}
+
+/// Tampering with an exported audit log is detected by the verifier.
+#[test]
+#[tracing_test::traced_test]
+fn audit_log_detects_tampering() {
+    let dir = tempdir::TempDir::new("test_audit_log").unwrap();
+    let path = dir.path().join("audit.log");
+    audit_log().export(&path).unwrap();
+    let imported = AuditLog::load(&path).unwrap();
+    assert_eq!(imported.len(), 3);
+    assert_eq!(imported.verify(), Ok(()));
+
+    // Drop one of the certificates supporting the second decision.
+    let mut entries = imported.entries().to_vec();
+    entries[1].support.pop();
+    let tampered = AuditLog::from_entries(entries);
+    assert_eq!(tampered.verify(), Err(AuditError::InvalidDigest(1)));
+
+    // A node never extends a tampered log.
+    tampered.export(&path).unwrap();
+    assert!(AuditLog::open(&path).is_err());
+
+    // Forge the second decision with a valid digest: the next entry no longer links to it.
+    let mut entries = imported.entries().to_vec();
+    let mut forged = AuditLog::from_entries(entries[..1].to_vec());
+    forged.append(
+        AuditDecision::Skip(entries[1].decision.authority(), 6),
+        Vec::new(),
+    );
+    entries[1] = forged.entries()[1].clone();
+    let tampered = AuditLog::from_entries(entries);
+    assert_eq!(tampered.verify(), Err(AuditError::BrokenLink(2)));
+}
+
+/// The audit log is persisted across restarts, without recording any decision twice.
+#[test]
+#[tracing_test::traced_test]
+fn audit_log_persists() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+    let dir = tempdir::TempDir::new("test_audit_log").unwrap();
+    let path = dir.path().join("audit.log");
+    let genesis = BlockReference::new_test(0, 0);
+
+    // Decide the leaders of the first two waves.
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let references = build_dag(&committee, &mut block_writer, None, 3 * wave_length - 1);
+    let audit_log = AuditLog::open(&path).unwrap();
+    let committer = audit_committer(&committee, block_writer.block_store(), audit_log);
+    assert_eq!(committer.try_commit(genesis).len(), 2);
+    drop(committer);
+
+    // The node crashes while writing an entry, then restarts and decides the third leader.
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[100, 0, 0, 0, 1, 2]).unwrap();
+    drop(file);
+    build_dag(
+        &committee,
+        &mut block_writer,
+        Some(references),
+        4 * wave_length - 1,
+    );
+    let audit_log = AuditLog::open(&path).unwrap();
+    assert_eq!(audit_log.len(), 2);
+    let committer = audit_committer(&committee, block_writer.block_store(), audit_log);
+    let sequence = committer.try_commit(genesis);
+    assert_eq!(sequence.len(), 3);
+    let last_committed = sequence[1].clone().into_decided_block().unwrap();
+    committer.try_commit(*last_committed.reference());
+    drop(committer);
+
+    let audit_log = AuditLog::load(&path).unwrap();
+    assert_eq!(audit_log.verify(), Ok(()));
+    let rounds: Vec<_> = audit_log
+        .entries()
+        .iter()
+        .map(|x| x.decision.round())
+        .collect();
+    assert_eq!(rounds, [3, 6, 9]);
+}
@@ -1,14 +1,26 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use prometheus::core::Metric;
+
 use crate::{
+    committee::Committee,
     consensus::{
+        base_committer::{BaseCommitter, BaseCommitterOptions},
         universal_committer::UniversalCommitterBuilder,
         LeaderStatus,
         DEFAULT_WAVE_LENGTH,
     },
+    crypto::compute_test_digests,
+    data::Data,
+    runtime,
     test_util::{build_dag, build_dag_layer, committee, test_metrics, TestBlockWriter},
-    types::BlockReference,
+    types::{BlockReference, StatementBlock},
 };
 
 /// Commit one leader.
@@ -219,6 +231,64 @@ fn no_leader() {
     }
 }
 
+/// A missing leader is skipped and does not stall the commit of the following leaders.
+#[test]
+#[tracing_test::traced_test]
+fn missing_leader_does_not_stall() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+
+    // Add enough blocks to finish wave 0.
+    let decision_round_0 = wave_length - 1;
+    let references = build_dag(&committee, &mut block_writer, None, decision_round_0);
+
+    // Add the round of the first leader (but without the leader).
+    let leader_round_1 = wave_length;
+    let leader_1 = committee.elect_leader(leader_round_1);
+
+    let connections = committee
+        .authorities()
+        .filter(|&authority| authority != leader_1)
+        .map(|authority| (authority, references.clone()));
+    let references = build_dag_layer(connections.collect(), &mut block_writer);
+
+    // Add enough blocks to reach the decision round of the 3rd leader.
+    let decision_round_3 = 4 * wave_length - 1;
+    build_dag(
+        &committee,
+        &mut block_writer,
+        Some(references),
+        decision_round_3,
+    );
+
+    // Ensure the first leader is skipped and the next ones are committed.
+    let committer = UniversalCommitterBuilder::new(
+        committee.clone(),
+        block_writer.into_block_store(),
+        test_metrics(),
+    )
+    .with_wave_length(wave_length)
+    .build();
+
+    let last_committed = BlockReference::new_test(0, 0);
+    let sequence = committer.try_commit(last_committed);
+    tracing::info!("Commit sequence: {sequence:?}");
+
+    assert_eq!(sequence.len(), 3);
+    assert_eq!(sequence[0], LeaderStatus::Skip(leader_1, leader_round_1));
+    for (i, status) in sequence[1..].iter().enumerate() {
+        let leader_round = (i as u64 + 2) * wave_length;
+        if let LeaderStatus::Commit(block) = status {
+            assert_eq!(block.author(), committee.elect_leader(leader_round));
+            assert_eq!(block.round(), leader_round);
+        } else {
+            panic!("Expected a committed leader");
+        }
+    }
+}
+
 /// We directly skip the leader if it has enough blame.
 #[test]
 #[tracing_test::traced_test]
@@ -515,3 +585,500 @@ fn undecided() {
     tracing::info!("Commit sequence: {sequence:?}");
     assert!(sequence.is_empty());
 }
+
+/// The direct rule uses the round offset to locate the voting and decision rounds of the leader.
+#[test]
+#[tracing_test::traced_test]
+fn direct_decide_with_round_offset() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+    let round_offset = 1;
+
+    // The first leader of a committer with round offset 1 is at round `wave_length + 1`.
+    let leader_round = wave_length + round_offset;
+    let decision_round = leader_round + wave_length - 1;
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let references = build_dag(&committee, &mut block_writer, None, decision_round - 1);
+
+    let options = BaseCommitterOptions {
+        wave_length,
+        round_offset,
+        ..Default::default()
+    };
+    let committer =
+        BaseCommitter::new(committee.clone(), block_writer.block_store()).with_options(options);
+    let leader = committer.elect_leader(leader_round).unwrap();
+
+    // The leader is undecided as long as the decision round is missing.
+    let status = committer.try_direct_decide(leader, leader_round);
+    assert_eq!(status, LeaderStatus::Undecided(leader, leader_round));
+
+    // The leader is committed once 2f+1 blocks of the decision round certify it.
+    build_dag(
+        &committee,
+        &mut block_writer,
+        Some(references),
+        decision_round,
+    );
+
+    let status = committer.try_direct_decide(leader, leader_round);
+    if let LeaderStatus::Commit(ref block) = status {
+        assert_eq!(block.author_round(), (leader, leader_round));
+    } else {
+        panic!("Expected to directly commit the leader, got {status}");
+    }
+}
+
+/// The direct rule skips a leader with round offset if the voting round blames it.
+#[test]
+#[tracing_test::traced_test]
+fn direct_skip_with_round_offset() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+    let round_offset = 1;
+
+    let leader_round = wave_length + round_offset;
+    let decision_round = leader_round + wave_length - 1;
+    let leader = committee.elect_leader(leader_round);
+
+    // Build a dag where no block of the voting round links to the leader.
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let references_without_leader: Vec<_> =
+        build_dag(&committee, &mut block_writer, None, leader_round)
+            .into_iter()
+            .filter(|x| x.authority != leader)
+            .collect();
+    build_dag(
+        &committee,
+        &mut block_writer,
+        Some(references_without_leader),
+        decision_round,
+    );
+
+    let options = BaseCommitterOptions {
+        wave_length,
+        round_offset,
+        ..Default::default()
+    };
+    let committer = BaseCommitter::new(committee.clone(), block_writer.into_block_store())
+        .with_options(options);
+    assert_eq!(committer.elect_leader(leader_round), Some(leader));
+
+    let status = committer.try_direct_decide(leader, leader_round);
+    assert_eq!(status, LeaderStatus::Skip(leader, leader_round));
+}
+
+/// The indirect rule decides a leader from the first committed anchor, skipping over the skipped
+/// anchors and stopping at the first undecided one.
+#[test]
+#[tracing_test::traced_test]
+fn indirect_decide_from_anchor() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+
+    // Add enough blocks to reach the voting round of the 1st leader.
+    let leader_round_1 = wave_length;
+    let voting_round_1 = leader_round_1 + 1;
+    let references = build_dag(&committee, &mut block_writer, None, voting_round_1);
+
+    // A single validator certifies the 1st leader.
+    let authority = committee.authorities().next().unwrap();
+    let certificate = build_dag_layer(vec![(authority, references)], &mut block_writer);
+
+    // Add enough blocks to decide the 2nd leader. Its blocks only link to that certificate.
+    let leader_round_2 = 2 * wave_length;
+    let decision_round_2 = 3 * wave_length - 1;
+    build_dag(
+        &committee,
+        &mut block_writer,
+        Some(certificate),
+        decision_round_2,
+    );
+
+    let committer = BaseCommitter::new(committee.clone(), block_writer.into_block_store());
+    let leader_1 = committee.elect_leader(leader_round_1);
+    let leader_2 = committee.elect_leader(leader_round_2);
+
+    // The 1st leader does not have enough support to be directly decided.
+    let status = committer.try_direct_decide(leader_1, leader_round_1);
+    assert_eq!(status, LeaderStatus::Undecided(leader_1, leader_round_1));
+
+    // The 2nd leader is directly committed and serves as anchor.
+    let anchor = committer.try_direct_decide(leader_2, leader_round_2);
+    assert!(matches!(anchor, LeaderStatus::Commit(..)));
+
+    // We cannot decide the 1st leader if an undecided leader precedes the anchor.
+    let undecided = LeaderStatus::Undecided(leader_1, leader_round_2);
+    let anchors = [&undecided, &anchor];
+    let status = committer.try_indirect_decide(leader_1, leader_round_1, anchors.into_iter());
+    assert_eq!(status, LeaderStatus::Undecided(leader_1, leader_round_1));
+
+    // The 1st leader is indirectly committed through the certified link from the anchor.
+    let skipped = LeaderStatus::Skip(leader_1, leader_round_2);
+    let anchors = [&skipped, &anchor];
+    let status = committer.try_indirect_decide(leader_1, leader_round_1, anchors.into_iter());
+    if let LeaderStatus::Commit(ref block) = status {
+        assert_eq!(block.author_round(), (leader_1, leader_round_1));
+    } else {
+        panic!("Expected to indirectly commit the leader, got {status}");
+    }
+}
+
+/// Equivocating blocks of the decision round are reported, and their author counts once toward
+/// the support of the leader.
+#[test]
+#[tracing_test::traced_test]
+fn equivocating_certificates() {
+    compute_test_digests();
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+    let leader_round = wave_length;
+    let decision_round = 2 * wave_length - 1;
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let references = build_dag(&committee, &mut block_writer, None, decision_round - 1);
+
+    // Authorities 0, 1, and 2 certify the leader, but authority 1 proposes two conflicting
+    // blocks at the decision round.
+    let connections = (0..3).map(|authority| (authority, references.clone()));
+    build_dag_layer(connections.collect(), &mut block_writer);
+    let conflicting = Data::new(StatementBlock::new(
+        1,
+        decision_round,
+        references.clone(),
+        vec![],
+        1,
+        false,
+        Default::default(),
+    ));
+    block_writer.add_block(conflicting.clone());
+
+    let block_store = block_writer.block_store();
+    let equivocations = block_store.equivocations();
+    assert_eq!(equivocations.len(), 1);
+    let (authority, round, [_, digest]) = equivocations[0];
+    assert_eq!((authority, round), (1, decision_round));
+    assert_eq!(digest, conflicting.digest());
+    assert!(matches!(
+        block_store.get_own_blocks_by_round_authority(decision_round, 1),
+        Err(references) if references.len() == 2 && references.contains(conflicting.reference())
+    ));
+    assert!(block_store
+        .get_own_blocks_by_round_authority(decision_round, 0)
+        .unwrap()
+        .is_some());
+
+    // The certificates of authorities 0, 1, and 2 form a quorum.
+    let committer = BaseCommitter::new(committee.clone(), block_store);
+    let leader = committer.elect_leader(leader_round).unwrap();
+    let status = committer.try_direct_decide(leader, leader_round);
+    if let LeaderStatus::Commit(ref block) = status {
+        assert_eq!(block.author_round(), (leader, leader_round));
+    } else {
+        panic!("Expected to directly commit the leader, got {status}");
+    }
+}
+
+/// Validators that received different blocks of an equivocating authority reach the same
+/// decision: the direct rule only depends on the certificates, not on the equivocations known
+/// locally.
+#[test]
+#[tracing_test::traced_test]
+fn equivocation_views() {
+    compute_test_digests();
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+    let leader_round = wave_length;
+    let decision_round = 2 * wave_length - 1;
+
+    // Both validators receive the certificates of authorities 0, 1, and 2.
+    let mut writers = [
+        TestBlockWriter::new(&committee),
+        TestBlockWriter::new(&committee),
+    ];
+    for block_writer in &mut writers {
+        let references = build_dag(&committee, block_writer, None, decision_round - 1);
+        let connections = (0..3).map(|authority| (authority, references.clone()));
+        build_dag_layer(connections.collect(), block_writer);
+    }
+
+    // Only the second validator receives another block of authority 1 at the decision round.
+    let references = writers[1]
+        .block_store()
+        .get_blocks_by_round(decision_round - 1)
+        .iter()
+        .map(|block| *block.reference())
+        .collect();
+    let conflicting = Data::new(StatementBlock::new(
+        1,
+        decision_round,
+        references,
+        vec![],
+        1,
+        false,
+        Default::default(),
+    ));
+    writers[1].add_block(conflicting);
+    assert!(writers[0].block_store().equivocations().is_empty());
+    assert_eq!(writers[1].block_store().equivocations().len(), 1);
+
+    let decisions: Vec<_> = writers
+        .iter()
+        .map(|block_writer| {
+            let committer = BaseCommitter::new(committee.clone(), block_writer.block_store());
+            let leader = committer.elect_leader(leader_round).unwrap();
+            committer.try_direct_decide(leader, leader_round)
+        })
+        .collect();
+    assert!(matches!(decisions[0], LeaderStatus::Commit(..)));
+    assert_eq!(decisions[0], decisions[1]);
+}
+
+/// Votes of authorities equivocating within the includes of a certificate are ignored.
+#[test]
+#[tracing_test::traced_test]
+fn equivocating_votes() {
+    compute_test_digests();
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+    let leader_round = wave_length;
+    let voting_round = leader_round + 1;
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let references = build_dag(&committee, &mut block_writer, None, leader_round);
+
+    // Authorities 0, 1, and 2 vote for the leader, and authority 1 equivocates.
+    let connections = (0..3).map(|authority| (authority, references.clone()));
+    let mut votes = build_dag_layer(connections.collect(), &mut block_writer);
+    let conflicting = Data::new(StatementBlock::new(
+        1,
+        voting_round,
+        references,
+        vec![],
+        1,
+        false,
+        Default::default(),
+    ));
+    votes.push(*conflicting.reference());
+    block_writer.add_block(conflicting);
+
+    // All authorities link to all the votes, so none of their blocks is a certificate.
+    let connections = (0..4).map(|authority| (authority, votes.clone()));
+    build_dag_layer(connections.collect(), &mut block_writer);
+
+    let committer = BaseCommitter::new(committee.clone(), block_writer.block_store());
+    let leader = committer.elect_leader(leader_round).unwrap();
+    let status = committer.try_direct_decide(leader, leader_round);
+    assert_eq!(status, LeaderStatus::Undecided(leader, leader_round));
+}
+
+/// Committing a leader records the number of rounds elapsed since its creation.
+#[test]
+#[tracing_test::traced_test]
+fn commit_latency_metrics() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+    let leader_round = wave_length;
+    let decision_round = 2 * wave_length - 1;
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+    build_dag(&committee, &mut block_writer, None, decision_round);
+
+    let metrics = test_metrics();
+    let committer = UniversalCommitterBuilder::new(
+        committee.clone(),
+        block_writer.into_block_store(),
+        metrics.clone(),
+    )
+    .with_wave_length(wave_length)
+    .build();
+
+    let last_committed = BlockReference::new_test(0, 0);
+    let sequence = committer.try_commit(last_committed);
+    assert_eq!(sequence.len(), 1);
+
+    // The leader is committed at the decision round.
+    let histogram = metrics.commit_latency_rounds.metric();
+    let histogram = histogram.get_histogram();
+    let latency = decision_round - leader_round;
+    assert_eq!(histogram.get_sample_count(), 1);
+    assert_eq!(histogram.get_sample_sum(), latency as f64);
+    for bucket in histogram.get_bucket() {
+        let expected = if bucket.get_upper_bound() >= latency as f64 {
+            1
+        } else {
+            0
+        };
+        assert_eq!(bucket.get_cumulative_count(), expected);
+    }
+    assert_eq!(metrics.commit_latency_seconds.get_sample_count(), 1);
+
+    // Trying to commit again does not record the latency of the same leader twice, whether
+    // the caller moved past it or not.
+    let leader = sequence[0].clone().into_decided_block().unwrap();
+    assert!(committer.try_commit(*leader.reference()).is_empty());
+    assert_eq!(committer.try_commit(last_committed).len(), 1);
+    let histogram = metrics.commit_latency_rounds.metric();
+    assert_eq!(histogram.get_histogram().get_sample_count(), 1);
+}
+
+/// The commit latency of each leader is measured from the creation time of its block.
+#[test]
+#[tracing_test::traced_test]
+fn commit_latency_seconds() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+    let mut block_writer = TestBlockWriter::new(&committee);
+
+    // Create the blocks of two leader rounds a known time before the commit.
+    let ages = [Duration::from_secs(2), Duration::from_millis(500)];
+    let mut references = build_dag(&committee, &mut block_writer, None, wave_length - 1);
+    let start = Instant::now();
+    for (wave, age) in ages.iter().enumerate() {
+        let leader_round = (wave as u64 + 1) * wave_length;
+        let creation_time = (runtime::timestamp_utc() - *age).as_nanos();
+        references = committee
+            .authorities()
+            .map(|authority| {
+                let block = Data::new(StatementBlock::new(
+                    authority,
+                    leader_round,
+                    references.clone(),
+                    vec![],
+                    creation_time,
+                    false,
+                    Default::default(),
+                ));
+                block_writer.add_block(block.clone());
+                *block.reference()
+            })
+            .collect();
+        references = build_dag(
+            &committee,
+            &mut block_writer,
+            Some(references),
+            leader_round + wave_length - 1,
+        );
+    }
+
+    let metrics = test_metrics();
+    let committer = UniversalCommitterBuilder::new(
+        committee.clone(),
+        block_writer.into_block_store(),
+        metrics.clone(),
+    )
+    .with_wave_length(wave_length)
+    .build();
+
+    let last_committed = BlockReference::new_test(0, 0);
+    let sequence = committer.try_commit(last_committed);
+    let elapsed = start.elapsed();
+    assert_eq!(sequence.len(), 2);
+
+    // Each observation is the age of the leader block at the time of the commit.
+    let histogram = &metrics.commit_latency_seconds;
+    assert_eq!(histogram.get_sample_count(), 2);
+    let expected: Duration = ages.iter().sum();
+    let sum = histogram.get_sample_sum();
+    assert!(sum >= expected.as_secs_f64());
+    assert!(sum <= (expected + 2 * elapsed).as_secs_f64());
+
+    // Deciding the same leaders again records no new observation.
+    let last = sequence[1].clone().into_decided_block().unwrap();
+    assert!(committer.try_commit(*last.reference()).is_empty());
+    assert_eq!(committer.try_commit(last_committed).len(), 2);
+    assert_eq!(histogram.get_sample_count(), 2);
+    assert_eq!(histogram.get_sample_sum(), sum);
+}
+
+/// Leaders stay undecided while the dag lacks a quorum of blocks, and are decided once the dag
+/// makes progress again.
+#[test]
+#[tracing_test::traced_test]
+fn undecided_leaders_metric() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let leader_round_1 = wave_length;
+    let mut references = build_dag(&committee, &mut block_writer, None, leader_round_1);
+
+    // Only two authorities make progress until the decision round of the third leader.
+    let decision_round_3 = 4 * wave_length - 1;
+    for _ in leader_round_1..decision_round_3 {
+        let connections = vec![(0, references.clone()), (1, references)];
+        references = build_dag_layer(connections, &mut block_writer);
+    }
+
+    let metrics = test_metrics();
+    let committer = UniversalCommitterBuilder::new(
+        committee.clone(),
+        block_writer.block_store(),
+        metrics.clone(),
+    )
+    .with_wave_length(wave_length)
+    .build();
+
+    let last_committed = BlockReference::new_test(0, 0);
+    let sequence = committer.try_commit(last_committed);
+    assert!(sequence.is_empty());
+    assert_eq!(metrics.undecided_leaders.get(), 3);
+
+    // All authorities resume until the decision round of the fourth leader.
+    let decision_round_4 = 5 * wave_length - 1;
+    build_dag(
+        &committee,
+        &mut block_writer,
+        Some(references),
+        decision_round_4,
+    );
+
+    let sequence = committer.try_commit(last_committed);
+    assert_eq!(sequence.len(), 4);
+    assert!(sequence.iter().all(|x| x.is_decided()));
+    assert_eq!(metrics.undecided_leaders.get(), 0);
+}
+
+/// Support is measured in stake rather than in number of authorities: a majority of the
+/// authorities holding a minority of the stake cannot certify a leader, while the blame of a
+/// single authority holding a quorum of the stake is enough to skip it.
+#[test]
+#[tracing_test::traced_test]
+fn stake_weighted_support() {
+    let wave_length = DEFAULT_WAVE_LENGTH;
+    let leader_round = wave_length;
+    let decision_round = 2 * wave_length - 1;
+
+    let decide = |committee: Arc<Committee>| {
+        let mut block_writer = TestBlockWriter::new(&committee);
+        let references = build_dag(&committee, &mut block_writer, None, leader_round);
+        let leader = committee.elect_leader(leader_round);
+        let references_without_leader: Vec<_> = references
+            .iter()
+            .cloned()
+            .filter(|x| x.authority != leader)
+            .collect();
+
+        // Authorities 1, 2, and 3 vote for the leader but authority 0 does not.
+        let connections = committee.authorities().map(|authority| match authority {
+            0 => (authority, references_without_leader.clone()),
+            _ => (authority, references.clone()),
+        });
+        let votes = build_dag_layer(connections.collect(), &mut block_writer);
+        build_dag(&committee, &mut block_writer, Some(votes), decision_round);
+
+        let committer = BaseCommitter::new(committee.clone(), block_writer.into_block_store());
+        (leader, committer.try_direct_decide(leader, leader_round))
+    };
+
+    // Three authorities out of four hold a quorum of an evenly distributed stake.
+    let (_, status) = decide(Committee::new_with_stakes(vec![1, 1, 1, 1]));
+    assert!(matches!(status, LeaderStatus::Commit(..)));
+
+    // They do not when authority 0 holds most of the stake, and its blame skips the leader.
+    let (leader, status) = decide(Committee::new_with_stakes(vec![7, 1, 1, 1]));
+    assert_eq!(status, LeaderStatus::Skip(leader, leader_round));
+}
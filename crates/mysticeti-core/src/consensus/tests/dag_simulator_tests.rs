@@ -0,0 +1,79 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    consensus::LeaderStatus,
+    dag_simulator::{DagSimulator, FaultModel},
+    test_util::committee,
+};
+
+/// Without faults, every leader is committed.
+#[test]
+fn simulate_no_faults() {
+    let simulator = DagSimulator::new(committee(4), FaultModel::default());
+    for seed in 0..5 {
+        let sequence = simulator.run(seed, 30);
+        assert!(sequence
+            .iter()
+            .all(|x| matches!(x, LeaderStatus::Commit(..))));
+    }
+}
+
+/// Leaders that never proposed their block are skipped, while the committer stays safe and live
+/// despite the missing parents.
+#[test]
+fn simulate_crash_faults() {
+    let faults = FaultModel {
+        crashes: vec![(2, 10), (5, 20)],
+        omission_probability: 0.3,
+    };
+    let simulator = DagSimulator::new(committee(7), faults.clone());
+    for seed in 0..10 {
+        let sequence = simulator.run(seed, 50);
+        for (authority, from) in &faults.crashes {
+            assert!(sequence
+                .iter()
+                .filter(|x| x.authority() == *authority && x.round() >= *from)
+                .all(|x| matches!(x, LeaderStatus::Skip(..))));
+        }
+    }
+}
+
+/// Longer waves, multiple leaders per round, and pipelined committers over a very asynchronous
+/// dag.
+#[test]
+fn simulate_multi_leader_pipeline() {
+    let faults = FaultModel {
+        crashes: vec![(0, 1)],
+        omission_probability: 0.6,
+    };
+    let simulator = DagSimulator::new(committee(4), faults)
+        .with_wave_length(4)
+        .with_number_of_leaders(2)
+        .with_pipeline(true);
+    for seed in 0..5 {
+        simulator.run(seed, 40);
+    }
+}
+
+/// Runs are fully determined by their seed.
+#[test]
+fn simulate_reproducible() {
+    let faults = FaultModel {
+        crashes: vec![(3, 5)],
+        omission_probability: 0.5,
+    };
+    let simulator = DagSimulator::new(committee(4), faults);
+    assert_eq!(simulator.run(42, 40), simulator.run(42, 40));
+}
+
+/// Too many crashed authorities break the assumptions of the protocol.
+#[test]
+#[should_panic]
+fn simulate_too_many_crashes() {
+    let faults = FaultModel {
+        crashes: vec![(0, 1), (1, 1)],
+        omission_probability: 0.0,
+    };
+    DagSimulator::new(committee(4), faults);
+}
@@ -0,0 +1,70 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    consensus::{
+        decision_stability::DecisionStability,
+        universal_committer::UniversalCommitterBuilder,
+        LeaderStatus,
+    },
+    test_util::{build_dag, committee, test_metrics, TestBlockWriter},
+    types::BlockReference,
+};
+
+/// Adding blocks to the dag only extends the sequence of decided leaders.
+#[test]
+#[tracing_test::traced_test]
+fn later_blocks_do_not_alter_decisions() {
+    let committee = committee(4);
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let committer = UniversalCommitterBuilder::new(
+        committee.clone(),
+        block_writer.block_store(),
+        test_metrics(),
+    )
+    .with_number_of_leaders(2)
+    .with_pipeline(true)
+    .build();
+
+    let stability = DecisionStability::default();
+    let last_committed = BlockReference::new_test(0, 0);
+    let mut references = build_dag(&committee, &mut block_writer, None, 0);
+    let mut previous_sequence = Vec::new();
+    for round in 1..=30 {
+        references = build_dag(&committee, &mut block_writer, Some(references), round);
+
+        let sequence = committer.try_commit(last_committed);
+        stability.check(&sequence);
+        assert!(sequence.starts_with(&previous_sequence));
+        previous_sequence = sequence;
+    }
+    assert!(!previous_sequence.is_empty());
+}
+
+/// A committer changing a decision it already output is caught.
+#[test]
+#[should_panic(expected = "changed")]
+fn altered_decision_is_caught() {
+    let committee = committee(4);
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+    build_dag(&committee, &mut block_writer, None, 5);
+
+    let committer = UniversalCommitterBuilder::new(
+        committee.clone(),
+        block_writer.into_block_store(),
+        test_metrics(),
+    )
+    .build();
+
+    let stability = DecisionStability::default();
+    let last_committed = BlockReference::new_test(0, 0);
+    let sequence = committer.try_commit(last_committed);
+    stability.check(&sequence);
+
+    // Plant a bug: the committed leader is now reported as skipped.
+    let leader = &sequence[0];
+    let altered = LeaderStatus::Skip(leader.authority(), leader.round());
+    stability.check(&[altered]);
+}
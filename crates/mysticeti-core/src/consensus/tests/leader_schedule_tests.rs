@@ -0,0 +1,291 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::HashSet, sync::Arc};
+
+use crate::{
+    committee::Committee,
+    consensus::{
+        base_committer::BaseCommitter,
+        leader_schedule::{
+            LeaderSchedule,
+            ReputationSchedule,
+            RoundRobinSchedule,
+            StakeWeightedSchedule,
+        },
+        universal_committer::UniversalCommitterBuilder,
+        LeaderStatus,
+    },
+    data::Data,
+    test_util::{build_dag, build_dag_layer, committee, test_metrics, TestBlockWriter},
+    types::{AuthorityIndex, BlockReference, RoundNumber, StatementBlock},
+};
+
+/// A schedule always electing the same authority.
+struct FixedSchedule(AuthorityIndex);
+
+impl LeaderSchedule for FixedSchedule {
+    fn leader(&self, _round: RoundNumber) -> AuthorityIndex {
+        self.0
+    }
+
+    fn for_committee(&self, _committee: Arc<Committee>) -> Arc<dyn LeaderSchedule> {
+        Arc::new(Self(self.0))
+    }
+}
+
+/// Every authority is elected exactly once over a full committee cycle.
+#[test]
+fn round_robin_coverage() {
+    let committee = committee(7);
+    let schedule = RoundRobinSchedule::new(committee.clone());
+
+    for start in [0, 1, 100] {
+        let leaders: HashSet<_> = (start..start + committee.len() as RoundNumber)
+            .map(|round| schedule.leader(round))
+            .collect();
+        let authorities: HashSet<_> = committee.authorities().collect();
+        assert_eq!(leaders, authorities);
+    }
+}
+
+/// The stake-weighted schedule elects the leaders of a committer.
+fn stake_weighted_committer(committee: Arc<Committee>) -> BaseCommitter {
+    let block_store = TestBlockWriter::new(&committee).into_block_store();
+    let schedule = Arc::new(StakeWeightedSchedule::new(committee.clone()));
+    BaseCommitter::new(committee, block_store).with_leader_schedule(schedule)
+}
+
+/// Assert that the frequency at which each authority leads is its share of the total stake.
+fn assert_stake_frequency(committee: &Committee, leaders: &[AuthorityIndex]) {
+    let stake = |authority| committee.get_stake(authority).unwrap();
+    let total_stake: u64 = committee.authorities().map(stake).sum();
+    for authority in committee.authorities() {
+        let elected = leaders.iter().filter(|x| **x == authority).count();
+        let frequency = elected as f64 / leaders.len() as f64;
+        let expected = stake(authority) as f64 / total_stake as f64;
+        assert!(
+            (frequency - expected).abs() < 0.01,
+            "Authority {authority} leads {frequency} of the rounds, expected {expected}"
+        );
+    }
+}
+
+/// With equal stakes, every authority leads as often as the others.
+#[test]
+fn stake_weighted_equal_stake() {
+    let committee = committee(7);
+    let schedule = StakeWeightedSchedule::new(committee.clone());
+
+    let leaders: Vec<_> = (0..7_000).map(|round| schedule.leader(round)).collect();
+    assert_stake_frequency(&committee, &leaders);
+}
+
+/// Over many rounds, the frequency at which each authority leads converges to its share of the
+/// total stake.
+#[test]
+fn stake_weighted_frequency() {
+    let committee = Committee::new_test(vec![1, 2, 3, 4]);
+    let committer = stake_weighted_committer(committee.clone());
+
+    let leaders = committer.leader_schedule(0..30_000);
+    assert_eq!(leaders.len(), 10_000);
+    assert_stake_frequency(&committee, &leaders);
+}
+
+/// The size of the schedule does not depend on the stakes: large coprime stakes are as cheap
+/// as small ones.
+#[test]
+fn stake_weighted_large_stakes() {
+    let committee = Committee::new_test(vec![1_000_000_007, 999_999_937, 3_000_000_019, 17]);
+    let schedule = StakeWeightedSchedule::new(committee.clone());
+
+    let leaders: Vec<_> = (0..10_000).map(|round| schedule.leader(round)).collect();
+    assert_stake_frequency(&committee, &leaders);
+}
+
+/// The stake-weighted schedule only depends on the committee.
+#[test]
+fn stake_weighted_is_deterministic() {
+    let stakes = vec![5, 1, 3, 7, 2];
+    let schedule = |stakes: Vec<_>| {
+        stake_weighted_committer(Committee::new_test(stakes)).leader_schedule(0..1_000)
+    };
+    assert_eq!(schedule(stakes.clone()), schedule(stakes));
+}
+
+/// Multiple leaders of the same round are always distinct, even when a high-stake authority is
+/// elected for several slots.
+#[test]
+fn stake_weighted_distinct_leaders() {
+    let committee = Committee::new_test(vec![1, 10, 1, 1]);
+    let block_store = TestBlockWriter::new(&committee).into_block_store();
+    let schedule = Arc::new(StakeWeightedSchedule::new(committee.clone()));
+    let committer = UniversalCommitterBuilder::new(committee, block_store, test_metrics())
+        .with_leader_schedule(schedule)
+        .with_number_of_leaders(3)
+        .build();
+
+    for round in 0..100 {
+        let leaders = committer.get_leaders(round);
+        let distinct: HashSet<_> = leaders.iter().collect();
+        assert_eq!(distinct.len(), leaders.len());
+    }
+}
+
+/// The committer follows a custom leader schedule.
+#[test]
+#[tracing_test::traced_test]
+fn custom_schedule() {
+    let committee = committee(4);
+    let mut block_writer = TestBlockWriter::new(&committee);
+    build_dag(&committee, &mut block_writer, None, 11);
+
+    let committer = UniversalCommitterBuilder::new(
+        committee.clone(),
+        block_writer.into_block_store(),
+        test_metrics(),
+    )
+    .with_leader_schedule(Arc::new(FixedSchedule(0)))
+    .build();
+    assert_eq!(committer.get_leaders(3), vec![0]);
+
+    let last_committed = BlockReference::new_test(0, 0);
+    let sequence = committer.try_commit(last_committed);
+    tracing::info!("Commit sequence: {sequence:?}");
+
+    assert_eq!(sequence.len(), 3);
+    for leader in sequence {
+        if let LeaderStatus::Commit(block) = leader {
+            assert_eq!(block.author(), 0);
+        } else {
+            panic!("Expected a committed leader")
+        }
+    }
+}
+
+/// Decide the leaders of the specified rounds, skipping the leader slots of `faulty`.
+fn decide_rounds(
+    schedule: &ReputationSchedule,
+    rounds: impl Iterator<Item = RoundNumber>,
+    faulty: AuthorityIndex,
+) {
+    for round in rounds {
+        let leader = schedule.leader(round);
+        let status = if leader == faulty {
+            LeaderStatus::Skip(leader, round)
+        } else {
+            let block =
+                StatementBlock::new(leader, round, vec![], vec![], 0, false, Default::default());
+            LeaderStatus::Commit(Data::new(block))
+        };
+        schedule.on_decided(&status);
+    }
+}
+
+/// An authority whose leader slots are repeatedly skipped is demoted.
+#[test]
+fn reputation_demotes_skipped_authority() {
+    let committee = committee(4);
+    let faulty = 1;
+    let schedule = ReputationSchedule::new(committee.clone(), 4);
+
+    let frequency = |rounds: std::ops::Range<RoundNumber>| {
+        rounds
+            .filter(|round| schedule.leader(*round) == faulty)
+            .count()
+    };
+    assert_eq!(frequency(100..200), 25);
+
+    decide_rounds(&schedule, 1..41, faulty);
+    let scores = schedule.scores();
+    assert_eq!(scores.skips(faulty), 1);
+    assert!(scores.score(faulty) < 0);
+    for authority in committee.authorities().filter(|x| *x != faulty) {
+        assert_eq!(scores.skips(authority), 0);
+        assert!(scores.score(authority) > 0);
+    }
+
+    // The faulty authority is not elected anymore after the first update.
+    assert_eq!(frequency(5..41), 0);
+    assert_eq!(frequency(100..200), 0);
+
+    // Dropping the tables below the commit watermark keeps the leaders above it.
+    let before: Vec<_> = (40..200).map(|round| schedule.leader(round)).collect();
+    schedule.on_committed(40);
+    let after: Vec<_> = (40..200).map(|round| schedule.leader(round)).collect();
+    assert_eq!(after, before);
+}
+
+/// The schedule only depends on the sequence of decided leaders, not on how many times each
+/// of them is notified.
+#[test]
+fn reputation_is_deterministic() {
+    let committee = committee(7);
+    let first = ReputationSchedule::new(committee.clone(), 3);
+    let second = ReputationSchedule::new(committee.clone(), 3);
+
+    decide_rounds(&first, 1..50, 2);
+    for end in (10..50).step_by(10) {
+        // Notify the same leaders multiple times.
+        decide_rounds(&second, 1..end, 2);
+    }
+    decide_rounds(&second, 1..50, 2);
+
+    assert_eq!(first.scores(), second.scores());
+    for round in 0..200 {
+        assert_eq!(first.leader(round), second.leader(round));
+    }
+}
+
+/// A restarted validator rebuilds the reputation schedule from the leaders it committed before
+/// the restart, and then elects the same leaders as the validators that kept running.
+#[test]
+#[tracing_test::traced_test]
+fn reputation_recovers_from_committed_leaders() {
+    let committee = committee(4);
+    let schedule = Arc::new(ReputationSchedule::new(committee.clone(), 1));
+
+    // No authority votes for the leader of round 3, which is skipped.
+    let skipped = schedule.leader(3);
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let references = build_dag(&committee, &mut block_writer, None, 3);
+    let without_leader: Vec<_> = references
+        .into_iter()
+        .filter(|x| x.authority != skipped)
+        .collect();
+    let connections = committee
+        .authorities()
+        .map(|authority| (authority, without_leader.clone()))
+        .collect();
+    let references = build_dag_layer(connections, &mut block_writer);
+    build_dag(&committee, &mut block_writer, Some(references), 30);
+    let block_store = block_writer.into_block_store();
+
+    // Commit as the core does: the committer stops at each change of the schedule and resumes
+    // from the last committed leader on the next call.
+    let committer =
+        UniversalCommitterBuilder::new(committee.clone(), block_store.clone(), test_metrics())
+            .with_leader_schedule(schedule.clone())
+            .build();
+    let mut committed = Vec::new();
+    let mut last_committed = BlockReference::new_test(0, 0);
+    for _ in 0..10 {
+        let sequence = committer.try_commit(last_committed);
+        let blocks = sequence.into_iter().filter_map(|x| x.into_decided_block());
+        committed.extend(blocks.map(|x| *x.reference()));
+        last_committed = committed.last().copied().unwrap_or(last_committed);
+    }
+    assert_eq!(last_committed.round(), 27);
+    assert_eq!(schedule.scores().skips(skipped), 1);
+
+    let recovered = Arc::new(ReputationSchedule::new(committee.clone(), 1));
+    let restarted = UniversalCommitterBuilder::new(committee, block_store, test_metrics())
+        .with_leader_schedule(recovered.clone())
+        .build();
+    restarted.recover_leader_schedule(&committed);
+    assert_eq!(recovered.scores(), schedule.scores());
+    for round in last_committed.round()..200 {
+        assert_eq!(recovered.leader(round), schedule.leader(round));
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+
+use crate::{
+    consensus::{
+        linearizer::{CommittedSubDag, Linearizer},
+        universal_committer::UniversalCommitterBuilder,
+        DEFAULT_WAVE_LENGTH,
+        GC_DEPTH,
+    },
+    test_util::{build_dag, committee, test_metrics, TestBlockWriter},
+    types::BlockReference,
+};
+
+/// Build a fully interconnected dag, commit it, and linearize the committed leaders.
+fn commit_and_linearize(number_of_leaders: usize, rounds: u64) -> Vec<CommittedSubDag> {
+    let committee = committee(4);
+    let mut block_writer = TestBlockWriter::new(&committee);
+    build_dag(&committee, &mut block_writer, None, rounds);
+    let block_store = block_writer.into_block_store();
+
+    let committer =
+        UniversalCommitterBuilder::new(committee.clone(), block_store.clone(), test_metrics())
+            .with_wave_length(DEFAULT_WAVE_LENGTH)
+            .with_number_of_leaders(number_of_leaders)
+            .build();
+
+    let last_committed = BlockReference::new_test(0, 0);
+    let leaders = committer
+        .try_commit(last_committed)
+        .into_iter()
+        .filter_map(|leader| leader.into_decided_block())
+        .collect();
+
+    let mut linearizer = Linearizer::new();
+    linearizer.handle_commit(&block_store, leaders)
+}
+
+/// Two independent committers over the same dag linearize it into the same sequence of blocks.
+#[test]
+#[tracing_test::traced_test]
+fn deterministic_linearization() {
+    for number_of_leaders in 1..=3 {
+        let first = commit_and_linearize(number_of_leaders, 10);
+        let second = commit_and_linearize(number_of_leaders, 10);
+        assert!(!first.is_empty());
+
+        let bytes = |sub_dags: &[CommittedSubDag]| -> Vec<u8> {
+            sub_dags
+                .iter()
+                .flat_map(|sub_dag| sub_dag.blocks.iter())
+                .flat_map(|block| block.serialized_bytes().to_vec())
+                .collect()
+        };
+        assert_eq!(bytes(&first), bytes(&second));
+    }
+}
+
+/// Each block is delivered at most once, and every sub-dag is sorted by round.
+#[test]
+#[tracing_test::traced_test]
+fn no_duplicate_blocks() {
+    let sub_dags = commit_and_linearize(2, 10);
+    tracing::info!("Committed sub-dags: {sub_dags:?}");
+
+    let mut delivered = HashSet::new();
+    for sub_dag in &sub_dags {
+        assert!(sub_dag
+            .blocks
+            .iter()
+            .any(|block| *block.reference() == sub_dag.anchor));
+        assert!(sub_dag
+            .blocks
+            .windows(2)
+            .all(|pair| pair[0].round() <= pair[1].round()));
+        for block in &sub_dag.blocks {
+            assert!(delivered.insert(*block.reference()));
+        }
+    }
+}
+
+/// Pruning the block store below the gc round of the last committed leader does not prevent the
+/// committer from making progress, and the committed sequence is unaffected by the pruning.
+#[test]
+#[tracing_test::traced_test]
+fn commit_after_pruning() {
+    let committee = committee(4);
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let references = build_dag(&committee, &mut block_writer, None, GC_DEPTH + 10);
+    let block_store = block_writer.block_store();
+
+    let committer =
+        UniversalCommitterBuilder::new(committee.clone(), block_store.clone(), test_metrics())
+            .with_wave_length(DEFAULT_WAVE_LENGTH)
+            .build();
+    let commit = |last_committed: BlockReference| -> Vec<_> {
+        committer
+            .try_commit(last_committed)
+            .into_iter()
+            .filter_map(|leader| leader.into_decided_block())
+            .collect()
+    };
+
+    // Commit the first leaders and prune the history below the gc round of the last of them.
+    let mut linearizer = Linearizer::new();
+    let leaders = commit(BlockReference::new_test(0, 0));
+    let last_committed = *leaders.last().unwrap().reference();
+    linearizer.handle_commit(&block_store, leaders);
+    block_store.set_committed_round(last_committed.round);
+    let gc_round = Linearizer::gc_round(&last_committed);
+    assert!(block_store.prune_below(gc_round) > 0);
+    assert_eq!(block_store.gc_round(), gc_round);
+
+    // Extend the dag and keep committing.
+    build_dag(
+        &committee,
+        &mut block_writer,
+        Some(references),
+        GC_DEPTH + 20,
+    );
+    let leaders = commit(last_committed);
+    assert!(!leaders.is_empty());
+    let sub_dags = linearizer.handle_commit(&block_store, leaders);
+
+    // The committed sequence matches the one obtained without pruning.
+    let expected: Vec<_> = commit_and_linearize(1, GC_DEPTH + 20)
+        .into_iter()
+        .filter(|sub_dag| sub_dag.anchor.round > last_committed.round)
+        .collect();
+    assert_eq!(sub_dags.len(), expected.len());
+    for (sub_dag, expected) in sub_dags.iter().zip(&expected) {
+        assert_eq!(sub_dag.anchor, expected.anchor);
+        let references: Vec<_> = sub_dag.blocks.iter().map(|x| *x.reference()).collect();
+        let expected: Vec<_> = expected.blocks.iter().map(|x| *x.reference()).collect();
+        assert_eq!(references, expected);
+        assert!(references.iter().all(|x| x.round >= gc_round));
+    }
+}
+
+/// The sub-dag of a leader never contains blocks below its gc round, whether or not the block
+/// store pruned them, so that validators pruning differently commit the same blocks.
+#[test]
+#[tracing_test::traced_test]
+fn garbage_collection() {
+    let committee = committee(4);
+    let leader_round = GC_DEPTH + 10;
+    let linearize = |prune: bool| {
+        let mut block_writer = TestBlockWriter::new(&committee);
+        build_dag(&committee, &mut block_writer, None, leader_round);
+        let block_store = block_writer.into_block_store();
+        let leader = block_store.get_blocks_by_round(leader_round).pop().unwrap();
+        if prune {
+            block_store.set_committed_round(leader_round);
+            // The store never prunes the blocks that may still be committed.
+            assert!(block_store.prune_below(leader_round) > 0);
+            assert_eq!(block_store.gc_round(), leader_round - GC_DEPTH);
+        }
+        Linearizer::new().handle_commit(&block_store, vec![leader])
+    };
+
+    // The leader is committed with the blocks of the last gc depth rounds below it.
+    let sub_dags = linearize(false);
+    assert_eq!(sub_dags.len(), 1);
+    let references: Vec<_> = sub_dags[0].blocks.iter().map(|x| *x.reference()).collect();
+    assert_eq!(references.len(), 4 * GC_DEPTH as usize + 1);
+    assert!(references
+        .iter()
+        .all(|x| x.round >= leader_round - GC_DEPTH));
+
+    let pruned = linearize(true);
+    let pruned_references: Vec<_> = pruned[0].blocks.iter().map(|x| *x.reference()).collect();
+    assert_eq!(references, pruned_references);
+}
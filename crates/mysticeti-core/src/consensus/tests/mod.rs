@@ -1,6 +1,13 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod audit_log_tests;
 mod base_committer_tests;
+mod dag_simulator_tests;
+mod decision_stability_tests;
+mod leader_schedule_tests;
+mod linearizer_tests;
 mod multi_committer_tests;
 mod pipelined_committer_tests;
+mod reconfiguration_tests;
+mod universal_committer_tests;
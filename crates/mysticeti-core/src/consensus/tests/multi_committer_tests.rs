@@ -1,8 +1,11 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::HashSet;
+
 use crate::{
     consensus::{
+        linearizer::Linearizer,
         universal_committer::UniversalCommitterBuilder,
         LeaderStatus,
         DEFAULT_WAVE_LENGTH,
@@ -644,3 +647,89 @@ fn undecided() {
     tracing::info!("Commit sequence: {sequence:?}");
     assert!(sequence.is_empty());
 }
+
+/// Compare a single-leader and a four-leaders committer over the same dag. The multi-leader
+/// sequence is ordered by round and then by leader slot, and its first slot matches the single
+/// leader of each round.
+#[test]
+#[tracing_test::traced_test]
+fn single_vs_multi_leader() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+    let mut block_writer = TestBlockWriter::new(&committee);
+    build_dag(&committee, &mut block_writer, None, 4 * wave_length - 1);
+    let block_store = block_writer.into_block_store();
+
+    let last_committed = BlockReference::new_test(0, 0);
+    let mut sequences = Vec::new();
+    for number_of_leaders in [1, 4] {
+        let committer =
+            UniversalCommitterBuilder::new(committee.clone(), block_store.clone(), test_metrics())
+                .with_wave_length(wave_length)
+                .with_number_of_leaders(number_of_leaders)
+                .build();
+        let sequence = committer.try_commit(last_committed);
+        tracing::info!("Commit sequence ({number_of_leaders} leaders): {sequence:?}");
+        assert_eq!(sequence.len(), 3 * number_of_leaders);
+        sequences.push(sequence);
+    }
+    let (single, multi) = (&sequences[0], &sequences[1]);
+
+    let slots: Vec<_> = multi.iter().map(|x| (x.round(), x.authority())).collect();
+    let mut expected = Vec::new();
+    for wave in 1..=3 {
+        let round = wave * wave_length;
+        for slot in 0..4 {
+            expected.push((round, committee.elect_leader(round + slot)));
+        }
+    }
+    assert_eq!(slots, expected);
+
+    for (leader, co_round_leaders) in single.iter().zip(multi.chunks(4)) {
+        assert_eq!(leader, &co_round_leaders[0]);
+    }
+}
+
+/// Co-round leaders share most of their causal history; the linearizer delivers each block once.
+#[test]
+#[tracing_test::traced_test]
+fn multi_leader_linearization() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+    let mut block_writer = TestBlockWriter::new(&committee);
+    build_dag(&committee, &mut block_writer, None, 4 * wave_length - 1);
+    let block_store = block_writer.into_block_store();
+
+    let mut delivered_sets = Vec::new();
+    for number_of_leaders in [1, 4] {
+        let committer =
+            UniversalCommitterBuilder::new(committee.clone(), block_store.clone(), test_metrics())
+                .with_wave_length(wave_length)
+                .with_number_of_leaders(number_of_leaders)
+                .build();
+        let leaders = committer
+            .try_commit(BlockReference::new_test(0, 0))
+            .into_iter()
+            .filter_map(|leader| leader.into_decided_block())
+            .collect();
+
+        let mut linearizer = Linearizer::new();
+        let mut delivered = HashSet::new();
+        for sub_dag in linearizer.handle_commit(&block_store, leaders) {
+            for block in sub_dag.blocks {
+                assert!(delivered.insert(*block.reference()));
+            }
+        }
+        delivered_sets.push(delivered);
+    }
+
+    // The last single leader is one of the last co-round leaders, so the multi-leader committer
+    // delivers a superset of the blocks.
+    let (single, multi) = (&delivered_sets[0], &delivered_sets[1]);
+    assert!(multi.is_superset(single));
+    let last_leader_round = 3 * wave_length;
+    assert_eq!(
+        multi.len(),
+        committee.len() * (last_leader_round as usize + 1)
+    );
+}
@@ -0,0 +1,196 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use crate::{
+    consensus::{
+        leader_schedule::{LeaderSchedule, RoundRobinSchedule, StakeWeightedSchedule},
+        universal_committer::UniversalCommitterBuilder,
+        LeaderStatus,
+    },
+    crypto::dummy_public_key,
+    test_util::{build_dag, build_dag_layer, committee, test_metrics, TestBlockWriter},
+    types::{AuthorityIndex, BlockReference},
+};
+
+/// Commit up to the end of an epoch, reconfigure the committer, and keep committing with the
+/// committee of the next epoch.
+#[test]
+#[tracing_test::traced_test]
+fn commit_across_epochs() {
+    let committee = committee(4);
+    let mut block_writer = TestBlockWriter::new(&committee);
+    build_dag(&committee, &mut block_writer, None, 30);
+
+    let mut committer = UniversalCommitterBuilder::new(
+        committee.clone(),
+        block_writer.block_store(),
+        test_metrics(),
+    )
+    .with_leader_schedule(Arc::new(StakeWeightedSchedule::new(committee.clone())))
+    .build();
+    let epoch_end = 10;
+    committer.close_epoch(epoch_end);
+
+    // Only the leaders of the first epoch are committed.
+    let genesis = BlockReference::new_test(0, 0);
+    let sequence = committer.try_commit(genesis);
+    let rounds: Vec<_> = sequence.iter().map(|x| x.round()).collect();
+    assert_eq!(rounds, [3, 6, 9]);
+    assert!(sequence
+        .iter()
+        .all(|x| matches!(x, LeaderStatus::Commit(..))));
+    let last_committed = *sequence
+        .last()
+        .and_then(|x| x.clone().into_decided_block())
+        .unwrap()
+        .reference();
+
+    // No leader of the next epoch is decided until the committer is reconfigured.
+    assert!(committer.try_commit(last_committed).is_empty());
+
+    let new_authorities = (0..4)
+        .map(|authority| (authority, authority + 1, dummy_public_key()))
+        .collect();
+    let new_committee = Arc::new(committee.reconfigure(new_authorities, 1));
+    committer.reconfigure(new_committee.clone(), epoch_end + 1);
+    assert_eq!(committer.epoch(), 1);
+
+    // The leaders of the new epoch are elected by the new committee.
+    let schedule = StakeWeightedSchedule::new(new_committee);
+    let sequence = committer.try_commit(last_committed);
+    let rounds: Vec<_> = sequence.iter().map(|x| x.round()).collect();
+    assert_eq!(rounds, [12, 15, 18, 21, 24, 27]);
+    for leader in &sequence {
+        assert!(matches!(leader, LeaderStatus::Commit(..)));
+        assert_eq!(leader.authority(), schedule.leader(leader.round()));
+    }
+
+    // Replaying the commit sequence from genesis yields the leaders of both epochs, in order.
+    let sequence = committer.try_commit(genesis);
+    let rounds: Vec<_> = sequence.iter().map(|x| x.round()).collect();
+    assert_eq!(rounds, [3, 6, 9, 12, 15, 18, 21, 24, 27]);
+}
+
+/// Commits before the switch round are decided with the thresholds of the old committee, and
+/// commits after it with the thresholds of the new one.
+#[test]
+#[tracing_test::traced_test]
+fn thresholds_across_epochs() {
+    let committee = committee(4);
+    let new_authorities = [7, 1, 1, 1]
+        .into_iter()
+        .enumerate()
+        .map(|(authority, stake)| (authority as AuthorityIndex, stake, dummy_public_key()))
+        .collect();
+    let new_committee = Arc::new(committee.reconfigure(new_authorities, 1));
+    let old_schedule = StakeWeightedSchedule::new(committee.clone());
+    let new_schedule = StakeWeightedSchedule::new(new_committee.clone());
+
+    // Authority 0 does not vote for the leaders of rounds 3 (old epoch) and 12 (new epoch), while
+    // the other authorities do.
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let mut references = None;
+    for (leader_round, leader) in [(3, old_schedule.leader(3)), (12, new_schedule.leader(12))] {
+        let round_references = build_dag(&committee, &mut block_writer, references, leader_round);
+        let without_leader: Vec<_> = round_references
+            .iter()
+            .cloned()
+            .filter(|x| x.authority != leader)
+            .collect();
+        let connections = committee.authorities().map(|authority| match authority {
+            0 => (authority, without_leader.clone()),
+            _ => (authority, round_references.clone()),
+        });
+        references = Some(build_dag_layer(connections.collect(), &mut block_writer));
+    }
+    build_dag(&committee, &mut block_writer, references, 18);
+
+    let mut committer = UniversalCommitterBuilder::new(
+        committee.clone(),
+        block_writer.into_block_store(),
+        test_metrics(),
+    )
+    .with_leader_schedule(Arc::new(old_schedule))
+    .build();
+    committer.reconfigure(new_committee, 7);
+
+    // Three authorities out of four are a quorum of the old committee, but authority 0 alone
+    // holds a quorum of the stake of the new committee.
+    let sequence = committer.try_commit(BlockReference::new_test(0, 0));
+    let rounds: Vec<_> = sequence.iter().map(|x| x.round()).collect();
+    assert_eq!(rounds, [3, 6, 9, 12, 15]);
+    for (i, leader) in sequence.iter().enumerate() {
+        match i {
+            3 => assert_eq!(leader, &LeaderStatus::Skip(new_schedule.leader(12), 12)),
+            _ => assert!(matches!(leader, LeaderStatus::Commit(..))),
+        }
+    }
+}
+
+/// Reconfiguring the committer keeps the kind of its leader schedule: the leaders of the new
+/// epoch are elected by a schedule of the same kind built for the new committee.
+#[test]
+#[tracing_test::traced_test]
+fn reconfigure_keeps_leader_schedule() {
+    let committee = committee(4);
+    let mut block_writer = TestBlockWriter::new(&committee);
+    build_dag(&committee, &mut block_writer, None, 30);
+
+    let mut committer = UniversalCommitterBuilder::new(
+        committee.clone(),
+        block_writer.block_store(),
+        test_metrics(),
+    )
+    .with_leader_schedule(Arc::new(RoundRobinSchedule::new(committee.clone())))
+    .build();
+    let epoch_end = 10;
+    committer.close_epoch(epoch_end);
+    let genesis = BlockReference::new_test(0, 0);
+    let sequence = committer.try_commit(genesis);
+    let last_committed = *sequence
+        .last()
+        .and_then(|x| x.clone().into_decided_block())
+        .unwrap()
+        .reference();
+
+    // With unequal stakes, a stake-weighted schedule would elect other leaders.
+    let new_authorities = (0..4)
+        .map(|authority| (authority, authority + 1, dummy_public_key()))
+        .collect();
+    let new_committee = Arc::new(committee.reconfigure(new_authorities, 1));
+    committer.reconfigure(new_committee.clone(), epoch_end + 1);
+
+    let round_robin = RoundRobinSchedule::new(new_committee.clone());
+    let stake_weighted = StakeWeightedSchedule::new(new_committee);
+    let sequence = committer.try_commit(last_committed);
+    assert!(!sequence.is_empty());
+    for leader in &sequence {
+        assert!(matches!(leader, LeaderStatus::Commit(..)));
+        assert_eq!(leader.authority(), round_robin.leader(leader.round()));
+    }
+    assert!(sequence
+        .iter()
+        .any(|x| x.authority() != stake_weighted.leader(x.round())));
+}
+
+/// A committer cannot switch epochs at another round than the one at which it was closed.
+#[test]
+#[should_panic]
+fn reconfigure_closed_epoch_elsewhere() {
+    let committee = committee(4);
+    let block_writer = TestBlockWriter::new(&committee);
+    let mut committer = UniversalCommitterBuilder::new(
+        committee.clone(),
+        block_writer.into_block_store(),
+        test_metrics(),
+    )
+    .build();
+    committer.close_epoch(10);
+
+    let new_authorities = (0..4)
+        .map(|authority| (authority, 1, dummy_public_key()))
+        .collect();
+    committer.reconfigure(Arc::new(committee.reconfigure(new_authorities, 1)), 5);
+}
@@ -0,0 +1,163 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+
+use crate::{
+    consensus::{universal_committer::UniversalCommitterBuilder, DEFAULT_WAVE_LENGTH, GC_DEPTH},
+    test_util::{build_dag, committee, test_metrics, TestBlockWriter},
+    types::BlockReference,
+};
+
+/// The blocks above the last decided leader are uncommitted.
+#[test]
+#[tracing_test::traced_test]
+fn uncommitted_blocks() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+    let tip = build_dag(&committee, &mut block_writer, None, 2 * wave_length - 1);
+    let block_store = block_writer.into_block_store();
+
+    let committer = UniversalCommitterBuilder::new(committee.clone(), block_store, test_metrics())
+        .with_wave_length(wave_length)
+        .build();
+
+    // Nothing is committed yet: all blocks but the genesis are uncommitted.
+    let genesis = BlockReference::new_test(0, 0);
+    let uncommitted = committer.uncommitted_blocks(genesis);
+    assert_eq!(
+        uncommitted.len(),
+        committee.len() * (2 * wave_length as usize - 1)
+    );
+    assert!(uncommitted.windows(2).all(|x| x[0].round <= x[1].round));
+
+    // Commit the leader of the first wave.
+    let sequence = committer.try_commit(genesis);
+    assert_eq!(sequence.len(), 1);
+    let leader = sequence[0].clone().into_decided_block().unwrap();
+    let last_decided = *leader.reference();
+    assert_eq!(last_decided.round, wave_length);
+
+    // Only the blocks above the leader remain uncommitted, up to the tip of the dag.
+    let uncommitted = committer.uncommitted_blocks(last_decided);
+    assert_eq!(
+        uncommitted.len(),
+        committee.len() * (wave_length as usize - 1)
+    );
+    assert!(uncommitted.iter().all(|x| x.round > last_decided.round));
+    assert!(tip.iter().all(|x| uncommitted.contains(x)));
+
+    // The gap follows the round up to which the leaders are decided, published by the committer.
+    assert_eq!(committer.last_committed_round(), last_decided.round);
+    assert_eq!(committer.commit_gap(), wave_length - 1);
+}
+
+/// Pruning never removes the blocks above the commit watermark minus the gc depth.
+#[test]
+#[tracing_test::traced_test]
+fn prune_below_watermark() {
+    let committee = committee(4);
+    let wave_length = 5;
+
+    // The leader of round 100 is decided by the last wave of the dag.
+    let mut block_writer = TestBlockWriter::new(&committee);
+    build_dag(&committee, &mut block_writer, None, 100 + wave_length - 1);
+    let block_store = block_writer.into_block_store();
+
+    let committer =
+        UniversalCommitterBuilder::new(committee.clone(), block_store.clone(), test_metrics())
+            .with_wave_length(wave_length)
+            .build();
+    assert_eq!(committer.last_committed_round(), 0);
+    let sequence = committer.try_commit(BlockReference::new_test(0, 0));
+    assert_eq!(sequence.last().unwrap().round(), 100);
+    assert_eq!(committer.last_committed_round(), 100);
+
+    // Pruning up to the tip of the dag stops at the watermark minus the gc depth.
+    let gc_round = 100 - GC_DEPTH;
+    block_store.prune_below(block_store.highest_round());
+    assert_eq!(block_store.gc_round(), gc_round);
+    assert!((0..gc_round).all(|round| block_store.get_blocks_by_round(round).is_empty()));
+    assert!((gc_round..=100).all(|round| block_store.get_blocks_by_round(round).len() == 4));
+}
+
+/// The receiver observes the sub-dag of each committed leader once, in commit order. Publishing
+/// never blocks the committer (running inside the runtime): a lagging consumer holds back the
+/// commits instead.
+#[tokio::test]
+#[tracing_test::traced_test]
+async fn commit_receiver() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+    build_dag(&committee, &mut block_writer, None, 6 * wave_length - 1);
+    let block_store = block_writer.into_block_store();
+
+    let mut committer = UniversalCommitterBuilder::new(committee, block_store, test_metrics())
+        .with_wave_length(wave_length)
+        .with_commit_channel_capacity(1)
+        .build();
+    let mut receiver = committer.commit_receiver();
+
+    // Five leaders are decided but only two sub-dags fit: one in the channel and one pending.
+    let genesis = BlockReference::new_test(0, 0);
+    let sequence = committer.try_commit(genesis);
+    assert_eq!(sequence.len(), 2);
+    // Deciding the same leaders again does not publish them twice.
+    assert_eq!(committer.try_commit(genesis).len(), 2);
+    let mut committed: Vec<_> = sequence
+        .into_iter()
+        .map(|x| *x.into_decided_block().unwrap().reference())
+        .collect();
+    let mut last_decided = *committed.last().unwrap();
+    assert!(committer.try_commit(last_decided).is_empty());
+
+    // Consuming the sub-dags lets the committer make progress again.
+    let mut sub_dags = Vec::new();
+    while sub_dags.len() < 5 {
+        sub_dags.push(receiver.recv().await.unwrap());
+        for leader in committer.try_commit(last_decided) {
+            last_decided = *leader.into_decided_block().unwrap().reference();
+            committed.push(last_decided);
+        }
+    }
+    assert!(receiver.try_recv().is_err());
+
+    let anchors: Vec<_> = sub_dags.iter().map(|x| x.anchor).collect();
+    assert_eq!(anchors, committed);
+    assert_eq!(committed.last().unwrap().round, 5 * wave_length);
+
+    // Each sub-dag ends with its leader and no block is committed twice.
+    let mut blocks = HashSet::new();
+    for sub_dag in &sub_dags {
+        assert_eq!(sub_dag.blocks.last().unwrap().reference(), &sub_dag.anchor);
+        assert!(sub_dag.blocks.iter().all(|x| blocks.insert(*x.reference())));
+    }
+    // The first sub-dag holds the causal history of the first leader (including the genesis).
+    assert_eq!(sub_dags[0].blocks.len(), 1 + 4 * wave_length as usize);
+}
+
+/// Dropping the receiver does not hold back the commits.
+#[tokio::test]
+#[tracing_test::traced_test]
+async fn commit_receiver_dropped() {
+    let committee = committee(4);
+    let wave_length = DEFAULT_WAVE_LENGTH;
+
+    let mut block_writer = TestBlockWriter::new(&committee);
+    build_dag(&committee, &mut block_writer, None, 6 * wave_length - 1);
+    let block_store = block_writer.into_block_store();
+
+    let mut committer = UniversalCommitterBuilder::new(committee, block_store, test_metrics())
+        .with_wave_length(wave_length)
+        .with_commit_channel_capacity(1)
+        .build();
+    drop(committer.commit_receiver());
+
+    let sequence = committer.try_commit(BlockReference::new_test(0, 0));
+    assert_eq!(sequence.len(), 5);
+    assert!(logs_contain("The commit receiver was dropped"));
+}
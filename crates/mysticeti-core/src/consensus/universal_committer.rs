@@ -1,15 +1,41 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::VecDeque, sync::Arc};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use parking_lot::{Mutex, MutexGuard};
+use tokio::sync::mpsc;
 
-use super::{base_committer::BaseCommitter, LeaderStatus, DEFAULT_WAVE_LENGTH};
+#[cfg(feature = "decision-stability")]
+use super::decision_stability::DecisionStability;
+use super::{
+    audit_log::{AuditDecision, AuditLog},
+    base_committer::BaseCommitter,
+    LeaderStatus,
+    DEFAULT_WAVE_LENGTH,
+};
 use crate::{
     block_store::BlockStore,
     committee::Committee,
-    consensus::base_committer::BaseCommitterOptions,
+    consensus::{
+        base_committer::BaseCommitterOptions,
+        leader_schedule::{LeaderSchedule, RoundRobinSchedule},
+        linearizer::{CommittedSubDag, Linearizer},
+    },
+    data::Data,
     metrics::Metrics,
-    types::{format_authority_round, AuthorityIndex, BlockReference, RoundNumber},
+    runtime,
+    types::{
+        format_authority_round,
+        AuthorityIndex,
+        BlockReference,
+        EpochNumber,
+        RoundNumber,
+        StatementBlock,
+    },
 };
 
 /// A universal committer uses a collection of committers to commit a sequence of leaders.
@@ -17,8 +43,25 @@ use crate::{
 /// multi-leaders, backup leaders, and pipelines.
 pub struct UniversalCommitter {
     block_store: BlockStore,
+    committee: Arc<Committee>,
     committers: Vec<BaseCommitter>,
+    leader_schedule: Arc<dyn LeaderSchedule>,
     metrics: Arc<Metrics>,
+    /// The options of the base committers, used to build the committers of the next epochs.
+    options: Vec<BaseCommitterOptions>,
+    /// The committers of the previous epochs, along with the last round of each epoch.
+    past_epochs: Vec<(RoundNumber, Vec<BaseCommitter>)>,
+    /// The last round of the current epoch (if the epoch is closed). Leaders above this round are
+    /// not decided until the committer is reconfigured for the next epoch.
+    epoch_end: Option<RoundNumber>,
+    /// The log of all decisions output by the committer (if enabled).
+    audit_log: Option<Mutex<AuditLog>>,
+    /// The capacity of the channel of committed sub-dags (see [`Self::commit_receiver`]).
+    commit_channel_capacity: usize,
+    /// Publishes the committed sub-dags, if a receiver was requested.
+    commit_publisher: Option<Mutex<CommitPublisher>>,
+    #[cfg(feature = "decision-stability")]
+    decision_stability: DecisionStability,
 }
 
 impl UniversalCommitter {
@@ -33,11 +76,7 @@ impl UniversalCommitter {
         // Try to decide as many leaders as possible, starting with the highest round.
         let mut leaders = VecDeque::new();
         for round in (last_decided_round..=highest_known_round).rev() {
-            for committer in self.committers.iter().rev() {
-                // Skip committers that don't have a leader for this round.
-                let Some(leader) = committer.elect_leader(round) else {
-                    continue;
-                };
+            for (committer, leader) in self.elect_leaders(round).into_iter().rev() {
                 tracing::debug!(
                     "Trying to decide {} with {committer}",
                     format_authority_round(leader, round)
@@ -59,8 +98,15 @@ impl UniversalCommitter {
             }
         }
 
+        // A growing number of undecided leaders signals that consensus is stalling.
+        let undecided = leaders
+            .iter()
+            .filter(|x| x.round() > last_decided_round && !x.is_decided())
+            .count();
+        self.metrics.undecided_leaders.set(undecided as i64);
+
         // The decided sequence is the longest prefix of decided leaders.
-        leaders
+        let mut sequence: Vec<_> = leaders
             .into_iter()
             // Skip all leaders before the last decided round.
             .skip_while(|x| (x.round(), x.authority()) != last_decided_round_authority)
@@ -70,20 +116,285 @@ impl UniversalCommitter {
             .filter(|x| x.round() > 0)
             // Stop the sequence upon encountering an undecided leader.
             .take_while(|x| x.is_decided())
-            .inspect(|x| tracing::debug!("Decided {x}"))
-            .collect()
+            .collect();
+
+        // Stop the sequence if the leader schedule changes: the following leaders need to be
+        // decided again under the new schedule.
+        if let Some(position) = sequence
+            .iter()
+            .position(|x| self.leader_schedule.on_decided(x))
+        {
+            sequence.truncate(position + 1);
+        }
+        // Withhold the leaders whose sub-dags cannot be published yet: they are decided again
+        // (and published) once the consumer of the commit receiver catches up.
+        if let Some(publisher) = &self.commit_publisher {
+            let published = publisher.lock().publish(&self.block_store, &sequence);
+            sequence.truncate(published);
+        }
+        for leader in &sequence {
+            tracing::debug!("Decided {leader}");
+        }
+        // Only the leaders above the commit watermark are newly committed: the others were
+        // already returned by a previous call and their latency is already recorded.
+        let committed_round = self.block_store.committed_round();
+        for leader in &sequence {
+            if let LeaderStatus::Commit(block) = leader {
+                if block.round() > committed_round {
+                    self.observe_commit_latency(block);
+                }
+            }
+        }
+        // Publish the commit watermark: blocks below it may be garbage collected.
+        if let Some(last) = sequence.last() {
+            self.block_store.set_committed_round(last.round());
+            self.leader_schedule.on_committed(last.round());
+        }
+        if let Some(audit_log) = &self.audit_log {
+            self.record_decisions(&mut audit_log.lock(), last_decided, &sequence);
+        }
+
+        #[cfg(feature = "decision-stability")]
+        self.decision_stability.check(&sequence);
+
+        sequence
+    }
+
+    /// Rebuild the leader schedule from the leaders committed before a restart (in commit order),
+    /// so that it elects the same leaders as the other validators. The schedule is notified of
+    /// every leader slot up to the last committed leader, as during the original run: the slots
+    /// whose leader is not committed were skipped.
+    pub fn recover_leader_schedule(&self, committed_leaders: &[BlockReference]) {
+        let Some(last) = committed_leaders.last() else {
+            return;
+        };
+        if !self.leader_schedule.is_adaptive() {
+            return;
+        }
+        let committed: HashMap<_, _> = committed_leaders
+            .iter()
+            .map(|x| ((x.round, x.authority), *x))
+            .collect();
+        'rounds: for round in 1..=last.round {
+            for (_, leader) in self.elect_leaders(round) {
+                let status = match committed.get(&(round, leader)) {
+                    Some(reference) => LeaderStatus::Commit(
+                        self.block_store
+                            .get_block(*reference)
+                            .expect("Committed leaders are in the block store"),
+                    ),
+                    None => LeaderStatus::Skip(leader, round),
+                };
+                self.leader_schedule.on_decided(&status);
+                if (round, leader) == (last.round, last.authority) {
+                    break 'rounds;
+                }
+            }
+        }
+        self.leader_schedule.on_committed(last.round);
     }
 
     /// Return list of leaders for the round. Syncer may give those leaders some extra time.
     /// To preserve (theoretical) liveness, we should wait `Delta` time for at least the first leader.
     /// Can return empty vec if round does not have a designated leader.
     pub fn get_leaders(&self, round: RoundNumber) -> Vec<AuthorityIndex> {
-        self.committers
-            .iter()
-            .filter_map(|committer| committer.elect_leader(round))
+        self.elect_leaders(round)
+            .into_iter()
+            .map(|(_, leader)| leader)
             .collect()
     }
 
+    /// Return the blocks above the round of the last decided leader, ordered by round. None of
+    /// them is in the causal history of a decided leader, that is, none of them is committed.
+    pub fn uncommitted_blocks(&self, last_decided: BlockReference) -> Vec<BlockReference> {
+        let highest_known_round = self.block_store.highest_round();
+        (last_decided.round() + 1..=highest_known_round)
+            .flat_map(|round| self.block_store.get_blocks_by_round(round))
+            .map(|block| *block.reference())
+            .collect()
+    }
+
+    /// The number of rounds between the highest round of the dag and the round up to which all
+    /// leaders are decided (as recorded in the block store). A growing gap signals that the
+    /// commits lag behind the dag.
+    pub fn commit_gap(&self) -> RoundNumber {
+        self.block_store
+            .highest_round()
+            .saturating_sub(self.block_store.committed_round())
+    }
+
+    /// The log of all decisions output by the committer, if the audit log is enabled.
+    pub fn audit_log(&self) -> Option<MutexGuard<'_, AuditLog>> {
+        self.audit_log.as_ref().map(|x| x.lock())
+    }
+
+    /// Return a channel receiving the sub-dag of each leader committed from now on, linearized
+    /// and in commit order, so that consumers do not need to poll `try_commit`. Publishing never
+    /// blocks: the sub-dags that do not fit in the (bounded) channel wait in a queue of the same
+    /// capacity, drained by the following calls to `try_commit`. Once this queue is full as well,
+    /// `try_commit` stops returning new leaders until the consumer catches up. Dropping the
+    /// receiver stops the publication. Requesting a new receiver closes the previous one.
+    pub fn commit_receiver(&mut self) -> mpsc::Receiver<CommittedSubDag> {
+        let capacity = self.commit_channel_capacity;
+        let (sender, receiver) = mpsc::channel(capacity);
+        self.commit_publisher = Some(Mutex::new(CommitPublisher::new(sender, capacity)));
+        receiver
+    }
+
+    /// The commit watermark: the highest round up to which all leaders are decided. Blocks below
+    /// it are no longer needed to decide leaders (see [`BlockStore::prune_below`]).
+    pub fn last_committed_round(&self) -> RoundNumber {
+        self.block_store.committed_round()
+    }
+
+    /// The epoch of the committee electing and deciding the leaders of the current epoch.
+    pub fn epoch(&self) -> EpochNumber {
+        self.committee.epoch()
+    }
+
+    /// Close the current epoch at the specified round. The leaders above this round are not
+    /// decided until the committer is reconfigured with the committee of the next epoch.
+    pub fn close_epoch(&mut self, last_round: RoundNumber) {
+        assert!(
+            self.epoch_end.is_none(),
+            "Epoch {} is already closed",
+            self.epoch()
+        );
+        self.epoch_end = Some(last_round);
+    }
+
+    /// Switch to the committee of the next epoch from the specified round onwards (closing the
+    /// current epoch right below it, if not already closed there). The leaders of the rounds
+    /// below `switch_round` are still decided by the committee of their own epoch, and no leader
+    /// of the new epoch is committed before all the leaders of the previous epochs are decided.
+    pub fn reconfigure(&mut self, committee: Arc<Committee>, switch_round: RoundNumber) {
+        let last_round = switch_round
+            .checked_sub(1)
+            .expect("The new epoch must start after the genesis round");
+        if let Some(epoch_end) = self.epoch_end.take() {
+            assert_eq!(
+                epoch_end,
+                last_round,
+                "Epoch {} is closed at round {epoch_end}, it cannot switch at round {switch_round}",
+                self.epoch()
+            );
+        }
+        if let Some((previous_end, _)) = self.past_epochs.last() {
+            assert!(
+                last_round > *previous_end,
+                "Epoch {} starts after round {previous_end}, it cannot switch at round {switch_round}",
+                self.epoch()
+            );
+        }
+        assert!(
+            committee.epoch() > self.epoch(),
+            "Cannot reconfigure epoch {} into epoch {}",
+            self.epoch(),
+            committee.epoch()
+        );
+        tracing::info!(
+            "Reconfiguring to epoch {} after round {last_round}",
+            committee.epoch()
+        );
+
+        let leader_schedule = self.leader_schedule.for_committee(committee.clone());
+        let committers = base_committers(
+            &committee,
+            &self.block_store,
+            &leader_schedule,
+            &self.options,
+        );
+        let previous = std::mem::replace(&mut self.committers, committers);
+        self.past_epochs.push((last_round, previous));
+        self.committee = committee;
+        self.leader_schedule = leader_schedule;
+    }
+
+    /// Return the committers of the epoch to which the specified round belongs.
+    fn epoch_committers(&self, round: RoundNumber) -> &[BaseCommitter] {
+        let past_epoch = self
+            .past_epochs
+            .iter()
+            .find(|(last_round, _)| round <= *last_round);
+        match past_epoch {
+            Some((_, committers)) => committers,
+            None if self.epoch_end.is_some_and(|last_round| round > last_round) => &[],
+            None => &self.committers,
+        }
+    }
+
+    /// Return the committers electing a leader for the specified round, along with their leader.
+    /// A stake-weighted schedule may elect the same authority for multiple slots of a round; only
+    /// the first of these slots is kept so that every leader block is decided once.
+    fn elect_leaders(&self, round: RoundNumber) -> Vec<(&BaseCommitter, AuthorityIndex)> {
+        let mut leaders: Vec<(&BaseCommitter, AuthorityIndex)> = Vec::new();
+        for committer in self.epoch_committers(round) {
+            // Skip committers that don't have a leader for this round.
+            let Some(leader) = committer.elect_leader(round) else {
+                continue;
+            };
+            if leaders.iter().all(|(_, x)| *x != leader) {
+                leaders.push((committer, leader));
+            }
+        }
+        leaders
+    }
+
+    /// Append the specified decided leaders to the audit log, along with their supporting blocks.
+    /// Since `try_commit` is idempotent, the leaders up to the last recorded one are skipped.
+    fn record_decisions(
+        &self,
+        audit_log: &mut AuditLog,
+        last_decided: BlockReference,
+        sequence: &[LeaderStatus],
+    ) {
+        let start = match audit_log.last_decision() {
+            Some(last) => {
+                let last = (last.round(), last.authority());
+                match sequence
+                    .iter()
+                    .position(|x| (x.round(), x.authority()) == last)
+                {
+                    Some(position) => position + 1,
+                    // The sequence starts right after the last decided leader.
+                    None if last.0 < last_decided.round
+                        || last == (last_decided.round, last_decided.authority) =>
+                    {
+                        0
+                    }
+                    None => sequence.partition_point(|x| x.round() <= last.0),
+                }
+            }
+            None => 0,
+        };
+        for status in &sequence[start..] {
+            let Some(decision) = AuditDecision::from_status(status) else {
+                continue;
+            };
+            let support = self
+                .elect_leaders(status.round())
+                .into_iter()
+                .find(|(_, leader)| *leader == status.authority())
+                .map(|(committer, _)| committer.supporting_blocks(status))
+                .unwrap_or_default();
+            audit_log.append(decision, support);
+        }
+    }
+
+    /// Record the number of rounds (and the time) elapsed between the creation of a leader
+    /// block and its commit.
+    fn observe_commit_latency(&self, leader_block: &Data<StatementBlock>) {
+        let latency = self
+            .block_store
+            .highest_round()
+            .saturating_sub(leader_block.round());
+        self.metrics.commit_latency_rounds.observe(latency as f64);
+        let latency = runtime::timestamp_utc().saturating_sub(leader_block.meta_creation_time());
+        self.metrics
+            .commit_latency_seconds
+            .observe(latency.as_secs_f64());
+    }
+
     /// Update metrics.
     fn update_metrics(&self, leader: &LeaderStatus, direct_decide: bool) {
         let authority = leader.authority().to_string();
@@ -109,17 +420,23 @@ pub struct UniversalCommitterBuilder {
     wave_length: RoundNumber,
     number_of_leaders: usize,
     pipeline: bool,
+    leader_schedule: Arc<dyn LeaderSchedule>,
+    audit_log: Option<AuditLog>,
+    commit_channel_capacity: usize,
 }
 
 impl UniversalCommitterBuilder {
     pub fn new(committee: Arc<Committee>, block_store: BlockStore, metrics: Arc<Metrics>) -> Self {
         Self {
+            leader_schedule: Arc::new(RoundRobinSchedule::new(committee.clone())),
             committee,
             block_store,
             metrics,
             wave_length: DEFAULT_WAVE_LENGTH,
             number_of_leaders: 1,
             pipeline: false,
+            audit_log: None,
+            commit_channel_capacity: DEFAULT_COMMIT_CHANNEL_CAPACITY,
         }
     }
 
@@ -138,27 +455,164 @@ impl UniversalCommitterBuilder {
         self
     }
 
+    pub fn with_leader_schedule(mut self, leader_schedule: Arc<dyn LeaderSchedule>) -> Self {
+        self.leader_schedule = leader_schedule;
+        self
+    }
+
+    /// Record all decisions of the committer in the specified tamper-evident audit log (which may
+    /// already hold the decisions made before a restart, see [`AuditLog::open`]).
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// The number of committed sub-dags buffered for a slow consumer of the commit channel before
+    /// the committer blocks (see [`UniversalCommitter::commit_receiver`]).
+    pub fn with_commit_channel_capacity(mut self, capacity: usize) -> Self {
+        self.commit_channel_capacity = capacity;
+        self
+    }
+
     pub fn build(self) -> UniversalCommitter {
-        let mut committers = Vec::new();
+        let mut options = Vec::new();
         let pipeline_stages = if self.pipeline { self.wave_length } else { 1 };
         for round_offset in 0..pipeline_stages {
             for leader_offset in 0..self.number_of_leaders {
-                let options = BaseCommitterOptions {
+                options.push(BaseCommitterOptions {
                     wave_length: self.wave_length,
                     round_offset,
                     leader_offset: leader_offset as RoundNumber,
-                };
-                let committer =
-                    BaseCommitter::new(self.committee.clone(), self.block_store.clone())
-                        .with_options(options);
-                committers.push(committer);
+                });
             }
         }
+        let committers = base_committers(
+            &self.committee,
+            &self.block_store,
+            &self.leader_schedule,
+            &options,
+        );
 
         UniversalCommitter {
             block_store: self.block_store,
+            committee: self.committee,
             committers,
+            leader_schedule: self.leader_schedule,
             metrics: self.metrics,
+            options,
+            past_epochs: Vec::new(),
+            epoch_end: None,
+            audit_log: self.audit_log.map(Mutex::new),
+            commit_channel_capacity: self.commit_channel_capacity,
+            commit_publisher: None,
+            #[cfg(feature = "decision-stability")]
+            decision_stability: DecisionStability::default(),
+        }
+    }
+}
+
+/// The default capacity of the channel of committed sub-dags.
+const DEFAULT_COMMIT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Linearizes the sub-dags of the committed leaders and sends them to the commit channel.
+struct CommitPublisher {
+    sender: mpsc::Sender<CommittedSubDag>,
+    linearizer: Linearizer,
+    /// The sub-dags waiting for room in the channel, in commit order.
+    pending: VecDeque<CommittedSubDag>,
+    /// The maximum number of pending sub-dags.
+    capacity: usize,
+    /// The round and authority of the last decided leader already published (or skipped).
+    last_published: Option<(RoundNumber, AuthorityIndex)>,
+    /// Whether the receiver was dropped, in which case nothing is published anymore.
+    closed: bool,
+}
+
+impl CommitPublisher {
+    fn new(sender: mpsc::Sender<CommittedSubDag>, capacity: usize) -> Self {
+        Self {
+            sender,
+            linearizer: Linearizer::new(),
+            pending: VecDeque::with_capacity(capacity),
+            capacity,
+            last_published: None,
+            closed: false,
+        }
+    }
+
+    /// Publish the sub-dags of the committed leaders of the sequence not yet published, and
+    /// return the length of the prefix of the sequence that is published (or queued). Since
+    /// `try_commit` is idempotent, the same leaders may be decided more than once.
+    fn publish(&mut self, block_store: &BlockStore, sequence: &[LeaderStatus]) -> usize {
+        self.flush();
+        if self.closed {
+            return sequence.len();
+        }
+
+        let start = match self.last_published {
+            Some(last) => match sequence
+                .iter()
+                .position(|x| (x.round(), x.authority()) == last)
+            {
+                Some(position) => position + 1,
+                None => sequence.partition_point(|x| x.round() <= last.0),
+            },
+            None => 0,
+        };
+        for (i, leader) in sequence.iter().enumerate().skip(start) {
+            if self.pending.len() >= self.capacity {
+                tracing::debug!("The commit receiver lags behind, withholding {leader}");
+                return i;
+            }
+            if let Some(block) = leader.clone().into_decided_block() {
+                self.pending
+                    .extend(self.linearizer.handle_commit(block_store, vec![block]));
+                self.flush();
+                if self.closed {
+                    break;
+                }
+            }
+            self.last_published = Some((leader.round(), leader.authority()));
         }
+        sequence.len()
     }
+
+    /// Move as many pending sub-dags as possible to the channel, without blocking.
+    fn flush(&mut self) {
+        while let Some(sub_dag) = self.pending.pop_front() {
+            match self.sender.try_send(sub_dag) {
+                Ok(()) => (),
+                Err(mpsc::error::TrySendError::Full(sub_dag)) => {
+                    self.pending.push_front(sub_dag);
+                    break;
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => {
+                    tracing::warn!(
+                        "The commit receiver was dropped, discarding {} committed sub-dags",
+                        self.pending.len() + 1
+                    );
+                    self.pending.clear();
+                    self.closed = true;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Make one base committer per set of options.
+fn base_committers(
+    committee: &Arc<Committee>,
+    block_store: &BlockStore,
+    leader_schedule: &Arc<dyn LeaderSchedule>,
+    options: &[BaseCommitterOptions],
+) -> Vec<BaseCommitter> {
+    options
+        .iter()
+        .map(|options| {
+            BaseCommitter::new(committee.clone(), block_store.clone())
+                .with_options(options.clone())
+                .with_leader_schedule(leader_schedule.clone())
+        })
+        .collect()
 }
@@ -5,6 +5,7 @@ use std::{
     collections::{HashSet, VecDeque},
     mem,
     sync::{atomic::AtomicU64, Arc},
+    time::Duration,
 };
 
 use minibytes::Bytes;
@@ -24,20 +25,28 @@ use crate::{
     committee::Committee,
     config::{NodePrivateConfig, NodePublicConfig},
     consensus::{
+        audit_log::AuditLog,
         linearizer::CommittedSubDag,
         universal_committer::{UniversalCommitter, UniversalCommitterBuilder},
+        GC_DEPTH,
     },
     crypto::Signer,
     data::Data,
+    dissemination::DisseminationTracker,
     epoch_close::EpochManager,
+    equivocation::ConflictingBlocks,
     metrics::{Metrics, UtilizationTimerVecExt},
-    runtime::timestamp_utc,
+    runtime::{timestamp_utc, TimeInstant},
     state::RecoveredState,
     threshold_clock::ThresholdClockAggregator,
     types::{AuthorityIndex, BaseStatement, BlockReference, RoundNumber, StatementBlock},
     wal::{WalPosition, WalSyncer, WalWriter},
 };
 
+/// The interval (in rounds) at which the core logs its progress. External tools (such as the
+/// orchestrator) rely on these logs to track the progress of the nodes.
+pub const ROUND_LOG_INTERVAL: RoundNumber = 10;
+
 pub struct Core<H: BlockHandler> {
     block_manager: BlockManager,
     pending: VecDeque<(WalPosition, MetaStatement)>,
@@ -56,11 +65,23 @@ pub struct Core<H: BlockHandler> {
     recovered_committed_blocks: Option<(HashSet<BlockReference>, Option<Bytes>)>,
     epoch_manager: EpochManager,
     rounds_in_epoch: RoundNumber,
+    retention_rounds: Option<RoundNumber>,
+    protect_lagging_peers: bool,
     committer: UniversalCommitter,
+    dissemination: DisseminationTracker,
+    /// The time at which the core started. Cleared once the first commit is recorded.
+    start_time: Option<TimeInstant>,
+    /// The blocks proposed in conflict with the own blocks (only when equivocating).
+    conflicting_blocks: ConflictingBlocks,
 }
 
 pub struct CoreOptions {
     fsync: bool,
+    /// Propose a second, conflicting block at every round (Byzantine testing only).
+    equivocate: bool,
+    /// Never send the own blocks to the peers, while still receiving theirs (Byzantine testing
+    /// only).
+    silent: bool,
 }
 
 #[derive(Debug)]
@@ -89,6 +110,7 @@ impl<H: BlockHandler> Core<H> {
             state,
             unprocessed_blocks,
             last_committed_leader,
+            committed_leaders,
             committed_blocks,
             committed_state,
         } = recovered;
@@ -130,11 +152,17 @@ impl<H: BlockHandler> Core<H> {
 
         let epoch_manager = EpochManager::new();
 
-        let committer =
+        let mut committer_builder =
             UniversalCommitterBuilder::new(committee.clone(), block_store.clone(), metrics.clone())
                 .with_number_of_leaders(public_config.parameters.number_of_leaders)
-                .with_pipeline(public_config.parameters.enable_pipelining)
-                .build();
+                .with_pipeline(public_config.parameters.enable_pipelining);
+        if public_config.parameters.enable_audit_log {
+            let audit_log =
+                AuditLog::open(private_config.audit_log()).expect("Failed to open the audit log");
+            committer_builder = committer_builder.with_audit_log(audit_log);
+        }
+        let committer = committer_builder.build();
+        committer.recover_leader_schedule(&committed_leaders);
         tracing::info!(
             "Pipeline enabled: {}",
             public_config.parameters.enable_pipelining
@@ -144,6 +172,8 @@ impl<H: BlockHandler> Core<H> {
             public_config.parameters.number_of_leaders
         );
 
+        block_store.set_cache_size(public_config.parameters.block_cache_size);
+
         let mut this = Self {
             block_manager,
             pending,
@@ -161,7 +191,12 @@ impl<H: BlockHandler> Core<H> {
             recovered_committed_blocks: Some((committed_blocks, committed_state)),
             epoch_manager,
             rounds_in_epoch: public_config.parameters.rounds_in_epoch,
+            retention_rounds: public_config.parameters.retention_rounds,
+            protect_lagging_peers: public_config.parameters.protect_lagging_peers,
             committer,
+            dissemination: DisseminationTracker::new(authority),
+            start_time: Some(TimeInstant::now()),
+            conflicting_blocks: ConflictingBlocks::default(),
         };
 
         if !unprocessed_blocks.is_empty() {
@@ -197,10 +232,24 @@ impl<H: BlockHandler> Core<H> {
                 .push_back((position, MetaStatement::Include(*processed.reference())));
             result.push(processed);
         }
+        self.record_dissemination(&result, timestamp_utc());
         self.run_block_handler(&result);
         result
     }
 
+    /// Record the dissemination latency of the own blocks that reached a quorum of authorities
+    /// thanks to the specified blocks, received at the specified time. The commit latency is
+    /// recorded separately by the committer.
+    fn record_dissemination(&mut self, blocks: &[Data<StatementBlock>], received: Duration) {
+        for block in blocks {
+            for latency in self.dissemination.receive(block, received, &self.committee) {
+                self.metrics
+                    .dissemination_latency_seconds
+                    .observe(latency.as_secs_f64());
+            }
+        }
+    }
+
     fn run_block_handler(&mut self, processed: &[Data<StatementBlock>]) {
         let _timer = self
             .metrics
@@ -300,9 +349,23 @@ impl<H: BlockHandler> Core<H> {
                 block.detailed()
             );
         }
+        if self.options.equivocate {
+            // The conflicting block has the same parents but no statement.
+            let conflicting = StatementBlock::new_with_signer(
+                self.authority,
+                clock_round,
+                block.includes().clone(),
+                vec![],
+                time_ns + 1,
+                self.epoch_changing(),
+                &self.signer,
+            );
+            self.conflicting_blocks.insert(Data::new(conflicting));
+        }
         self.threshold_clock
             .add_block(*block.reference(), &self.committee);
         self.block_handler.handle_proposal(&block);
+        self.dissemination.propose(&block, &self.committee);
         self.proposed_block_stats(&block);
         let next_entry = if let Some((pos, _)) = self.pending.get(0) {
             *pos
@@ -320,6 +383,10 @@ impl<H: BlockHandler> Core<H> {
         }
 
         tracing::debug!("Created block {block:?}");
+        self.metrics.current_round.set(clock_round as i64);
+        if clock_round % ROUND_LOG_INTERVAL == 0 {
+            tracing::info!("Reached round {clock_round}");
+        }
         Some(block)
     }
 
@@ -358,6 +425,11 @@ impl<H: BlockHandler> Core<H> {
 
         if let Some(last) = sequence.last() {
             self.last_commit_leader = *last.reference();
+            if let Some(start_time) = self.start_time.take() {
+                self.metrics
+                    .time_to_first_commit
+                    .set(start_time.elapsed().as_secs_f64());
+            }
         }
 
         // todo: should ideally come from execution result of epoch smart contract
@@ -376,10 +448,39 @@ impl<H: BlockHandler> Core<H> {
                 .round()
                 .saturating_sub(RETAIN_BELOW_COMMIT_ROUNDS),
         );
+        if let Some(retention_rounds) = self.retention_rounds {
+            let slowest_peer_round = self
+                .protect_lagging_peers
+                .then(|| self.block_store.slowest_peer_round());
+            self.block_store.prune_below(Self::pruning_floor(
+                self.committer.last_committed_round(),
+                self.block_store.highest_round(),
+                retention_rounds,
+                slowest_peer_round,
+            ));
+        }
 
         self.block_handler.cleanup();
     }
 
+    /// The round below which blocks can be pruned from the block store. Blocks less than
+    /// [`GC_DEPTH`] rounds below the last decided leader may still be committed along with the
+    /// next leaders, and blocks above the round of the slowest peer (if specified) may still be
+    /// needed by that peer, so they are never pruned. The local views (retention window and
+    /// slowest peer) can only lower the floor below the gc round shared by all validators, so
+    /// they never change which blocks are committed.
+    fn pruning_floor(
+        last_decided_round: RoundNumber,
+        highest_round: RoundNumber,
+        retention_rounds: RoundNumber,
+        slowest_peer_round: Option<RoundNumber>,
+    ) -> RoundNumber {
+        last_decided_round
+            .saturating_sub(GC_DEPTH)
+            .min(highest_round.saturating_sub(retention_rounds))
+            .min(slowest_peer_round.unwrap_or(RoundNumber::MAX))
+    }
+
     /// This only checks readiness in terms of helping liveness for commit rule,
     /// try_new_block might still return None if threshold clock is not ready
     ///
@@ -441,6 +542,10 @@ impl<H: BlockHandler> Core<H> {
         self.wal_writer
             .write(WAL_ENTRY_COMMIT, &commits)
             .expect("Write to wal has failed");
+        // Each batch of commits is persisted before being acted upon.
+        if self.options.fsync {
+            self.wal_writer.sync().expect("Wal sync failed");
+        }
     }
 
     pub fn take_recovered_committed_blocks(&mut self) -> (HashSet<BlockReference>, Option<Bytes>) {
@@ -492,6 +597,14 @@ impl<H: BlockHandler> Core<H> {
     pub fn epoch_closing_time(&self) -> Arc<AtomicU64> {
         self.epoch_manager.closing_time()
     }
+
+    pub fn conflicting_blocks(&self) -> ConflictingBlocks {
+        self.conflicting_blocks.clone()
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.options.silent
+    }
 }
 
 impl Default for CoreOptions {
@@ -502,11 +615,31 @@ impl Default for CoreOptions {
 
 impl CoreOptions {
     pub fn test() -> Self {
-        Self { fsync: false }
+        Self {
+            fsync: false,
+            equivocate: false,
+            silent: false,
+        }
     }
 
     pub fn production() -> Self {
-        Self { fsync: true }
+        Self {
+            fsync: true,
+            equivocate: false,
+            silent: false,
+        }
+    }
+
+    /// Make the core propose a second, conflicting block at every round.
+    pub fn with_equivocation(mut self, equivocate: bool) -> Self {
+        self.equivocate = equivocate;
+        self
+    }
+
+    /// Make the core withhold its own blocks from the peers.
+    pub fn with_silence(mut self, silent: bool) -> Self {
+        self.silent = silent;
+        self
     }
 }
 
@@ -518,6 +651,8 @@ mod test {
 
     use super::*;
     use crate::{
+        block_handler::TestBlockHandler,
+        crypto::compute_test_digests,
         test_util::{committee_and_cores, committee_and_cores_persisted},
         threshold_clock,
     };
@@ -735,4 +870,142 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_time_to_first_commit() {
+        let (_committee, mut cores, _) = committee_and_cores(4);
+
+        let mut first_commit = None;
+        let mut later_commits = 0;
+        for _ in 0..20 {
+            let blocks: Vec<_> = cores
+                .iter_mut()
+                .map(|core| {
+                    core.run_block_handler(&[]);
+                    core.try_new_block()
+                        .expect("Must be able to create block after full round")
+                })
+                .collect();
+            for core in &mut cores {
+                core.add_blocks(blocks.clone());
+            }
+
+            let committed = cores[0].try_commit();
+            let time_to_first_commit = cores[0].metrics.time_to_first_commit.get();
+            match first_commit {
+                None if committed.is_empty() => assert_eq!(time_to_first_commit, 0.0),
+                None => {
+                    assert!(time_to_first_commit > 0.0);
+                    first_commit = Some(time_to_first_commit);
+                    // Ensure a second update would be noticed.
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                }
+                Some(first) => {
+                    later_commits += committed.len();
+                    assert_eq!(time_to_first_commit, first);
+                }
+            }
+        }
+        assert!(first_commit.is_some());
+        assert!(later_commits > 0);
+    }
+
+    #[test]
+    fn test_equivocation() {
+        compute_test_digests();
+        let (committee, mut cores, _) = committee_and_cores(4);
+        let equivocator = cores
+            .remove(0)
+            .with_options(CoreOptions::test().with_equivocation(true));
+        cores.insert(0, equivocator);
+
+        let blocks: Vec<_> = cores
+            .iter_mut()
+            .map(|core| {
+                core.run_block_handler(&[]);
+                core.try_new_block().unwrap()
+            })
+            .collect();
+        let conflicting = cores[0]
+            .conflicting_blocks()
+            .get(1)
+            .expect("The equivocator must propose a conflicting block");
+        assert_ne!(conflicting.reference(), blocks[0].reference());
+        assert_eq!(conflicting.includes(), blocks[0].includes());
+        conflicting.verify(&committee).unwrap();
+        assert!(cores[1].conflicting_blocks().get(1).is_none());
+
+        // A peer receiving both blocks detects the equivocation.
+        cores[1].add_blocks(vec![blocks[0].clone(), conflicting]);
+        assert!(cores[1].block_store().is_equivocation(0, 1));
+    }
+
+    #[test]
+    fn test_dissemination_latency() {
+        let (_committee, mut cores, _) = committee_and_cores(4);
+
+        let blocks_r1: Vec<_> = cores
+            .iter_mut()
+            .map(|core| {
+                core.run_block_handler(&[]);
+                core.try_new_block().unwrap()
+            })
+            .collect();
+        let mut blocks_r2 = vec![];
+        for core in &mut cores {
+            core.add_blocks(blocks_r1.clone());
+            blocks_r2.push(core.try_new_block().unwrap());
+        }
+
+        // The round-1 block of authority 0 is received by a quorum along with the second
+        // round-2 block of its peers.
+        let proposed = blocks_r1[0].meta_creation_time();
+        let metrics = cores[0].metrics.clone();
+        cores[0].record_dissemination(&blocks_r2[1..2], proposed + Duration::from_millis(100));
+        assert_eq!(metrics.dissemination_latency_seconds.get_sample_count(), 0);
+        cores[0].record_dissemination(&blocks_r2[2..], proposed + Duration::from_millis(250));
+        assert_eq!(metrics.dissemination_latency_seconds.get_sample_count(), 1);
+        assert_eq!(metrics.dissemination_latency_seconds.get_sample_sum(), 0.25);
+
+        // Dissemination is not mistaken for commit latency.
+        assert_eq!(metrics.commit_latency_seconds.get_sample_count(), 0);
+    }
+
+    #[test]
+    fn test_pruning_floor() {
+        // The retention window bounds the floor when the commits are recent.
+        assert_eq!(
+            Core::<TestBlockHandler>::pruning_floor(950 + GC_DEPTH, 1000, 100, None),
+            900
+        );
+        // Blocks that may still be committed along with the next leaders are never pruned.
+        assert_eq!(
+            Core::<TestBlockHandler>::pruning_floor(500 + GC_DEPTH, 1000, 100, None),
+            500
+        );
+        assert_eq!(Core::<TestBlockHandler>::pruning_floor(0, 50, 100, None), 0);
+    }
+
+    #[test]
+    fn test_pruning_floor_lagging_peer() {
+        // A lagging peer prevents pruning the blocks it still needs.
+        assert_eq!(
+            Core::<TestBlockHandler>::pruning_floor(950 + GC_DEPTH, 1000, 100, Some(700)),
+            700
+        );
+        // Peers ahead of the retention window do not hold back pruning.
+        assert_eq!(
+            Core::<TestBlockHandler>::pruning_floor(950 + GC_DEPTH, 1000, 100, Some(990)),
+            900
+        );
+        // Nor do peers ahead of the gc round of the last decided leader, whatever the window.
+        assert_eq!(
+            Core::<TestBlockHandler>::pruning_floor(500 + GC_DEPTH, 1000, 0, Some(990)),
+            500
+        );
+        for slowest_peer_round in [None, Some(0), Some(700), Some(1000)] {
+            let floor = Core::<TestBlockHandler>::pruning_floor(700, 1000, 0, slowest_peer_round);
+            assert!(floor <= 700 - GC_DEPTH);
+        }
+    }
 }
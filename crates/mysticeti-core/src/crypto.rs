@@ -1,6 +1,8 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(test)]
+use std::cell::Cell;
 use std::fmt;
 
 use digest::Digest;
@@ -10,8 +12,6 @@ use rand::{rngs::StdRng, SeedableRng};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use zeroize::Zeroize;
 
-#[cfg(not(test))]
-use crate::types::Vote;
 use crate::{
     serde::{ByteRepr, BytesVisitor},
     types::{
@@ -22,6 +22,7 @@ use crate::{
         RoundNumber,
         StatementBlock,
         TimestampNs,
+        Vote,
     },
 };
 
@@ -41,11 +42,22 @@ pub struct SignatureBytes([u8; SIGNATURE_SIZE]);
 #[derive(Serialize, Deserialize)]
 pub struct Signer(Box<ed25519_consensus::SigningKey>);
 
-#[cfg(not(test))]
 type BlockHasher = blake2::Blake2b<digest::consts::U32>;
 
+#[cfg(test)]
+thread_local! {
+    static COMPUTE_TEST_DIGESTS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Compute the digests of the blocks created by the current thread. Test blocks otherwise share
+/// the default digest, so that tests can refer to them by author and round alone; conflicting
+/// blocks (equivocations) are only told apart by their digests.
+#[cfg(test)]
+pub fn compute_test_digests() {
+    COMPUTE_TEST_DIGESTS.with(|x| x.set(true));
+}
+
 impl BlockDigest {
-    #[cfg(not(test))]
     pub fn new(
         authority: AuthorityIndex,
         round: RoundNumber,
@@ -55,6 +67,10 @@ impl BlockDigest {
         epoch_marker: EpochStatus,
         signature: &SignatureBytes,
     ) -> Self {
+        #[cfg(test)]
+        if !COMPUTE_TEST_DIGESTS.with(Cell::get) {
+            return Default::default();
+        }
         let mut hasher = BlockHasher::default();
         Self::digest_without_signature(
             &mut hasher,
@@ -69,17 +85,10 @@ impl BlockDigest {
         Self(hasher.finalize().into())
     }
 
-    #[cfg(test)]
-    pub fn new(
-        _authority: AuthorityIndex,
-        _round: RoundNumber,
-        _includes: &[BlockReference],
-        _statements: &[BaseStatement],
-        _meta_creation_time_ns: TimestampNs,
-        _epoch_marker: EpochStatus,
-        _signature: &SignatureBytes,
-    ) -> Self {
-        Default::default()
+    /// Parse a digest from its (full) hex representation.
+    pub fn from_hex(s: &str) -> Option<Self> {
+        let bytes = hex::decode(s).ok()?;
+        Some(Self(bytes.try_into().ok()?))
     }
 
     /// There is a bit of a complexity around what is considered block digest and what is being signed
@@ -89,7 +98,6 @@ impl BlockDigest {
     ///
     /// This is not very beautiful, but it allows to optimize block synchronization,
     /// by skipping signature verification for all the descendants of the certified block.
-    #[cfg(not(test))]
     fn digest_without_signature(
         hasher: &mut BlockHasher,
         authority: AuthorityIndex,
@@ -0,0 +1,286 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    committee::Committee,
+    consensus::{
+        universal_committer::{UniversalCommitter, UniversalCommitterBuilder},
+        LeaderStatus,
+        DEFAULT_WAVE_LENGTH,
+    },
+    data::Data,
+    test_util::{test_metrics, TestBlockWriter},
+    types::{AuthorityIndex, BlockReference, RoundNumber, StatementBlock},
+};
+
+/// The faults injected in the dags generated by the simulator.
+#[derive(Clone, Debug, Default)]
+pub struct FaultModel {
+    /// The authorities crashing, along with the round from which they stop proposing blocks.
+    pub crashes: Vec<(AuthorityIndex, RoundNumber)>,
+    /// The probability that a block omits each parent that is not needed to reach a quorum
+    /// (modelling asynchrony). Ignored during the synchronous rounds closing each run.
+    pub omission_probability: f64,
+}
+
+impl FaultModel {
+    /// Whether the specified authority proposes a block at the specified round.
+    fn proposes(&self, authority: AuthorityIndex, round: RoundNumber) -> bool {
+        self.crashes
+            .iter()
+            .all(|(crashed, from)| *crashed != authority || round < *from)
+    }
+}
+
+/// Generates random (valid) dags under a fault model and drives a committer over them
+/// incrementally, checking its safety and liveness. Runs are fully determined by their seed.
+pub struct DagSimulator {
+    committee: Arc<Committee>,
+    faults: FaultModel,
+    wave_length: RoundNumber,
+    number_of_leaders: usize,
+    pipeline: bool,
+}
+
+impl DagSimulator {
+    pub fn new(committee: Arc<Committee>, faults: FaultModel) -> Self {
+        let crashed_stake = faults
+            .crashes
+            .iter()
+            .map(|(authority, _)| committee.get_stake(*authority).unwrap())
+            .sum();
+        assert!(
+            !committee.is_valid(crashed_stake),
+            "The crashed authorities must hold less than a third of the stake"
+        );
+        Self {
+            committee,
+            faults,
+            wave_length: DEFAULT_WAVE_LENGTH,
+            number_of_leaders: 1,
+            pipeline: false,
+        }
+    }
+
+    pub fn with_wave_length(mut self, wave_length: RoundNumber) -> Self {
+        self.wave_length = wave_length;
+        self
+    }
+
+    pub fn with_number_of_leaders(mut self, number_of_leaders: usize) -> Self {
+        self.number_of_leaders = number_of_leaders;
+        self
+    }
+
+    pub fn with_pipeline(mut self, pipeline: bool) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Generate a random dag of the specified number of rounds (followed by a few synchronous
+    /// rounds) and deliver it to one observer per authority, each receiving the blocks in its own
+    /// random order and trying to commit after every round. Panics if two observers commit
+    /// conflicting sequences, if an observer changes a decision, or if a leader of the random
+    /// rounds is still undecided at the end of the run. Returns the decided leaders.
+    pub fn run(&self, seed: u64, rounds: RoundNumber) -> Vec<LeaderStatus> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut observers: Vec<_> = self
+            .committee
+            .authorities()
+            .map(|_| Observer::new(self))
+            .collect();
+
+        // Leave enough synchronous rounds for every authority to be elected leader at least once.
+        let synchronous_rounds = (self.committee.len() as RoundNumber + 2) * self.wave_length;
+        let genesis = self
+            .committee
+            .authorities()
+            .map(StatementBlock::new_genesis)
+            .collect();
+        let mut dag: Vec<Vec<_>> = vec![genesis];
+        for round in 1..=rounds + synchronous_rounds {
+            let omission_probability = if round <= rounds {
+                self.faults.omission_probability
+            } else {
+                0.0
+            };
+            let blocks = self.build_round(&mut rng, &dag[round as usize - 1], omission_probability);
+            dag.push(blocks);
+            for observer in &mut observers {
+                observer.receive(&mut rng, &dag);
+                observer.try_commit();
+            }
+        }
+        for observer in &mut observers {
+            observer.receive_all(&dag);
+            observer.try_commit();
+        }
+
+        // Safety: the decided sequences of all observers are prefixes of the longest one.
+        let longest = observers
+            .iter()
+            .map(|x| &x.decided)
+            .max_by_key(|x| x.len())
+            .unwrap()
+            .clone();
+        for observer in &observers {
+            assert_eq!(
+                observer.decided[..],
+                longest[..observer.decided.len()],
+                "Observers decided conflicting sequences (seed {seed})"
+            );
+        }
+
+        // Liveness: all leaders of the random rounds are decided once the network is synchronous.
+        for observer in &observers {
+            let last_decided = observer.decided.last().map_or(0, LeaderStatus::round);
+            assert!(
+                last_decided > rounds,
+                "Leaders up to round {rounds} are not all decided (seed {seed})"
+            );
+        }
+        longest
+    }
+
+    /// Build the blocks of the next round. Each authority references its own previous block and
+    /// enough random blocks of the previous round to reach a quorum, along with each remaining
+    /// block with probability `1 - omission_probability`.
+    fn build_round(
+        &self,
+        rng: &mut StdRng,
+        previous: &[Data<StatementBlock>],
+        omission_probability: f64,
+    ) -> Vec<Data<StatementBlock>> {
+        let round = previous[0].round() + 1;
+        let mut blocks = Vec::new();
+        for authority in self.committee.authorities() {
+            if !self.faults.proposes(authority, round) {
+                continue;
+            }
+            let own = previous.iter().find(|x| x.author() == authority);
+            let mut others: Vec<_> = previous
+                .iter()
+                .filter(|x| x.author() != authority)
+                .collect();
+            others.shuffle(rng);
+
+            let mut includes: Vec<_> = own.map(|x| *x.reference()).into_iter().collect();
+            let mut stake = own.map_or(0, |_| self.committee.get_stake(authority).unwrap());
+            for block in others {
+                let needed = !self.committee.is_quorum(stake);
+                if needed || !rng.gen_bool(omission_probability) {
+                    includes.push(*block.reference());
+                    stake += self.committee.get_stake(block.author()).unwrap();
+                }
+            }
+
+            blocks.push(Data::new(StatementBlock::new(
+                authority,
+                round,
+                includes,
+                vec![],
+                0,
+                false,
+                Default::default(),
+            )));
+        }
+        blocks
+    }
+}
+
+/// An authority receiving the blocks of the dag and running its own committer.
+struct Observer {
+    block_writer: TestBlockWriter,
+    committer: UniversalCommitter,
+    /// The number of rounds of the dag fully received.
+    received_rounds: usize,
+    /// The blocks received from the round above the fully received rounds.
+    partial: Vec<BlockReference>,
+    /// The leaders decided so far, in order.
+    decided: Vec<LeaderStatus>,
+    /// The number of decided leaders up to (and including) the last committed one.
+    committed: usize,
+}
+
+impl Observer {
+    fn new(simulator: &DagSimulator) -> Self {
+        let block_writer = TestBlockWriter::new(&simulator.committee);
+        let committer = UniversalCommitterBuilder::new(
+            simulator.committee.clone(),
+            block_writer.block_store(),
+            test_metrics(),
+        )
+        .with_wave_length(simulator.wave_length)
+        .with_number_of_leaders(simulator.number_of_leaders)
+        .with_pipeline(simulator.pipeline)
+        .build();
+        Self {
+            block_writer,
+            committer,
+            received_rounds: 0,
+            partial: Vec::new(),
+            decided: Vec::new(),
+            committed: 0,
+        }
+    }
+
+    /// Receive all pending blocks but those of the highest round of the dag, of which only a
+    /// random subset is received. The observer thus always holds a causally complete dag.
+    fn receive(&mut self, rng: &mut StdRng, dag: &[Vec<Data<StatementBlock>>]) {
+        self.receive_all(&dag[..dag.len() - 1]);
+        for block in dag.last().unwrap() {
+            if rng.gen_bool(0.5) {
+                self.receive_block(block);
+            }
+        }
+    }
+
+    /// Receive all pending blocks of the specified rounds.
+    fn receive_all(&mut self, dag: &[Vec<Data<StatementBlock>>]) {
+        for blocks in &dag[self.received_rounds..] {
+            for block in blocks {
+                self.receive_block(block);
+            }
+            self.received_rounds += 1;
+            self.partial.clear();
+        }
+    }
+
+    fn receive_block(&mut self, block: &Data<StatementBlock>) {
+        if !self.partial.contains(block.reference()) {
+            self.partial.push(*block.reference());
+            self.block_writer.add_block(block.clone());
+        }
+    }
+
+    /// Try to commit from the last committed leader, as the core does, and check that the
+    /// leaders decided since are decided again.
+    fn try_commit(&mut self) {
+        let last_committed = match self.committed {
+            0 => BlockReference::new_test(0, 0),
+            n => *self.decided[n - 1]
+                .clone()
+                .into_decided_block()
+                .unwrap()
+                .reference(),
+        };
+        let sequence = self.committer.try_commit(last_committed);
+        assert!(
+            sequence.starts_with(&self.decided[self.committed..]),
+            "The committer changed a decision"
+        );
+        self.decided.truncate(self.committed);
+        self.decided.extend(sequence);
+        if let Some(position) = self
+            .decided
+            .iter()
+            .rposition(|x| matches!(x, LeaderStatus::Commit(..)))
+        {
+            self.committed = position + 1;
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::{
+    committee::{Committee, QuorumThreshold, StakeAggregator},
+    types::{AuthorityIndex, RoundNumber, StatementBlock},
+};
+
+/// Track how long the own blocks of an authority take to reach a quorum of the committee. A peer
+/// received an own block once it references it (or a later own block, since every own block
+/// references its predecessor). Measuring this at the proposer only compares timestamps of the
+/// same clock, so the dissemination latency is not skewed by clock drift between nodes.
+pub struct DisseminationTracker {
+    authority: AuthorityIndex,
+    /// The own blocks not yet received by a quorum, indexed by round, along with their creation
+    /// time and the authorities that received them.
+    pending: BTreeMap<RoundNumber, (Duration, StakeAggregator<QuorumThreshold>)>,
+}
+
+impl DisseminationTracker {
+    pub fn new(authority: AuthorityIndex) -> Self {
+        Self {
+            authority,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Start tracking a block proposed by this authority.
+    pub fn propose(&mut self, block: &StatementBlock, committee: &Committee) {
+        let mut aggregator = StakeAggregator::new();
+        aggregator.add(self.authority, committee);
+        self.pending
+            .insert(block.round(), (block.meta_creation_time(), aggregator));
+    }
+
+    /// Process a block of a peer received at the specified time. Returns the dissemination
+    /// latency of the own blocks that reached a quorum thanks to this block.
+    pub fn receive(
+        &mut self,
+        block: &StatementBlock,
+        received: Duration,
+        committee: &Committee,
+    ) -> Vec<Duration> {
+        if block.author() == self.authority {
+            return Vec::new();
+        }
+        let Some(highest) = block
+            .includes()
+            .iter()
+            .filter(|x| x.authority == self.authority)
+            .map(|x| x.round)
+            .max()
+        else {
+            return Vec::new();
+        };
+
+        let mut latencies = Vec::new();
+        let mut disseminated = Vec::new();
+        for (round, (created, aggregator)) in self.pending.range_mut(..=highest) {
+            if aggregator.add(block.author(), committee) {
+                latencies.push(received.saturating_sub(*created));
+                disseminated.push(*round);
+            }
+        }
+        for round in disseminated {
+            self.pending.remove(&round);
+        }
+        latencies
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::DisseminationTracker;
+    use crate::{
+        committee::Committee,
+        types::{BlockReference, StatementBlock},
+    };
+
+    fn block(
+        authority: u64,
+        round: u64,
+        includes: Vec<BlockReference>,
+        time_ms: u64,
+    ) -> StatementBlock {
+        let time_ns = Duration::from_millis(time_ms).as_nanos();
+        StatementBlock::new(
+            authority,
+            round,
+            includes,
+            vec![],
+            time_ns,
+            false,
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn quorum_received() {
+        let committee = Committee::new_test(vec![1, 1, 1, 1]);
+        let mut tracker = DisseminationTracker::new(0);
+        let own = block(0, 1, vec![], 1_000);
+        tracker.propose(&own, &committee);
+
+        // The own block reaches a quorum once two peers reference it.
+        let first = block(1, 2, vec![*own.reference()], 1_100);
+        let received = tracker.receive(&first, Duration::from_millis(1_150), &committee);
+        assert!(received.is_empty());
+
+        let unrelated = block(2, 2, vec![], 1_150);
+        let received = tracker.receive(&unrelated, Duration::from_millis(1_200), &committee);
+        assert!(received.is_empty());
+
+        let second = block(3, 2, vec![*own.reference()], 1_200);
+        let received = tracker.receive(&second, Duration::from_millis(1_250), &committee);
+        assert_eq!(received, vec![Duration::from_millis(250)]);
+        assert!(tracker.pending.is_empty());
+    }
+
+    #[test]
+    fn later_block_received() {
+        let committee = Committee::new_test(vec![1, 1, 1, 1]);
+        let mut tracker = DisseminationTracker::new(0);
+        let first = block(0, 1, vec![], 1_000);
+        let second = block(0, 2, vec![*first.reference()], 1_100);
+        tracker.propose(&first, &committee);
+        tracker.propose(&second, &committee);
+
+        // Referencing the second own block implies having received the first one.
+        for author in 1..3 {
+            let peer = block(author, 3, vec![*second.reference()], 1_200);
+            let received = tracker.receive(&peer, Duration::from_millis(1_300), &committee);
+            if author == 2 {
+                assert_eq!(
+                    received,
+                    vec![Duration::from_millis(300), Duration::from_millis(200)]
+                );
+            }
+        }
+        assert!(tracker.pending.is_empty());
+
+        // Own blocks do not count as received by a peer.
+        let third = block(0, 3, vec![*second.reference()], 1_200);
+        tracker.propose(&third, &committee);
+        let received = tracker.receive(&third, Duration::from_millis(1_300), &committee);
+        assert!(received.is_empty());
+        assert_eq!(tracker.pending.len(), 1);
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use parking_lot::Mutex;
+
+use crate::{
+    data::Data,
+    types::{AuthorityIndex, BlockReference, RoundNumber, StatementBlock},
+};
+
+/// The blocks a deliberately equivocating (Byzantine) validator proposes in conflict with its
+/// own blocks, to test the safety of the protocol. There is at most one conflicting block per
+/// round. Conflicting blocks are not part of the block store (which holds a single own block
+/// per round): they are only sent to the peers selected by [`Self::receives_conflicting`], in
+/// place of the own block of the same round, and to the peers requesting them.
+#[derive(Clone, Default)]
+pub struct ConflictingBlocks(Arc<Mutex<BTreeMap<RoundNumber, Data<StatementBlock>>>>);
+
+impl ConflictingBlocks {
+    /// The number of rounds for which conflicting blocks are kept.
+    const RETAINED_ROUNDS: RoundNumber = 1_000;
+
+    /// Record a conflicting block, and forget the blocks of old rounds.
+    pub fn insert(&self, block: Data<StatementBlock>) {
+        let mut blocks = self.0.lock();
+        let floor = block.round().saturating_sub(Self::RETAINED_ROUNDS);
+        *blocks = blocks.split_off(&floor);
+        blocks.insert(block.round(), block);
+    }
+
+    /// The conflicting block of the specified round, if any.
+    pub fn get(&self, round: RoundNumber) -> Option<Data<StatementBlock>> {
+        self.0.lock().get(&round).cloned()
+    }
+
+    /// The conflicting block with the specified reference, if any.
+    pub fn get_block(&self, reference: BlockReference) -> Option<Data<StatementBlock>> {
+        self.get(reference.round)
+            .filter(|block| *block.reference() == reference)
+    }
+
+    /// Whether the specified peer receives the conflicting blocks instead of the own blocks. The
+    /// committee is split in half so that both blocks of each round reach several peers.
+    pub fn receives_conflicting(peer: AuthorityIndex) -> bool {
+        peer % 2 == 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ConflictingBlocks;
+    use crate::{data::Data, types::StatementBlock};
+
+    fn block(round: u64) -> Data<StatementBlock> {
+        Data::new(StatementBlock::new(
+            0,
+            round,
+            vec![],
+            vec![],
+            round as u128,
+            false,
+            Default::default(),
+        ))
+    }
+
+    #[test]
+    fn retain_recent_rounds() {
+        let blocks = ConflictingBlocks::default();
+        for round in 1..=1_500 {
+            blocks.insert(block(round));
+        }
+        assert!(blocks.get(499).is_none());
+        assert!(blocks.get(500).is_some());
+
+        let latest = block(1_500);
+        assert_eq!(blocks.get_block(*latest.reference()), Some(latest));
+        assert!(blocks.get_block(*block(1_501).reference()).is_none());
+    }
+}
@@ -4,18 +4,24 @@
 pub mod block_handler;
 mod block_manager;
 mod block_store;
+pub mod commit_sink;
 pub mod committee;
 pub mod config;
 pub mod consensus;
 pub mod core;
 mod core_thread;
 mod crypto;
+#[cfg(test)]
+mod dag_simulator;
 mod data;
+mod dissemination;
 mod epoch_close;
+mod equivocation;
 mod finalization_interpreter;
 #[cfg(test)]
 #[cfg(feature = "simulator")]
 mod future_simulator;
+mod load_controller;
 #[allow(dead_code)] // todo - delete if unused after a while
 mod lock;
 mod log;
@@ -0,0 +1,87 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/// Adjust the load submitted by the clients so that the commit throughput of the system settles
+/// near a target. Every node observes the same (system-wide) commit throughput and runs the same
+/// controller, so all nodes agree on the total submission rate and each submits its share.
+pub struct LoadController {
+    /// The target commit throughput of the system (tx/s).
+    target: f64,
+    /// The current total submission rate of the system (tx/s).
+    rate: f64,
+    /// The number of nodes sharing the load.
+    nodes: usize,
+}
+
+impl LoadController {
+    /// The fraction of the throughput error corrected at each update.
+    const GAIN: f64 = 0.5;
+    /// How far above the observed commit throughput the rate may increase. This prevents the
+    /// rate from growing without bounds when the system cannot reach the target.
+    const HEADROOM: f64 = 1.5;
+
+    /// Create a new controller starting at the specified (per-node) load.
+    pub fn new(target: usize, initial_load: usize, nodes: usize) -> Self {
+        let nodes = nodes.max(1);
+        Self {
+            target: target as f64,
+            rate: (initial_load * nodes) as f64,
+            nodes,
+        }
+    }
+
+    /// The load (tx/s) each node should submit.
+    pub fn load(&self) -> usize {
+        (self.rate / self.nodes as f64).round() as usize
+    }
+
+    /// Update the submission rate given the commit throughput (tx/s) observed over the last
+    /// interval, and return the new per-node load.
+    pub fn update(&mut self, observed: f64) -> usize {
+        let mut rate = self.rate + Self::GAIN * (self.target - observed);
+        if observed < self.target {
+            let ceiling = (observed * Self::HEADROOM).max(self.rate);
+            rate = rate.min(ceiling);
+        }
+        self.rate = rate.max(0.0);
+        self.load()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LoadController;
+
+    /// A synthetic system committing the submitted load up to its capacity, with a delay of one
+    /// control interval.
+    fn simulate(controller: &mut LoadController, nodes: usize, capacity: f64, steps: usize) {
+        let mut observed = 0.0;
+        for _ in 0..steps {
+            let submitted = (controller.load() * nodes) as f64;
+            let load = controller.update(observed);
+            assert!(load * nodes < 10 * 10_000, "The load diverged: {load}");
+            observed = submitted.min(capacity);
+        }
+    }
+
+    #[test]
+    fn converges_to_target() {
+        let nodes = 4;
+        for initial_load in [10, 2_500, 10_000] {
+            let mut controller = LoadController::new(10_000, initial_load, nodes);
+            simulate(&mut controller, nodes, 50_000.0, 50);
+            let total = (controller.load() * nodes) as f64;
+            assert!((total - 10_000.0).abs() < 100.0, "{initial_load}: {total}");
+        }
+    }
+
+    #[test]
+    fn saturated_system() {
+        // The system cannot reach the target: the rate remains bounded close to its capacity.
+        let nodes = 4;
+        let mut controller = LoadController::new(10_000, 250, nodes);
+        simulate(&mut controller, nodes, 4_000.0, 100);
+        let total = (controller.load() * nodes) as f64;
+        assert!((4_000.0..=6_000.0).contains(&total), "{total}");
+    }
+}
@@ -10,12 +10,16 @@ use std::{
 
 use prometheus::{
     register_counter_vec_with_registry,
+    register_gauge_with_registry,
     register_histogram_vec_with_registry,
+    register_histogram_with_registry,
     register_int_counter_vec_with_registry,
     register_int_counter_with_registry,
     register_int_gauge_vec_with_registry,
     register_int_gauge_with_registry,
     CounterVec,
+    Gauge,
+    Histogram,
     HistogramVec,
     IntCounter,
     IntCounterVec,
@@ -38,10 +42,25 @@ const LATENCY_SEC_BUCKETS: &[f64] = &[
     0.1, 0.25, 0.5, 0.75, 1., 1.25, 1.5, 1.75, 2., 2.5, 3.0, 4.0, 5., 10., 20., 30., 60., 90.,
 ];
 
+const COMMIT_LATENCY_SEC_BUCKETS: &[f64] = &[
+    0.01, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1., 1.5, 2., 3., 5., 7.5, 10.,
+];
+
+const LATENCY_ROUND_BUCKETS: &[f64] = &[1., 2., 3., 4., 5., 6., 8., 10., 15., 20., 30., 50., 100.];
+
 /// Metrics collected by the benchmark.
 pub const BENCHMARK_DURATION: &str = "benchmark_duration";
 pub const LATENCY_S: &str = "latency_s";
 pub const LATENCY_SQUARED_S: &str = "latency_squared_s";
+pub const TIME_TO_FIRST_COMMIT: &str = "time_to_first_commit";
+pub const COMMITTED_LEADERS_TOTAL: &str = "committed_leaders_total";
+pub const COMMITTED_LEADERS_LABELS: [&str; 2] = ["authority", "commit_type"];
+pub const COMMIT_LATENCY_S: &str = "commit_latency_seconds";
+pub const DISSEMINATION_LATENCY_S: &str = "dissemination_latency_seconds";
+pub const GLOBAL_IN_MEMORY_BLOCKS_BYTES: &str = "global_in_memory_blocks_bytes";
+pub const UNDECIDED_LEADERS: &str = "undecided_leaders";
+pub const SUBMITTED_TRANSACTIONS: &str = "submitted_transactions";
+pub const CURRENT_ROUND: &str = "current_round";
 
 #[derive(Clone)]
 pub struct Metrics {
@@ -49,13 +68,24 @@ pub struct Metrics {
     pub latency_s: HistogramVec,
     pub latency_squared_s: CounterVec,
     pub committed_leaders_total: IntCounterVec,
+    pub commit_latency_rounds: Histogram,
+    pub commit_latency_seconds: Histogram,
+    pub dissemination_latency_seconds: Histogram,
+    pub undecided_leaders: IntGauge,
+    pub current_round: IntGauge,
     pub leader_timeout_total: IntCounter,
+    pub time_to_first_commit: Gauge,
     pub inter_block_latency_s: HistogramVec,
 
     pub block_store_unloaded_blocks: IntCounter,
+    pub block_store_pruned_blocks: IntCounter,
     pub block_store_loaded_blocks: IntCounter,
     pub block_store_entries: IntCounter,
     pub block_store_cleanup_util: IntCounter,
+    pub block_store_equivocations: IntCounter,
+    pub block_store_cache_hits: IntCounter,
+    pub block_store_cache_misses: IntCounter,
+    pub block_store_evicted_blocks: IntCounter,
 
     pub wal_mappings: IntGauge,
 
@@ -183,7 +213,7 @@ impl Metrics {
             )
             .unwrap(),
             global_in_memory_blocks_bytes: register_int_gauge_with_registry!(
-                "global_in_memory_blocks_bytes",
+                GLOBAL_IN_MEMORY_BLOCKS_BYTES,
                 "Total size of blocks loaded in memory",
                 registry,
             )
@@ -212,9 +242,44 @@ impl Metrics {
             )
             .unwrap(),
             committed_leaders_total: register_int_counter_vec_with_registry!(
-                "committed_leaders_total",
+                COMMITTED_LEADERS_TOTAL,
                 "Total number of (direct or indirect) committed leaders per authority",
-                &["authority", "commit_type"],
+                &COMMITTED_LEADERS_LABELS,
+                registry,
+            )
+            .unwrap(),
+            commit_latency_rounds: register_histogram_with_registry!(
+                "commit_latency_rounds",
+                "Buckets measuring the number of rounds between a leader block and its commit",
+                LATENCY_ROUND_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
+            commit_latency_seconds: register_histogram_with_registry!(
+                COMMIT_LATENCY_S,
+                "Buckets measuring the time between the creation of a leader block and its \
+                commit in seconds",
+                COMMIT_LATENCY_SEC_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
+            dissemination_latency_seconds: register_histogram_with_registry!(
+                DISSEMINATION_LATENCY_S,
+                "Buckets measuring the time between the proposal of a block and its reception by \
+                a quorum of authorities in seconds",
+                COMMIT_LATENCY_SEC_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
+            undecided_leaders: register_int_gauge_with_registry!(
+                UNDECIDED_LEADERS,
+                "Number of leaders above the last decided leader that could not be decided yet",
+                registry,
+            )
+            .unwrap(),
+            current_round: register_int_gauge_with_registry!(
+                CURRENT_ROUND,
+                "The round of the last block proposed by the authority",
                 registry,
             )
             .unwrap(),
@@ -226,7 +291,7 @@ impl Metrics {
                 registry,
             ).unwrap(),
             submitted_transactions: register_int_counter_with_registry!(
-                "submitted_transactions",
+                SUBMITTED_TRANSACTIONS,
                 "Total number of submitted transactions",
                 registry,
             )
@@ -237,6 +302,12 @@ impl Metrics {
                 registry,
             )
             .unwrap(),
+            time_to_first_commit: register_gauge_with_registry!(
+                TIME_TO_FIRST_COMMIT,
+                "Time between the start of the node and its first commit in seconds",
+                registry,
+            )
+            .unwrap(),
 
             block_store_loaded_blocks: register_int_counter_with_registry!(
                 "block_store_loaded_blocks",
@@ -250,6 +321,12 @@ impl Metrics {
                 registry,
             )
             .unwrap(),
+            block_store_pruned_blocks: register_int_counter_with_registry!(
+                "block_store_pruned_blocks",
+                "Blocks dropped from the block store below the retention window",
+                registry,
+            )
+            .unwrap(),
             block_store_entries: register_int_counter_with_registry!(
                 "block_store_entries",
                 "Number of entries in block store",
@@ -262,6 +339,30 @@ impl Metrics {
                 registry,
             )
             .unwrap(),
+            block_store_equivocations: register_int_counter_with_registry!(
+                "block_store_equivocations",
+                "Blocks conflicting with a block of the same author and round already in the block store",
+                registry,
+            )
+            .unwrap(),
+            block_store_cache_hits: register_int_counter_with_registry!(
+                "block_store_cache_hits",
+                "Blocks read from memory in the block store",
+                registry,
+            )
+            .unwrap(),
+            block_store_cache_misses: register_int_counter_with_registry!(
+                "block_store_cache_misses",
+                "Blocks read back from the wal in the block store",
+                registry,
+            )
+            .unwrap(),
+            block_store_evicted_blocks: register_int_counter_with_registry!(
+                "block_store_evicted_blocks",
+                "Blocks evicted from memory when the block store cache is full",
+                registry,
+            )
+            .unwrap(),
 
             wal_mappings: register_int_gauge_with_registry!(
                 "wal_mappings",
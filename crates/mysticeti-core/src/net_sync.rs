@@ -23,6 +23,7 @@ use crate::{
     config::NodePublicConfig,
     core::Core,
     core_thread::CoreThreadDispatcher,
+    equivocation::ConflictingBlocks,
     metrics::Metrics,
     network::{Connection, Network, NetworkMessage},
     runtime::{self, timestamp_utc, Handle, JoinError, JoinHandle},
@@ -50,6 +51,11 @@ pub struct NetworkSyncerInner<H: BlockHandler, C: CommitObserver> {
     stop: mpsc::Sender<()>,
     epoch_close_signal: mpsc::Sender<()>,
     pub epoch_closing_time: Arc<AtomicU64>,
+    pub conflicting_blocks: ConflictingBlocks,
+    /// The index of this authority.
+    pub authority: AuthorityIndex,
+    /// Whether this authority withholds its own blocks from the peers.
+    pub silent: bool,
 }
 
 impl<H: BlockHandler + 'static, C: CommitObserver + 'static> NetworkSyncer<H, C> {
@@ -72,6 +78,8 @@ impl<H: BlockHandler + 'static, C: CommitObserver + 'static> NetworkSyncer<H, C>
         let wal_syncer = core.wal_syncer();
         let block_store = core.block_store().clone();
         let epoch_closing_time = core.epoch_closing_time();
+        let conflicting_blocks = core.conflicting_blocks();
+        let silent = core.is_silent();
         let mut syncer = Syncer::new(
             core,
             commit_period,
@@ -93,6 +101,9 @@ impl<H: BlockHandler + 'static, C: CommitObserver + 'static> NetworkSyncer<H, C>
             stop: stop_sender.clone(),
             epoch_close_signal: epoch_sender.clone(),
             epoch_closing_time,
+            conflicting_blocks,
+            authority: authority_index,
+            silent,
         });
         let block_fetcher = Arc::new(BlockFetcher::start(
             authority_index,
@@ -204,7 +215,7 @@ impl<H: BlockHandler + 'static, C: CommitObserver + 'static> NetworkSyncer<H, C>
         while let Some(message) = inner.recv_or_stopped(&mut connection.receiver).await {
             match message {
                 NetworkMessage::SubscribeOwnFrom(round) => {
-                    disseminator.disseminate_own_blocks(round).await
+                    disseminator.disseminate_own_blocks(id, round).await
                 }
                 NetworkMessage::Block(block) => {
                     tracing::debug!("Received {} from {}", block.reference(), peer);
@@ -415,7 +426,15 @@ impl AsyncWalSyncer {
 mod tests {
     use std::time::Duration;
 
-    use crate::test_util::{check_commits, network_syncers};
+    use crate::{
+        core::CoreOptions,
+        test_util::{
+            check_commits,
+            committee_and_cores,
+            network_syncers,
+            network_syncers_with_cores,
+        },
+    };
 
     #[tokio::test]
     async fn test_network_sync() {
@@ -431,6 +450,33 @@ mod tests {
 
         check_commits(&syncers);
     }
+
+    #[tokio::test]
+    async fn test_network_sync_silent_node() {
+        let (committee, mut cores, _) = committee_and_cores(4);
+        let silent = cores
+            .remove(0)
+            .with_options(CoreOptions::test().with_silence(true));
+        cores.insert(0, silent);
+        let network_syncers = network_syncers_with_cores(&committee, cores).await;
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        let mut syncers = vec![];
+        for network_syncer in network_syncers {
+            let syncer = network_syncer.shutdown().await;
+            syncers.push(syncer);
+        }
+
+        // The honest nodes keep committing without ever receiving a block of the silent node.
+        check_commits(&syncers);
+        for syncer in &syncers[1..] {
+            assert!(!syncer.commit_observer().committed_leaders().is_empty());
+            let block_store = syncer.core().block_store();
+            assert_eq!(block_store.highest_round_for_authority(0), 0);
+        }
+        // The silent node still receives the blocks of its peers.
+        let block_store = syncers[0].core().block_store();
+        assert!(block_store.highest_round_for_authority(1) > 0);
+    }
 }
 
 #[cfg(test)]
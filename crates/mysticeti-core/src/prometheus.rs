@@ -5,22 +5,33 @@ use std::net::SocketAddr;
 
 use axum::{http::StatusCode, routing::get, Extension, Router, Server};
 use prometheus::{Registry, TextEncoder};
+use tokio::sync::oneshot;
 
 use crate::runtime::{Handle, JoinHandle};
 
 pub const METRICS_ROUTE: &str = "/metrics";
 
+/// Serve the metrics of the registry (in the prometheus text format) at the specified address.
+/// The server gracefully shuts down once the returned sender is used or dropped.
 pub fn start_prometheus_server(
     address: SocketAddr,
     registry: &Registry,
-) -> JoinHandle<Result<(), hyper::Error>> {
+) -> (JoinHandle<Result<(), hyper::Error>>, oneshot::Sender<()>) {
     let app = Router::new()
         .route(METRICS_ROUTE, get(metrics))
         .layer(Extension(registry.clone()));
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel();
 
     tracing::info!("Prometheus server booted on {address}");
-    Handle::current()
-        .spawn(async move { Server::bind(&address).serve(app.into_make_service()).await })
+    let handle = Handle::current().spawn(async move {
+        Server::bind(&address)
+            .serve(app.into_make_service())
+            .with_graceful_shutdown(async {
+                shutdown_receiver.await.ok();
+            })
+            .await
+    });
+    (handle, shutdown_sender)
 }
 
 async fn metrics(registry: Extension<Registry>) -> (StatusCode, String) {
@@ -33,3 +44,49 @@ async fn metrics(registry: Extension<Registry>) -> (StatusCode, String) {
         ),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{net::TcpListener, time::Duration};
+
+    use prometheus::Registry;
+
+    use super::{start_prometheus_server, METRICS_ROUTE};
+    use crate::metrics::{Metrics, COMMITTED_LEADERS_TOTAL};
+
+    #[tokio::test]
+    async fn serve_metrics() {
+        let registry = Registry::new();
+        let (metrics, _reporter) = Metrics::new(&registry, None);
+        metrics
+            .committed_leaders_total
+            .with_label_values(&["A", "direct-commit"])
+            .inc();
+
+        // Pick a free port rather than a fixed one that may already be in use.
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let (handle, shutdown) = start_prometheus_server(address, &registry);
+        let url = format!("http://{address}{METRICS_ROUTE}");
+        let poll = async {
+            // Wait for the server to boot.
+            loop {
+                match reqwest::get(&url).await {
+                    Ok(response) => break response.text().await.unwrap(),
+                    Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+                }
+            }
+        };
+        let text = tokio::time::timeout(Duration::from_secs(10), poll)
+            .await
+            .expect("The metrics server did not boot");
+        assert!(text.contains(COMMITTED_LEADERS_TOTAL));
+
+        // The server stops once signaled.
+        shutdown.send(()).unwrap();
+        handle.await.unwrap().unwrap();
+        assert!(reqwest::get(&url).await.is_err());
+    }
+}
@@ -21,6 +21,8 @@ pub struct RecoveredState {
     pub unprocessed_blocks: Vec<Data<StatementBlock>>,
 
     pub last_committed_leader: Option<BlockReference>,
+    /// All committed leaders, in commit order.
+    pub committed_leaders: Vec<BlockReference>,
     pub committed_blocks: HashSet<BlockReference>,
     pub committed_state: Option<Bytes>,
 }
@@ -32,7 +34,7 @@ pub struct RecoveredStateBuilder {
     state: Option<Bytes>,
     unprocessed_blocks: Vec<Data<StatementBlock>>,
 
-    last_committed_leader: Option<BlockReference>,
+    committed_leaders: Vec<BlockReference>,
     committed_blocks: HashSet<BlockReference>,
     committed_state: Option<Bytes>,
 }
@@ -66,7 +68,7 @@ impl RecoveredStateBuilder {
 
     pub fn commit_data(&mut self, commits: Vec<CommitData>, committed_state: Bytes) {
         for commit_data in commits {
-            self.last_committed_leader = Some(commit_data.leader);
+            self.committed_leaders.push(commit_data.leader);
             self.committed_blocks
                 .extend(commit_data.sub_dag.into_iter());
         }
@@ -85,7 +87,8 @@ impl RecoveredStateBuilder {
             block_store,
             state: self.state,
             unprocessed_blocks: self.unprocessed_blocks,
-            last_committed_leader: self.last_committed_leader,
+            last_committed_leader: self.committed_leaders.last().copied(),
+            committed_leaders: self.committed_leaders,
             committed_blocks: self.committed_blocks,
             committed_state: self.committed_state,
         }
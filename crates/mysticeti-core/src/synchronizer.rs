@@ -9,6 +9,7 @@ use tokio::sync::mpsc;
 
 use crate::{
     block_handler::BlockHandler,
+    equivocation::ConflictingBlocks,
     metrics::Metrics,
     net_sync::{self, NetworkSyncerInner},
     network::NetworkMessage,
@@ -105,7 +106,16 @@ where
     ) -> Option<()> {
         let mut missing = Vec::new();
         for reference in references {
-            let stored_block = self.inner.block_store.get_block(reference);
+            // Silent validators never hand out their own blocks, not even on request.
+            if self.inner.silent && reference.authority == self.inner.authority {
+                missing.push(reference);
+                continue;
+            }
+            let stored_block = self
+                .inner
+                .block_store
+                .get_block(reference)
+                .or_else(|| self.inner.conflicting_blocks.get_block(reference));
             let found = stored_block.is_some();
             match stored_block {
                 // TODO: Should we be able to send more than one block in a single network message?
@@ -123,7 +133,10 @@ where
             .ok()
     }
 
-    pub async fn disseminate_own_blocks(&mut self, round: RoundNumber) {
+    pub async fn disseminate_own_blocks(&mut self, peer: AuthorityIndex, round: RoundNumber) {
+        if self.inner.silent {
+            return;
+        }
         if let Some(existing) = self.own_blocks.take() {
             existing.abort();
             existing.await.ok();
@@ -132,6 +145,7 @@ where
         let handle = Handle::current().spawn(Self::stream_own_blocks(
             self.sender.clone(),
             self.inner.clone(),
+            peer,
             round,
             self.parameters.batch_size,
         ));
@@ -141,14 +155,22 @@ where
     async fn stream_own_blocks(
         to: mpsc::Sender<NetworkMessage>,
         inner: Arc<NetworkSyncerInner<H, C>>,
+        peer: AuthorityIndex,
         mut round: RoundNumber,
         batch_size: usize,
     ) -> Option<()> {
+        let conflicting = ConflictingBlocks::receives_conflicting(peer);
         loop {
             let notified = inner.notify.notified();
             let blocks = inner.block_store.get_own_blocks(round, batch_size);
             for block in blocks {
                 round = block.round();
+                // Equivocating validators send their conflicting blocks to half of the peers.
+                let block = if conflicting {
+                    inner.conflicting_blocks.get(round).unwrap_or(block)
+                } else {
+                    block
+                };
                 to.send(NetworkMessage::Block(block)).await.ok()?;
             }
             notified.await
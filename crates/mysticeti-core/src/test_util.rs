@@ -2,12 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
+    fs::File,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     path::Path,
     sync::Arc,
 };
 
 use futures::future::join_all;
+use minibytes::Bytes;
 use prometheus::Registry;
 use rand::{rngs::StdRng, SeedableRng};
 
@@ -17,7 +19,15 @@ use crate::future_simulator::OverrideNodeContext;
 use crate::simulated_network::SimulatedNetwork;
 use crate::{
     block_handler::{BlockHandler, TestBlockHandler, TestCommitHandler},
-    block_store::{BlockStore, BlockWriter, OwnBlockData, WAL_ENTRY_BLOCK},
+    block_store::{
+        BlockStore,
+        BlockWriter,
+        CommitData,
+        EquivocationDetected,
+        OwnBlockData,
+        WAL_ENTRY_BLOCK,
+        WAL_ENTRY_COMMIT,
+    },
     committee::Committee,
     config::{self, NodePrivateConfig, NodePublicConfig},
     core::{Core, CoreOptions},
@@ -25,6 +35,7 @@ use crate::{
     metrics::{MetricReporter, Metrics},
     net_sync::NetworkSyncer,
     network::Network,
+    state::RecoveredState,
     syncer::{Syncer, SyncerSignals},
     types::{format_authority_index, AuthorityIndex, BlockReference, RoundNumber, StatementBlock},
     wal::{open_file_for_wal, walf, WalPosition, WalWriter},
@@ -229,6 +240,14 @@ pub async fn network_syncers_with_epoch_duration(
     rounds_in_epoch: RoundNumber,
 ) -> Vec<NetworkSyncer<TestBlockHandler, TestCommitHandler>> {
     let (committee, cores, _) = committee_and_cores_epoch_duration(n, rounds_in_epoch);
+    network_syncers_with_cores(&committee, cores).await
+}
+
+pub async fn network_syncers_with_cores(
+    committee: &Arc<Committee>,
+    cores: Vec<Core<TestBlockHandler>>,
+) -> Vec<NetworkSyncer<TestBlockHandler, TestCommitHandler>> {
+    let n = cores.len();
     let metrics: Vec<_> = cores.iter().map(|c| c.metrics.clone()).collect();
     let (networks, _) = networks_and_addresses(&metrics).await;
     let mut network_syncers = vec![];
@@ -348,6 +367,17 @@ pub struct TestBlockWriter {
 impl TestBlockWriter {
     pub fn new(committee: &Committee) -> Self {
         let file = tempfile::tempfile().unwrap();
+        Self::from_file(committee, file).0
+    }
+
+    /// Open (or create) a block writer persisting its blocks to the wal at the specified path,
+    /// along with the state recovered from that wal.
+    pub fn new_persisted(committee: &Committee, path: &Path) -> (Self, RecoveredState) {
+        let file = open_file_for_wal(path).unwrap();
+        Self::from_file(committee, file)
+    }
+
+    fn from_file(committee: &Committee, file: File) -> (Self, RecoveredState) {
         let (wal_writer, wal_reader) = walf(file).unwrap();
         let state = BlockStore::open(
             0,
@@ -356,20 +386,33 @@ impl TestBlockWriter {
             test_metrics(),
             committee,
         );
-        let block_store = state.block_store;
-        Self {
+        let block_store = state.block_store.clone();
+        let this = Self {
             block_store,
             wal_writer,
-        }
+        };
+        (this, state)
     }
 
     pub fn add_block(&mut self, block: Data<StatementBlock>) -> WalPosition {
+        // Tests may build equivocating dags on purpose, the store keeps the evidence.
+        self.try_add_block(block)
+            .unwrap_or_else(|(position, _)| position)
+    }
+
+    /// Add a block to the store, failing (after inserting it) if the block is an equivocation.
+    pub fn try_add_block(
+        &mut self,
+        block: Data<StatementBlock>,
+    ) -> Result<WalPosition, (WalPosition, EquivocationDetected)> {
         let pos = self
             .wal_writer
-            .write(WAL_ENTRY_BLOCK, &bincode::serialize(&block).unwrap())
+            .write(WAL_ENTRY_BLOCK, block.serialized_bytes())
             .unwrap();
-        self.block_store.insert_block(block, pos);
-        pos
+        match self.block_store.insert_block(block, pos) {
+            Ok(()) => Ok(pos),
+            Err(equivocation) => Err((pos, equivocation)),
+        }
     }
 
     pub fn add_blocks(&mut self, blocks: Vec<Data<StatementBlock>>) {
@@ -378,6 +421,12 @@ impl TestBlockWriter {
         }
     }
 
+    /// Journal the specified commits (with an empty commit state), as the core does.
+    pub fn add_commits(&mut self, commits: &[CommitData]) {
+        let commits = bincode::serialize(&(commits, Bytes::new())).unwrap();
+        self.wal_writer.write(WAL_ENTRY_COMMIT, &commits).unwrap();
+    }
+
     pub fn into_block_store(self) -> BlockStore {
         self.block_store
     }
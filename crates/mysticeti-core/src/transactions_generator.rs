@@ -9,6 +9,7 @@ use tokio::sync::mpsc;
 use crate::{
     config::{ClientParameters, NodePublicConfig},
     crypto::AsBytes,
+    load_controller::LoadController,
     metrics::Metrics,
     runtime::{self, timestamp_utc},
     types::{AuthorityIndex, Transaction},
@@ -24,6 +25,8 @@ pub struct TransactionGenerator {
 
 impl TransactionGenerator {
     const TARGET_BLOCK_INTERVAL: Duration = Duration::from_millis(100);
+    /// The interval between two updates of the load in closed-loop mode.
+    const CONTROL_INTERVAL: Duration = Duration::from_secs(1);
 
     pub fn start(
         sender: mpsc::Sender<Vec<Transaction>>,
@@ -38,6 +41,9 @@ impl TransactionGenerator {
             client_parameters.load,
             client_parameters.initial_delay
         );
+        if !client_parameters.load_schedule.is_empty() {
+            tracing::info!("Load schedule: {:?}", client_parameters.load_schedule);
+        }
         runtime::Handle::current().spawn(
             Self {
                 sender,
@@ -51,14 +57,13 @@ impl TransactionGenerator {
     }
 
     pub async fn run(mut self) {
-        let load = self.client_parameters.load;
-        let transactions_per_block_interval = (load + 9) / 10;
+        let mut load = self.client_parameters.load_at(Duration::ZERO);
+        let mut transactions_per_block_interval = (load + 9) / 10;
         tracing::info!(
             "Generating {transactions_per_block_interval} transactions per {} ms",
             Self::TARGET_BLOCK_INTERVAL.as_millis()
         );
         let max_block_size = self.node_public_config.parameters.max_block_size;
-        let target_block_size = min(max_block_size, transactions_per_block_interval);
 
         let mut counter = 0;
         let mut tx_to_report = 0;
@@ -67,10 +72,39 @@ impl TransactionGenerator {
 
         let mut interval = runtime::TimeInterval::new(Self::TARGET_BLOCK_INTERVAL);
         runtime::sleep(self.client_parameters.initial_delay).await;
+        let start = runtime::TimeInstant::now();
+        let committee_size = self.node_public_config.identifiers.len();
+        let mut controller = self
+            .client_parameters
+            .target_commit_tps
+            .map(|target| LoadController::new(target, load, committee_size));
+        let mut control = (runtime::TimeInstant::now(), self.committed_transactions());
         loop {
             interval.tick().await;
             let timestamp = (timestamp_utc().as_millis() as u64).to_le_bytes();
 
+            let current_load = match &mut controller {
+                Some(controller) if control.0.elapsed() >= Self::CONTROL_INTERVAL => {
+                    let (last, last_committed) = control;
+                    let committed = self.committed_transactions();
+                    let elapsed = last.elapsed().as_secs_f64();
+                    let observed = committed.saturating_sub(last_committed) as f64 / elapsed;
+                    control = (runtime::TimeInstant::now(), committed);
+                    controller.update(observed)
+                }
+                Some(_) => load,
+                None => self.client_parameters.load_at(start.elapsed()),
+            };
+            if current_load != load {
+                load = current_load;
+                transactions_per_block_interval = (load + 9) / 10;
+                tracing::info!(
+                    "Generating {transactions_per_block_interval} transactions per {} ms",
+                    Self::TARGET_BLOCK_INTERVAL.as_millis()
+                );
+            }
+            let target_block_size = min(max_block_size, transactions_per_block_interval);
+
             let mut block = Vec::with_capacity(target_block_size);
             let mut block_size = 0;
             for _ in 0..transactions_per_block_interval {
@@ -106,6 +140,14 @@ impl TransactionGenerator {
         }
     }
 
+    /// The number of transactions committed by the system, as reported by the latency metrics.
+    fn committed_transactions(&self) -> u64 {
+        self.metrics
+            .latency_s
+            .with_label_values(&["shared"])
+            .get_sample_count()
+    }
+
     pub fn extract_timestamp(transaction: &Transaction) -> Duration {
         let bytes = transaction.as_bytes()[0..8]
             .try_into()
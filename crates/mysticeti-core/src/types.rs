@@ -9,6 +9,7 @@ pub struct Transaction {
 }
 
 pub type RoundNumber = u64;
+pub type EpochNumber = u64;
 pub type BlockDigest = crate::crypto::BlockDigest;
 pub type Stake = u64;
 pub type KeyPair = u64;
@@ -111,9 +112,12 @@ impl PartialOrd for BlockReference {
     }
 }
 
+/// Block references are totally ordered by round, then by authority, then by digest. Replicas
+/// holding the same blocks thus always list them in the same order, which keeps the choices of
+/// the committer deterministic.
 impl Ord for BlockReference {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        (self.round, self.authority, self.digest).cmp(&(other.round, other.authority, self.digest))
+        (self.round, self.authority, self.digest).cmp(&(other.round, other.authority, other.digest))
     }
 }
 
@@ -768,6 +772,34 @@ mod test {
         );
     }
 
+    #[test]
+    fn block_reference_order() {
+        let digest = |byte: u8| BlockDigest::from_hex(&format!("{byte:02x}").repeat(32)).unwrap();
+        let reference = |round, authority, byte| BlockReference {
+            authority,
+            round,
+            digest: digest(byte),
+        };
+        let canonical = vec![
+            reference(1, 0, 9),
+            reference(1, 2, 0),
+            reference(1, 2, 5),
+            reference(2, 0, 1),
+            reference(2, 1, 0),
+            reference(2, 1, 7),
+            reference(3, 0, 0),
+        ];
+
+        let mut references = canonical.clone();
+        references.shuffle(&mut rand::thread_rng());
+        references.sort();
+        assert_eq!(references, canonical);
+
+        // References only differing by their digest are ordered by digest.
+        assert!(reference(1, 2, 5) > reference(1, 2, 0));
+        assert!(reference(1, 2, 0) < reference(1, 2, 5));
+    }
+
     #[test]
     fn authority_set_test() {
         let mut a = AuthoritySet::default();
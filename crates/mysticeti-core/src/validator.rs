@@ -8,10 +8,12 @@ use std::{
 
 use ::prometheus::Registry;
 use eyre::{eyre, Context, Result};
+use tokio::sync::oneshot;
 
 use crate::{
     block_handler::{RealBlockHandler, TestCommitHandler},
     block_store::BlockStore,
+    commit_sink::CommitSink,
     committee::Committee,
     config::{ClientParameters, NodePrivateConfig, NodePublicConfig},
     core::{Core, CoreOptions},
@@ -23,12 +25,12 @@ use crate::{
     runtime::{JoinError, JoinHandle},
     transactions_generator::TransactionGenerator,
     types::AuthorityIndex,
-    wal::{self, walf},
 };
 
 pub struct Validator {
     network_synchronizer: NetworkSyncer<RealBlockHandler, TestCommitHandler<TransactionLog>>,
     metrics_handle: JoinHandle<Result<(), hyper::Error>>,
+    metrics_shutdown: oneshot::Sender<()>,
 }
 
 impl Validator {
@@ -38,6 +40,26 @@ impl Validator {
         public_config: NodePublicConfig,
         private_config: NodePrivateConfig,
         client_parameters: ClientParameters,
+    ) -> Result<Self> {
+        Self::start_with_options(
+            authority,
+            committee,
+            public_config,
+            private_config,
+            client_parameters,
+            CoreOptions::default(),
+        )
+        .await
+    }
+
+    /// Start a validator whose core runs with the specified options.
+    pub async fn start_with_options(
+        authority: AuthorityIndex,
+        committee: Arc<Committee>,
+        public_config: NodePublicConfig,
+        private_config: NodePrivateConfig,
+        client_parameters: ClientParameters,
+        core_options: CoreOptions,
     ) -> Result<Self> {
         let network_address = public_config
             .network_address(authority)
@@ -58,20 +80,17 @@ impl Validator {
         let (metrics, reporter) = Metrics::new(&registry, Some(&committee));
         reporter.start();
 
-        let metrics_handle =
+        let (metrics_handle, metrics_shutdown) =
             prometheus::start_prometheus_server(binding_metrics_address, &registry);
 
         // Open the block store.
-        let wal_file =
-            wal::open_file_for_wal(private_config.wal()).expect("Failed to open wal file");
-        let (wal_writer, wal_reader) = walf(wal_file).expect("Failed to open wal");
-        let recovered = BlockStore::open(
+        let (wal_writer, recovered) = BlockStore::open_persisted(
             authority,
-            Arc::new(wal_reader),
-            &wal_writer,
+            private_config.wal(),
             metrics.clone(),
             &committee,
-        );
+        )
+        .expect("Failed to open wal");
 
         // Boot the validator node.
         let (block_handler, block_sender) = RealBlockHandler::new(
@@ -93,12 +112,17 @@ impl Validator {
         let committed_transaction_log =
             TransactionLog::start(private_config.committed_transactions_log())
                 .expect("Failed to open committed transaction log for write");
-        let commit_handler = TestCommitHandler::new_with_handler(
+        let mut commit_handler = TestCommitHandler::new_with_handler(
             committee.clone(),
             block_handler.transaction_time.clone(),
             metrics.clone(),
             committed_transaction_log,
         );
+        if public_config.parameters.enable_commit_sink {
+            let commit_sink = CommitSink::start(private_config.committed_leaders_log())
+                .expect("Failed to open commit sink for write");
+            commit_handler = commit_handler.with_commit_sink(commit_sink);
+        }
         let core = Core::open(
             block_handler,
             authority,
@@ -108,7 +132,7 @@ impl Validator {
             metrics.clone(),
             recovered,
             wal_writer,
-            CoreOptions::default(),
+            core_options,
         );
         let network = Network::load(
             &public_config,
@@ -133,6 +157,7 @@ impl Validator {
         Ok(Self {
             network_synchronizer,
             metrics_handle,
+            metrics_shutdown,
         })
     }
 
@@ -142,6 +167,8 @@ impl Validator {
         Result<(), JoinError>,
         Result<Result<(), hyper::Error>, JoinError>,
     ) {
+        // Keep serving the metrics as long as the validator runs.
+        let _metrics_shutdown = self.metrics_shutdown;
         tokio::join!(
             self.network_synchronizer.await_completion(),
             self.metrics_handle
@@ -150,6 +177,7 @@ impl Validator {
 
     pub async fn stop(self) {
         self.network_synchronizer.shutdown().await;
+        self.metrics_shutdown.send(()).ok();
     }
 }
 
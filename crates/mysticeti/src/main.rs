@@ -11,8 +11,11 @@ use std::{
 use clap::{command, Parser};
 use eyre::{eyre, Context, Result};
 use mysticeti_core::{
+    commit_sink::{first_divergence, format_entry, read_commit_sequence},
     committee::Committee,
     config::{ClientParameters, ImportExport, NodeParameters, NodePrivateConfig, NodePublicConfig},
+    consensus::audit_log::AuditLog,
+    core::CoreOptions,
     types::AuthorityIndex,
     validator::Validator,
 };
@@ -57,6 +60,16 @@ enum Operation {
         /// Path to the file holding the client parameters (for benchmarks).
         #[clap(long, value_name = "FILE")]
         client_parameters_path: String,
+        /// Deliberately propose two conflicting blocks at every round, each sent to half of the
+        /// peers. Only use this flag to test the safety of the protocol against Byzantine
+        /// validators. The node also equivocates if it is listed in the
+        /// `equivocating_authorities` of the node parameters.
+        #[clap(long)]
+        equivocate: bool,
+        /// Deliberately withhold the own blocks from the peers while still receiving theirs.
+        /// Only use this flag to test the liveness of the protocol against Byzantine validators.
+        #[clap(long)]
+        silent: bool,
     },
     /// Deploy a local validator for test. Dryrun mode uses default keys and committee configurations.
     DryRun {
@@ -67,6 +80,19 @@ enum Operation {
         #[clap(long, value_name = "INT")]
         committee_size: usize,
     },
+    /// Replay the sequences of committed leaders written by the commit sink of one or more
+    /// validators, and check that they all agree.
+    Replay {
+        /// The commit sink files to replay.
+        #[clap(value_name = "FILE", required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Verify the hash chain of the audit log of a validator and print its decisions.
+    VerifyAuditLog {
+        /// The audit log file to verify.
+        #[clap(value_name = "FILE")]
+        path: PathBuf,
+    },
 }
 
 #[tokio::main]
@@ -91,6 +117,8 @@ async fn main() -> Result<()> {
             public_config_path,
             private_config_path,
             client_parameters_path,
+            equivocate,
+            silent,
         } => {
             run(
                 authority,
@@ -98,6 +126,8 @@ async fn main() -> Result<()> {
                 public_config_path,
                 private_config_path,
                 client_parameters_path,
+                equivocate,
+                silent,
             )
             .await?
         }
@@ -105,6 +135,8 @@ async fn main() -> Result<()> {
             authority,
             committee_size,
         } => dryrun(authority, committee_size).await?,
+        Operation::Replay { paths } => replay(paths)?,
+        Operation::VerifyAuditLog { path } => verify_audit_log(path)?,
     }
 
     Ok(())
@@ -173,6 +205,8 @@ async fn run(
     public_config_path: String,
     private_config_path: String,
     client_parameters_path: String,
+    equivocate: bool,
+    silent: bool,
 ) -> Result<()> {
     tracing::info!("Starting validator {authority}");
 
@@ -190,6 +224,18 @@ async fn run(
 
     let committee = Arc::new(committee);
 
+    let equivocate = equivocate
+        || public_config
+            .parameters
+            .equivocating_authorities
+            .contains(&authority);
+    if equivocate {
+        tracing::warn!("Validator {authority} equivocates at every round");
+    }
+    if silent {
+        tracing::warn!("Validator {authority} withholds its own blocks");
+    }
+
     let network_address = public_config
         .network_address(authority)
         .ok_or(eyre!("No network address for authority {authority}"))
@@ -205,12 +251,15 @@ async fn run(
     binding_metrics_address.set_ip(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
 
     // Boot the validator node.
-    let validator = Validator::start(
+    let validator = Validator::start_with_options(
         authority,
         committee,
         public_config.clone(),
         private_config,
         client_parameters,
+        CoreOptions::default()
+            .with_equivocation(equivocate)
+            .with_silence(silent),
     )
     .await?;
     let (network_result, _metrics_result) = validator.await_completion().await;
@@ -265,3 +314,62 @@ async fn dryrun(authority: AuthorityIndex, committee_size: usize) -> Result<()>
 
     Ok(())
 }
+
+/// Replay the commit sequences of multiple validators and check that they are consistent.
+fn replay(paths: Vec<PathBuf>) -> Result<()> {
+    let mut sequences = Vec::new();
+    for path in &paths {
+        let sequence = read_commit_sequence(path).wrap_err(format!(
+            "Failed to read commit sequence '{}'",
+            path.display()
+        ))?;
+        tracing::info!("{}: {} commits", path.display(), sequence.len());
+        sequences.push(sequence);
+    }
+
+    let Some((reference, others)) = sequences.split_first() else {
+        return Ok(());
+    };
+    for (path, sequence) in paths[1..].iter().zip(others) {
+        if let Some(i) = first_divergence(reference, sequence) {
+            return Err(eyre!(
+                "'{}' diverges from '{}' at commit {i}: {} != {}",
+                path.display(),
+                paths[0].display(),
+                sequence[i],
+                reference[i]
+            ));
+        }
+    }
+
+    // Print the longest sequence (all other sequences are prefixes of it).
+    let longest = sequences.iter().max_by_key(|x| x.len()).unwrap();
+    for reference in longest {
+        println!("{}", format_entry(reference));
+    }
+    Ok(())
+}
+
+/// Verify the audit log of a validator and print the decisions it records.
+fn verify_audit_log(path: PathBuf) -> Result<()> {
+    let audit_log =
+        AuditLog::load(&path).wrap_err(format!("Failed to read audit log '{}'", path.display()))?;
+    audit_log
+        .verify()
+        .map_err(|e| eyre!("Audit log '{}' is invalid: {e}", path.display()))?;
+
+    let head: String = audit_log
+        .head()
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect();
+    tracing::info!(
+        "{}: {} decisions, head {head}",
+        path.display(),
+        audit_log.len()
+    );
+    for entry in audit_log.entries() {
+        println!("{} {}", entry.height, entry.decision);
+    }
+    Ok(())
+}
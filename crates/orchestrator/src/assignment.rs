@@ -0,0 +1,208 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::Instance,
+    ensure,
+    error::{TestbedError, TestbedResult},
+};
+
+/// The strategy assigning the authorities of the committee to the instances of the testbed.
+/// Authority `i` runs on the `i`-th instance returned by the strategy.
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug, Default, PartialEq, Eq)]
+pub enum AssignmentStrategy {
+    /// Assign the authorities to the instances in the order of the pool.
+    Sequential,
+    /// Assign the authorities to the regions in a round-robin fashion, so that the committee
+    /// is spread as equally as possible across all regions.
+    #[default]
+    RegionBalanced,
+    /// Assign the authorities according to a yaml file listing one instance id per authority.
+    Explicit { mapping_file: PathBuf },
+}
+
+impl Display for AssignmentStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sequential => write!(f, "sequential"),
+            Self::RegionBalanced => write!(f, "region-balanced"),
+            Self::Explicit { mapping_file } => write!(f, "explicit ({})", mapping_file.display()),
+        }
+    }
+}
+
+impl AssignmentStrategy {
+    /// Remove from the pool the instances hosting a committee of the specified size. The
+    /// returned instances are ordered by authority index.
+    pub fn assign(
+        &self,
+        pool: &mut Vec<Instance>,
+        regions: &[String],
+        committee: usize,
+    ) -> TestbedResult<Vec<Instance>> {
+        ensure!(
+            pool.len() >= committee,
+            TestbedError::InsufficientCapacity(committee - pool.len())
+        );
+
+        match self {
+            Self::Sequential => Ok(pool.drain(..committee).collect()),
+            Self::RegionBalanced => Ok(take_region_balanced(pool, regions, committee)),
+            Self::Explicit { mapping_file } => {
+                let ids = Self::load_mapping(mapping_file)?;
+                ensure!(
+                    ids.len() >= committee,
+                    TestbedError::InvalidAssignment(format!(
+                        "{} maps {} authorities but the committee has {committee}",
+                        mapping_file.display(),
+                        ids.len()
+                    ))
+                );
+
+                let mut instances = Vec::with_capacity(committee);
+                for id in ids.into_iter().take(committee) {
+                    let Some(index) = pool.iter().position(|x| x.id == id) else {
+                        return Err(TestbedError::InvalidAssignment(format!(
+                            "Instance '{id}' is not available"
+                        )));
+                    };
+                    instances.push(pool.remove(index));
+                }
+                Ok(instances)
+            }
+        }
+    }
+
+    /// Load the list of instance ids (one per authority) from a yaml file.
+    fn load_mapping(mapping_file: &Path) -> TestbedResult<Vec<String>> {
+        let content = fs::read_to_string(mapping_file).map_err(|e| {
+            TestbedError::InvalidAssignment(format!(
+                "Failed to read {}: {e}",
+                mapping_file.display()
+            ))
+        })?;
+        serde_yaml::from_str(&content).map_err(|e| {
+            TestbedError::InvalidAssignment(format!(
+                "Failed to parse {}: {e}",
+                mapping_file.display()
+            ))
+        })
+    }
+}
+
+/// Remove (at most) the specified number of instances from the pool, picking them from each
+/// region in a round-robin fashion.
+pub fn take_region_balanced(
+    pool: &mut Vec<Instance>,
+    regions: &[String],
+    count: usize,
+) -> Vec<Instance> {
+    let mut instances = Vec::new();
+    while instances.len() < count {
+        let mut progress = false;
+        for region in regions {
+            if instances.len() == count {
+                break;
+            }
+            if let Some(index) = pool.iter().position(|x| &x.region == region) {
+                instances.push(pool.remove(index));
+                progress = true;
+            }
+        }
+        if !progress {
+            break;
+        }
+    }
+    instances
+}
+
+/// Run the specified selection on the instances of the pool with the specified specs only,
+/// returning the instances it did not select to the pool.
+pub fn take_with_specs<T>(
+    pool: &mut Vec<Instance>,
+    specs: &str,
+    select: impl FnOnce(&mut Vec<Instance>) -> T,
+) -> T {
+    let (mut matching, others): (Vec<_>, Vec<_>) = pool.drain(..).partition(|x| x.has_specs(specs));
+    let selected = select(&mut matching);
+    pool.extend(matching.into_iter().chain(others));
+    selected
+}
+
+#[cfg(test)]
+mod test {
+    use super::AssignmentStrategy;
+    use crate::client::Instance;
+
+    /// Make a pool of instances alternating between two regions.
+    fn pool() -> (Vec<Instance>, Vec<String>) {
+        let regions = vec!["a".to_string(), "b".to_string()];
+        let pool = ["a0", "a1", "a2", "b0", "b1", "b2"]
+            .into_iter()
+            .map(|id| {
+                let mut instance = Instance::new_for_test(id.into());
+                instance.region = id[..1].into();
+                instance
+            })
+            .collect();
+        (pool, regions)
+    }
+
+    fn ids(instances: &[Instance]) -> Vec<&str> {
+        instances.iter().map(|x| x.id.as_str()).collect()
+    }
+
+    #[test]
+    fn sequential() {
+        let (mut pool, regions) = pool();
+        let assigned = AssignmentStrategy::Sequential
+            .assign(&mut pool, &regions, 4)
+            .unwrap();
+        assert_eq!(ids(&assigned), ["a0", "a1", "a2", "b0"]);
+        assert_eq!(ids(&pool), ["b1", "b2"]);
+    }
+
+    #[test]
+    fn region_balanced() {
+        let (mut pool, regions) = pool();
+        let assigned = AssignmentStrategy::RegionBalanced
+            .assign(&mut pool, &regions, 4)
+            .unwrap();
+        assert_eq!(ids(&assigned), ["a0", "b0", "a1", "b1"]);
+        assert_eq!(ids(&pool), ["a2", "b2"]);
+    }
+
+    #[test]
+    fn explicit() {
+        let (mut pool, regions) = pool();
+        let directory = tempfile::tempdir().unwrap();
+        let mapping_file = directory.path().join("mapping.yaml");
+        std::fs::write(&mapping_file, "- b2\n- a1\n- b0\n- a0\n").unwrap();
+
+        let assigned = AssignmentStrategy::Explicit { mapping_file }
+            .assign(&mut pool, &regions, 4)
+            .unwrap();
+        assert_eq!(ids(&assigned), ["b2", "a1", "b0", "a0"]);
+        assert_eq!(ids(&pool), ["a2", "b1"]);
+    }
+
+    #[test]
+    fn explicit_unknown_instance() {
+        let (mut pool, regions) = pool();
+        let directory = tempfile::tempdir().unwrap();
+        let mapping_file = directory.path().join("mapping.yaml");
+        std::fs::write(&mapping_file, "- a0\n- c0\n").unwrap();
+
+        let strategy = AssignmentStrategy::Explicit { mapping_file };
+        assert!(strategy.assign(&mut pool, &regions, 2).is_err());
+    }
+}
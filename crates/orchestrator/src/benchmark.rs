@@ -1,11 +1,20 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::fmt::{Debug, Display};
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+    time::Duration,
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{protocol::ProtocolParameters, settings::Settings, ClientParameters, NodeParameters};
+use crate::{
+    protocol::ProtocolParameters,
+    settings::{InstanceRole, Settings},
+    ClientParameters,
+    NodeParameters,
+};
 
 /// Shortcut avoiding to use the generic version of the benchmark parameters.
 pub type BenchmarkParameters = BenchmarkParametersGeneric<NodeParameters, ClientParameters>;
@@ -22,8 +31,23 @@ pub struct BenchmarkParametersGeneric<N, C> {
     pub client_parameters: C,
     /// The committee size.
     pub nodes: usize,
-    /// The total load (tx/s) to submit to the system.
+    /// The total load (tx/s) to submit to the system. This is the peak load of the load profile
+    /// if the load is not constant.
     pub load: usize,
+    /// The profile of the load over time, or `None` if the load is constant.
+    #[serde(default)]
+    pub load_profile: Option<LoadProfile>,
+    /// Whether the clients adjust their load to reach a target commit throughput.
+    #[serde(default)]
+    pub load_mode: LoadMode,
+    /// The specs of the instances running the benchmark, overriding the specs of the settings.
+    #[serde(default)]
+    pub instance_specs: Option<String>,
+    /// The time to let the system settle before measuring it. The transactions finalized during
+    /// the warm-up are excluded from the latency statistics, and the benchmark runs for the
+    /// warm-up followed by the benchmark duration of the settings.
+    #[serde(default)]
+    pub warmup: Duration,
 }
 
 impl<N: Debug, C: Debug> Debug for BenchmarkParametersGeneric<N, C> {
@@ -36,41 +60,166 @@ impl<N: Debug, C: Debug> Debug for BenchmarkParametersGeneric<N, C> {
             self.settings.faults,
             self.nodes,
             self.load
-        )
+        )?;
+        if let Some(profile) = &self.load_profile {
+            write!(f, "-{profile}")?;
+        }
+        if self.load_mode != LoadMode::OpenLoop {
+            write!(f, "-{}", self.load_mode)?;
+        }
+        match &self.instance_specs {
+            Some(specs) => write!(f, "-{specs}"),
+            None => Ok(()),
+        }
     }
 }
 
 impl<N, C> Display for BenchmarkParametersGeneric<N, C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} nodes ({}) - {} tx/s",
-            self.nodes, self.settings.faults, self.load
-        )
+        write!(f, "{} nodes ({}) - ", self.nodes, self.settings.faults)?;
+        match &self.load_profile {
+            Some(profile) => write!(f, "{profile} tx/s")?,
+            None => write!(f, "{} tx/s", self.load)?,
+        }
+        if let Some(target) = self.load_mode.target_commit_tps() {
+            write!(f, " (closed loop, target {target} tx/s)")?;
+        }
+        match &self.instance_specs {
+            Some(specs) => write!(f, " - {specs}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<N, C> BenchmarkParametersGeneric<N, C> {
+    /// The specs of the instances running the benchmark.
+    pub fn specs(&self) -> &str {
+        self.instance_specs
+            .as_deref()
+            .unwrap_or(&self.settings.specs)
+    }
+
+    /// The specs of the instances with the specified role. The specs overriding the ones of the
+    /// settings apply to all roles.
+    pub fn specs_for(&self, role: InstanceRole) -> &str {
+        self.instance_specs
+            .as_deref()
+            .unwrap_or_else(|| self.settings.specs_for(role))
+    }
+
+    /// The number of active instances of each specs required to run the benchmark.
+    pub fn required_instances_by_specs(&self) -> Vec<(String, usize)> {
+        match &self.instance_specs {
+            Some(specs) => {
+                let required = self.settings.required_instances(self.nodes);
+                vec![(specs.clone(), required)]
+            }
+            None => self.settings.required_instances_by_specs(self.nodes),
+        }
+    }
+
+    /// The total running time of the benchmark, that is, the warm-up followed by the benchmark
+    /// duration of the settings.
+    pub fn total_duration(&self) -> Duration {
+        self.warmup + self.settings.benchmark_duration
     }
 }
 
 impl<N: ProtocolParameters, C: ProtocolParameters> BenchmarkParametersGeneric<N, C> {
-    /// Make a new benchmark parameters.
+    /// Make a new benchmark parameters. Each load is run on the instances of each of the
+    /// specified specs, or on the instances specified by the settings if no specs are provided.
     pub fn new_from_loads(
         settings: Settings,
         node_parameters: N,
         client_parameters: C,
         nodes: usize,
         loads: Vec<usize>,
+        specs: Vec<String>,
     ) -> Vec<Self> {
-        loads
+        let specs: Vec<_> = if specs.is_empty() {
+            vec![None]
+        } else {
+            specs.into_iter().map(Some).collect()
+        };
+        specs
             .into_iter()
-            .map(|load| Self {
+            .flat_map(|instance_specs| {
+                loads
+                    .iter()
+                    .map(move |&load| (instance_specs.clone(), load))
+            })
+            .map(|(instance_specs, load)| Self {
                 settings: settings.clone(),
                 node_parameters: node_parameters.clone(),
                 client_parameters: client_parameters.clone(),
                 nodes,
                 load,
+                load_profile: None,
+                load_mode: LoadMode::default(),
+                instance_specs,
+                warmup: Duration::ZERO,
+            })
+            .collect()
+    }
+
+    /// Make a new benchmark parameters for each load profile (and each specs). Constant profiles
+    /// are equivalent to the loads of [`Self::new_from_loads`].
+    pub fn new_from_profiles(
+        settings: Settings,
+        node_parameters: N,
+        client_parameters: C,
+        nodes: usize,
+        profiles: Vec<LoadProfile>,
+        specs: Vec<String>,
+    ) -> Vec<Self> {
+        let specs: Vec<_> = if specs.is_empty() {
+            vec![None]
+        } else {
+            specs.into_iter().map(Some).collect()
+        };
+        specs
+            .into_iter()
+            .flat_map(|instance_specs| {
+                profiles
+                    .iter()
+                    .map(move |profile| (instance_specs.clone(), profile.clone()))
+            })
+            .map(|(instance_specs, profile)| {
+                Self::new_from_profile(
+                    settings.clone(),
+                    node_parameters.clone(),
+                    client_parameters.clone(),
+                    nodes,
+                    profile,
+                    instance_specs,
+                )
             })
             .collect()
     }
 
+    /// Make a new benchmark parameters submitting the specified load profile. The load of the
+    /// parameters is the peak of the profile.
+    pub fn new_from_profile(
+        settings: Settings,
+        node_parameters: N,
+        client_parameters: C,
+        nodes: usize,
+        profile: LoadProfile,
+        instance_specs: Option<String>,
+    ) -> Self {
+        Self {
+            settings,
+            node_parameters,
+            client_parameters,
+            nodes,
+            load: profile.peak(),
+            load_profile: (!matches!(profile, LoadProfile::Constant(_))).then_some(profile),
+            load_mode: LoadMode::default(),
+            instance_specs,
+            warmup: Duration::ZERO,
+        }
+    }
+
     #[cfg(test)]
     pub fn new_for_tests() -> Self {
         Self {
@@ -79,17 +228,294 @@ impl<N: ProtocolParameters, C: ProtocolParameters> BenchmarkParametersGeneric<N,
             client_parameters: C::default(),
             nodes: 4,
             load: 500,
+            load_profile: None,
+            load_mode: LoadMode::default(),
+            instance_specs: None,
+            warmup: Duration::ZERO,
+        }
+    }
+}
+
+/// How the clients submit their load.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Submit the load (or load profile) regardless of the commit throughput.
+    #[default]
+    OpenLoop,
+    /// Start from the load and adjust it until the commit throughput of the system settles near
+    /// the target (tx/s).
+    ClosedLoop { target: usize },
+}
+
+impl LoadMode {
+    /// The commit throughput targeted by the clients, if any.
+    pub fn target_commit_tps(&self) -> Option<usize> {
+        match self {
+            Self::OpenLoop => None,
+            Self::ClosedLoop { target } => Some(*target),
+        }
+    }
+}
+
+impl Display for LoadMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OpenLoop => write!(f, "open"),
+            Self::ClosedLoop { target } => write!(f, "closed:{target}"),
+        }
+    }
+}
+
+impl FromStr for LoadMode {
+    type Err = String;
+
+    /// Parse a load mode from the command line: `open` or `closed:<target>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid load mode '{s}' (expected open or closed:<target>)");
+        match s.strip_prefix("closed:") {
+            Some(target) => Ok(Self::ClosedLoop {
+                target: target.parse().map_err(|_| invalid())?,
+            }),
+            None if s == "open" => Ok(Self::OpenLoop),
+            None => Err(invalid()),
+        }
+    }
+}
+
+/// The load (tx/s) submitted to the system over the course of a benchmark.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum LoadProfile {
+    /// A constant load.
+    Constant(usize),
+    /// A load increasing (or decreasing) linearly from `start` to `end` over `duration`, and
+    /// remaining at `end` afterwards. The load changes every `step_duration` and holds in
+    /// between, so that each intermediate load is measured for a while.
+    Ramp {
+        start: usize,
+        end: usize,
+        duration: Duration,
+        #[serde(default = "LoadProfile::default_step_duration")]
+        step_duration: Duration,
+    },
+    /// A `base` load spiking to `spike` during the first tenth of every `period`.
+    Burst {
+        base: usize,
+        spike: usize,
+        period: Duration,
+    },
+    /// A sequence of (time since the beginning of the benchmark, load) steps sorted by time. Each
+    /// load holds until the next step, and the load is zero before the first step.
+    Step(Vec<(Duration, usize)>),
+}
+
+impl LoadProfile {
+    /// The default interval between two load changes of a ramp.
+    const RAMP_RESOLUTION: Duration = Duration::from_secs(1);
+
+    fn default_step_duration() -> Duration {
+        Self::RAMP_RESOLUTION
+    }
+
+    /// The load at the specified time since the beginning of the benchmark.
+    pub fn load_at(&self, elapsed: Duration) -> usize {
+        match self {
+            Self::Constant(load) => *load,
+            Self::Ramp {
+                start,
+                end,
+                duration,
+                step_duration,
+            } => {
+                if elapsed >= *duration {
+                    return *end;
+                }
+                let elapsed = match elapsed.as_nanos().checked_div(step_duration.as_nanos()) {
+                    Some(steps) => *step_duration * steps as u32,
+                    None => elapsed,
+                };
+                let progress = elapsed.as_secs_f64() / duration.as_secs_f64();
+                let load = *start as f64 + (*end as f64 - *start as f64) * progress;
+                load.round() as usize
+            }
+            Self::Burst {
+                base,
+                spike,
+                period,
+            } => {
+                let offset = elapsed.as_nanos() % period.as_nanos().max(1);
+                if offset < period.as_nanos() / 10 {
+                    *spike
+                } else {
+                    *base
+                }
+            }
+            Self::Step(steps) => steps
+                .iter()
+                .take_while(|(time, _)| *time <= elapsed)
+                .last()
+                .map_or(0, |(_, load)| *load),
+        }
+    }
+
+    /// The highest load of the profile.
+    pub fn peak(&self) -> usize {
+        match self {
+            Self::Constant(load) => *load,
+            Self::Ramp { start, end, .. } => *start.max(end),
+            Self::Burst { base, spike, .. } => *base.max(spike),
+            Self::Step(steps) => steps.iter().map(|(_, load)| *load).max().unwrap_or(0),
+        }
+    }
+
+    /// The (time, load) points at which the load changes over a benchmark of the specified
+    /// duration. The first point is always at time zero.
+    pub fn schedule(&self, duration: Duration) -> Vec<(Duration, usize)> {
+        let times: Vec<_> = match self {
+            Self::Constant(_) => vec![Duration::ZERO],
+            Self::Ramp {
+                duration: ramp,
+                step_duration,
+                ..
+            } => {
+                let step = if step_duration.is_zero() {
+                    Self::RAMP_RESOLUTION
+                } else {
+                    *step_duration
+                };
+                let end = duration.min(*ramp);
+                let steps = end.as_nanos() / step.as_nanos();
+                (0..=steps as u32)
+                    .map(|i| step * i)
+                    .chain((end == *ramp).then_some(end))
+                    .collect()
+            }
+            Self::Burst { period, .. } if !period.is_zero() => (0..)
+                .map(|i| *period * i)
+                .take_while(|time| *time < duration)
+                .flat_map(|time| [time, time + *period / 10])
+                .collect(),
+            Self::Burst { .. } => vec![Duration::ZERO],
+            Self::Step(steps) => std::iter::once(Duration::ZERO)
+                .chain(steps.iter().map(|(time, _)| *time))
+                .filter(|time| *time < duration)
+                .collect(),
+        };
+
+        let mut schedule: Vec<(Duration, usize)> = Vec::new();
+        for time in times {
+            let load = self.load_at(time);
+            match schedule.last() {
+                Some((_, last)) if *last == load => (),
+                Some((last, _)) if *last >= time => (),
+                _ => schedule.push((time, load)),
+            }
+        }
+        schedule
+    }
+}
+
+impl Display for LoadProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Constant(load) => write!(f, "{load}"),
+            Self::Ramp {
+                start,
+                end,
+                duration,
+                step_duration,
+            } => {
+                write!(f, "ramp:{start}-{end}/{}", duration.as_secs())?;
+                if *step_duration != Self::RAMP_RESOLUTION {
+                    write!(f, "@{}", step_duration.as_secs())?;
+                }
+                Ok(())
+            }
+            Self::Burst {
+                base,
+                spike,
+                period,
+            } => write!(f, "burst:{base}-{spike}/{}", period.as_secs()),
+            Self::Step(steps) => {
+                let steps: Vec<_> = steps
+                    .iter()
+                    .map(|(time, load)| format!("{}={load}", time.as_secs()))
+                    .collect();
+                write!(f, "step:{}", steps.join(","))
+            }
+        }
+    }
+}
+
+impl FromStr for LoadProfile {
+    type Err = String;
+
+    /// Parse a load profile from the command line. The supported formats are `<load>` for a
+    /// constant load, `ramp:<start>-<end>/<secs>[@<step secs>]`, `burst:<base>-<spike>/<secs>`,
+    /// and `step:<secs>=<load>,<secs>=<load>,...` (the same format as the display).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid load profile '{s}' (expected <load>, ramp:<start>-<end>/<secs>[@<secs>], \
+                burst:<base>-<spike>/<secs>, or step:<secs>=<load>,...)"
+            )
+        };
+        let parse_range = |s: &str| -> Result<(usize, usize, Duration), String> {
+            let (range, secs) = s.split_once('/').ok_or_else(invalid)?;
+            let (low, high) = range.split_once('-').ok_or_else(invalid)?;
+            Ok((
+                low.parse().map_err(|_| invalid())?,
+                high.parse().map_err(|_| invalid())?,
+                Duration::from_secs(secs.parse().map_err(|_| invalid())?),
+            ))
+        };
+
+        if let Some(ramp) = s.strip_prefix("ramp:") {
+            let (ramp, step_duration) = match ramp.split_once('@') {
+                Some((ramp, step)) => match step.parse() {
+                    Ok(secs) if secs > 0 => (ramp, Duration::from_secs(secs)),
+                    _ => return Err(invalid()),
+                },
+                None => (ramp, Self::RAMP_RESOLUTION),
+            };
+            let (start, end, duration) = parse_range(ramp)?;
+            Ok(Self::Ramp {
+                start,
+                end,
+                duration,
+                step_duration,
+            })
+        } else if let Some(burst) = s.strip_prefix("burst:") {
+            let (base, spike, period) = parse_range(burst)?;
+            Ok(Self::Burst {
+                base,
+                spike,
+                period,
+            })
+        } else if let Some(steps) = s.strip_prefix("step:") {
+            let mut steps = steps
+                .split(',')
+                .map(|step| {
+                    let (secs, load) = step.split_once('=').ok_or_else(invalid)?;
+                    let time = Duration::from_secs(secs.parse().map_err(|_| invalid())?);
+                    Ok((time, load.parse().map_err(|_| invalid())?))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            steps.sort();
+            Ok(Self::Step(steps))
+        } else {
+            Ok(Self::Constant(s.parse().map_err(|_| invalid())?))
         }
     }
 }
 
 #[cfg(test)]
 pub mod test {
-    use std::{fmt::Display, str::FromStr};
+    use std::{fmt::Display, str::FromStr, time::Duration};
 
     use serde::{Deserialize, Serialize};
 
-    use super::ProtocolParameters;
+    use super::{BenchmarkParametersGeneric, LoadMode, LoadProfile, ProtocolParameters};
+    use crate::settings::Settings;
 
     /// Mock benchmark type for unit tests.
     #[derive(
@@ -112,4 +538,171 @@ pub mod test {
     }
 
     impl ProtocolParameters for TestNodeConfig {}
+
+    type TestBenchmarkParameters = BenchmarkParametersGeneric<TestNodeConfig, TestNodeConfig>;
+
+    #[test]
+    fn ramp_profile() {
+        let profile: LoadProfile = "ramp:100-1000/60".parse().unwrap();
+        assert_eq!(
+            profile,
+            LoadProfile::Ramp {
+                start: 100,
+                end: 1000,
+                duration: Duration::from_secs(60),
+                step_duration: Duration::from_secs(1),
+            }
+        );
+        assert_eq!(profile.peak(), 1000);
+        assert_eq!(profile.load_at(Duration::from_secs(30)), 550);
+        assert_eq!(profile.load_at(Duration::from_secs(90)), 1000);
+
+        let schedule = profile.schedule(Duration::from_secs(180));
+        assert_eq!(schedule.first(), Some(&(Duration::ZERO, 100)));
+        assert_eq!(schedule.last(), Some(&(Duration::from_secs(60), 1000)));
+        for window in schedule.windows(2) {
+            assert!(window[0].0 < window[1].0);
+            assert!(window[0].1 < window[1].1);
+        }
+
+        // The schedule stops at the end of the benchmark.
+        let schedule = profile.schedule(Duration::from_secs(30));
+        assert_eq!(schedule.last(), Some(&(Duration::from_secs(30), 550)));
+    }
+
+    #[test]
+    fn stepped_ramp_profile() {
+        let profile: LoadProfile = "ramp:1000-5000/40@10".parse().unwrap();
+        assert_eq!(profile.to_string(), "ramp:1000-5000/40@10");
+        assert_eq!(profile.load_at(Duration::from_secs(9)), 1000);
+        assert_eq!(profile.load_at(Duration::from_secs(15)), 2000);
+        assert_eq!(profile.load_at(Duration::from_secs(39)), 4000);
+        assert_eq!(profile.load_at(Duration::from_secs(40)), 5000);
+
+        let schedule = profile.schedule(Duration::from_secs(60));
+        let expected: Vec<_> = [(0, 1000), (10, 2000), (20, 3000), (30, 4000), (40, 5000)]
+            .into_iter()
+            .map(|(secs, load)| (Duration::from_secs(secs), load))
+            .collect();
+        assert_eq!(schedule, expected);
+
+        // The step duration defaults to one second and must be positive.
+        assert_eq!(
+            "ramp:100-1000/60"
+                .parse::<LoadProfile>()
+                .unwrap()
+                .to_string(),
+            "ramp:100-1000/60"
+        );
+        assert!("ramp:100-1000/60@0".parse::<LoadProfile>().is_err());
+
+        let parameters = TestBenchmarkParameters::new_from_profile(
+            Settings::new_for_test(),
+            TestNodeConfig,
+            TestNodeConfig,
+            4,
+            profile.clone(),
+            None,
+        );
+        assert_eq!(parameters.load, 5000);
+        assert_eq!(parameters.load_profile, Some(profile));
+    }
+
+    #[test]
+    fn burst_and_step_profiles() {
+        let burst: LoadProfile = "burst:100-500/10".parse().unwrap();
+        assert_eq!(
+            burst.schedule(Duration::from_secs(25)),
+            vec![
+                (Duration::ZERO, 500),
+                (Duration::from_secs(1), 100),
+                (Duration::from_secs(10), 500),
+                (Duration::from_secs(11), 100),
+                (Duration::from_secs(20), 500),
+                (Duration::from_secs(21), 100),
+            ]
+        );
+
+        let step: LoadProfile = "step:20=300,10=200".parse().unwrap();
+        assert_eq!(step.to_string(), "step:10=200,20=300");
+        assert_eq!(step.peak(), 300);
+        assert_eq!(
+            step.schedule(Duration::from_secs(60)),
+            vec![
+                (Duration::ZERO, 0),
+                (Duration::from_secs(10), 200),
+                (Duration::from_secs(20), 300),
+            ]
+        );
+
+        assert!("ramp:100/60".parse::<LoadProfile>().is_err());
+        assert!("step:10".parse::<LoadProfile>().is_err());
+    }
+
+    #[test]
+    fn constant_profile() {
+        let settings = Settings::new_for_test();
+        let specs = vec!["small".to_string(), "large".to_string()];
+        let from_loads = TestBenchmarkParameters::new_from_loads(
+            settings.clone(),
+            TestNodeConfig,
+            TestNodeConfig,
+            4,
+            vec![200, 400],
+            specs.clone(),
+        );
+        let from_profiles = TestBenchmarkParameters::new_from_profiles(
+            settings,
+            TestNodeConfig,
+            TestNodeConfig,
+            4,
+            vec!["200".parse().unwrap(), LoadProfile::Constant(400)],
+            specs,
+        );
+
+        assert_eq!(from_loads.len(), from_profiles.len());
+        for (a, b) in from_loads.iter().zip(from_profiles.iter()) {
+            assert_eq!(format!("{a:?}"), format!("{b:?}"));
+            assert_eq!(a.to_string(), b.to_string());
+            assert_eq!(b.load_profile, None);
+            assert_eq!(
+                serde_json::to_string(a).unwrap(),
+                serde_json::to_string(b).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn non_constant_profile() {
+        let profile: LoadProfile = "ramp:100-1000/60".parse().unwrap();
+        let parameters = TestBenchmarkParameters::new_from_profiles(
+            Settings::new_for_test(),
+            TestNodeConfig,
+            TestNodeConfig,
+            4,
+            vec![LoadProfile::Constant(200), profile.clone()],
+            vec![],
+        );
+        assert_eq!(parameters[0].load_profile, None);
+        assert_eq!(parameters[1].load, 1000);
+        assert_eq!(parameters[1].load_profile, Some(profile));
+        assert!(format!("{:?}", parameters[1]).ends_with("-1000-ramp:100-1000/60"));
+    }
+
+    #[test]
+    fn load_mode() {
+        assert_eq!("open".parse(), Ok(LoadMode::OpenLoop));
+        let mode: LoadMode = "closed:5000".parse().unwrap();
+        assert_eq!(mode, LoadMode::ClosedLoop { target: 5000 });
+        assert_eq!(mode.target_commit_tps(), Some(5000));
+        assert_eq!(mode.to_string(), "closed:5000");
+        assert!("closed".parse::<LoadMode>().is_err());
+        assert!("closed:fast".parse::<LoadMode>().is_err());
+
+        // Open-loop benchmarks keep the same identifier.
+        let mut parameters = TestBenchmarkParameters::new_for_tests();
+        let open = format!("{parameters:?}");
+        parameters.load_mode = mode;
+        assert_eq!(format!("{parameters:?}"), format!("{open}-closed:5000"));
+    }
 }
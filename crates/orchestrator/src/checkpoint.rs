@@ -0,0 +1,124 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::benchmark::BenchmarkParameters;
+
+/// The benchmarks completed by previous runs of the orchestrator, letting an interrupted sweep
+/// resume without running them again. A benchmark only matches a checkpoint if all its
+/// parameters (including the settings) are identical, so that changing any of them (e.g., the
+/// committee size) runs the benchmark again.
+pub struct Checkpoint {
+    /// The file recording the parameters of the completed benchmarks, one per line.
+    path: PathBuf,
+    /// The parameters of the completed benchmarks.
+    completed: Vec<Value>,
+}
+
+impl Checkpoint {
+    pub const FILENAME: &'static str = "checkpoint.jsonl";
+
+    /// Load the checkpoints stored in the specified directory (if any). Lines that cannot be
+    /// parsed (e.g., because the orchestrator crashed while writing them) are ignored.
+    pub fn load<P: AsRef<Path>>(directory: P) -> io::Result<Self> {
+        let path = directory.as_ref().join(Self::FILENAME);
+        let completed = match fs::read_to_string(&path) {
+            Ok(content) => content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path, completed })
+    }
+
+    /// Whether the benchmark with the specified parameters already completed.
+    pub fn is_completed(&self, parameters: &BenchmarkParameters) -> bool {
+        self.completed.contains(&Self::key(parameters))
+    }
+
+    /// Record that the benchmark with the specified parameters completed. The record is written
+    /// to disk right away so that it survives a crash of the orchestrator.
+    pub fn record(&mut self, parameters: &BenchmarkParameters) -> io::Result<()> {
+        let key = Self::key(parameters);
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{key}")?;
+        self.completed.push(key);
+        Ok(())
+    }
+
+    /// The representation of the parameters compared across runs.
+    fn key(parameters: &BenchmarkParameters) -> Value {
+        serde_json::to_value(parameters).expect("Failed to serialize the benchmark parameters")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, time::Duration};
+
+    use super::Checkpoint;
+    use crate::benchmark::BenchmarkParameters;
+
+    /// Make benchmark parameters differing from the base ones only by their committee size and load.
+    fn parameters(base: &BenchmarkParameters, nodes: usize, load: usize) -> BenchmarkParameters {
+        let mut parameters = base.clone();
+        parameters.nodes = nodes;
+        parameters.load = load;
+        parameters
+    }
+
+    #[test]
+    fn record_and_reload() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("results");
+        let base = BenchmarkParameters::new_for_tests();
+
+        let mut checkpoint = Checkpoint::load(&path).unwrap();
+        assert!(!checkpoint.is_completed(&parameters(&base, 4, 100)));
+        checkpoint.record(&parameters(&base, 4, 100)).unwrap();
+        assert!(checkpoint.is_completed(&parameters(&base, 4, 100)));
+
+        // The checkpoints survive across runs of the orchestrator.
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        assert!(checkpoint.is_completed(&parameters(&base, 4, 100)));
+        assert!(!checkpoint.is_completed(&parameters(&base, 4, 200)));
+
+        // Changing any other parameter invalidates the checkpoint.
+        assert!(!checkpoint.is_completed(&parameters(&base, 7, 100)));
+        let mut other = parameters(&base, 4, 100);
+        other.settings.benchmark_duration = Duration::from_secs(300);
+        assert!(!checkpoint.is_completed(&other));
+    }
+
+    #[test]
+    fn ignore_truncated_records() {
+        let directory = tempfile::tempdir().unwrap();
+        let base = BenchmarkParameters::new_for_tests();
+        let mut checkpoint = Checkpoint::load(directory.path()).unwrap();
+        checkpoint.record(&parameters(&base, 4, 100)).unwrap();
+
+        // Simulate a crash while appending a record.
+        let path = directory.path().join(Checkpoint::FILENAME);
+        let mut content = fs::read_to_string(&path).unwrap();
+        content.push_str("{\"nodes\": 4, \"lo");
+        fs::write(&path, content).unwrap();
+
+        let checkpoint = Checkpoint::load(directory.path()).unwrap();
+        assert!(checkpoint.is_completed(&parameters(&base, 4, 100)));
+    }
+}
@@ -22,13 +22,19 @@ use aws_sdk_ec2::{
         },
         EphemeralNvmeSupport,
         Instance as AwsInstance,
+        InstanceInterruptionBehavior,
+        InstanceLifecycleType,
+        InstanceMarketOptionsRequest,
+        MarketType,
         ResourceType,
+        SpotInstanceType,
+        SpotMarketOptions,
         VolumeType,
     },
 };
 use serde::Serialize;
 
-use super::{Instance, ServerProviderClient};
+use super::{create_with_spot_fallback, Instance, InstanceStatus, ServerProviderClient};
 use crate::{
     error::{CloudProviderError, CloudProviderResult},
     settings::Settings,
@@ -62,6 +68,7 @@ impl AwsClient {
     const OS_IMAGE: &'static str =
         "Canonical, Ubuntu, 24.04 LTS, amd64 noble image build on 2024-04-23";
     const DEFAULT_EBS_SIZE_GB: i32 = 500; // Default size of the EBS volume in GB.
+    const TAG_KEY_PREFIX: &'static str = "orchestrator:"; // Prefix of the keys of the user tags.
 
     /// Make a new AWS client.
     pub async fn new(settings: Settings) -> Self {
@@ -100,8 +107,42 @@ impl AwsClient {
         Ok(())
     }
 
+    /// Return whether the state reason of an instance indicates that AWS reclaimed its spot
+    /// capacity (e.g., 'Server.SpotInstanceTermination').
+    fn is_spot_interruption(state_reason_code: Option<&str>) -> bool {
+        state_reason_code.is_some_and(|x| x.starts_with("Server.SpotInstance"))
+    }
+
+    /// Convert the state of an AWS instance into an instance status. Interrupted spot instances
+    /// are reported as inactive (even if AWS terminated them) so the testbed can replace them.
+    fn instance_status(state_name: &str, state_reason_code: Option<&str>) -> InstanceStatus {
+        match InstanceStatus::from(state_name) {
+            _ if Self::is_spot_interruption(state_reason_code) => InstanceStatus::Inactive,
+            status => status,
+        }
+    }
+
     /// Convert an AWS instance into an orchestrator instance (used in the rest of the codebase).
     fn make_instance(&self, region: String, aws_instance: &AwsInstance) -> Instance {
+        let mut tags = vec![self.settings.testbed_id.clone()];
+        if aws_instance.instance_lifecycle() == Some(&InstanceLifecycleType::Spot) {
+            tags.push(Instance::SPOT_TAG.into());
+        }
+        let user_tags = aws_instance.tags().iter().filter(|x| {
+            x.key()
+                .is_some_and(|key| key.starts_with(Self::TAG_KEY_PREFIX))
+        });
+        tags.extend(user_tags.filter_map(|x| x.value()).map(String::from));
+        let state_name = format!(
+            "{:?}",
+            aws_instance
+                .state()
+                .expect("AWS instance should have a state")
+                .name()
+                .expect("AWS status should have a name")
+        );
+        let state_reason_code = aws_instance.state_reason().and_then(|x| x.code());
+
         Instance {
             id: aws_instance
                 .instance_id()
@@ -113,23 +154,14 @@ impl AwsClient {
                 .unwrap_or("0.0.0.0") // Stopped instances do not have an ip address.
                 .parse()
                 .expect("AWS instance should have a valid ip"),
-            tags: vec![self.settings.testbed_id.clone()],
+            tags,
             specs: format!(
                 "{:?}",
                 aws_instance
                     .instance_type()
                     .expect("AWS instance should have a type")
             ),
-            status: format!(
-                "{:?}",
-                aws_instance
-                    .state()
-                    .expect("AWS instance should have a state")
-                    .name()
-                    .expect("AWS status should have a name")
-            )
-            .as_str()
-            .into(),
+            status: Self::instance_status(&state_name, state_reason_code),
         }
     }
 
@@ -189,6 +221,79 @@ impl AwsClient {
         Ok(())
     }
 
+    /// The market options of a spot request, bidding at most the specified hourly price (or the
+    /// on-demand price if unspecified). One-time spot requests fail right away if there is no
+    /// spot capacity available.
+    fn spot_market_options(max_price: Option<String>) -> InstanceMarketOptionsRequest {
+        let options = SpotMarketOptions::builder()
+            .set_max_price(max_price)
+            .spot_instance_type(SpotInstanceType::OneTime)
+            .instance_interruption_behavior(InstanceInterruptionBehavior::Terminate)
+            .build();
+        InstanceMarketOptionsRequest::builder()
+            .market_type(MarketType::Spot)
+            .spot_options(options)
+            .build()
+    }
+
+    /// Request a new instance, either with spot or on-demand capacity.
+    async fn run_instance(
+        &self,
+        client: &aws_sdk_ec2::Client,
+        region: &str,
+        image_id: &str,
+        specs: &str,
+        tags: &[String],
+        spot: bool,
+    ) -> CloudProviderResult<Instance> {
+        let testbed_id = &self.settings.testbed_id;
+
+        // AWS tags are key-value pairs, so each tag is stored under a key derived from itself.
+        let mut specification = TagSpecificationBuilder::default()
+            .resource_type(ResourceType::Instance)
+            .tags(TagBuilder::default().key("Name").value(testbed_id).build());
+        for tag in tags {
+            let key = format!("{}{tag}", Self::TAG_KEY_PREFIX);
+            specification = specification.tags(TagBuilder::default().key(key).value(tag).build());
+        }
+        let tags = specification.build();
+
+        let storage = BlockDeviceMappingBuilder::default()
+            .device_name("/dev/sda1")
+            .ebs(
+                EbsBlockDeviceBuilder::default()
+                    .delete_on_termination(true)
+                    .volume_size(Self::DEFAULT_EBS_SIZE_GB)
+                    .volume_type(VolumeType::Gp2)
+                    .build(),
+            )
+            .build();
+
+        let mut request = client
+            .run_instances()
+            .image_id(image_id)
+            .instance_type(specs.into())
+            .key_name(testbed_id)
+            .min_count(1)
+            .max_count(1)
+            .security_groups(testbed_id)
+            .block_device_mappings(storage)
+            .tag_specifications(tags);
+
+        if spot {
+            let options = Self::spot_market_options(self.settings.max_spot_price.clone());
+            request = request.instance_market_options(options);
+        }
+
+        let response = request.send().await?;
+        let instance = &response
+            .instances()
+            .first()
+            .expect("AWS instances list should contain instances");
+
+        Ok(self.make_instance(region.into(), instance))
+    }
+
     /// Return the command to mount the first (standard) NVMe drive.
     fn nvme_mount_command(&self) -> Vec<String> {
         const DRIVE: &str = "nvme1n1";
@@ -307,12 +412,16 @@ impl ServerProviderClient for AwsClient {
         Ok(())
     }
 
-    async fn create_instance<S>(&self, region: S) -> CloudProviderResult<Instance>
+    async fn create_instance<S>(
+        &self,
+        region: S,
+        specs: &str,
+        tags: &[String],
+    ) -> CloudProviderResult<Instance>
     where
         S: Into<String> + Serialize + Send,
     {
         let region = region.into();
-        let testbed_id = &self.settings.testbed_id;
 
         let client = self.clients.get(&region).ok_or_else(|| {
             CloudProviderError::RequestError(format!("Undefined region {region:?}"))
@@ -324,41 +433,16 @@ impl ServerProviderClient for AwsClient {
         // Query the image id.
         let image_id = self.find_image_id(client).await?;
 
-        // Create a new instance.
-        let tags = TagSpecificationBuilder::default()
-            .resource_type(ResourceType::Instance)
-            .tags(TagBuilder::default().key("Name").value(testbed_id).build())
-            .build();
-
-        let storage = BlockDeviceMappingBuilder::default()
-            .device_name("/dev/sda1")
-            .ebs(
-                EbsBlockDeviceBuilder::default()
-                    .delete_on_termination(true)
-                    .volume_size(Self::DEFAULT_EBS_SIZE_GB)
-                    .volume_type(VolumeType::Gp2)
-                    .build(),
-            )
-            .build();
-
-        let request = client
-            .run_instances()
-            .image_id(image_id)
-            .instance_type(self.settings.specs.as_str().into())
-            .key_name(testbed_id)
-            .min_count(1)
-            .max_count(1)
-            .security_groups(&self.settings.testbed_id)
-            .block_device_mappings(storage)
-            .tag_specifications(tags);
-
-        let response = request.send().await?;
-        let instance = &response
-            .instances()
-            .first()
-            .expect("AWS instances list should contain instances");
-
-        Ok(self.make_instance(region, instance))
+        // Create a new instance (with spot capacity if possible).
+        if self.settings.use_spot_instances {
+            let spot = self.run_instance(client, &region, &image_id, specs, tags, true);
+            let on_demand = || self.run_instance(client, &region, &image_id, specs, tags, false);
+            let timeout = self.settings.spot_request_timeout;
+            create_with_spot_fallback(spot, on_demand, timeout).await
+        } else {
+            self.run_instance(client, &region, &image_id, specs, tags, false)
+                .await
+        }
     }
 
     async fn delete_instance(&self, instance: Instance) -> CloudProviderResult<()> {
@@ -396,3 +480,52 @@ impl ServerProviderClient for AwsClient {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use aws_sdk_ec2::types::{InstanceInterruptionBehavior, MarketType, SpotInstanceType};
+
+    use super::AwsClient;
+    use crate::client::InstanceStatus;
+
+    #[test]
+    fn spot_request_parameters() {
+        let options = AwsClient::spot_market_options(Some("0.05".into()));
+        assert_eq!(options.market_type(), Some(&MarketType::Spot));
+
+        let spot = options.spot_options().unwrap();
+        assert_eq!(spot.max_price(), Some("0.05"));
+        assert_eq!(spot.spot_instance_type(), Some(&SpotInstanceType::OneTime));
+        assert_eq!(
+            spot.instance_interruption_behavior(),
+            Some(&InstanceInterruptionBehavior::Terminate)
+        );
+
+        // Without a max price, AWS caps the bid at the on-demand price.
+        let options = AwsClient::spot_market_options(None);
+        assert_eq!(options.spot_options().unwrap().max_price(), None);
+    }
+
+    #[test]
+    fn spot_interruption() {
+        assert_eq!(
+            AwsClient::instance_status("Running", None),
+            InstanceStatus::Active
+        );
+        assert_eq!(
+            AwsClient::instance_status("Terminated", Some("Client.UserInitiatedShutdown")),
+            InstanceStatus::Terminated
+        );
+
+        // Interrupted spot instances are inactive until the testbed replaces them.
+        for code in [
+            "Server.SpotInstanceTermination",
+            "Server.SpotInstanceShutdown",
+        ] {
+            assert_eq!(
+                AwsClient::instance_status("Terminated", Some(code)),
+                InstanceStatus::Inactive
+            );
+        }
+    }
+}
@@ -0,0 +1,468 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fmt::Display, net::Ipv4Addr};
+
+use reqwest::{Client as NetworkClient, Response, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{Instance, InstanceStatus, ServerProviderClient};
+use crate::{
+    error::{CloudProviderError, CloudProviderResult},
+    settings::Settings,
+};
+
+/// Represents the ssh key information as defined by DigitalOcean.
+#[derive(Debug, Deserialize)]
+pub struct SshKey {
+    pub id: u64,
+    pub name: String,
+}
+
+/// Represents a region as defined by DigitalOcean.
+#[derive(Debug, Deserialize)]
+pub struct DropletRegion {
+    pub slug: String,
+}
+
+/// Represents a network interface as defined by DigitalOcean.
+#[derive(Debug, Deserialize)]
+pub struct DropletNetwork {
+    pub ip_address: Ipv4Addr,
+    #[serde(rename = "type")]
+    pub network_type: String,
+}
+
+/// Represents the network interfaces of a droplet as defined by DigitalOcean.
+#[derive(Debug, Default, Deserialize)]
+pub struct DropletNetworks {
+    #[serde(default)]
+    pub v4: Vec<DropletNetwork>,
+}
+
+/// Represents an instance (droplet) as defined by DigitalOcean.
+#[derive(Debug, Deserialize)]
+pub struct Droplet {
+    pub id: u64,
+    pub status: String,
+    pub size_slug: String,
+    pub region: DropletRegion,
+    #[serde(default)]
+    pub networks: DropletNetworks,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl From<Droplet> for Instance {
+    fn from(droplet: Droplet) -> Self {
+        // The public ip address is only assigned once the droplet boots.
+        let main_ip = droplet
+            .networks
+            .v4
+            .iter()
+            .find(|x| x.network_type == "public")
+            .map_or(Ipv4Addr::UNSPECIFIED, |x| x.ip_address);
+        let status = match droplet.status.as_str() {
+            "active" => InstanceStatus::Active,
+            "archive" => InstanceStatus::Terminated,
+            _ => InstanceStatus::Inactive,
+        };
+        Self {
+            id: droplet.id.to_string(),
+            region: droplet.region.slug,
+            main_ip,
+            tags: droplet.tags,
+            specs: droplet.size_slug,
+            status,
+        }
+    }
+}
+
+impl Droplet {
+    /// Return whether the droplet belongs to the testbed specified in the setting file. The
+    /// droplets of all sizes are listed since benchmarks may override the specs of the settings.
+    pub fn filter(&self, settings: &Settings) -> bool {
+        settings.regions.contains(&self.region.slug) && self.tags.contains(&settings.testbed_id)
+    }
+}
+
+/// A DigitalOcean client. The regions and specs of the settings are DigitalOcean slugs, e.g.,
+/// `nyc3` and `s-8vcpu-16gb`.
+pub struct DigitalOceanClient {
+    token: String,
+    settings: Settings,
+    base_url: Url,
+    client: NetworkClient,
+}
+
+impl Display for DigitalOceanClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DigitalOcean API client v2")
+    }
+}
+
+impl DigitalOceanClient {
+    const BASE_URL: &'static str = "https://api.digitalocean.com/v2/";
+    const DEFAULT_IMAGE: &'static str = "ubuntu-22-04-x64";
+    /// The maximum number of items DigitalOcean returns per page.
+    const PAGE_SIZE: usize = 200;
+
+    /// Make a new DigitalOcean client.
+    pub fn new<T: Into<String>>(token: T, settings: Settings) -> Self {
+        Self {
+            token: token.into(),
+            settings,
+            base_url: Self::BASE_URL.parse().unwrap(),
+            client: NetworkClient::new(),
+        }
+    }
+
+    /// Send the requests to the specified server instead of the DigitalOcean API.
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = format!("{}/", base_url.trim_end_matches('/'))
+            .parse()
+            .unwrap();
+        self
+    }
+
+    /// Check an http response and extract the error message returned by DigitalOcean (if any).
+    async fn check_response(response: Response) -> CloudProviderResult<Response> {
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status().to_string();
+        let message = match response.json::<Value>().await {
+            Ok(json) => json["message"].to_string(),
+            Err(_) => "[no body]".into(),
+        };
+        Err(CloudProviderError::FailureResponseCode(status, message))
+    }
+
+    /// Retrieve the ssh key associated with the current testbed.
+    pub async fn get_key(&self) -> CloudProviderResult<Option<SshKey>> {
+        let mut url = self.base_url.join("account/keys").unwrap();
+        url.query_pairs_mut()
+            .append_pair("per_page", &Self::PAGE_SIZE.to_string());
+        let response = self.client.get(url).bearer_auth(&self.token).send().await?;
+
+        let json: Value = Self::check_response(response).await?.json().await?;
+        let content = json["ssh_keys"].clone();
+        let keys: Vec<SshKey> = serde_json::from_value(content)?;
+
+        Ok(keys
+            .into_iter()
+            .find(|x| x.name == self.settings.testbed_id))
+    }
+
+    /// Run the specified action (e.g., 'power_on') on the specified droplets.
+    async fn run_action<'a, I>(&self, instances: I, action: &str) -> CloudProviderResult<()>
+    where
+        I: Iterator<Item = &'a Instance> + Send,
+    {
+        for instance in instances {
+            let url = self
+                .base_url
+                .join(&format!("droplets/{}/actions", instance.id))
+                .unwrap();
+            let parameters = json!({ "type": action });
+
+            let response = self
+                .client
+                .post(url)
+                .bearer_auth(&self.token)
+                .json(&parameters)
+                .send()
+                .await?;
+
+            Self::check_response(response).await?;
+        }
+        Ok(())
+    }
+}
+
+impl ServerProviderClient for DigitalOceanClient {
+    const USERNAME: &'static str = "root";
+
+    async fn list_instances(&self) -> CloudProviderResult<Vec<Instance>> {
+        let mut url = self.base_url.join("droplets").unwrap();
+        url.query_pairs_mut()
+            .append_pair("tag_name", &self.settings.testbed_id)
+            .append_pair("per_page", &Self::PAGE_SIZE.to_string());
+
+        // Follow the pagination links until all droplets are listed.
+        let mut droplets = Vec::new();
+        let mut next = Some(url);
+        while let Some(url) = next.take() {
+            let response = self.client.get(url).bearer_auth(&self.token).send().await?;
+
+            let json: Value = Self::check_response(response).await?.json().await?;
+            let content = json["droplets"].clone();
+            droplets.extend(serde_json::from_value::<Vec<Droplet>>(content)?);
+            next = json["links"]["pages"]["next"]
+                .as_str()
+                .and_then(|x| x.parse().ok());
+        }
+
+        let filtered = droplets
+            .into_iter()
+            .filter(|x| x.filter(&self.settings))
+            .map(|x| x.into())
+            .collect();
+
+        Ok(filtered)
+    }
+
+    async fn start_instances<'a, I>(&self, instances: I) -> CloudProviderResult<()>
+    where
+        I: Iterator<Item = &'a Instance> + Send,
+    {
+        self.run_action(instances, "power_on").await
+    }
+
+    async fn stop_instances<'a, I>(&self, instances: I) -> CloudProviderResult<()>
+    where
+        I: Iterator<Item = &'a Instance> + Send,
+    {
+        self.run_action(instances, "power_off").await
+    }
+
+    async fn create_instance<S>(
+        &self,
+        region: S,
+        specs: &str,
+        tags: &[String],
+    ) -> CloudProviderResult<Instance>
+    where
+        S: Into<String> + Serialize + Send,
+    {
+        let testbed_name = self.settings.testbed_id.clone();
+        let ssh_key_id = match self.get_key().await? {
+            Some(key) => key.id,
+            None => return Err(CloudProviderError::SshKeyNotFound(testbed_name.clone())),
+        };
+
+        let mut tags = tags.to_vec();
+        tags.insert(0, testbed_name.clone());
+        let url = self.base_url.join("droplets").unwrap();
+        let parameters = json!({
+                "name": testbed_name,
+                "region": region,
+                "size": specs,
+                "image": Self::DEFAULT_IMAGE,
+                "ssh_keys": [ssh_key_id],
+                "tags": tags
+        });
+
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(&self.token)
+            .json(&parameters)
+            .send()
+            .await?;
+
+        let json: Value = Self::check_response(response).await?.json().await?;
+        let content = json["droplet"].clone();
+        let droplet: Droplet = serde_json::from_value(content)?;
+        Ok(droplet.into())
+    }
+
+    async fn delete_instance(&self, instance: Instance) -> CloudProviderResult<()> {
+        let url = self
+            .base_url
+            .join(&format!("droplets/{}", &instance.id))
+            .unwrap();
+
+        let response = self
+            .client
+            .delete(url)
+            .bearer_auth(&self.token)
+            .send()
+            .await?;
+
+        Self::check_response(response).await?;
+        Ok(())
+    }
+
+    async fn register_ssh_public_key(&self, public_key: String) -> CloudProviderResult<()> {
+        // Do not upload the key if it already exists.
+        if self.get_key().await?.is_some() {
+            return Ok(());
+        }
+
+        let url = self.base_url.join("account/keys").unwrap();
+        let parameters = json!({
+                "name": self.settings.testbed_id.clone(),
+                "public_key": public_key
+        });
+
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(&self.token)
+            .json(&parameters)
+            .send()
+            .await?;
+
+        Self::check_response(response).await?;
+        Ok(())
+    }
+
+    async fn instance_setup_commands(&self) -> CloudProviderResult<Vec<String>> {
+        Ok(vec!["sudo ufw disable".into()])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use mockito::{Matcher, Server};
+    use serde_json::{json, Value};
+
+    use super::DigitalOceanClient;
+    use crate::{
+        client::{Instance, ServerProviderClient},
+        error::CloudProviderError,
+        settings::Settings,
+    };
+
+    fn droplet(id: u64, region: &str, status: &str) -> Value {
+        json!({
+            "id": id,
+            "name": "testbed",
+            "status": status,
+            "size_slug": "s-8vcpu-16gb",
+            "region": { "slug": region, "name": "New York 3" },
+            "networks": {
+                "v4": [
+                    { "ip_address": "10.0.0.1", "type": "private" },
+                    { "ip_address": "192.0.2.1", "type": "public" }
+                ]
+            },
+            "tags": ["testbed"]
+        })
+    }
+
+    fn settings() -> Settings {
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["nyc3".into()];
+        settings.specs = "s-8vcpu-16gb".into();
+        settings
+    }
+
+    #[tokio::test]
+    async fn list_instances() {
+        let mut server = Server::new_async().await;
+        let droplets = json!({
+            "droplets": [droplet(1, "nyc3", "active"), droplet(2, "nyc3", "off"), droplet(3, "ams3", "active")],
+            "links": {}
+        });
+        let mock = server
+            .mock("GET", "/droplets")
+            .match_query(Matcher::UrlEncoded("tag_name".into(), "testbed".into()))
+            .match_header("authorization", "Bearer token")
+            .with_body(droplets.to_string())
+            .create_async()
+            .await;
+
+        let client = DigitalOceanClient::new("token", settings()).with_base_url(&server.url());
+        let instances = client.list_instances().await.unwrap();
+        mock.assert_async().await;
+
+        // The droplets outside the regions of the settings are ignored.
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[0].id, "1");
+        assert_eq!(instances[0].region, "nyc3");
+        assert_eq!(instances[0].main_ip, Ipv4Addr::new(192, 0, 2, 1));
+        assert!(instances[0].has_specs("s-8vcpu-16gb"));
+        assert!(instances[0].is_active());
+        assert!(instances[1].is_inactive());
+    }
+
+    #[tokio::test]
+    async fn create_instance() {
+        let mut server = Server::new_async().await;
+        let keys = json!({ "ssh_keys": [{ "id": 42, "name": "testbed", "fingerprint": "ab" }] });
+        let keys_mock = server
+            .mock("GET", "/account/keys")
+            .match_query(Matcher::Any)
+            .with_body(keys.to_string())
+            .create_async()
+            .await;
+
+        // The droplet has no public address until it boots.
+        let mut created = droplet(7, "nyc3", "new");
+        created["networks"] = json!({ "v4": [] });
+        let create_mock = server
+            .mock("POST", "/droplets")
+            .match_body(Matcher::PartialJson(json!({
+                "region": "nyc3",
+                "size": "s-8vcpu-16gb",
+                "ssh_keys": [42],
+                "tags": ["testbed", "run-1"]
+            })))
+            .with_status(202)
+            .with_body(json!({ "droplet": created }).to_string())
+            .create_async()
+            .await;
+
+        let client = DigitalOceanClient::new("token", settings()).with_base_url(&server.url());
+        let instance = client
+            .create_instance("nyc3", "s-8vcpu-16gb", &["run-1".into()])
+            .await
+            .unwrap();
+        keys_mock.assert_async().await;
+        create_mock.assert_async().await;
+
+        assert_eq!(instance.id, "7");
+        assert_eq!(instance.main_ip, Ipv4Addr::UNSPECIFIED);
+        assert!(instance.is_inactive());
+    }
+
+    #[tokio::test]
+    async fn create_instance_without_key() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/account/keys")
+            .match_query(Matcher::Any)
+            .with_body(json!({ "ssh_keys": [] }).to_string())
+            .create_async()
+            .await;
+        let create_mock = server
+            .mock("POST", "/droplets")
+            .expect(0)
+            .create_async()
+            .await;
+
+        let client = DigitalOceanClient::new("token", settings()).with_base_url(&server.url());
+        let result = client.create_instance("nyc3", "s-8vcpu-16gb", &[]).await;
+        assert!(matches!(result, Err(CloudProviderError::SshKeyNotFound(_))));
+        create_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn failure_response() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("DELETE", "/droplets/7")
+            .with_status(429)
+            .with_body(
+                json!({ "id": "too_many_requests", "message": "API rate limit exceeded" })
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let client = DigitalOceanClient::new("token", settings()).with_base_url(&server.url());
+        let instance = Instance::new_for_test("7".into());
+        let error = client.delete_instance(instance).await.unwrap_err();
+        assert!(error.is_transient());
+        assert!(
+            error.to_string().contains("API rate limit exceeded"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,514 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::HashMap, fmt::Display, net::Ipv4Addr, path::Path};
+
+use reqwest::{Client as NetworkClient, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{Instance, InstanceStatus, ServerProviderClient};
+use crate::{
+    error::{CloudProviderError, CloudProviderResult},
+    settings::Settings,
+};
+
+/// Represents the access configuration of a network interface as defined by GCP.
+#[derive(Debug, Deserialize)]
+pub struct GcpAccessConfig {
+    #[serde(rename = "natIP")]
+    pub nat_ip: Option<Ipv4Addr>,
+}
+
+/// Represents a network interface as defined by GCP.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcpNetworkInterface {
+    #[serde(default)]
+    pub access_configs: Vec<GcpAccessConfig>,
+}
+
+/// Represents an instance as defined by GCP.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcpInstance {
+    pub name: String,
+    /// The url of the zone hosting the instance.
+    pub zone: String,
+    /// The url of the machine type of the instance.
+    pub machine_type: String,
+    pub status: String,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub network_interfaces: Vec<GcpNetworkInterface>,
+}
+
+impl GcpInstance {
+    /// Return the last segment of a GCP resource url (e.g., the zone name).
+    fn resource_name(url: &str) -> &str {
+        url.rsplit('/').next().unwrap_or(url)
+    }
+
+    /// Return the public ip of the instance. Stopped instances do not have an ip address.
+    fn public_ip(&self) -> Ipv4Addr {
+        self.network_interfaces
+            .iter()
+            .flat_map(|x| x.access_configs.iter())
+            .find_map(|x| x.nat_ip)
+            .unwrap_or(Ipv4Addr::UNSPECIFIED)
+    }
+
+    /// Return whether the instance belongs to the testbed specified in the setting file. The
+    /// instances of all specs are listed since benchmarks may override the specs of the settings.
+    pub fn filter(&self, settings: &Settings) -> bool {
+        settings
+            .regions
+            .iter()
+            .any(|x| x == Self::resource_name(&self.zone))
+            && self.labels.get(GcpClient::TESTBED_LABEL) == Some(&GcpClient::label(settings))
+    }
+}
+
+impl From<GcpInstance> for Instance {
+    fn from(instance: GcpInstance) -> Self {
+        // GCP reports stopped instances as 'TERMINATED'; deleted instances are simply not listed.
+        let status = match instance.status.as_str() {
+            "RUNNING" => InstanceStatus::Active,
+            _ => InstanceStatus::Inactive,
+        };
+        Self {
+            main_ip: instance.public_ip(),
+            region: GcpInstance::resource_name(&instance.zone).into(),
+            specs: GcpInstance::resource_name(&instance.machine_type).into(),
+            tags: instance.labels.into_values().collect(),
+            id: instance.name,
+            status,
+        }
+    }
+}
+
+/// A Google Cloud Platform (Compute Engine) client. The regions specified in the settings
+/// file are interpreted as GCP zones (e.g., 'us-central1-a').
+pub struct GcpClient {
+    token: String,
+    project_id: String,
+    settings: Settings,
+    base_url: Url,
+    client: NetworkClient,
+}
+
+impl Display for GcpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "GCP Compute Engine API client v1")
+    }
+}
+
+impl GcpClient {
+    const BASE_URL: &'static str = "https://compute.googleapis.com/compute/v1/";
+    const OS_IMAGE: &'static str = "projects/ubuntu-os-cloud/global/images/family/ubuntu-2204-lts";
+    const DEFAULT_DISK_SIZE_GB: u32 = 500; // Default size of the boot disk in GB.
+    const TESTBED_LABEL: &'static str = "testbed";
+
+    /// Make a new GCP client.
+    pub fn new<T, P>(token: T, project_id: P, settings: Settings) -> Self
+    where
+        T: Into<String>,
+        P: Into<String>,
+    {
+        Self {
+            token: token.into(),
+            project_id: project_id.into(),
+            settings,
+            base_url: Self::BASE_URL.parse().unwrap(),
+            client: NetworkClient::new(),
+        }
+    }
+
+    /// Obtain an OAuth access token for the specified service account (through the gcloud cli).
+    pub async fn access_token<P: AsRef<Path>>(
+        service_account_file: P,
+    ) -> CloudProviderResult<String> {
+        let output = tokio::process::Command::new("gcloud")
+            .args(["auth", "application-default", "print-access-token"])
+            .env(
+                "GOOGLE_APPLICATION_CREDENTIALS",
+                service_account_file.as_ref(),
+            )
+            .output()
+            .await
+            .map_err(|e| CloudProviderError::RequestError(e.to_string()))?;
+
+        if !output.status.success() {
+            return Err(CloudProviderError::FailureResponseCode(
+                output.status.to_string(),
+                String::from_utf8_lossy(&output.stderr).into(),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// GCP labels only accept lowercase letters, digits, dashes, and underscores.
+    fn label(settings: &Settings) -> String {
+        settings
+            .testbed_id
+            .to_lowercase()
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | '0'..='9' | '-' | '_' => c,
+                _ => '-',
+            })
+            .collect()
+    }
+
+    /// Return the url of a project resource (or of the project itself if the path is empty).
+    fn project_url(&self, path: &str) -> Url {
+        let project = format!("projects/{}", self.project_id);
+        let path = match path {
+            "" => project,
+            _ => format!("{project}/{path}"),
+        };
+        self.base_url.join(&path).unwrap()
+    }
+
+    /// Return the url of the instance within its zone.
+    fn instance_url(&self, instance: &Instance, action: Option<&str>) -> Url {
+        let mut path = format!("zones/{}/instances/{}", instance.region, instance.id);
+        if let Some(action) = action {
+            path = format!("{path}/{action}");
+        }
+        self.project_url(&path)
+    }
+
+    /// Check an http response code.
+    async fn check_status_code(response: Response) -> CloudProviderResult<()> {
+        if !response.status().is_success() {
+            let status = response.status().to_string();
+            let message = response.text().await.unwrap_or_else(|_| "[no body]".into());
+            return Err(CloudProviderError::FailureResponseCode(status, message));
+        }
+        Ok(())
+    }
+
+    /// Check an http response and deduced whether it contains an error.
+    fn check_response(response: &Value) -> CloudProviderResult<()> {
+        response.get("error").map_or_else(
+            || Ok(()),
+            |error| {
+                let status = error["code"].to_string();
+                let message = error["message"].to_string();
+                Err(CloudProviderError::FailureResponseCode(status, message))
+            },
+        )
+    }
+
+    /// Create a firewall rule allowing all traffic (if it doesn't already exist).
+    async fn create_firewall_rule(&self) -> CloudProviderResult<()> {
+        let url = self.project_url("global/firewalls");
+        let parameters = json!({
+            "name": Self::label(&self.settings),
+            "description": "Allow all traffic (used for benchmarks).",
+            "allowed": [{ "IPProtocol": "all" }],
+            "sourceRanges": ["0.0.0.0/0"],
+        });
+
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(&self.token)
+            .json(&parameters)
+            .send()
+            .await?;
+
+        // Ignore the error if the rule already exists.
+        if response.status() == StatusCode::CONFLICT {
+            return Ok(());
+        }
+        Self::check_status_code(response).await
+    }
+
+    /// Fetch a specific instance.
+    async fn get_instance(&self, instance: &Instance) -> CloudProviderResult<Instance> {
+        let url = self.instance_url(instance, None);
+        let response = self.client.get(url).bearer_auth(&self.token).send().await?;
+
+        let json: Value = response.json().await?;
+        Self::check_response(&json)?;
+        let instance: GcpInstance = serde_json::from_value(json)?;
+        Ok(instance.into())
+    }
+}
+
+impl ServerProviderClient for GcpClient {
+    const USERNAME: &'static str = "ubuntu";
+
+    async fn list_instances(&self) -> CloudProviderResult<Vec<Instance>> {
+        let mut instances = Vec::new();
+        for zone in &self.settings.regions {
+            let mut url = self.project_url(&format!("zones/{zone}/instances"));
+            let filter = format!(
+                "labels.{}={}",
+                Self::TESTBED_LABEL,
+                Self::label(&self.settings)
+            );
+            url.query_pairs_mut().append_pair("filter", &filter);
+            let response = self.client.get(url).bearer_auth(&self.token).send().await?;
+
+            let json: Value = response.json().await?;
+            Self::check_response(&json)?;
+            if let Some(content) = json.get("items") {
+                let items: Vec<GcpInstance> = serde_json::from_value(content.clone())?;
+                instances.extend(
+                    items
+                        .into_iter()
+                        .filter(|x| x.filter(&self.settings))
+                        .map(Instance::from),
+                );
+            }
+        }
+        Ok(instances)
+    }
+
+    async fn start_instances<'a, I>(&self, instances: I) -> CloudProviderResult<()>
+    where
+        I: Iterator<Item = &'a Instance> + Send,
+    {
+        for instance in instances {
+            let url = self.instance_url(instance, Some("start"));
+            let response = self
+                .client
+                .post(url)
+                .bearer_auth(&self.token)
+                .send()
+                .await?;
+            Self::check_status_code(response).await?;
+        }
+        Ok(())
+    }
+
+    async fn stop_instances<'a, I>(&self, instances: I) -> CloudProviderResult<()>
+    where
+        I: Iterator<Item = &'a Instance> + Send,
+    {
+        for instance in instances {
+            let url = self.instance_url(instance, Some("stop"));
+            let response = self
+                .client
+                .post(url)
+                .bearer_auth(&self.token)
+                .send()
+                .await?;
+            Self::check_status_code(response).await?;
+        }
+        Ok(())
+    }
+
+    async fn create_instance<S>(
+        &self,
+        region: S,
+        specs: &str,
+        tags: &[String],
+    ) -> CloudProviderResult<Instance>
+    where
+        S: Into<String> + Serialize + Send,
+    {
+        let zone: String = region.into();
+        let label = Self::label(&self.settings);
+
+        // Create a firewall rule (if needed).
+        self.create_firewall_rule().await?;
+
+        // Instance names must be unique within a zone.
+        let name = format!("{label}-{:08x}", rand::random::<u32>());
+        // Labels are key-value pairs, so each tag is stored as a label named after itself.
+        let mut labels: HashMap<_, _> = tags.iter().map(|x| (x.clone(), x.clone())).collect();
+        labels.insert(Self::TESTBED_LABEL.into(), label);
+
+        let url = self.project_url(&format!("zones/{zone}/instances"));
+        let parameters = json!({
+            "name": name,
+            "machineType": format!("zones/{zone}/machineTypes/{specs}"),
+            "labels": labels,
+            "disks": [{
+                "boot": true,
+                "autoDelete": true,
+                "initializeParams": {
+                    "sourceImage": Self::OS_IMAGE,
+                    "diskSizeGb": Self::DEFAULT_DISK_SIZE_GB.to_string(),
+                },
+            }],
+            "networkInterfaces": [{
+                "network": "global/networks/default",
+                "accessConfigs": [{ "type": "ONE_TO_ONE_NAT", "name": "External NAT" }],
+            }],
+        });
+
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(&self.token)
+            .json(&parameters)
+            .send()
+            .await?;
+
+        // The response is a (pending) zone operation rather than the instance itself.
+        let json: Value = response.json().await?;
+        Self::check_response(&json)?;
+
+        let instance = Instance {
+            id: name,
+            region: zone,
+            main_ip: Ipv4Addr::UNSPECIFIED,
+            tags: labels.into_values().collect(),
+            specs: specs.into(),
+            status: InstanceStatus::Inactive,
+        };
+        match self.get_instance(&instance).await {
+            Ok(instance) => Ok(instance),
+            // The instance may not be visible yet; it will be picked up by the next listing.
+            Err(_) => Ok(instance),
+        }
+    }
+
+    async fn delete_instance(&self, instance: Instance) -> CloudProviderResult<()> {
+        let url = self.instance_url(&instance, None);
+
+        let response = self
+            .client
+            .delete(url)
+            .bearer_auth(&self.token)
+            .send()
+            .await?;
+
+        Self::check_status_code(response).await
+    }
+
+    async fn register_ssh_public_key(&self, public_key: String) -> CloudProviderResult<()> {
+        // Fetch the current project-wide metadata.
+        let url = self.project_url("");
+        let response = self.client.get(url).bearer_auth(&self.token).send().await?;
+
+        let json: Value = response.json().await?;
+        Self::check_response(&json)?;
+        let metadata = &json["commonInstanceMetadata"];
+        let mut items: Vec<Value> = match metadata.get("items") {
+            Some(items) => serde_json::from_value(items.clone())?,
+            None => Vec::new(),
+        };
+
+        // Do not upload the key if it already exists.
+        let entry = format!("{}:{public_key}", Self::USERNAME);
+        let keys = items.iter_mut().find(|x| x["key"] == "ssh-keys");
+        match keys {
+            Some(keys) => {
+                let existing = keys["value"].as_str().unwrap_or_default().to_string();
+                if existing.lines().any(|x| x.trim() == entry) {
+                    return Ok(());
+                }
+                keys["value"] = json!(format!("{}\n{entry}", existing.trim_end()));
+            }
+            None => items.push(json!({ "key": "ssh-keys", "value": entry })),
+        }
+
+        let url = self.project_url("setCommonInstanceMetadata");
+        let parameters = json!({
+            "fingerprint": metadata["fingerprint"].clone(),
+            "items": items,
+        });
+
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(&self.token)
+            .json(&parameters)
+            .send()
+            .await?;
+
+        let json: Value = response.json().await?;
+        Self::check_response(&json)?;
+        Ok(())
+    }
+
+    async fn instance_setup_commands(&self) -> CloudProviderResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use serde_json::json;
+
+    use super::{GcpClient, GcpInstance};
+    use crate::{client::Instance, settings::Settings};
+
+    fn gcp_instance(status: &str) -> GcpInstance {
+        let content = json!({
+            "name": "testbed-0000002a",
+            "zone": "https://www.googleapis.com/compute/v1/projects/p/zones/us-central1-a",
+            "machineType": "https://www.googleapis.com/compute/v1/projects/p/zones/us-central1-a/machineTypes/n2-standard-8",
+            "status": status,
+            "labels": { "testbed": "testbed" },
+            "networkInterfaces": [{ "accessConfigs": [{ "natIP": "10.0.0.1" }] }],
+        });
+        serde_json::from_value(content).unwrap()
+    }
+
+    #[test]
+    fn parse_instance() {
+        let instance: Instance = gcp_instance("RUNNING").into();
+        assert_eq!(instance.id, "testbed-0000002a");
+        assert_eq!(instance.region, "us-central1-a");
+        assert_eq!(instance.specs, "n2-standard-8");
+        assert_eq!(instance.main_ip, Ipv4Addr::new(10, 0, 0, 1));
+        assert!(instance.is_active());
+
+        // GCP reports stopped instances as terminated.
+        let instance: Instance = gcp_instance("TERMINATED").into();
+        assert!(instance.is_inactive());
+        assert!(!instance.is_terminated());
+    }
+
+    #[test]
+    fn lifecycle_transitions() {
+        // Only running instances are usable; all transient states are reported as inactive.
+        let lifecycle = [
+            ("PROVISIONING", false),
+            ("STAGING", false),
+            ("RUNNING", true),
+            ("STOPPING", false),
+            ("TERMINATED", false),
+            ("SUSPENDED", false),
+        ];
+        for (status, active) in lifecycle {
+            let instance: Instance = gcp_instance(status).into();
+            assert_eq!(instance.is_active(), active, "{status}");
+            assert_eq!(instance.is_inactive(), !active, "{status}");
+            assert!(!instance.is_terminated(), "{status}");
+        }
+    }
+
+    #[test]
+    fn filter_instance() {
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["us-central1-a".into()];
+        settings.specs = "n2-standard-8".into();
+        assert!(gcp_instance("RUNNING").filter(&settings));
+
+        // Instances with other specs are listed, but not selected by default.
+        settings.specs = "e2-medium".into();
+        assert!(gcp_instance("RUNNING").filter(&settings));
+        assert!(!settings.filter_instances(&gcp_instance("RUNNING").into()));
+
+        settings.regions = vec!["europe-west1-b".into()];
+        assert!(!gcp_instance("RUNNING").filter(&settings));
+    }
+
+    #[test]
+    fn sanitize_label() {
+        let mut settings = Settings::new_for_test();
+        settings.testbed_id = "Alice.Mysticeti".into();
+        assert_eq!(GcpClient::label(&settings), "alice-mysticeti");
+    }
+}
@@ -70,7 +70,12 @@ impl ServerProviderClient for LocalClient {
         Ok(())
     }
 
-    async fn create_instance<S>(&self, _region: S) -> CloudProviderResult<Instance>
+    async fn create_instance<S>(
+        &self,
+        _region: S,
+        specs: &str,
+        tags: &[String],
+    ) -> CloudProviderResult<Instance>
     where
         S: Into<String> + Serialize + Send,
     {
@@ -80,8 +85,8 @@ impl ServerProviderClient for LocalClient {
             id: id.to_string(),
             region: "local".to_string(),
             main_ip: Ipv4Addr::LOCALHOST,
-            tags: Vec::new(),
-            specs: "local".to_string(),
+            tags: tags.to_vec(),
+            specs: specs.into(),
             status: InstanceStatus::Active,
         };
         guard.push(instance.clone());
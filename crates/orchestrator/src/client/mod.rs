@@ -3,7 +3,9 @@
 
 use std::{
     fmt::Display,
+    future::Future,
     net::{Ipv4Addr, SocketAddr},
+    time::Duration,
 };
 
 use serde::{Deserialize, Serialize};
@@ -11,6 +13,8 @@ use serde::{Deserialize, Serialize};
 use crate::error::CloudProviderResult;
 
 pub mod aws;
+pub mod digitalocean;
+pub mod gcp;
 pub mod local;
 pub mod vultr;
 
@@ -49,6 +53,9 @@ pub struct Instance {
 }
 
 impl Instance {
+    /// The tag marking spot instances.
+    pub const SPOT_TAG: &'static str = "spot";
+
     /// Return whether the instance is active and running.
     pub fn is_active(&self) -> bool {
         matches!(self.status, InstanceStatus::Active)
@@ -64,6 +71,21 @@ impl Instance {
         matches!(self.status, InstanceStatus::Terminated)
     }
 
+    /// Return whether the instance has the specified specs (ignoring case and dots).
+    pub fn has_specs(&self, specs: &str) -> bool {
+        self.specs.to_lowercase().replace('.', "") == specs.to_lowercase().replace('.', "")
+    }
+
+    /// Return whether the instance carries all the specified tags.
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter().all(|x| self.tags.contains(x))
+    }
+
+    /// Return whether the instance runs on spot capacity (and may thus be interrupted).
+    pub fn is_spot(&self) -> bool {
+        self.tags.iter().any(|x| x == Self::SPOT_TAG)
+    }
+
     /// Return the ssh address to connect to the instance.
     pub fn ssh_address(&self) -> SocketAddr {
         SocketAddr::new(self.main_ip.into(), 22)
@@ -99,8 +121,14 @@ pub trait ServerProviderClient: Display {
     where
         I: Iterator<Item = &'a Instance> + Send;
 
-    /// Create an instance in a specific region.
-    async fn create_instance<S>(&self, region: S) -> CloudProviderResult<Instance>
+    /// Create an instance with the specified specs in a specific region, marked with the
+    /// specified tags (in addition to the tag of the testbed).
+    async fn create_instance<S>(
+        &self,
+        region: S,
+        specs: &str,
+        tags: &[String],
+    ) -> CloudProviderResult<Instance>
     where
         S: Into<String> + Serialize + Send;
 
@@ -115,25 +143,62 @@ pub trait ServerProviderClient: Display {
     async fn instance_setup_commands(&self) -> CloudProviderResult<Vec<String>>;
 }
 
+/// Create an instance with spot capacity, falling back to on-demand capacity if the spot
+/// request fails or is not fulfilled within the specified timeout.
+pub async fn create_with_spot_fallback<S, D, F>(
+    spot: S,
+    on_demand: D,
+    timeout: Duration,
+) -> CloudProviderResult<Instance>
+where
+    S: Future<Output = CloudProviderResult<Instance>>,
+    D: FnOnce() -> F,
+    F: Future<Output = CloudProviderResult<Instance>>,
+{
+    match tokio::time::timeout(timeout, spot).await {
+        Ok(Ok(instance)) => Ok(instance),
+        Ok(Err(_)) | Err(_) => on_demand().await,
+    }
+}
+
 #[cfg(test)]
 pub mod test_client {
-    use std::{fmt::Display, sync::Mutex};
+    use std::{
+        fmt::Display,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+    };
 
     use serde::Serialize;
 
     use super::{Instance, InstanceStatus, ServerProviderClient};
-    use crate::{error::CloudProviderResult, settings::Settings};
+    use crate::error::{CloudProviderError, CloudProviderResult};
 
+    #[derive(Default)]
     pub struct TestClient {
-        settings: Settings,
         instances: Mutex<Vec<Instance>>,
+        /// The number of upcoming requests to reject with a transient error.
+        failures: AtomicUsize,
     }
 
     impl TestClient {
-        pub fn new(settings: Settings) -> Self {
-            Self {
-                settings,
-                instances: Mutex::new(Vec::new()),
+        /// Reject the next requests listing or creating instances as if the cloud provider
+        /// was overloaded.
+        pub fn with_transient_failures(self, failures: usize) -> Self {
+            self.failures.store(failures, Ordering::Relaxed);
+            self
+        }
+
+        fn check_failure(&self) -> CloudProviderResult<()> {
+            let (order, update) = (Ordering::Relaxed, |x: usize| x.checked_sub(1));
+            match self.failures.fetch_update(order, order, update) {
+                Ok(_) => Err(CloudProviderError::FailureResponseCode(
+                    "503 Service Unavailable".into(),
+                    "[no body]".into(),
+                )),
+                Err(_) => Ok(()),
             }
         }
     }
@@ -148,6 +213,7 @@ pub mod test_client {
         const USERNAME: &'static str = "root";
 
         async fn list_instances(&self) -> CloudProviderResult<Vec<Instance>> {
+            self.check_failure()?;
             let guard = self.instances.lock().unwrap();
             Ok(guard.clone())
         }
@@ -176,18 +242,24 @@ pub mod test_client {
             Ok(())
         }
 
-        async fn create_instance<S>(&self, region: S) -> CloudProviderResult<Instance>
+        async fn create_instance<S>(
+            &self,
+            region: S,
+            specs: &str,
+            tags: &[String],
+        ) -> CloudProviderResult<Instance>
         where
             S: Into<String> + Serialize + Send,
         {
+            self.check_failure()?;
             let mut guard = self.instances.lock().unwrap();
             let id = guard.len();
             let instance = Instance {
                 id: id.to_string(),
                 region: region.into(),
                 main_ip: format!("0.0.0.{id}").parse().unwrap(),
-                tags: Vec::new(),
-                specs: self.settings.specs.clone(),
+                tags: tags.to_vec(),
+                specs: specs.into(),
                 status: InstanceStatus::Active,
             };
             guard.push(instance.clone());
@@ -209,3 +281,143 @@ pub mod test_client {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{future, time::Duration};
+
+    use super::{
+        create_with_spot_fallback,
+        test_client::TestClient,
+        Instance,
+        ServerProviderClient,
+    };
+    use crate::{error::CloudProviderError, retry::RetryPolicy};
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    fn spot_instance() -> Instance {
+        let mut instance = Instance::new_for_test("spot".into());
+        instance.tags.push(Instance::SPOT_TAG.into());
+        instance
+    }
+
+    #[tokio::test]
+    async fn spot_request_fulfilled() {
+        let client = TestClient::default();
+        let spot = async { Ok(spot_instance()) };
+        let on_demand = || client.create_instance("region", "specs", &[]);
+
+        let instance = create_with_spot_fallback(spot, on_demand, TIMEOUT)
+            .await
+            .unwrap();
+        assert!(instance.is_spot());
+        assert!(client.list_instances().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn spot_request_rejected() {
+        let client = TestClient::default();
+        let spot = async { Err(CloudProviderError::RequestError("No capacity".into())) };
+        let on_demand = || client.create_instance("region", "specs", &[]);
+
+        let instance = create_with_spot_fallback(spot, on_demand, TIMEOUT)
+            .await
+            .unwrap();
+        assert!(!instance.is_spot());
+        assert_eq!(client.list_instances().await.unwrap(), vec![instance]);
+    }
+
+    #[tokio::test]
+    async fn spot_request_timeout() {
+        let client = TestClient::default();
+        let spot = future::pending();
+        let on_demand = || client.create_instance("region", "specs", &[]);
+
+        let instance = create_with_spot_fallback(spot, on_demand, TIMEOUT)
+            .await
+            .unwrap();
+        assert!(!instance.is_spot());
+        assert_eq!(client.list_instances().await.unwrap(), vec![instance]);
+    }
+
+    #[tokio::test]
+    async fn list_tagged_instances() {
+        let client = TestClient::default();
+        let (run_a, run_b) = (vec!["run-a".to_string()], vec!["run-b".to_string()]);
+        let a = client
+            .create_instance("region", "specs", &run_a)
+            .await
+            .unwrap();
+        let b = client
+            .create_instance("region", "specs", &run_b)
+            .await
+            .unwrap();
+
+        let instances = client.list_instances().await.unwrap();
+        let listed = |tags: &[String]| -> Vec<_> {
+            instances
+                .iter()
+                .filter(|x| x.has_tags(tags))
+                .cloned()
+                .collect()
+        };
+        assert_eq!(listed(&run_a), vec![a.clone()]);
+        assert_eq!(listed(&run_b), vec![b.clone()]);
+
+        // An empty selector matches all instances.
+        assert_eq!(listed(&[]), vec![a, b]);
+    }
+
+    /// A retry policy with short delays.
+    fn retry_policy() -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(100),
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_transient_failures() {
+        let client = TestClient::default().with_transient_failures(2);
+        let (policy, transient) = (retry_policy(), CloudProviderError::is_transient);
+
+        let instance = policy
+            .retry_if(3, transient, || {
+                client.create_instance("region", "specs", &[])
+            })
+            .await
+            .unwrap();
+        let instances = policy
+            .retry_if(3, transient, || client.list_instances())
+            .await
+            .unwrap();
+        assert_eq!(instances, vec![instance]);
+    }
+
+    #[tokio::test]
+    async fn retry_exhausted() {
+        let client = TestClient::default().with_transient_failures(3);
+
+        let transient = CloudProviderError::is_transient;
+        let result = retry_policy()
+            .retry_if(2, transient, || client.list_instances())
+            .await;
+        assert!(matches!(result, Err(e) if e.is_transient()));
+        assert!(client.list_instances().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn no_retry_on_permanent_failure() {
+        let mut attempts = 0;
+        let result: Result<(), _> = retry_policy()
+            .retry_if(3, CloudProviderError::is_transient, || {
+                attempts += 1;
+                async { Err(CloudProviderError::SshKeyNotFound("key".into())) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+}
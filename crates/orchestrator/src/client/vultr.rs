@@ -59,11 +59,10 @@ impl From<VultrInstance> for Instance {
 }
 
 impl VultrInstance {
-    /// Return whether the instance matches the parameters specified in the setting file.
+    /// Return whether the instance belongs to the testbed specified in the setting file. The
+    /// instances of all specs are listed since benchmarks may override the specs of the settings.
     pub fn filter(&self, settings: &Settings) -> bool {
-        settings.regions.contains(&self.region)
-            && self.tags.contains(&settings.testbed_id)
-            && self.plan == settings.specs
+        settings.regions.contains(&self.region) && self.tags.contains(&settings.testbed_id)
     }
 }
 
@@ -195,7 +194,12 @@ impl ServerProviderClient for VultrClient {
         Ok(())
     }
 
-    async fn create_instance<S>(&self, region: S) -> CloudProviderResult<Instance>
+    async fn create_instance<S>(
+        &self,
+        region: S,
+        specs: &str,
+        tags: &[String],
+    ) -> CloudProviderResult<Instance>
     where
         S: Into<String> + Serialize + Send,
     {
@@ -205,15 +209,17 @@ impl ServerProviderClient for VultrClient {
             None => return Err(CloudProviderError::SshKeyNotFound(testbed_name.clone())),
         };
 
+        let mut tags = tags.to_vec();
+        tags.insert(0, testbed_name);
         let url = self.base_url.join("instances").unwrap();
         let parameters = json!({
                 "region": region,
-                "plan": self.settings.specs.clone(),
+                "plan": specs,
                 "os_id": Self::DEFAULT_OS,
                 "label": self.settings.testbed_id.clone(),
                 "sshkey_id": [ssh_key_id],
                 "hostname": "validator",
-                "tag": testbed_name
+                "tags": tags
         });
 
         let response = self
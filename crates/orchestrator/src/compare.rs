@@ -0,0 +1,283 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::BTreeMap, io, path::Path, time::Duration};
+
+use prettytable::{row, Table};
+
+use crate::{display, measurements::MeasurementsCollection};
+
+/// The metrics of a benchmark run compared across two sets of runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RunMetrics {
+    /// The load submitted to the system (tx/s).
+    pub load: usize,
+    /// The throughput (tx/s) summed across workloads.
+    pub tps: f64,
+    /// The median latency.
+    pub p50: Duration,
+    /// The 99th percentile latency.
+    pub p99: Duration,
+    /// The number of committed transactions per second over the whole benchmark.
+    pub commit_rate: f64,
+}
+
+impl RunMetrics {
+    pub fn from_collection(collection: &MeasurementsCollection) -> Self {
+        let duration = collection.benchmark_duration().as_secs_f64();
+        let (committed, _) = collection.totals();
+        Self {
+            load: collection.parameters.load,
+            tps: collection
+                .labels()
+                .map(|label| collection.aggregate_tps(label) as f64)
+                .sum(),
+            p50: collection.percentile(50.0),
+            p99: collection.percentile(99.0),
+            commit_rate: if duration == 0.0 {
+                0.0
+            } else {
+                committed as f64 / duration
+            },
+        }
+    }
+
+    /// Load the metrics of the measurements collection at the specified path, or of all the
+    /// measurements collections (`measurements-*.json`) of the specified directory.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<Self>> {
+        let path = path.as_ref();
+        if !path.is_dir() {
+            let collection = MeasurementsCollection::load(path)?;
+            return Ok(vec![Self::from_collection(&collection)]);
+        }
+
+        let collections = MeasurementsCollection::load_dir(path)?;
+        Ok(collections.iter().map(Self::from_collection).collect())
+    }
+}
+
+/// The change of a metric between the baseline and the candidate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Delta {
+    pub metric: &'static str,
+    pub baseline: f64,
+    pub candidate: f64,
+    /// Whether higher values are better (e.g., throughput) or worse (e.g., latency).
+    pub higher_is_better: bool,
+}
+
+impl Delta {
+    /// The relative change of the metric (positive if the candidate is higher).
+    pub fn change(&self) -> f64 {
+        if self.baseline == 0.0 {
+            return 0.0;
+        }
+        (self.candidate - self.baseline) / self.baseline
+    }
+
+    /// Whether the metric worsened by more than the threshold (expressed as a fraction).
+    pub fn is_regression(&self, threshold: f64) -> bool {
+        if self.higher_is_better {
+            -self.change() > threshold
+        } else {
+            self.change() > threshold
+        }
+    }
+}
+
+/// A side-by-side comparison of two sets of benchmark runs, aligned on their loads.
+pub struct Comparison {
+    /// The deltas of the metrics at each load measured by both sets of runs.
+    deltas: BTreeMap<usize, Vec<Delta>>,
+    /// The loads only measured by the baseline.
+    baseline_only: Vec<usize>,
+    /// The loads only measured by the candidate.
+    candidate_only: Vec<usize>,
+    /// The relative change beyond which a metric is considered to regress.
+    threshold: f64,
+}
+
+impl Comparison {
+    /// Compare the candidate runs with the baseline runs. If several runs have the same load,
+    /// the last one is used.
+    pub fn new(baseline: &[RunMetrics], candidate: &[RunMetrics], threshold: f64) -> Self {
+        let baseline: BTreeMap<_, _> = baseline.iter().map(|x| (x.load, x)).collect();
+        let candidate: BTreeMap<_, _> = candidate.iter().map(|x| (x.load, x)).collect();
+
+        let deltas = baseline
+            .iter()
+            .filter_map(|(load, a)| {
+                let b = candidate.get(load)?;
+                let delta = |metric, baseline, candidate, higher_is_better| Delta {
+                    metric,
+                    baseline,
+                    candidate,
+                    higher_is_better,
+                };
+                let millis = |x: Duration| x.as_secs_f64() * 1_000.0;
+                Some((
+                    *load,
+                    vec![
+                        delta("Throughput (tx/s)", a.tps, b.tps, true),
+                        delta("Latency p50 (ms)", millis(a.p50), millis(b.p50), false),
+                        delta("Latency p99 (ms)", millis(a.p99), millis(b.p99), false),
+                        delta("Commit rate (tx/s)", a.commit_rate, b.commit_rate, true),
+                    ],
+                ))
+            })
+            .collect();
+        let baseline_only = baseline
+            .keys()
+            .filter(|x| !candidate.contains_key(x))
+            .copied()
+            .collect();
+        let candidate_only = candidate
+            .keys()
+            .filter(|x| !baseline.contains_key(x))
+            .copied()
+            .collect();
+
+        Self {
+            deltas,
+            baseline_only,
+            candidate_only,
+            threshold,
+        }
+    }
+
+    /// The metrics that regressed beyond the threshold, along with their load.
+    pub fn regressions(&self) -> Vec<(usize, Delta)> {
+        self.deltas
+            .iter()
+            .flat_map(|(load, deltas)| deltas.iter().map(move |x| (*load, *x)))
+            .filter(|(_, x)| x.is_regression(self.threshold))
+            .collect()
+    }
+
+    /// The exit code of the comparison: non-zero if any metric regressed.
+    pub fn exit_code(&self) -> i32 {
+        if self.regressions().is_empty() {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Display the deltas at each common load, the loads measured by a single set of runs, and
+    /// the regressions.
+    pub fn display(&self) {
+        let mut table = Table::new();
+        table.set_format(display::default_table_format());
+
+        table.set_titles(row![bH4->"Benchmark Comparison"]);
+        for (load, deltas) in &self.deltas {
+            table.add_row(row![bH4->""]);
+            let title = format!("Load {load} tx/s");
+            table.add_row(row![b->title, b->"Baseline", b->"Candidate", b->"Change"]);
+            for delta in deltas {
+                let regression = if delta.is_regression(self.threshold) {
+                    " (regression)"
+                } else {
+                    ""
+                };
+                table.add_row(row![
+                    b->format!("{}:", delta.metric),
+                    format!("{:.1}", delta.baseline),
+                    format!("{:.1}", delta.candidate),
+                    format!("{:+.1}%{regression}", delta.change() * 100.0)
+                ]);
+            }
+        }
+
+        display::newline();
+        table.printstd();
+        display::newline();
+
+        let format_loads = |loads: &[usize]| {
+            loads
+                .iter()
+                .map(|x| format!("{x} tx/s"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        if !self.baseline_only.is_empty() {
+            let loads = format_loads(&self.baseline_only);
+            display::warn(format!("Loads only measured by the baseline: {loads}"));
+        }
+        if !self.candidate_only.is_empty() {
+            let loads = format_loads(&self.candidate_only);
+            display::warn(format!("Loads only measured by the candidate: {loads}"));
+        }
+        for (load, delta) in self.regressions() {
+            display::error(format!(
+                "{} regressed by {:.1}% at {load} tx/s",
+                delta.metric,
+                delta.change().abs() * 100.0
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{Comparison, RunMetrics};
+
+    fn run(load: usize, tps: f64, p50_ms: u64, p99_ms: u64) -> RunMetrics {
+        RunMetrics {
+            load,
+            tps,
+            p50: Duration::from_millis(p50_ms),
+            p99: Duration::from_millis(p99_ms),
+            commit_rate: tps,
+        }
+    }
+
+    #[test]
+    fn no_regression() {
+        let baseline = [run(1_000, 1_000.0, 500, 900)];
+        let candidate = [run(1_000, 1_020.0, 490, 920)];
+        let comparison = Comparison::new(&baseline, &candidate, 0.05);
+        assert!(comparison.regressions().is_empty());
+        assert_eq!(comparison.exit_code(), 0);
+    }
+
+    #[test]
+    fn regressed_candidate() {
+        let baseline = [
+            run(1_000, 1_000.0, 500, 900),
+            run(2_000, 2_000.0, 600, 1_000),
+            run(4_000, 3_000.0, 900, 2_000),
+        ];
+        // The candidate sustains less throughput at 2000 tx/s with a higher tail latency, and
+        // was not run at 4000 tx/s but at 8000 tx/s.
+        let candidate = [
+            run(1_000, 1_000.0, 500, 900),
+            run(2_000, 1_800.0, 600, 1_500),
+            run(8_000, 3_000.0, 900, 2_000),
+        ];
+        let comparison = Comparison::new(&baseline, &candidate, 0.05);
+        assert_eq!(comparison.exit_code(), 1);
+        assert_eq!(comparison.baseline_only, vec![4_000]);
+        assert_eq!(comparison.candidate_only, vec![8_000]);
+
+        let regressions: Vec<_> = comparison
+            .regressions()
+            .into_iter()
+            .map(|(load, x)| (load, x.metric, (x.change() * 100.0).round() as i64))
+            .collect();
+        assert_eq!(
+            regressions,
+            vec![
+                (2_000, "Throughput (tx/s)", -10),
+                (2_000, "Latency p99 (ms)", 50),
+                (2_000, "Commit rate (tx/s)", -10),
+            ]
+        );
+
+        // A looser threshold tolerates the throughput drop.
+        let comparison = Comparison::new(&baseline, &candidate, 0.2);
+        assert_eq!(comparison.regressions().len(), 1);
+    }
+}
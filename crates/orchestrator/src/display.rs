@@ -82,6 +82,18 @@ pub fn done() {
     }
 }
 
+/// Print an action that a dry run skipped.
+pub fn dry_run<S: Display>(message: S) {
+    if cfg!(not(test)) {
+        crossterm::execute!(
+            stdout(),
+            PrintStyledContent("[dry-run] ".dim()),
+            Print(format!("{message}\n"))
+        )
+        .unwrap();
+    }
+}
+
 pub fn newline() {
     if cfg!(not(test)) {
         crossterm::execute!(stdout(), Print("\n")).unwrap();
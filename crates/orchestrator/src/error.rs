@@ -1,7 +1,11 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::net::SocketAddr;
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 #[macro_export(local_inner_macros)]
 macro_rules! ensure {
@@ -24,6 +28,13 @@ pub enum SettingsError {
 
     #[error("Failed to read ssh public key file '{file:?}': {message}")]
     SshPublicKeyFileError { file: String, message: String },
+
+    #[error("Invalid setting '{field}': {message} (fix: {suggestion})")]
+    InvalidField {
+        field: &'static str,
+        message: String,
+        suggestion: String,
+    },
 }
 
 pub type CloudProviderResult<T> = Result<T, CloudProviderError>;
@@ -41,6 +52,24 @@ pub enum CloudProviderError {
 
     #[error("SSH key \"{0}\" not found")]
     SshKeyNotFound(String),
+
+    #[error("Spot instance {0} was interrupted by the cloud provider")]
+    SpotInterrupted(String),
+}
+
+impl CloudProviderError {
+    /// Whether the request may succeed if retried, that is, if it failed to reach the cloud
+    /// provider or was rejected because of rate limiting (429) or a server error (5xx).
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::RequestError(_) => true,
+            Self::FailureResponseCode(status, _) => {
+                let status = status.trim_matches('"');
+                status.starts_with("429") || status.starts_with('5')
+            }
+            _ => false,
+        }
+    }
 }
 
 pub type SshResult<T> = Result<T, SshError>;
@@ -65,6 +94,68 @@ pub enum SshError {
         code: i32,
         message: String,
     },
+
+    #[error("Directory {path:?} does not exist on {address}")]
+    MissingDirectory { address: SocketAddr, path: PathBuf },
+
+    #[error("Failed to load ssh private key {path:?}: {message}")]
+    InvalidKey { path: PathBuf, message: String },
+
+    #[error("Unsupported ssh key type '{key_type}' in {path:?} (use rsa, ed25519, or ecdsa)")]
+    UnsupportedKey { path: PathBuf, key_type: String },
+
+    #[error(
+        "Ssh private key {path:?} is encrypted but no passphrase was provided (set \
+        'ssh_private_key_passphrase' or the SSH_KEY_PASSPHRASE environment variable)"
+    )]
+    MissingPassphrase { path: PathBuf },
+
+    #[error("Local execution of '{command}' in {directory:?} failed: {error}")]
+    Local {
+        /// The directory in which the command ran.
+        directory: PathBuf,
+        /// The command (or file operation) that failed.
+        command: String,
+        error: LocalExecutionError,
+    },
+}
+
+impl SshError {
+    /// Whether the error denotes a failure of the ssh session itself (rather than of the command
+    /// it executed), in which case the session should not be reused.
+    pub fn is_connection_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::SessionError { .. } | Self::ConnectionError { .. }
+        )
+    }
+
+    /// Make an error from the failure of a command executed on the local machine.
+    pub fn local<P, S, E>(directory: P, command: S, error: E) -> Self
+    where
+        P: AsRef<Path>,
+        S: Into<String>,
+        E: Into<LocalExecutionError>,
+    {
+        Self::Local {
+            directory: directory.as_ref().to_path_buf(),
+            command: command.into(),
+            error: error.into(),
+        }
+    }
+}
+
+/// The reason why a command executed on the local machine failed.
+#[derive(thiserror::Error, Debug)]
+pub enum LocalExecutionError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("exit code ({code}): {message}")]
+    NonZeroExitCode { code: i32, message: String },
+
+    #[error("timed out after {0:?}")]
+    Timeout(Duration),
 }
 
 pub type MonitorResult<T> = Result<T, MonitorError>;
@@ -76,6 +167,9 @@ pub enum MonitorError {
 
     #[error("Failed to start Grafana: {0}")]
     GrafanaError(String),
+
+    #[error("Failed to push measurements to {endpoint}: {message}")]
+    RemoteWrite { endpoint: String, message: String },
 }
 
 pub type TestbedResult<T> = Result<T, TestbedError>;
@@ -94,6 +188,18 @@ pub enum TestbedError {
     #[error("Not enough instances: missing {0} instances")]
     InsufficientCapacity(usize),
 
+    #[error("Invalid authority assignment: {0}")]
+    InvalidAssignment(String),
+
+    #[error("Instances never became reachable: {0}")]
+    UnreachableInstances(String),
+
     #[error(transparent)]
     MonitorError(#[from] MonitorError),
+
+    #[error("Critical error in the logs: {0}")]
+    CriticalLogFinding(String),
+
+    #[error("Correct nodes committed conflicting sequences: {0}")]
+    SafetyViolation(String),
 }
@@ -1,13 +1,21 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::net::SocketAddr;
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+};
+
+use futures::future::try_join_all;
+use tokio::sync::{mpsc, Semaphore};
 
 use crate::{
     client::Instance,
+    display,
     error::SshResult,
-    local_executor::{LocalCommandExecutor, LocalConnection},
-    ssh::{CommandContext, CommandStatus, SshConnection, SshConnectionManager},
+    local_executor::{LocalCommandExecutor, LocalConnection, PortRange},
+    pool::PooledConnection,
+    ssh::{CommandContext, CommandStatus, OutputLine, SshConnection, SshConnectionManager},
 };
 
 /// An executor that can use either SSH or local execution.
@@ -15,6 +23,8 @@ use crate::{
 pub enum Executor {
     Ssh(SshConnectionManager),
     Local(LocalCommandExecutor),
+    /// Display the commands instead of running them, and pretend they succeeded with no output.
+    DryRun,
 }
 
 impl Executor {
@@ -28,6 +38,26 @@ impl Executor {
         Self::Local(executor)
     }
 
+    /// Create an executor that does not run anything.
+    pub fn dry_run() -> Self {
+        Self::DryRun
+    }
+
+    /// Display the command that would run on each instance and return an empty output for each.
+    fn skip_per_instance<I, S>(instances: I, context: &CommandContext) -> Vec<(String, String)>
+    where
+        I: IntoIterator<Item = (Instance, S)>,
+        S: Into<String>,
+    {
+        instances
+            .into_iter()
+            .map(|(instance, command)| {
+                display::dry_run(format!("{} $ {}", instance.id, context.apply(command)));
+                (String::new(), String::new())
+            })
+            .collect()
+    }
+
     /// Execute the specified command on all provided instances.
     pub async fn execute<I, S>(
         &self,
@@ -42,6 +72,10 @@ impl Executor {
         match self {
             Self::Ssh(ssh) => ssh.execute(instances, command, context).await,
             Self::Local(local) => local.execute(instances, command, context).await,
+            Self::DryRun => {
+                let targets = instances.into_iter().map(|x| (x, command.clone()));
+                Ok(Self::skip_per_instance(targets, &context))
+            }
         }
     }
 
@@ -58,9 +92,59 @@ impl Executor {
         match self {
             Self::Ssh(ssh) => ssh.execute_per_instance(instances, context).await,
             Self::Local(local) => local.execute_per_instance(instances, context).await,
+            Self::DryRun => Ok(Self::skip_per_instance(instances, &context)),
         }
     }
 
+    /// Execute the specified command on all provided instances, calling `on_line` with each line
+    /// of their stdout and stderr as it is printed (rather than only once the command exits).
+    pub async fn execute_streaming<I, S, F>(
+        &self,
+        instances: I,
+        command: S,
+        context: CommandContext,
+        mut on_line: F,
+    ) -> SshResult<Vec<(String, String)>>
+    where
+        I: IntoIterator<Item = Instance>,
+        S: Into<String> + Clone + Send + 'static,
+        F: FnMut(&Instance, &str),
+    {
+        let instances: Vec<_> = instances.into_iter().collect();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+
+        // The sender is dropped once all commands exit, which ends the forwarding loop.
+        let targets = instances.clone();
+        let execution = async move {
+            match self {
+                Self::Ssh(ssh) => {
+                    ssh.execute_streaming(targets, command, context, sender)
+                        .await
+                }
+                Self::Local(local) => {
+                    local
+                        .execute_streaming(targets, command, context, sender)
+                        .await
+                }
+                Self::DryRun => {
+                    let targets = targets.into_iter().map(|x| (x, command.clone()));
+                    Ok(Self::skip_per_instance(targets, &context))
+                }
+            }
+        };
+        let forwarding = async {
+            while let Some((id, line)) = receiver.recv().await {
+                let (OutputLine::Stdout(line) | OutputLine::Stderr(line)) = line;
+                if let Some(instance) = instances.iter().find(|x| x.id == id) {
+                    on_line(instance, &line);
+                }
+            }
+        };
+
+        let (result, ()) = tokio::join!(execution, forwarding);
+        result
+    }
+
     /// Wait until a command running in the background returns or started.
     pub async fn wait_for_command<I>(
         &self,
@@ -74,6 +158,7 @@ impl Executor {
         match self {
             Self::Ssh(ssh) => ssh.wait_for_command(instances, command_id, status).await,
             Self::Local(local) => local.wait_for_command(instances, command_id, status).await,
+            Self::DryRun => Ok(()),
         }
     }
 
@@ -86,6 +171,7 @@ impl Executor {
         match self {
             Self::Ssh(ssh) => ssh.wait_for_success(instances).await,
             Self::Local(local) => local.wait_for_success(instances).await,
+            Self::DryRun => (),
         }
     }
 
@@ -97,28 +183,108 @@ impl Executor {
         match self {
             Self::Ssh(ssh) => ssh.kill(instances, command_id).await,
             Self::Local(local) => local.kill(instances, command_id).await,
+            Self::DryRun => {
+                for instance in instances {
+                    display::dry_run(format!("{} $ kill '{command_id}'", instance.id));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Reserve a distinct range of free ports for the node of each instance. Returns `None`
+    /// unless the instances run on the local machine (where they would otherwise collide).
+    pub fn reserve_ports(&self, instances: &[Instance]) -> SshResult<Option<Vec<PortRange>>> {
+        match self {
+            Self::Local(local) => instances
+                .iter()
+                .map(|x| local.reserve_ports(x))
+                .collect::<SshResult<_>>()
+                .map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Unbind the ports reserved for the specified instances, so that their nodes can bind them.
+    pub fn release_ports(&self, instances: &[Instance]) {
+        if let Self::Local(local) = self {
+            instances.iter().for_each(|x| local.release_ports(x));
         }
     }
 
+    /// Download the specified file from each instance, with at most `concurrency` downloads in
+    /// flight at once. Returns the content of the files in the order of the instances.
+    pub async fn download_per_instance<I, P>(
+        &self,
+        targets: I,
+        concurrency: usize,
+    ) -> SshResult<Vec<String>>
+    where
+        I: IntoIterator<Item = (Instance, P)>,
+        P: Into<PathBuf>,
+    {
+        try_join_bounded(targets, concurrency, |(instance, path)| async move {
+            self.download(&instance, path).await
+        })
+        .await
+    }
+
+    /// Download the specified file from an instance.
+    pub async fn download<P: Into<PathBuf>>(
+        &self,
+        instance: &Instance,
+        path: P,
+    ) -> SshResult<String> {
+        let path = path.into();
+        let connection = self.connect(instance).await?;
+        tokio::task::spawn_blocking(move || connection.download(path))
+            .await
+            .expect("Download task panicked")
+    }
+
+    /// Download a directory of each instance into the associated local directory, with at most
+    /// `concurrency` downloads in flight at once.
+    pub async fn download_dir_per_instance<I>(
+        &self,
+        targets: I,
+        concurrency: usize,
+    ) -> SshResult<()>
+    where
+        I: IntoIterator<Item = (Instance, PathBuf, PathBuf)>,
+    {
+        try_join_bounded(targets, concurrency, |(instance, remote, local)| async move {
+            let connection = self.connect(&instance).await?;
+            let download = move || connection.download_dir(&remote, &local);
+            tokio::task::spawn_blocking(download)
+                .await
+                .expect("Download task panicked")
+        })
+        .await?;
+        Ok(())
+    }
+
     /// Connect to an instance.
-    pub async fn connect(&self, address: SocketAddr) -> SshResult<ExecutorConnection> {
+    pub async fn connect(&self, instance: &Instance) -> SshResult<ExecutorConnection> {
         match self {
             Self::Ssh(ssh) => {
-                let conn = ssh.connect(address).await?;
+                let conn = ssh.connect(instance.ssh_address()).await?;
                 Ok(ExecutorConnection::Ssh(conn))
             }
             Self::Local(local) => {
-                let conn = local.connect(address).await?;
+                let conn = local.connect(instance).await?;
                 Ok(ExecutorConnection::Local(conn))
             }
+            Self::DryRun => Ok(ExecutorConnection::DryRun(instance.id.clone())),
         }
     }
 }
 
 /// A connection that can download files, either via SSH or local.
 pub enum ExecutorConnection {
-    Ssh(SshConnection),
+    Ssh(PooledConnection<SshConnection>),
     Local(LocalConnection),
+    /// Pretend to download empty files from the instance with the specified id.
+    DryRun(String),
 }
 
 impl ExecutorConnection {
@@ -130,7 +296,150 @@ impl ExecutorConnection {
                 let path_buf = path.as_ref().to_path_buf();
                 local.download(&path_buf)
             }
+            Self::DryRun(id) => {
+                display::dry_run(format!("{id} $ download '{}'", path.as_ref().display()));
+                Ok(String::new())
+            }
         }
     }
+
+    /// Download a whole directory from the remote/local machine into a local directory.
+    pub fn download_dir(&self, remote: &Path, local: &Path) -> SshResult<()> {
+        match self {
+            Self::Ssh(ssh) => ssh.download_dir(remote, local),
+            Self::Local(connection) => connection.download_dir(remote, local),
+            Self::DryRun(id) => {
+                display::dry_run(format!("{id} $ download '{}'", remote.display()));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Run the future produced by `f` for each item, with at most `limit` of them in flight at once.
+/// Returns the outputs in the order of the items, or the first error.
+pub async fn try_join_bounded<T, F, Fut, O, E>(
+    items: impl IntoIterator<Item = T>,
+    limit: usize,
+    f: F,
+) -> Result<Vec<O>, E>
+where
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = Result<O, E>>,
+{
+    let permits = Semaphore::new(limit.max(1));
+    let futures = items.into_iter().map(|item| {
+        let (permits, f) = (&permits, &f);
+        async move {
+            let _permit = permits.acquire().await.expect("Semaphore closed");
+            f(item).await
+        }
+    });
+    try_join_all(futures).await
 }
 
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::{try_join_bounded, Executor};
+    use crate::{client::Instance, local_executor::LocalCommandExecutor, ssh::CommandContext};
+
+    #[tokio::test]
+    async fn dry_run() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let marker = working_dir.path().join("marker");
+        let executor = Executor::dry_run();
+        let instances: Vec<_> = (0..3)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+
+        // Nothing runs, but every instance reports an empty (successful) output.
+        let command = format!("touch {}", marker.display());
+        let stdio = executor
+            .execute(instances.clone(), command, CommandContext::default())
+            .await
+            .unwrap();
+        assert_eq!(stdio, vec![(String::new(), String::new()); 3]);
+
+        let targets = instances
+            .iter()
+            .map(|x| (x.clone(), format!("touch {}-{}", marker.display(), x.id)));
+        let stdio = executor
+            .execute_per_instance(targets, CommandContext::default())
+            .await
+            .unwrap();
+        assert_eq!(stdio.len(), 3);
+
+        executor.kill(instances.clone(), "node").await.unwrap();
+        let targets = instances.iter().map(|x| (x.clone(), marker.clone()));
+        let contents = executor.download_per_instance(targets, 2).await.unwrap();
+        assert_eq!(contents, vec![String::new(); 3]);
+
+        assert_eq!(std::fs::read_dir(working_dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn bounded_downloads() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let executor = Executor::local(LocalCommandExecutor::new(working_dir.path().into()));
+        let instances: Vec<_> = (0..10)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+        for instance in &instances {
+            let directory = working_dir.path().join(format!("instance-{}", instance.id));
+            std::fs::create_dir_all(&directory).unwrap();
+            std::fs::write(directory.join("node.log"), &instance.id).unwrap();
+        }
+
+        // All downloads complete, in the order of the instances.
+        let targets = instances.iter().map(|x| (x.clone(), "node.log"));
+        let contents = executor.download_per_instance(targets, 3).await.unwrap();
+        let ids: Vec<_> = instances.iter().map(|x| x.id.clone()).collect();
+        assert_eq!(contents, ids);
+
+        // No more than the specified number of downloads are in flight at once.
+        let (in_flight, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let contents = try_join_bounded(&instances, 3, |instance| {
+            let (executor, in_flight, peak) = (&executor, &in_flight, &peak);
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(current, Ordering::SeqCst);
+                let connection = executor.connect(instance).await?;
+                let content = connection.download("node.log");
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                content
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(contents, ids);
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn streaming_callback() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let executor = Executor::local(LocalCommandExecutor::new(working_dir.path().into()));
+        let instance = Instance::new_for_test("0".into());
+
+        let mut lines = Vec::new();
+        let command = "for i in 1 2 3; do echo line-$i; done";
+        let stdio = executor
+            .execute_streaming([instance], command, CommandContext::default(), |x, line| {
+                lines.push((x.id.clone(), line.to_string()))
+            })
+            .await
+            .unwrap();
+
+        let expected: Vec<_> = (1..=3)
+            .map(|i| (String::from("0"), format!("line-{i}")))
+            .collect();
+        assert_eq!(lines, expected);
+        assert_eq!(stdio[0].0, "line-1\nline-2\nline-3\n");
+    }
+}
@@ -3,14 +3,18 @@
 
 use std::{
     fmt::{Debug, Display},
+    net::Ipv4Addr,
+    str::FromStr,
     time::Duration,
 };
 
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::client::Instance;
 
-#[derive(Clone, Serialize, Deserialize, Hash, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Hash, PartialEq, Eq)]
 pub enum FaultsType {
     /// Permanently crash the maximum number of nodes from the beginning.
     Permanent { faults: usize },
@@ -19,6 +23,40 @@ pub enum FaultsType {
         max_faults: usize,
         interval: Duration,
     },
+    /// Permanently crash the specified number of nodes from the beginning and promote a hot
+    /// spare in place of each of them after the specified delay.
+    Replacement { faults: usize, delay: Duration },
+    /// Silence the specified number of nodes from the beginning. Silent nodes keep running and
+    /// receiving the blocks of their peers, but never send their own.
+    Silent { faults: usize },
+    /// Permanently crash the specified number of randomly-chosen nodes once the benchmark
+    /// reaches the specified round.
+    Crash { count: usize, at_round: u64 },
+    /// Delay all the traffic sent by the nodes by the specified number of milliseconds, plus or
+    /// minus a random jitter (in milliseconds).
+    Latency { millis: u64, jitter: u64 },
+    /// Crash the nodes with the specified indices once the benchmark ran for `crash_after`, and
+    /// restart them (from their storage) after they were down for `down_for`.
+    CrashRecover {
+        crash_after: Duration,
+        down_for: Duration,
+        targets: Vec<usize>,
+    },
+    /// Make the specified number of nodes equivocate from the beginning: they propose two
+    /// conflicting blocks at every round (see the `equivocating_authorities` node parameter).
+    Equivocate { faults: usize },
+    /// Degrade the outgoing traffic of the nodes with the specified indices: delay it by the
+    /// specified number of milliseconds, drop the specified percentage of packets, and
+    /// optionally cap the bandwidth (in Mbit/s).
+    Degrade {
+        targets: Vec<usize>,
+        delay_ms: u64,
+        loss_percent: u64,
+        rate_mbit: Option<u64>,
+    },
+    /// Split the nodes into groups (of node indices) that cannot talk to each other. Nodes
+    /// that are not part of any group can talk to everyone.
+    Partition { groups: Vec<Vec<usize>> },
 }
 
 impl Default for FaultsType {
@@ -35,6 +73,37 @@ impl Debug for FaultsType {
                 max_faults,
                 interval,
             } => write!(f, "{max_faults}-{}cr", interval.as_secs()),
+            Self::Replacement { faults, delay } => write!(f, "{faults}-{}r", delay.as_secs()),
+            Self::Silent { faults } => write!(f, "{faults}s"),
+            Self::Crash { count, at_round } => write!(f, "{count}@{at_round}c"),
+            Self::Latency { millis, jitter } => write!(f, "{millis}-{jitter}l"),
+            Self::CrashRecover {
+                crash_after,
+                down_for,
+                targets,
+            } => write!(
+                f,
+                "{}@{}+{}rec",
+                join_targets(targets, "."),
+                crash_after.as_secs(),
+                down_for.as_secs()
+            ),
+            Self::Equivocate { faults } => write!(f, "{faults}e"),
+            Self::Degrade {
+                targets,
+                delay_ms,
+                loss_percent,
+                rate_mbit,
+            } => write!(
+                f,
+                "{}@{delay_ms}-{loss_percent}-{}d",
+                join_targets(targets, "."),
+                rate_mbit.unwrap_or_default()
+            ),
+            Self::Partition { groups } => {
+                let groups: Vec<_> = groups.iter().map(|x| join_targets(x, ".")).collect();
+                write!(f, "{}p", groups.join("|"))
+            }
         }
     }
 }
@@ -53,21 +122,193 @@ impl Display for FaultsType {
                 max_faults,
                 interval,
             } => write!(f, "{max_faults} crash-recovery, {}s", interval.as_secs()),
+            Self::Replacement { faults, delay } => {
+                write!(f, "{faults} replaced by spares, {}s", delay.as_secs())
+            }
+            Self::Silent { faults } => write!(f, "{faults} silent"),
+            Self::Crash { count, at_round } => {
+                write!(f, "{count} crashed at round {at_round}")
+            }
+            Self::Latency { millis, jitter } => write!(f, "{millis}ms latency (+/- {jitter}ms)"),
+            Self::CrashRecover {
+                crash_after,
+                down_for,
+                targets,
+            } => write!(
+                f,
+                "nodes {} down at {}s for {}s",
+                join_targets(targets, ", "),
+                crash_after.as_secs(),
+                down_for.as_secs()
+            ),
+            Self::Equivocate { faults } => write!(f, "{faults} equivocating"),
+            Self::Degrade {
+                targets,
+                delay_ms,
+                loss_percent,
+                rate_mbit,
+            } => {
+                write!(
+                    f,
+                    "nodes {} degraded ({delay_ms}ms, {loss_percent}% loss",
+                    join_targets(targets, ", ")
+                )?;
+                if let Some(rate) = rate_mbit {
+                    write!(f, ", {rate}Mbit/s")?;
+                }
+                write!(f, ")")
+            }
+            Self::Partition { groups } => {
+                let groups: Vec<_> = groups.iter().map(|x| join_targets(x, ", ")).collect();
+                write!(f, "partition {}", groups.join(" | "))
+            }
         }
     }
 }
 
+fn join_targets(targets: &[usize], separator: &str) -> String {
+    targets
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
 impl FaultsType {
     /// The interval between crashes. If the type is `Permanent`, the interval is 1s
     /// to crash the nodes as fast as possible.
     pub fn crash_interval(&self) -> Duration {
         match self {
-            Self::Permanent { .. } => Duration::from_secs(1),
+            Self::Permanent { .. }
+            | Self::Silent { .. }
+            | Self::Crash { .. }
+            | Self::Latency { .. }
+            | Self::CrashRecover { .. }
+            | Self::Equivocate { .. }
+            | Self::Degrade { .. }
+            | Self::Partition { .. } => Duration::from_secs(1),
             Self::CrashRecovery { interval, .. } => *interval,
+            Self::Replacement { delay, .. } => *delay,
+        }
+    }
+
+    /// Whether the faults are injected in the network of the nodes (and need to be removed at
+    /// the end of the benchmark).
+    pub fn is_network_fault(&self) -> bool {
+        matches!(
+            self,
+            Self::Latency { .. } | Self::Degrade { .. } | Self::Partition { .. }
+        )
+    }
+
+    /// The number of nodes instructed to equivocate. These are the nodes with the lowest
+    /// authority indices.
+    pub fn equivocating_nodes(&self) -> usize {
+        match self {
+            Self::Equivocate { faults } => *faults,
+            _ => 0,
+        }
+    }
+
+    /// The number of nodes instructed to withhold their own blocks. These are the nodes with the
+    /// lowest authority indices.
+    pub fn silent_nodes(&self) -> usize {
+        match self {
+            Self::Silent { faults } => *faults,
+            _ => 0,
         }
     }
 }
 
+impl FromStr for FaultsType {
+    type Err = String;
+
+    /// Parse faults from the command line. The supported formats are `<faults>` to crash
+    /// nodes from the beginning, `crash:<count>@<round>` to crash nodes at a given round,
+    /// `latency:<millis>[/<jitter>]` to delay the traffic between nodes,
+    /// `recover:<node>,<node>,...@<secs>/<secs>` to crash the specified nodes after the first
+    /// duration and restart them after the second one, `equivocate:<faults>` to make nodes
+    /// propose conflicting blocks, `degrade:<node>,...@<millis>ms[/<loss>%][/<rate>mbit]` to
+    /// degrade the links of the specified nodes, and `partition:<node>,...|<node>,...` to split
+    /// the nodes into groups that cannot talk to each other.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid faults '{s}' (expected <faults>, crash:<count>@<round>, \
+                latency:<millis>[/<jitter>], recover:<node>,...@<secs>/<secs>, \
+                equivocate:<faults>, degrade:<node>,...@<millis>ms[/<loss>%][/<rate>mbit], \
+                or partition:<node>,...|<node>,...)"
+            )
+        };
+        if let Some(crash) = s.strip_prefix("crash:") {
+            let (count, at_round) = crash.split_once('@').ok_or_else(invalid)?;
+            Ok(Self::Crash {
+                count: count.parse().map_err(|_| invalid())?,
+                at_round: at_round.parse().map_err(|_| invalid())?,
+            })
+        } else if let Some(latency) = s.strip_prefix("latency:") {
+            let (millis, jitter) = latency.split_once('/').unwrap_or((latency, "0"));
+            Ok(Self::Latency {
+                millis: millis.parse().map_err(|_| invalid())?,
+                jitter: jitter.parse().map_err(|_| invalid())?,
+            })
+        } else if let Some(recover) = s.strip_prefix("recover:") {
+            let (targets, timing) = recover.split_once('@').ok_or_else(invalid)?;
+            let (crash_after, down_for) = timing.split_once('/').ok_or_else(invalid)?;
+            let targets = parse_targets(targets).ok_or_else(invalid)?;
+            Ok(Self::CrashRecover {
+                crash_after: Duration::from_secs(crash_after.parse().map_err(|_| invalid())?),
+                down_for: Duration::from_secs(down_for.parse().map_err(|_| invalid())?),
+                targets,
+            })
+        } else if let Some(faults) = s.strip_prefix("equivocate:") {
+            Ok(Self::Equivocate {
+                faults: faults.parse().map_err(|_| invalid())?,
+            })
+        } else if let Some(degrade) = s.strip_prefix("degrade:") {
+            let (targets, conditions) = degrade.split_once('@').ok_or_else(invalid)?;
+            let mut conditions = conditions.split('/');
+            let delay_ms = conditions.next().and_then(|x| x.strip_suffix("ms"));
+            let (mut loss_percent, mut rate_mbit) = (0, None);
+            for condition in conditions {
+                if let Some(loss) = condition.strip_suffix('%') {
+                    loss_percent = loss.parse().map_err(|_| invalid())?;
+                } else if let Some(rate) = condition.strip_suffix("mbit") {
+                    rate_mbit = Some(rate.parse().map_err(|_| invalid())?);
+                } else {
+                    return Err(invalid());
+                }
+            }
+            Ok(Self::Degrade {
+                targets: parse_targets(targets).ok_or_else(invalid)?,
+                delay_ms: delay_ms
+                    .ok_or_else(invalid)?
+                    .parse()
+                    .map_err(|_| invalid())?,
+                loss_percent,
+                rate_mbit,
+            })
+        } else if let Some(partition) = s.strip_prefix("partition:") {
+            let groups = partition
+                .split('|')
+                .map(parse_targets)
+                .collect::<Option<Vec<_>>>()
+                .filter(|x| x.len() > 1)
+                .ok_or_else(invalid)?;
+            Ok(Self::Partition { groups })
+        } else {
+            Ok(Self::Permanent {
+                faults: s.parse().map_err(|_| invalid())?,
+            })
+        }
+    }
+}
+
+/// Parse a comma-separated list of node indices.
+fn parse_targets(s: &str) -> Option<Vec<usize>> {
+    s.split(',').map(|x| x.parse().ok()).collect()
+}
+
 /// The actions to apply to the testbed, i.e., which instances to crash and recover.
 #[derive(Default)]
 pub struct CrashRecoveryAction {
@@ -75,14 +316,20 @@ pub struct CrashRecoveryAction {
     pub boot: Vec<Instance>,
     /// The instances to kill.
     pub kill: Vec<Instance>,
+    /// The spare instances to promote, each along with the crashed node whose identity it takes
+    /// over (as `(crashed, spare)`).
+    pub promote: Vec<(Instance, Instance)>,
 }
 
 impl Display for CrashRecoveryAction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let booted = self.boot.len();
         let killed = self.kill.len();
+        let promoted = self.promote.len();
 
-        if self.boot.is_empty() {
+        if !self.promote.is_empty() {
+            write!(f, "{promoted} spare(s) promoted")
+        } else if self.boot.is_empty() {
             write!(f, "{killed} node(s) killed")
         } else if self.kill.is_empty() {
             write!(f, "{booted} node(s) recovered")
@@ -96,20 +343,140 @@ impl CrashRecoveryAction {
     pub fn boot(instances: impl Iterator<Item = Instance>) -> Self {
         Self {
             boot: instances.collect(),
-            kill: Vec::new(),
+            ..Default::default()
         }
     }
 
     pub fn kill(instances: impl Iterator<Item = Instance>) -> Self {
         Self {
-            boot: Vec::new(),
             kill: instances.collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn promote(instances: impl Iterator<Item = (Instance, Instance)>) -> Self {
+        Self {
+            promote: instances.collect(),
+            ..Default::default()
         }
     }
 
     pub fn no_op() -> Self {
         Self::default()
     }
+
+    /// Return whether the action leaves the testbed unchanged.
+    pub fn is_no_op(&self) -> bool {
+        self.boot.is_empty() && self.kill.is_empty() && self.promote.is_empty()
+    }
+}
+
+/// Delay all the traffic a node sends using the netem queueing discipline of its default
+/// network interface. Every node delays its own outgoing packets, so the latency applies
+/// symmetrically to both directions of each link.
+pub struct NetworkLatency;
+
+impl NetworkLatency {
+    /// The shell expression resolving to the network interface of the default route.
+    const INTERFACE: &'static str = "$(ip route show default | awk '{print $5; exit}')";
+
+    /// The command delaying all outgoing packets by the specified latency, plus or minus a
+    /// random jitter (both in milliseconds).
+    pub fn inject_command(millis: u64, jitter: u64) -> String {
+        let mut command = format!(
+            "sudo tc qdisc replace dev {} root netem delay {millis}ms",
+            Self::INTERFACE
+        );
+        if jitter != 0 {
+            command.push_str(&format!(" {jitter}ms"));
+        }
+        command
+    }
+
+    /// The command degrading all outgoing packets: delay them by the specified number of
+    /// milliseconds, drop the specified percentage of them, and optionally cap the bandwidth
+    /// (in Mbit/s).
+    pub fn degrade_command(delay_ms: u64, loss_percent: u64, rate_mbit: Option<u64>) -> String {
+        let mut command = Self::inject_command(delay_ms, 0);
+        if loss_percent != 0 {
+            command.push_str(&format!(" loss {loss_percent}%"));
+        }
+        if let Some(rate) = rate_mbit {
+            command.push_str(&format!(" rate {rate}mbit"));
+        }
+        command
+    }
+
+    /// The command removing the latency injected on the node.
+    pub fn clear_command() -> String {
+        format!("(sudo tc qdisc del dev {} root || true)", Self::INTERFACE)
+    }
+}
+
+/// Partition the nodes with firewall rules: each node drops all the packets exchanged with the
+/// nodes of the other groups.
+pub struct NetworkPartition;
+
+impl NetworkPartition {
+    /// The comment identifying the firewall rules installed by the orchestrator.
+    const RULE_COMMENT: &'static str = "mysticeti-partition";
+
+    /// The command isolating a node from the specified peers, in both directions.
+    pub fn isolate_command(peers: &[Ipv4Addr]) -> String {
+        peers
+            .iter()
+            .flat_map(|ip| {
+                [("INPUT", "-s"), ("OUTPUT", "-d")].map(|(chain, direction)| {
+                    format!(
+                        "sudo iptables -A {chain} {direction} {ip} \
+                        -m comment --comment {} -j DROP",
+                        Self::RULE_COMMENT
+                    )
+                })
+            })
+            .collect::<Vec<_>>()
+            .join(" && ")
+    }
+
+    /// The commands partitioning the specified instances (indexed by node) into the specified
+    /// groups of node indices. Indices beyond the number of instances are ignored.
+    pub fn partition_commands(
+        instances: &[Instance],
+        groups: &[Vec<usize>],
+    ) -> Vec<(Instance, String)> {
+        let group_ips = |group: &Vec<usize>| {
+            group
+                .iter()
+                .filter_map(|i| instances.get(*i))
+                .map(|x| x.main_ip)
+                .collect::<Vec<_>>()
+        };
+        groups
+            .iter()
+            .enumerate()
+            .flat_map(|(g, group)| {
+                let peers: Vec<_> = groups
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != g)
+                    .flat_map(|(_, x)| group_ips(x))
+                    .collect();
+                let command = Self::isolate_command(&peers);
+                group
+                    .iter()
+                    .filter_map(|i| instances.get(*i))
+                    .map(move |x| (x.clone(), command.clone()))
+            })
+            .collect()
+    }
+
+    /// The command removing all the firewall rules installed to partition nodes.
+    pub fn clear_command() -> String {
+        format!(
+            "(sudo iptables-save | grep -v {} | sudo iptables-restore || true)",
+            Self::RULE_COMMENT
+        )
+    }
 }
 
 pub struct CrashRecoverySchedule {
@@ -117,8 +484,18 @@ pub struct CrashRecoverySchedule {
     faults_type: FaultsType,
     /// The available instances.
     instances: Vec<Instance>,
+    /// The hot spares that have not been promoted yet.
+    spares: Vec<Instance>,
     /// The current number of dead nodes.
     dead: usize,
+    /// The number of dead nodes already replaced by spares.
+    replaced: usize,
+    /// The seed selecting the nodes to crash when they are chosen randomly.
+    seed: u64,
+    /// The highest round reached by the nodes (as far as we know).
+    round: u64,
+    /// The time elapsed since the beginning of the benchmark.
+    elapsed: Duration,
 }
 
 impl CrashRecoverySchedule {
@@ -126,9 +503,52 @@ impl CrashRecoverySchedule {
         Self {
             faults_type,
             instances,
+            spares: Vec::new(),
             dead: 0,
+            replaced: 0,
+            seed: 0,
+            round: 0,
+            elapsed: Duration::ZERO,
         }
     }
+
+    /// Set the seed selecting the nodes to crash when they are chosen randomly.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Record the highest round reached by the nodes.
+    pub fn observe_round(&mut self, round: u64) {
+        self.round = self.round.max(round);
+    }
+
+    /// Record the time elapsed since the beginning of the benchmark.
+    pub fn observe_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+
+    /// Whether the schedule needs to know the progress of the nodes to decide which action to
+    /// take next.
+    pub fn awaits_round(&self) -> bool {
+        matches!(self.faults_type, FaultsType::Crash { at_round, .. } if self.round < at_round)
+    }
+
+    /// Return the specified number of nodes chosen uniformly at random. The choice only
+    /// depends on the seed, so it is stable across runs.
+    pub fn random_instances(&self, count: usize) -> Vec<Instance> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut instances = self.instances.clone();
+        instances.shuffle(&mut rng);
+        instances.truncate(count);
+        instances
+    }
+
+    /// Set the hot spares that may be promoted to replace crashed nodes.
+    pub fn with_spares(mut self, spares: Vec<Instance>) -> Self {
+        self.spares = spares;
+        self
+    }
     pub fn update(&mut self) -> CrashRecoveryAction {
         let mut instances = self.instances.clone();
 
@@ -168,6 +588,63 @@ impl CrashRecoverySchedule {
                     CrashRecoveryAction::kill(to_kill)
                 }
             }
+
+            // Permanently crash the specified number of nodes, then replace them by spares.
+            FaultsType::Replacement { faults, .. } => {
+                if self.dead == 0 {
+                    self.dead = *faults;
+                    CrashRecoveryAction::kill(instances.drain(0..*faults))
+                } else {
+                    let promoted = self.spares.len().min(self.dead - self.replaced);
+                    let crashed = instances.drain(self.replaced..self.replaced + promoted);
+                    self.replaced += promoted;
+                    CrashRecoveryAction::promote(crashed.zip(self.spares.drain(0..promoted)))
+                }
+            }
+
+            // Permanently crash random nodes once the benchmark reaches the specified round.
+            FaultsType::Crash { count, at_round } => {
+                if self.dead == 0 && self.round >= *at_round {
+                    let to_kill = self.random_instances(*count);
+                    self.dead = to_kill.len();
+                    CrashRecoveryAction::kill(to_kill.into_iter())
+                } else {
+                    CrashRecoveryAction::no_op()
+                }
+            }
+
+            // The latency is injected when the benchmark starts, no node ever crashes.
+            FaultsType::Latency { .. } => CrashRecoveryAction::no_op(),
+
+            // Crash the targeted nodes once, and recover them after their downtime.
+            FaultsType::CrashRecover {
+                crash_after,
+                down_for,
+                targets,
+            } => {
+                let recover_at = *crash_after + *down_for;
+                let targeted = targets.iter().filter_map(|i| instances.get(*i).cloned());
+                if self.dead == 0 && self.elapsed >= *crash_after && self.elapsed < recover_at {
+                    let to_kill: Vec<_> = targeted.collect();
+                    self.dead = to_kill.len();
+                    CrashRecoveryAction::kill(to_kill.into_iter())
+                } else if self.dead != 0 && self.elapsed >= recover_at {
+                    self.dead = 0;
+                    CrashRecoveryAction::boot(targeted)
+                } else {
+                    CrashRecoveryAction::no_op()
+                }
+            }
+
+            // The nodes equivocate or stay silent from the moment they boot, no node ever crashes.
+            FaultsType::Equivocate { .. } | FaultsType::Silent { .. } => {
+                CrashRecoveryAction::no_op()
+            }
+
+            // The network faults are injected when the benchmark starts, no node ever crashes.
+            FaultsType::Degrade { .. } | FaultsType::Partition { .. } => {
+                CrashRecoveryAction::no_op()
+            }
         }
     }
 }
@@ -176,8 +653,12 @@ impl CrashRecoverySchedule {
 mod faults_tests {
     use std::time::Duration;
 
-    use super::{CrashRecoverySchedule, FaultsType};
-    use crate::client::Instance;
+    use super::{CrashRecoverySchedule, FaultsType, NetworkLatency, NetworkPartition};
+    use crate::{
+        client::Instance,
+        local_executor::LocalCommandExecutor,
+        ssh::{session_name, CommandContext, CommandStatus},
+    };
 
     #[test]
     fn crash_recovery_1_fault() {
@@ -282,4 +763,335 @@ mod faults_tests {
             assert_eq!(action.kill.len(), min_faults);
         }
     }
+
+    #[test]
+    fn replacement() {
+        let faults = 2;
+        let delay = Duration::from_secs(10);
+        let instances = (0..4)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+        let spares: Vec<_> = (4..7)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+        let mut schedule =
+            CrashRecoverySchedule::new(FaultsType::Replacement { faults, delay }, instances)
+                .with_spares(spares.clone());
+
+        let action = schedule.update();
+        assert_eq!(action.kill.len(), faults);
+        assert!(action.promote.is_empty());
+        let crashed = action.kill;
+
+        // Only as many spares as crashed nodes are promoted, and only once. Each of them replaces
+        // one of the crashed nodes.
+        let action = schedule.update();
+        assert!(action.kill.is_empty());
+        let expected: Vec<_> = crashed.into_iter().zip(spares[..faults].to_vec()).collect();
+        assert_eq!(action.promote, expected);
+
+        let action = schedule.update();
+        assert!(action.kill.is_empty());
+        assert!(action.promote.is_empty());
+    }
+
+    #[test]
+    fn silent() {
+        let faults = 1;
+        let instances = (0..4)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+        let mut schedule = CrashRecoverySchedule::new(FaultsType::Silent { faults }, instances);
+
+        // The nodes are silenced when they boot, the schedule never touches them.
+        for _ in 0..3 {
+            assert!(schedule.update().is_no_op());
+        }
+        assert_eq!(FaultsType::Silent { faults }.silent_nodes(), faults);
+        assert_eq!(FaultsType::Equivocate { faults }.silent_nodes(), 0);
+    }
+
+    #[test]
+    fn parse_faults() {
+        let crash = FaultsType::Crash {
+            count: 3,
+            at_round: 100,
+        };
+        assert_eq!("crash:3@100".parse(), Ok(crash));
+        assert_eq!("2".parse(), Ok(FaultsType::Permanent { faults: 2 }));
+        assert!("crash:3".parse::<FaultsType>().is_err());
+        assert!("crash:x@100".parse::<FaultsType>().is_err());
+
+        let latency = FaultsType::Latency {
+            millis: 100,
+            jitter: 10,
+        };
+        assert_eq!("latency:100/10".parse(), Ok(latency));
+        let latency = FaultsType::Latency {
+            millis: 50,
+            jitter: 0,
+        };
+        assert_eq!("latency:50".parse(), Ok(latency));
+        assert!("latency:".parse::<FaultsType>().is_err());
+        assert!("latency:50/x".parse::<FaultsType>().is_err());
+
+        let equivocate = FaultsType::Equivocate { faults: 1 };
+        assert_eq!("equivocate:1".parse(), Ok(equivocate.clone()));
+        assert_eq!(equivocate.equivocating_nodes(), 1);
+        assert_eq!(FaultsType::Permanent { faults: 1 }.equivocating_nodes(), 0);
+        assert!("equivocate:".parse::<FaultsType>().is_err());
+    }
+
+    #[test]
+    fn latency_never_crashes_nodes() {
+        let instances = (0..4)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+        let faults_type = FaultsType::Latency {
+            millis: 100,
+            jitter: 10,
+        };
+        let mut schedule = CrashRecoverySchedule::new(faults_type, instances);
+        assert!(schedule.update().is_no_op());
+        assert!(schedule.update().is_no_op());
+    }
+
+    #[test]
+    fn crash_at_round() {
+        let faults_type = FaultsType::Crash {
+            count: 3,
+            at_round: 100,
+        };
+        let instances: Vec<_> = (0..10)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+        let mut schedule = CrashRecoverySchedule::new(faults_type.clone(), instances.clone());
+
+        // Nothing happens before the benchmark reaches the round.
+        assert!(schedule.awaits_round());
+        schedule.observe_round(99);
+        assert!(schedule.update().is_no_op());
+
+        schedule.observe_round(100);
+        assert!(!schedule.awaits_round());
+        let action = schedule.update();
+        assert_eq!(action.kill.len(), 3);
+        assert!(schedule.update().is_no_op());
+
+        // The crashed nodes only depend on the seed.
+        let other = CrashRecoverySchedule::new(faults_type, instances);
+        assert_eq!(other.random_instances(3), action.kill);
+        assert_ne!(other.with_seed(1).random_instances(10), action.kill);
+    }
+
+    #[test]
+    fn seeded_crashes() {
+        let faults_type = FaultsType::Crash {
+            count: 3,
+            at_round: 0,
+        };
+        let instances: Vec<_> = (0..10)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+        let crashes = |seed| {
+            let mut schedule =
+                CrashRecoverySchedule::new(faults_type.clone(), instances.clone()).with_seed(seed);
+            schedule.update().kill
+        };
+
+        // Schedules with the same seed crash the same nodes.
+        assert_eq!(crashes(42), crashes(42));
+        assert_ne!(crashes(42), crashes(43));
+    }
+
+    #[test]
+    fn crash_recover() {
+        let faults_type: FaultsType = "recover:1,3@30/20".parse().unwrap();
+        assert_eq!(
+            faults_type,
+            FaultsType::CrashRecover {
+                crash_after: Duration::from_secs(30),
+                down_for: Duration::from_secs(20),
+                targets: vec![1, 3],
+            }
+        );
+        assert_eq!(format!("{faults_type:?}"), "1.3@30+20rec");
+        assert!("recover:1,3@30".parse::<FaultsType>().is_err());
+        assert!("recover:x@30/20".parse::<FaultsType>().is_err());
+
+        let instances: Vec<_> = (0..4)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+        let targets = vec![instances[1].clone(), instances[3].clone()];
+        let mut schedule = CrashRecoverySchedule::new(faults_type.clone(), instances);
+
+        // Tick every second and record when each action fires.
+        let mut fired = Vec::new();
+        for secs in 1..=120 {
+            schedule.observe_elapsed(Duration::from_secs(secs));
+            let action = schedule.update();
+            if !action.is_no_op() {
+                fired.push((secs, action.kill, action.boot));
+            }
+        }
+        assert_eq!(
+            fired,
+            vec![(30, targets.clone(), vec![]), (50, vec![], targets)]
+        );
+    }
+
+    #[tokio::test]
+    async fn crash_local_instances() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let executor = LocalCommandExecutor::new(working_dir.path().to_path_buf());
+        // Prefix the ids with the process id to avoid clashing with other tmux sessions.
+        let instances: Vec<_> = (0..4)
+            .map(|i| Instance::new_for_test(format!("{}-{i}", std::process::id())))
+            .collect();
+
+        // Boot a fake node on every instance.
+        let id = "crash-test";
+        let context = CommandContext::new().run_background(id.into());
+        executor
+            .execute(instances.clone(), "sleep 60", context)
+            .await
+            .unwrap();
+        executor
+            .wait_for_command(instances.clone(), id, CommandStatus::Running)
+            .await
+            .unwrap();
+
+        let faults_type = FaultsType::Crash {
+            count: 2,
+            at_round: 10,
+        };
+        let mut schedule = CrashRecoverySchedule::new(faults_type, instances.clone());
+        schedule.observe_round(10);
+        let action = schedule.update();
+        executor.kill(action.kill.clone(), id).await.unwrap();
+        executor
+            .wait_for_command(action.kill.clone(), id, CommandStatus::Terminated)
+            .await
+            .unwrap();
+
+        // Exactly the crashed instances are down.
+        let (stdout, _) = executor
+            .execute(
+                instances.iter().take(1).cloned(),
+                "(tmux ls || true)",
+                CommandContext::default(),
+            )
+            .await
+            .unwrap()
+            .remove(0);
+        let running = instances
+            .iter()
+            .filter(|x| {
+                CommandStatus::status(&session_name(id, x), &stdout) == CommandStatus::Running
+            })
+            .count();
+        assert_eq!(running, instances.len() - 2);
+
+        executor.kill(instances, id).await.unwrap();
+    }
+
+    #[test]
+    fn latency_commands() {
+        let interface = "$(ip route show default | awk '{print $5; exit}')";
+        assert_eq!(
+            NetworkLatency::inject_command(100, 10),
+            format!("sudo tc qdisc replace dev {interface} root netem delay 100ms 10ms")
+        );
+        assert_eq!(
+            NetworkLatency::inject_command(50, 0),
+            format!("sudo tc qdisc replace dev {interface} root netem delay 50ms")
+        );
+        assert_eq!(
+            NetworkLatency::clear_command(),
+            format!("(sudo tc qdisc del dev {interface} root || true)")
+        );
+        assert_eq!(
+            NetworkLatency::degrade_command(100, 2, Some(10)),
+            format!(
+                "sudo tc qdisc replace dev {interface} root netem delay 100ms loss 2% rate 10mbit"
+            )
+        );
+        assert_eq!(
+            NetworkLatency::degrade_command(0, 5, None),
+            format!("sudo tc qdisc replace dev {interface} root netem delay 0ms loss 5%")
+        );
+    }
+
+    #[test]
+    fn degrade_and_partition() {
+        let degrade: FaultsType = "degrade:1,3@100ms/2%/10mbit".parse().unwrap();
+        assert_eq!(
+            degrade,
+            FaultsType::Degrade {
+                targets: vec![1, 3],
+                delay_ms: 100,
+                loss_percent: 2,
+                rate_mbit: Some(10),
+            }
+        );
+        assert_eq!(format!("{degrade:?}"), "1.3@100-2-10d");
+        let degrade: FaultsType = "degrade:0@50ms".parse().unwrap();
+        assert_eq!(degrade.to_string(), "nodes 0 degraded (50ms, 0% loss)");
+        assert!("degrade:0@50".parse::<FaultsType>().is_err());
+        assert!("degrade:0@50ms/2".parse::<FaultsType>().is_err());
+
+        let partition: FaultsType = "partition:0,1|2,3".parse().unwrap();
+        assert_eq!(
+            partition,
+            FaultsType::Partition {
+                groups: vec![vec![0, 1], vec![2, 3]]
+            }
+        );
+        assert_eq!(format!("{partition:?}"), "0.1|2.3p");
+        assert!(partition.is_network_fault());
+        assert!("partition:0,1".parse::<FaultsType>().is_err());
+        assert!("partition:0,x|2".parse::<FaultsType>().is_err());
+    }
+
+    #[test]
+    fn two_group_partition_commands() {
+        let instances: Vec<_> = (0..4)
+            .map(|i| {
+                let mut instance = Instance::new_for_test(i.to_string());
+                instance.main_ip = format!("10.0.0.{i}").parse().unwrap();
+                instance
+            })
+            .collect();
+        let groups = vec![vec![0, 1], vec![2, 3]];
+        let commands = NetworkPartition::partition_commands(&instances, &groups);
+
+        let rule = |chain, direction, ip| {
+            format!(
+                "sudo iptables -A {chain} {direction} {ip} \
+                -m comment --comment mysticeti-partition -j DROP"
+            )
+        };
+        let isolate = |a, b| {
+            [
+                rule("INPUT", "-s", a),
+                rule("OUTPUT", "-d", a),
+                rule("INPUT", "-s", b),
+                rule("OUTPUT", "-d", b),
+            ]
+            .join(" && ")
+        };
+        let first = isolate("10.0.0.2", "10.0.0.3");
+        let second = isolate("10.0.0.0", "10.0.0.1");
+        let expected: Vec<_> = [&first, &first, &second, &second]
+            .into_iter()
+            .zip(&instances)
+            .map(|(command, instance)| (instance.clone(), command.clone()))
+            .collect();
+        assert_eq!(commands, expected);
+
+        assert_eq!(
+            NetworkPartition::clear_command(),
+            "(sudo iptables-save | grep -v mysticeti-partition | sudo iptables-restore || true)"
+        );
+    }
 }
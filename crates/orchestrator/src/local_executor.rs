@@ -2,86 +2,289 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    net::SocketAddr,
-    path::PathBuf,
+    collections::HashMap,
+    net::TcpListener,
+    path::{Path, PathBuf},
     process::Stdio,
+    sync::{Arc, Mutex},
     time::Duration,
 };
 
 use futures::future::try_join_all;
 use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
     process::Command,
-    time::sleep,
+    time::{sleep, timeout},
 };
 
 use crate::{
     client::Instance,
-    error::{SshError, SshResult},
-    ssh::{CommandContext, CommandStatus},
+    error::{LocalExecutionError, SshError, SshResult},
+    retry::RetryPolicy,
+    ssh::{
+        kill_session_command,
+        session_name,
+        CommandContext,
+        CommandStatus,
+        OutputLine,
+        OutputSender,
+    },
 };
 
+/// The range of ports reserved for the node of a local instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PortRange {
+    /// The first port of the range.
+    pub base: u16,
+}
+
+impl PortRange {
+    /// The number of ports of each range: consensus and metrics (the clients currently run
+    /// within the node).
+    pub const SIZE: u16 = 2;
+
+    /// The port on which the node accepts consensus connections.
+    pub fn consensus(&self) -> u16 {
+        self.base
+    }
+
+    /// The port on which the node exposes its metrics.
+    pub fn metrics(&self) -> u16 {
+        self.base + 1
+    }
+
+    /// Whether the two ranges share at least one port.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.base < other.base + Self::SIZE && other.base < self.base + Self::SIZE
+    }
+}
+
+/// Assign a distinct range of free ports to each local instance, so that several validators
+/// running on the same machine do not collide. Ranges are assigned for the lifetime of the
+/// allocator (and shared by its clones). The ports stay bound until they are released, right
+/// before the node of the instance boots and binds them itself.
+#[derive(Clone, Default)]
+pub struct PortAllocator {
+    ranges: Arc<Mutex<HashMap<String, PortRange>>>,
+    reserved: Arc<Mutex<HashMap<String, Vec<TcpListener>>>>,
+}
+
+impl PortAllocator {
+    /// The number of attempts at finding a free range before giving up.
+    const MAX_ATTEMPTS: usize = 100;
+
+    /// Return the range of the specified instance, reserving a new one if needed.
+    pub fn allocate(&self, instance: &Instance) -> std::io::Result<PortRange> {
+        let mut ranges = self.ranges.lock().unwrap();
+        if let Some(range) = ranges.get(&instance.id) {
+            return Ok(*range);
+        }
+
+        for _ in 0..Self::MAX_ATTEMPTS {
+            // Let the OS pick a free port, then check that the whole range starting at this
+            // port is free and not reserved by another instance.
+            let base = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+            if base > u16::MAX - PortRange::SIZE {
+                continue;
+            }
+            let range = PortRange { base };
+            if ranges.values().any(|x| x.overlaps(&range)) {
+                continue;
+            }
+            let bound: std::io::Result<Vec<_>> = (base..base + PortRange::SIZE)
+                .map(|port| TcpListener::bind(("127.0.0.1", port)))
+                .collect();
+            if let Ok(listeners) = bound {
+                ranges.insert(instance.id.clone(), range);
+                self.reserved
+                    .lock()
+                    .unwrap()
+                    .insert(instance.id.clone(), listeners);
+                return Ok(range);
+            }
+        }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::AddrInUse,
+            "no free port range available",
+        ))
+    }
+
+    /// Unbind the ports of the specified instance (which keeps its range).
+    pub fn release(&self, instance: &Instance) {
+        self.reserved.lock().unwrap().remove(&instance.id);
+    }
+}
+
 /// A local command executor that runs commands directly on the local machine
 /// without using SSH. This is used when running benchmarks locally.
 #[derive(Clone)]
 pub struct LocalCommandExecutor {
     /// Working directory for local execution
     working_dir: PathBuf,
+    /// The maximum duration of a command. Commands running longer are killed.
+    timeout: Option<Duration>,
+    /// The ports reserved for each instance.
+    ports: PortAllocator,
+    /// The delays between the attempts at running a command until it succeeds.
+    retry_policy: RetryPolicy,
 }
 
 impl LocalCommandExecutor {
     /// Create a new local command executor.
     pub fn new(working_dir: PathBuf) -> Self {
-        Self { working_dir }
+        Self {
+            working_dir,
+            timeout: None,
+            ports: PortAllocator::default(),
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    /// Set a timeout duration for the commands.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
 
-    /// Delay before re-attempting command execution.
+    /// Set the delays between the attempts at running a command until it succeeds.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Reserve a range of free ports for the node of the specified instance. The same range is
+    /// returned for the lifetime of the executor.
+    pub fn reserve_ports(&self, instance: &Instance) -> SshResult<PortRange> {
+        self.ports
+            .allocate(instance)
+            .map_err(|e| SshError::local(self.instance_dir(instance), "reserve ports", e))
+    }
+
+    /// Unbind the ports reserved for the specified instance, so that its node can bind them.
+    pub fn release_ports(&self, instance: &Instance) {
+        self.ports.release(instance);
+    }
+
+    /// Delay between two checks of the status of a command.
     const RETRY_DELAY: Duration = Duration::from_secs(1);
 
-    /// Execute a command locally using shell.
-    async fn execute_command(
+    /// Return the directory dedicated to the specified instance. All virtual instances run on
+    /// the same machine, so each of them gets its own directory to avoid overwriting each
+    /// other's files.
+    pub fn instance_dir(&self, instance: &Instance) -> PathBuf {
+        self.working_dir.join(format!("instance-{}", instance.id))
+    }
+
+    /// Build the shell command running the specified command in the directory of the instance.
+    /// The child is killed when its handle (or output future) is dropped.
+    fn shell_command(
         &self,
+        instance: &Instance,
         command: String,
-        context: CommandContext,
-    ) -> SshResult<(String, String)> {
-        let full_command = context.apply(command);
-
-        // Ensure working directory exists
-        if let Err(e) = std::fs::create_dir_all(&self.working_dir) {
-            return Err(SshError::ConnectionError {
-                address: SocketAddr::from(([127, 0, 0, 1], 22)),
-                error: std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("Failed to create working directory {}: {}", self.working_dir.display(), e),
-                ),
-            });
+        mut context: CommandContext,
+    ) -> SshResult<Command> {
+        // Relative execution paths (e.g., the repository) are shared by all instances.
+        if let Some(path) = &context.path {
+            if path.is_relative() && !path.starts_with("~") {
+                context.path = Some(self.working_dir.join(path));
+            }
         }
+        let instance_dir = self.instance_dir(instance);
+        let full_command = context.for_instance(instance).apply(command);
+
+        // Ensure the instance's directory exists
+        std::fs::create_dir_all(&instance_dir)
+            .map_err(|e| SshError::local(&instance_dir, &full_command, e))?;
 
-        // Run the command in a shell
-        let output = Command::new("sh")
+        let mut command = Command::new("sh");
+        command
             .arg("-c")
             .arg(&full_command)
-            .current_dir(&self.working_dir)
+            .current_dir(&instance_dir)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .output()
-            .await
-            .map_err(|e| SshError::ConnectionError {
-                address: SocketAddr::from(([127, 0, 0, 1], 22)),
-                error: std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("Failed to execute command: {}", e),
-                ),
-            })?;
+            .kill_on_drop(true);
+        Ok(command)
+    }
+
+    /// Execute a command locally using shell.
+    async fn execute_command(
+        &self,
+        instance: &Instance,
+        command: String,
+        context: CommandContext,
+    ) -> SshResult<(String, String)> {
+        let directory = self.instance_dir(instance);
+        let error = |e: LocalExecutionError| SshError::local(&directory, &command, e);
+
+        // The child is killed if the command times out (and its output future is dropped).
+        let output = self
+            .shell_command(instance, command.clone(), context)?
+            .output();
+        let output = match self.timeout {
+            Some(duration) => timeout(duration, output)
+                .await
+                .map_err(|_| error(LocalExecutionError::Timeout(duration)))?,
+            None => output.await,
+        }
+        .map_err(|e| error(e.into()))?;
 
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
 
         if !output.status.success() {
-            return Err(SshError::NonZeroExitCode {
-                address: SocketAddr::from(([127, 0, 0, 1], 22)),
+            return Err(error(LocalExecutionError::NonZeroExitCode {
                 code: output.status.code().unwrap_or(1),
-                message: stderr.clone(),
-            });
+                message: stderr,
+            }));
+        }
+
+        Ok((stdout, stderr))
+    }
+
+    /// Execute a command locally using shell, forwarding each line of its stdout and stderr to
+    /// the sender as soon as it is printed. The complete output is returned once it exits.
+    async fn execute_command_streaming(
+        &self,
+        instance: &Instance,
+        command: String,
+        context: CommandContext,
+        sender: OutputSender,
+    ) -> SshResult<(String, String)> {
+        let directory = self.instance_dir(instance);
+        let error = |e: LocalExecutionError| SshError::local(&directory, &command, e);
+
+        let mut child = self
+            .shell_command(instance, command.clone(), context)?
+            .spawn()
+            .map_err(|e| error(e.into()))?;
+        let stdout = child.stdout.take().expect("Stdout is piped");
+        let stderr = child.stderr.take().expect("Stderr is piped");
+
+        let id = &instance.id;
+        let run = async {
+            let (stdout, stderr) = tokio::join!(
+                forward_lines(stdout, id, &sender, OutputLine::Stdout),
+                forward_lines(stderr, id, &sender, OutputLine::Stderr),
+            );
+            (stdout, stderr, child.wait().await)
+        };
+        // The child is killed if the command times out (and its handle is dropped).
+        let (stdout, stderr, status) = match self.timeout {
+            Some(duration) => timeout(duration, run)
+                .await
+                .map_err(|_| error(LocalExecutionError::Timeout(duration)))?,
+            None => run.await,
+        };
+        let stdout = stdout.map_err(|e| error(e.into()))?;
+        let stderr = stderr.map_err(|e| error(e.into()))?;
+        let status = status.map_err(|e| error(e.into()))?;
+
+        if !status.success() {
+            return Err(error(LocalExecutionError::NonZeroExitCode {
+                code: status.code().unwrap_or(1),
+                message: stderr,
+            }));
         }
 
         Ok((stdout, stderr))
@@ -105,8 +308,10 @@ impl LocalCommandExecutor {
         let command_str: String = command.into();
         let mut results = Vec::new();
 
-        for _instance in &instances {
-            let result = self.execute_command(command_str.clone(), context.clone()).await?;
+        for instance in &instances {
+            let result = self
+                .execute_command(instance, command_str.clone(), context.clone())
+                .await?;
             results.push(result);
         }
 
@@ -126,13 +331,13 @@ impl LocalCommandExecutor {
         let instances: Vec<_> = instances.into_iter().collect();
         let handles: Vec<_> = instances
             .into_iter()
-            .map(|(_instance, command)| {
+            .map(|(instance, command)| {
                 let executor = self.clone();
                 let command: String = command.into();
                 let context = context.clone();
 
                 tokio::spawn(async move {
-                    executor.execute_command(command, context).await
+                    executor.execute_command(&instance, command, context).await
                 })
             })
             .collect();
@@ -141,6 +346,30 @@ impl LocalCommandExecutor {
         results.into_iter().collect()
     }
 
+    /// Execute the specified command on all provided instances, forwarding their output to the
+    /// sender line by line as it is printed.
+    pub async fn execute_streaming<I, S>(
+        &self,
+        instances: I,
+        command: S,
+        context: CommandContext,
+        sender: OutputSender,
+    ) -> SshResult<Vec<(String, String)>>
+    where
+        I: IntoIterator<Item = Instance>,
+        S: Into<String> + Clone + Send + 'static,
+    {
+        let command: String = command.into();
+        let futures = instances.into_iter().map(|instance| {
+            let (command, context, sender) = (command.clone(), context.clone(), sender.clone());
+            async move {
+                self.execute_command_streaming(&instance, command, context, sender)
+                    .await
+            }
+        });
+        try_join_all(futures).await
+    }
+
     /// Wait until a command running in the background returns or started.
     pub async fn wait_for_command<I>(
         &self,
@@ -151,6 +380,7 @@ impl LocalCommandExecutor {
     where
         I: IntoIterator<Item = Instance> + Clone,
     {
+        let instances: Vec<_> = instances.into_iter().collect();
         loop {
             sleep(Self::RETRY_DELAY).await;
 
@@ -163,7 +393,10 @@ impl LocalCommandExecutor {
                 .await?;
             if result
                 .iter()
-                .all(|(stdout, _)| CommandStatus::status(command_id, stdout) == status)
+                .zip(&instances)
+                .all(|((stdout, _), instance)| {
+                    CommandStatus::status(&session_name(command_id, instance), stdout) == status
+                })
             {
                 break;
             }
@@ -177,8 +410,9 @@ impl LocalCommandExecutor {
         I: IntoIterator<Item = (Instance, S)> + Clone,
         S: Into<String> + Send + 'static + Clone,
     {
+        let mut backoff = self.retry_policy.backoff();
         loop {
-            sleep(Self::RETRY_DELAY).await;
+            sleep(backoff.next_delay_or_max()).await;
 
             if self
                 .execute_per_instance(instances.clone(), CommandContext::default())
@@ -195,39 +429,90 @@ impl LocalCommandExecutor {
     where
         I: IntoIterator<Item = Instance>,
     {
-        let command = format!("(tmux kill-session -t {command_id} || true)");
-        let targets: Vec<_> = instances.into_iter().map(|x| (x, command.clone())).collect();
+        let targets: Vec<_> = instances
+            .into_iter()
+            .map(|x| {
+                let command = kill_session_command(command_id, &x);
+                (x, command)
+            })
+            .collect();
         self.execute_per_instance(targets, CommandContext::default())
             .await?;
         Ok(())
     }
 
-    /// Connect to an instance (for local execution, this is a no-op wrapper).
-    pub async fn connect(&self, _address: SocketAddr) -> SshResult<LocalConnection> {
-        Ok(LocalConnection {
-            working_dir: self.working_dir.clone(),
-        })
+    /// Connect to an instance. For local execution, this only ensures the directory of the
+    /// instance exists.
+    pub async fn connect(&self, instance: &Instance) -> SshResult<LocalConnection> {
+        let working_dir = self.instance_dir(instance);
+        let command = format!("mkdir -p {}", working_dir.display());
+        std::fs::create_dir_all(&working_dir)
+            .map_err(|e| SshError::local(&working_dir, command, e))?;
+        Ok(LocalConnection { working_dir })
     }
 }
 
+/// Forward each line read from the reader to the sender, and return everything read.
+async fn forward_lines<R: AsyncRead + Unpin>(
+    reader: R,
+    id: &str,
+    sender: &OutputSender,
+    make_line: fn(String) -> OutputLine,
+) -> std::io::Result<String> {
+    let mut output = String::new();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        output.push_str(&line);
+        output.push('\n');
+        // The output is still collected if nobody listens to the stream anymore.
+        let _ = sender.send((id.into(), make_line(line)));
+    }
+    Ok(output)
+}
+
 /// A local connection for downloading files.
 pub struct LocalConnection {
+    /// The directory of the instance, against which relative paths are resolved.
     working_dir: PathBuf,
 }
 
 impl LocalConnection {
     /// Download a file from the local machine.
-    pub fn download<P: AsRef<std::path::Path>>(&self, path: P) -> SshResult<String> {
+    pub fn download<P: AsRef<Path>>(&self, path: P) -> SshResult<String> {
         let path = path.as_ref();
+        let error = |e: std::io::Error| {
+            SshError::local(&self.working_dir, format!("cat {}", path.display()), e)
+        };
+        let full_path = self.resolve(path).map_err(error)?;
+        std::fs::read_to_string(full_path).map_err(error)
+    }
+
+    /// Copy a whole directory (and its sub-directories) of the local machine to the specified
+    /// destination, preserving its structure.
+    pub fn download_dir(&self, source: &Path, destination: &Path) -> SshResult<()> {
+        let command = format!("cp -r {} {}", source.display(), destination.display());
+        let error = |e: std::io::Error| SshError::local(&self.working_dir, &command, e);
+
+        let source = self.resolve(source).map_err(error)?;
+        if !source.is_dir() {
+            let message = format!("Directory {} does not exist", source.display());
+            let kind = std::io::ErrorKind::NotFound;
+            return Err(error(std::io::Error::new(kind, message)));
+        }
+        copy_dir(&source, destination).map_err(error)
+    }
+
+    /// Resolve a path against the directory of the instance, expanding `~` to the home
+    /// directory.
+    fn resolve(&self, path: &Path) -> std::io::Result<PathBuf> {
         // Expand ~ to home directory
         let path_str = path.to_string_lossy();
         let expanded_path = if path_str.starts_with("~") {
-            let home = std::env::var("HOME").map_err(|_| SshError::ConnectionError {
-                address: SocketAddr::from(([127, 0, 0, 1], 22)),
-                error: std::io::Error::new(
+            let home = std::env::var("HOME").map_err(|_| {
+                std::io::Error::new(
                     std::io::ErrorKind::NotFound,
                     "HOME environment variable not set",
-                ),
+                )
             })?;
             PathBuf::from(path_str.replace("~", &home))
         } else {
@@ -235,16 +520,293 @@ impl LocalConnection {
         };
 
         // If path is relative, make it relative to working dir
-        let full_path = if expanded_path.is_absolute() {
-            expanded_path
+        if expanded_path.is_absolute() {
+            Ok(expanded_path)
         } else {
-            self.working_dir.join(&expanded_path)
-        };
+            Ok(self.working_dir.join(&expanded_path))
+        }
+    }
+}
 
-        std::fs::read_to_string(&full_path).map_err(|e| SshError::ConnectionError {
-            address: SocketAddr::from(([127, 0, 0, 1], 22)),
-            error: e,
-        })
+/// Recursively copy the content of a directory.
+fn copy_dir(source: &Path, destination: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(destination)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let target = destination.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
     }
+    Ok(())
 }
 
+#[cfg(test)]
+mod test {
+    use std::{
+        net::TcpListener,
+        path::{Path, PathBuf},
+        time::{Duration, Instant},
+    };
+
+    use tokio::{sync::mpsc, time::sleep};
+
+    use crate::{
+        client::Instance,
+        error::{LocalExecutionError, SshError},
+        local_executor::{LocalCommandExecutor, PortAllocator},
+        ssh::{session_name, CommandContext, CommandStatus, OutputLine},
+    };
+
+    #[tokio::test]
+    async fn separate_instance_directories() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let executor = LocalCommandExecutor::new(working_dir.path().to_path_buf());
+        let instances: Vec<_> = (0..2)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+
+        let targets = instances
+            .iter()
+            .map(|x| (x.clone(), format!("echo {} > file.txt", x.id)));
+        executor
+            .execute_per_instance(targets, CommandContext::default())
+            .await
+            .unwrap();
+
+        for instance in &instances {
+            let path = working_dir
+                .path()
+                .join(format!("instance-{}", instance.id))
+                .join("file.txt");
+            let content = std::fs::read_to_string(path).unwrap();
+            assert_eq!(content.trim(), instance.id);
+
+            let connection = executor.connect(instance).await.unwrap();
+            let content = connection.download("file.txt").unwrap();
+            assert_eq!(content.trim(), instance.id);
+        }
+    }
+
+    #[test]
+    fn distinct_port_ranges() {
+        let allocator = PortAllocator::default();
+        let instances: Vec<_> = (0..2)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+        let first = allocator.allocate(&instances[0]).unwrap();
+        let second = allocator.allocate(&instances[1]).unwrap();
+        assert!(!first.overlaps(&second), "{first:?} overlaps {second:?}");
+        assert_eq!(allocator.allocate(&instances[0]).unwrap(), first);
+
+        // The ports stay reserved until they are released.
+        assert!(TcpListener::bind(("127.0.0.1", first.consensus())).is_err());
+        assert!(TcpListener::bind(("127.0.0.1", first.metrics())).is_err());
+        allocator.release(&instances[0]);
+        assert!(TcpListener::bind(("127.0.0.1", first.consensus())).is_ok());
+        assert!(TcpListener::bind(("127.0.0.1", second.consensus())).is_err());
+    }
+
+    #[tokio::test]
+    async fn error_messages() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let executor = LocalCommandExecutor::new(working_dir.path().to_path_buf());
+        let instance = Instance::new_for_test("0".into());
+        let directory = executor.instance_dir(&instance);
+
+        let command = "echo failure >&2; exit 3";
+        let error = executor
+            .execute([instance.clone()], command, CommandContext::default())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains(command), "{error}");
+        assert!(error.contains(&*directory.to_string_lossy()), "{error}");
+        assert!(error.contains("exit code (3): failure"), "{error}");
+        assert!(!error.contains("127.0.0.1:22"), "{error}");
+
+        let connection = executor.connect(&instance).await.unwrap();
+        let error = connection.download("missing.txt").unwrap_err().to_string();
+        assert!(error.contains("cat missing.txt"), "{error}");
+        assert!(error.contains(&*directory.to_string_lossy()), "{error}");
+    }
+
+    #[tokio::test]
+    async fn download_dir() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        let executor = LocalCommandExecutor::new(working_dir.path().to_path_buf());
+        let instance = Instance::new_for_test("0".into());
+        let connection = executor.connect(&instance).await.unwrap();
+
+        let logs = executor.instance_dir(&instance).join("logs");
+        std::fs::create_dir_all(logs.join("archive")).unwrap();
+        std::fs::write(logs.join("node.log"), "current").unwrap();
+        std::fs::write(logs.join("archive").join("node.log"), "previous").unwrap();
+
+        let local = destination.path().join("node-0");
+        connection.download_dir(Path::new("logs"), &local).unwrap();
+        let read = |path: PathBuf| std::fs::read_to_string(path).unwrap();
+        assert_eq!(read(local.join("node.log")), "current");
+        assert_eq!(read(local.join("archive").join("node.log")), "previous");
+
+        // Missing directories are reported rather than silently producing an empty copy.
+        let error = connection
+            .download_dir(Path::new("missing"), &destination.path().join("missing"))
+            .unwrap_err();
+        assert!(error.to_string().contains("does not exist"), "{error}");
+        assert!(!destination.path().join("missing").exists());
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let executor = LocalCommandExecutor::new(working_dir.path().to_path_buf())
+            .with_timeout(Duration::from_secs(1));
+        let instance = Instance::new_for_test("0".into());
+
+        // A failing command is not reported as a timeout.
+        let result = executor
+            .execute([instance.clone()], "exit 3", CommandContext::default())
+            .await;
+        assert!(matches!(
+            result,
+            Err(SshError::Local {
+                error: LocalExecutionError::NonZeroExitCode { code: 3, .. },
+                ..
+            })
+        ));
+
+        let start = Instant::now();
+        let result = executor
+            .execute(
+                [instance.clone()],
+                "echo $$ > pid; exec sleep 10",
+                CommandContext::default(),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(SshError::Local {
+                error: LocalExecutionError::Timeout(_),
+                ..
+            })
+        ));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // The process is killed and reaped once the command times out.
+        let pid = std::fs::read_to_string(executor.instance_dir(&instance).join("pid")).unwrap();
+        let proc = PathBuf::from(format!("/proc/{}", pid.trim()));
+        for _ in 0..50 {
+            if !proc.exists() {
+                return;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        panic!("The timed out process {} was not reaped", pid.trim());
+    }
+
+    #[tokio::test]
+    async fn independent_sessions() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let executor = LocalCommandExecutor::new(working_dir.path().to_path_buf());
+        // Prefix the ids with the process id to avoid clashing with other tmux sessions.
+        let instances: Vec<_> = (0..2)
+            .map(|i| Instance::new_for_test(format!("{}-{i}", std::process::id())))
+            .collect();
+        let (first, second) = (instances[0].clone(), instances[1].clone());
+
+        // Distinct instances get distinct session names.
+        let id = "sleep";
+        assert_ne!(session_name(id, &first), session_name(id, &second));
+
+        let context = CommandContext::new().run_background(id.into());
+        executor
+            .execute(instances.clone(), "sleep 60", context)
+            .await
+            .unwrap();
+        executor
+            .wait_for_command(instances.clone(), id, CommandStatus::Running)
+            .await
+            .unwrap();
+
+        // Killing the session of an instance leaves the other one running.
+        executor.kill([first.clone()], id).await.unwrap();
+        executor
+            .wait_for_command([first], id, CommandStatus::Terminated)
+            .await
+            .unwrap();
+        let result = executor
+            .execute(
+                [second.clone()],
+                "(tmux ls || true)",
+                CommandContext::default(),
+            )
+            .await
+            .unwrap();
+        let status = CommandStatus::status(&session_name(id, &second), &result[0].0);
+        assert!(status == CommandStatus::Running);
+
+        executor.kill(instances.clone(), id).await.unwrap();
+        executor
+            .wait_for_command(instances, id, CommandStatus::Terminated)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn environment_variables() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let executor = LocalCommandExecutor::new(working_dir.path().to_path_buf());
+        let instance = Instance::new_for_test("0".into());
+
+        let context = CommandContext::new()
+            .with_env("RUST_LOG", "debug")
+            .with_env("OTHER", "value");
+        let result = executor
+            .execute([instance], "echo $RUST_LOG $OTHER", context)
+            .await
+            .unwrap();
+        assert_eq!(result[0].0.trim(), "debug value");
+    }
+
+    #[tokio::test]
+    async fn streaming_output() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let executor = LocalCommandExecutor::new(working_dir.path().to_path_buf());
+        let instance = Instance::new_for_test("0".into());
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let command = "for i in 1 2 3; do echo line-$i; sleep 1; done; echo done >&2";
+        let handle = tokio::spawn(async move {
+            executor
+                .execute_streaming([instance], command, CommandContext::default(), sender)
+                .await
+        });
+
+        // Each line is delivered while the command is still running.
+        for i in 1..=3 {
+            let (id, line) = receiver.recv().await.unwrap();
+            assert_eq!(id, "0");
+            assert_eq!(line, OutputLine::Stdout(format!("line-{i}")));
+            assert!(!handle.is_finished());
+        }
+        let (_, line) = receiver.recv().await.unwrap();
+        assert_eq!(line, OutputLine::Stderr("done".into()));
+
+        // The complete output is returned once the command exits.
+        let result = handle.await.unwrap().unwrap();
+        let stdout = String::from("line-1\nline-2\nline-3\n");
+        assert_eq!(result, [(stdout, String::from("done\n"))]);
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[test]
+    fn session_status() {
+        let text = "node-10: 1 windows (created Thu Jan  1 00:00:00 2024)\n";
+        assert!(CommandStatus::status("node-10", text) == CommandStatus::Running);
+        assert!(CommandStatus::status("node-1", text) == CommandStatus::Terminated);
+    }
+}
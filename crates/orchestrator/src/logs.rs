@@ -1,9 +1,246 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::cmp::max;
+use std::{
+    cmp::max,
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    fmt::Display,
+    future::Future,
+    path::PathBuf,
+};
 
-use crate::display;
+use mysticeti_core::{commit_sink::first_divergence, types::BlockReference};
+use prettytable::{row, Table};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+
+use crate::{
+    client::Instance,
+    display,
+    error::SshError,
+    executor::{try_join_bounded, Executor},
+};
+
+/// A pattern to look for in the logs.
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct LogPattern {
+    /// The name of the pattern, used to summarize the findings.
+    pub name: String,
+    /// The regex matching the log lines.
+    #[serde_as(as = "DisplayFromStr")]
+    pub regex: Regex,
+    /// Whether a match fails the benchmark run.
+    #[serde(default)]
+    pub critical: bool,
+}
+
+impl LogPattern {
+    pub fn new(name: &str, regex: &str, critical: bool) -> Self {
+        Self {
+            name: name.into(),
+            regex: Regex::new(regex).expect("Invalid log pattern"),
+            critical,
+        }
+    }
+
+    /// The default patterns: panics, error lines, out-of-memory kills, and consensus stalls.
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("panic", r"panicked at", true),
+            Self::new("error", r"\sERROR\s", false),
+            Self::new("oom", r"(?i)out of memory|oom[-_ ]?kill", true),
+            Self::new("stall", r"(?i)consensus stalled", true),
+        ]
+    }
+}
+
+/// A log line matching one of the patterns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogFinding {
+    /// The id of the instance that produced the log.
+    pub instance: String,
+    /// The name of the matched pattern.
+    pub pattern: String,
+    /// Whether the pattern is critical.
+    pub critical: bool,
+    /// The line number (starting at 1) of the match.
+    pub line: usize,
+    /// The matched line.
+    pub text: String,
+}
+
+impl Display for LogFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (instance {}, line {}): {}",
+            self.pattern, self.instance, self.line, self.text
+        )
+    }
+}
+
+/// Scan the logs of each instance for the specified patterns. The findings are sorted by instance
+/// and line number. A line matching several patterns produces a finding for each of them.
+pub fn scan_for_errors(
+    logs: &HashMap<Instance, String>,
+    patterns: &[LogPattern],
+) -> Vec<LogFinding> {
+    let mut findings = Vec::new();
+    for (instance, log) in logs {
+        for (i, line) in log.lines().enumerate() {
+            for pattern in patterns.iter().filter(|x| x.regex.is_match(line)) {
+                findings.push(LogFinding {
+                    instance: instance.id.clone(),
+                    pattern: pattern.name.clone(),
+                    critical: pattern.critical,
+                    line: i + 1,
+                    text: line.trim().to_string(),
+                });
+            }
+        }
+    }
+    findings.sort_by(|a, b| (&a.instance, a.line).cmp(&(&b.instance, b.line)));
+    findings
+}
+
+/// Display the number of findings per pattern, along with the critical findings.
+pub fn print_findings(findings: &[LogFinding]) {
+    if findings.is_empty() {
+        return;
+    }
+
+    let mut counts = BTreeMap::new();
+    for finding in findings {
+        *counts
+            .entry((&finding.pattern, finding.critical))
+            .or_insert(0) += 1;
+    }
+
+    let mut table = Table::new();
+    table.set_format(display::default_table_format());
+    table.set_titles(row![bH2->"Log Findings"]);
+    for ((pattern, critical), count) in counts {
+        let severity = if critical { "critical" } else { "non-critical" };
+        table.add_row(row![b->format!("{pattern} ({severity}):"), count]);
+    }
+
+    display::newline();
+    table.printstd();
+    display::newline();
+
+    for finding in findings.iter().filter(|x| x.critical) {
+        display::error(finding);
+    }
+}
+
+/// A commit on which the commit log of a node disagrees with the one of another node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommitDivergence {
+    /// The index of the diverging node.
+    pub node: usize,
+    /// The index of the node it was compared with.
+    pub reference: usize,
+    /// The position of the first commit on which the two nodes disagree.
+    pub position: usize,
+}
+
+impl Display for CommitDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Node {} diverges from node {} at commit {}",
+            self.node, self.reference, self.position
+        )
+    }
+}
+
+/// Check that the nodes committed a single consistent order, given the sequences parsed from
+/// their commit logs (indexed by node). Nodes may lag behind each other, so every sequence is
+/// compared with the longest one: they agree if one is a prefix of the other.
+pub fn check_commit_sequences(sequences: &[(usize, Vec<BlockReference>)]) -> Vec<CommitDivergence> {
+    let Some((reference, longest)) = sequences.iter().max_by_key(|(_, x)| x.len()) else {
+        return Vec::new();
+    };
+    sequences
+        .iter()
+        .filter_map(|(node, sequence)| {
+            first_divergence(longest, sequence).map(|position| CommitDivergence {
+                node: *node,
+                reference: *reference,
+                position,
+            })
+        })
+        .collect()
+}
+
+/// The logs collected from a set of instances. A failed download does not prevent collecting
+/// the logs of the other instances.
+pub struct CollectedLogs {
+    /// The content of the logs in the order of the instances, or `None` if the download failed.
+    pub contents: Vec<Option<String>>,
+    /// The instances from which the logs could not be downloaded, along with the error.
+    pub failures: Vec<(Instance, SshError)>,
+}
+
+impl CollectedLogs {
+    /// Download the specified log file from each instance, with at most `concurrency` downloads
+    /// in flight at once (to avoid exhausting the ssh connections).
+    pub async fn download<I, P>(executor: &Executor, targets: I, concurrency: usize) -> Self
+    where
+        I: IntoIterator<Item = (Instance, P)>,
+        P: Into<PathBuf>,
+    {
+        Self::collect(targets, concurrency, |instance, path| async move {
+            executor.download(&instance, path).await
+        })
+        .await
+    }
+
+    /// Run the download of each target and collect the results.
+    async fn collect<I, P, F, Fut>(targets: I, concurrency: usize, download: F) -> Self
+    where
+        I: IntoIterator<Item = (Instance, P)>,
+        F: Fn(Instance, P) -> Fut,
+        Fut: Future<Output = Result<String, SshError>>,
+    {
+        let results = try_join_bounded(targets, concurrency, |(instance, path)| {
+            let download = &download;
+            async move {
+                let result = download(instance.clone(), path).await;
+                Ok::<_, Infallible>((instance, result))
+            }
+        })
+        .await
+        .unwrap_or_else(|e| match e {});
+
+        let mut contents = Vec::new();
+        let mut failures = Vec::new();
+        for (instance, result) in results {
+            match result {
+                Ok(content) => contents.push(Some(content)),
+                Err(e) => {
+                    contents.push(None);
+                    failures.push((instance, e));
+                }
+            }
+        }
+        Self { contents, failures }
+    }
+
+    /// Warn about the instances from which the logs could not be downloaded.
+    pub fn print_failures(&self) {
+        for (instance, error) in &self.failures {
+            display::warn(format!(
+                "Failed to download the logs of instance {}: {error}",
+                instance.id
+            ));
+        }
+    }
+}
 
 /// A simple log analyzer counting the number of errors and panics.
 #[derive(Default, PartialEq, Eq)]
@@ -51,6 +288,14 @@ impl LogsAnalyzer {
         self.client_panic = log.contains("panic");
     }
 
+    /// Deduce the highest round reached by a node from its logs. Nodes periodically log the
+    /// rounds they reach as `Reached round <round>`.
+    pub fn highest_round(log: &str) -> Option<u64> {
+        log.lines()
+            .filter_map(|line| line.split_once("Reached round ")?.1.trim().parse().ok())
+            .max()
+    }
+
     /// Print a summary of the errors.
     pub fn print_summary(&self) {
         if self.node_panic {
@@ -66,3 +311,162 @@ impl LogsAnalyzer {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use mysticeti_core::types::BlockReference;
+
+    use super::{check_commit_sequences, scan_for_errors, CollectedLogs, LogPattern, LogsAnalyzer};
+    use crate::{client::Instance, executor::Executor, local_executor::LocalCommandExecutor};
+
+    #[test]
+    fn scan_default_patterns() {
+        let healthy = "\
+            2024-01-01T00:00:00.000000Z  INFO mysticeti_core::core: Reached round 10\n\
+            2024-01-01T00:00:01.000000Z  WARN mysticeti_core::net_sync: Timeout 21\n\
+        ";
+        let faulty = "\
+            2024-01-01T00:00:00.000000Z  INFO mysticeti_core::core: Reached round 10\n\
+            2024-01-01T00:00:01.000000Z ERROR mysticeti_core::network: Connection reset\n\
+            2024-01-01T00:00:02.000000Z  WARN mysticeti_core::core: Consensus stalled at round 10\n\
+            thread 'main' panicked at 'index out of bounds', src/core.rs:12:5\n\
+        ";
+        let killed = "Out of memory: Killed process 1234 (mysticeti)\n";
+        let logs = HashMap::from([
+            (Instance::new_for_test("0".into()), healthy.to_string()),
+            (Instance::new_for_test("1".into()), faulty.to_string()),
+            (Instance::new_for_test("2".into()), killed.to_string()),
+        ]);
+
+        let findings = scan_for_errors(&logs, &LogPattern::defaults());
+        let summary: Vec<_> = findings
+            .iter()
+            .map(|x| (x.instance.as_str(), x.pattern.as_str(), x.line, x.critical))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("1", "error", 2, false),
+                ("1", "stall", 3, true),
+                ("1", "panic", 4, true),
+                ("2", "oom", 1, true),
+            ]
+        );
+        assert_eq!(findings[3].text, killed.trim());
+    }
+
+    #[test]
+    fn scan_custom_patterns() {
+        // Patterns can be overridden through the settings.
+        let patterns: Vec<LogPattern> =
+            serde_json::from_str(r#"[{"name": "timeout", "regex": "Timeout \\d+"}]"#).unwrap();
+        assert!(!patterns[0].critical);
+        assert!(
+            serde_json::from_str::<Vec<LogPattern>>(r#"[{"name": "x", "regex": "("}]"#).is_err()
+        );
+
+        let log = "thread 'main' panicked at 'oops'\nTimeout 21\nTimeout\n";
+        let logs = HashMap::from([(Instance::new_for_test("0".into()), log.to_string())]);
+        let findings = scan_for_errors(&logs, &patterns);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].line, 2);
+        assert_eq!(findings[0].text, "Timeout 21");
+    }
+
+    #[tokio::test]
+    async fn collect_logs() {
+        // Every third instance has no log file.
+        let working_dir = tempfile::tempdir().unwrap();
+        let executor = Executor::local(LocalCommandExecutor::new(working_dir.path().into()));
+        let instances: Vec<_> = (0..20)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+        for (i, instance) in instances.iter().enumerate() {
+            let directory = working_dir.path().join(format!("instance-{}", instance.id));
+            std::fs::create_dir_all(&directory).unwrap();
+            if i % 3 != 0 {
+                std::fs::write(directory.join("node.log"), &instance.id).unwrap();
+            }
+        }
+
+        // The downloads are bounded, and failures do not abort the collection.
+        let (in_flight, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let targets = instances.iter().map(|x| (x.clone(), "node.log"));
+        let logs = CollectedLogs::collect(targets, 4, |instance, path| {
+            let (executor, in_flight, peak) = (&executor, &in_flight, &peak);
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(current, Ordering::SeqCst);
+                let content = executor.download(&instance, path).await;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                content
+            }
+        })
+        .await;
+        assert_eq!(peak.load(Ordering::SeqCst), 4);
+
+        let expected: Vec<_> = instances
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (i % 3 != 0).then(|| x.id.clone()))
+            .collect();
+        assert_eq!(logs.contents, expected);
+        let failed: Vec<_> = logs.failures.iter().map(|(x, _)| x.id.clone()).collect();
+        let expected: Vec<_> = (0..20).step_by(3).map(|i| i.to_string()).collect();
+        assert_eq!(failed, expected);
+
+        // The same holds through the executor.
+        let targets = instances.iter().map(|x| (x.clone(), "node.log"));
+        let logs = CollectedLogs::download(&executor, targets, 4).await;
+        assert_eq!(logs.failures.len(), 7);
+        assert_eq!(logs.contents.iter().flatten().count(), 13);
+    }
+
+    #[test]
+    fn highest_round() {
+        let log = "\
+            2024-01-01T00:00:00.000000Z  INFO mysticeti_core::core: Reached round 10\n\
+            2024-01-01T00:00:01.000000Z  INFO mysticeti_core::core: Reached round 20\n\
+            2024-01-01T00:00:01.500000Z  WARN mysticeti_core::net_sync: Timeout 21\n\
+        ";
+        assert_eq!(LogsAnalyzer::highest_round(log), Some(20));
+        assert_eq!(LogsAnalyzer::highest_round(""), None);
+    }
+
+    #[test]
+    fn commit_sequences() {
+        let leader = |authority, round| BlockReference {
+            authority,
+            round,
+            digest: Default::default(),
+        };
+        let sequence: Vec<_> = (1..=6).map(|round| leader(round % 4, round)).collect();
+
+        // Lagging nodes agree with the others.
+        let sequences = vec![
+            (0, sequence[..4].to_vec()),
+            (1, sequence.clone()),
+            (2, sequence[..5].to_vec()),
+        ];
+        assert!(check_commit_sequences(&sequences).is_empty());
+        assert!(check_commit_sequences(&[]).is_empty());
+
+        // A node committing a different leader diverges.
+        let mut forked = sequence[..3].to_vec();
+        forked.push(leader(1, 4));
+        let sequences = vec![(0, sequence.clone()), (3, forked)];
+        let divergences = check_commit_sequences(&sequences);
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(
+            divergences[0].to_string(),
+            "Node 3 diverges from node 0 at commit 3"
+        );
+    }
+}
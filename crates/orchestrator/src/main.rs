@@ -3,23 +3,37 @@
 
 //! Orchestrator entry point.
 
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
-use benchmark::BenchmarkParameters;
+use benchmark::{BenchmarkParameters, LoadMode, LoadProfile};
 use clap::Parser;
-use client::{aws::AwsClient, local::LocalClient, vultr::VultrClient, ServerProviderClient};
+use client::{
+    aws::AwsClient,
+    digitalocean::DigitalOceanClient,
+    gcp::GcpClient,
+    local::LocalClient,
+    vultr::VultrClient,
+    ServerProviderClient,
+};
+use compare::{Comparison, RunMetrics};
 use eyre::Context;
 use executor::Executor;
+use faults::FaultsType;
 use local_executor::LocalCommandExecutor;
-use measurements::MeasurementsCollection;
+use measurements::{ExportFormat, MeasurementsCollection};
 use orchestrator::Orchestrator;
 use protocol::ProtocolParameters;
+use schema::ConfigFile;
 use settings::{CloudProvider, Settings};
 use ssh::SshConnectionManager;
+use ssh_key::SshPrivateKey;
 use testbed::Testbed;
 
+mod assignment;
 mod benchmark;
+mod checkpoint;
 mod client;
+mod compare;
 mod display;
 mod error;
 mod executor;
@@ -29,9 +43,17 @@ mod logs;
 mod measurements;
 mod monitor;
 mod orchestrator;
+mod placement;
+mod plan;
+mod pool;
 mod protocol;
+mod remote_write;
+mod retry;
+mod schema;
 mod settings;
+mod soak;
 mod ssh;
+mod ssh_key;
 mod testbed;
 
 /// NOTE: Link these types to the correct protocol.
@@ -45,7 +67,8 @@ type ClientParameters = protocol::mysticeti::MysticetiClientParameters;
 #[clap(rename_all = "kebab-case")]
 pub struct Opts {
     /// The path to the settings file. This file contains basic information to deploy testbeds
-    /// and run benchmarks such as the url of the git repo, the commit to deploy, etc.
+    /// and run benchmarks such as the url of the git repo, the commit to deploy, etc. Files with
+    /// a `.json` extension are parsed as JSON, and all others as YAML.
     #[clap(
         long,
         value_name = "FILE",
@@ -54,6 +77,17 @@ pub struct Opts {
     )]
     settings_path: String,
 
+    /// Display what the orchestrator would do without doing it: no instance is created,
+    /// deleted, started, or stopped, and the commands are displayed instead of being run. Dry
+    /// runs are planned from the settings alone, without contacting the cloud provider.
+    #[clap(long, action, default_value_t = false, global = true)]
+    dry_run: bool,
+
+    /// The seed of the randomized benchmark decisions, such as the nodes to crash. Two runs
+    /// with the same seed and settings make the same decisions.
+    #[clap(long, value_name = "INT", default_value_t = 0, global = true)]
+    seed: u64,
+
     /// The type of operation to run.
     #[clap(subcommand)]
     operation: Operation,
@@ -81,6 +115,37 @@ pub enum Operation {
         #[clap(long, value_name = "[INT]", default_value = "200", global = true)]
         loads: Vec<usize>,
 
+        /// The set of load profiles to submit to the system, replacing the constant loads. Use
+        /// `<load>` for a constant load, `ramp:<start>-<end>/<secs>` to ramp the load over the
+        /// specified duration, `burst:<base>-<spike>/<secs>` to spike the load during the first
+        /// tenth of every period, or `step:<secs>=<load>,...` to change the load at given times.
+        #[clap(
+            long,
+            value_name = "[PROFILE]",
+            value_parser = parse_load_profile,
+            conflicts_with = "loads",
+            global = true
+        )]
+        load_profiles: Vec<LoadProfile>,
+
+        /// Whether the clients submit the load regardless of the commit throughput (`open`), or
+        /// start from the load and adjust it until the commit throughput of the system settles
+        /// near the specified target (`closed:<target>`).
+        #[clap(
+            long,
+            value_name = "MODE",
+            value_parser = parse_load_mode,
+            default_value = "open",
+            global = true
+        )]
+        load_mode: LoadMode,
+
+        /// The specs of the instances on which to run the benchmarks, overriding the specs of the
+        /// settings file. Each load is run on the instances of each specs, and instances with
+        /// these specs are deployed if the testbed does not have enough of them.
+        #[clap(long, value_name = "[SPECS]", global = true)]
+        specs: Vec<String>,
+
         /// Whether to skip testbed updates before running benchmarks. This is a dangerous
         /// operation as it may lead to running benchmarks on outdated nodes. It is however
         /// useful when debugging in some specific scenarios.
@@ -92,12 +157,90 @@ pub enum Operation {
         /// useful when debugging in some specific scenarios.
         #[clap(long, action, default_value_t = false, global = true)]
         skip_testbed_configuration: bool,
+
+        /// The number of times to run each benchmark. When greater than one, the summary
+        /// reports the mean and 95% confidence interval of each metric across runs.
+        #[clap(long, value_name = "INT", default_value_t = 1, global = true)]
+        repeat: usize,
+
+        /// The faults to inject in the nodes, overriding the ones of the settings file. Use
+        /// `<faults>` to crash nodes from the beginning, `crash:<count>@<round>` to crash
+        /// `count` random nodes once the benchmark reaches the specified round,
+        /// `latency:<millis>[/<jitter>]` to delay the traffic between nodes,
+        /// `recover:<node>,...@<secs>/<secs>` to crash the specified nodes after the first
+        /// duration and restart them after the second one, `equivocate:<faults>` to make the
+        /// first `faults` nodes propose two conflicting blocks per round,
+        /// `degrade:<node>,...@<millis>ms[/<loss>%][/<rate>mbit]` to degrade the links of the
+        /// specified nodes, or `partition:<node>,...|<node>,...` to split the nodes into groups
+        /// that cannot talk to each other.
+        #[clap(long, value_name = "FAULTS", value_parser = parse_faults, global = true)]
+        faults: Option<FaultsType>,
+
+        /// The time to let the system settle before measuring it (in seconds). The transactions
+        /// finalized during the warm-up are excluded from the latency statistics.
+        #[clap(long, value_name = "INT", default_value_t = 0, global = true)]
+        warmup: u64,
+
+        /// The duration of the measurements following the warm-up (in seconds), overriding the
+        /// benchmark duration of the settings file.
+        #[clap(long, value_name = "INT", conflicts_with = "soak", global = true)]
+        duration: Option<u64>,
+
+        /// Run each benchmark as a soak test of the specified duration (in seconds), overriding
+        /// the benchmark duration of the settings file. The orchestrator takes periodic
+        /// measurement snapshots and flags the drifts of throughput, latency, and memory usage.
+        #[clap(long, value_name = "INT", global = true)]
+        soak: Option<u64>,
+
+        /// The interval between two measurement snapshots of a soak test (in seconds).
+        #[clap(
+            long,
+            value_name = "INT",
+            default_value_t = 60,
+            requires = "soak",
+            global = true
+        )]
+        snapshot_interval: u64,
+
+        /// Skip the benchmarks that a previous (interrupted) run already completed with the exact
+        /// same parameters, and only run the remaining ones.
+        #[clap(long, action, default_value_t = false, global = true)]
+        resume: bool,
     },
-    /// Print a summary of the specified measurements collection.
+    /// Print a summary of the specified measurements collection, or of all the collections of a
+    /// directory merged together (with the throughput and latency at each load).
     Summarize {
-        /// The path to the settings file.
-        #[clap(long, value_name = "FILE")]
+        /// The measurements collection (or directory of collections) to summarize.
+        #[clap(long, value_name = "PATH")]
         path: PathBuf,
+
+        /// The output format. The csv format prints one row per data point and the json
+        /// format prints the whole collection.
+        #[clap(long, value_enum, default_value_t = ExportFormat::Text)]
+        format: ExportFormat,
+    },
+    /// Compare the throughput and latency of two sets of measurements, aligned on their loads.
+    /// Exits with a non-zero code if any metric of the candidate regressed.
+    Compare {
+        /// The measurements collection (or directory of collections) to compare against.
+        #[clap(long, value_name = "PATH")]
+        baseline: PathBuf,
+
+        /// The measurements collection (or directory of collections) to compare.
+        #[clap(long, value_name = "PATH")]
+        candidate: PathBuf,
+
+        /// The relative change (e.g., 0.05 for 5%) beyond which a metric is considered to
+        /// regress.
+        #[clap(long, value_name = "FLOAT", default_value_t = 0.05)]
+        threshold: f64,
+    },
+    /// Print the JSON schema of the specified configuration file. Editors use it to autocomplete
+    /// and validate the settings and parameters files.
+    Schema {
+        /// The configuration file to describe.
+        #[clap(long, value_enum, default_value_t = ConfigFile::Settings)]
+        file: ConfigFile,
     },
 }
 
@@ -119,6 +262,19 @@ pub enum TestbedAction {
         /// setting file.
         #[clap(long)]
         region: Option<String>,
+
+        /// Delete the instances exceeding the specified number (per region) rather than leaving
+        /// them running.
+        #[clap(long)]
+        prune_extra: bool,
+    },
+
+    /// Deploy enough instances to run a committee of the specified size along with the hot
+    /// spares, dedicated load generators, and monitoring instance specified in the setting file.
+    Provision {
+        /// The committee size to provision.
+        #[clap(long)]
+        committee: usize,
     },
 
     /// Start at most the specified number of instances per region on an existing testbed.
@@ -132,7 +288,12 @@ pub enum TestbedAction {
     Stop,
 
     /// Destroy the testbed and terminate all instances.
-    Destroy,
+    Destroy {
+        /// Only terminate the instances of the specified benchmark run (see the `run_id`
+        /// setting).
+        #[clap(long, value_name = "ID")]
+        run: Option<String>,
+    },
 }
 
 #[tokio::main]
@@ -140,9 +301,22 @@ async fn main() -> eyre::Result<()> {
     color_eyre::install()?;
     let opts: Opts = Opts::parse();
 
+    // Printing the schemas does not require (valid) settings.
+    if let Operation::Schema { file } = opts.operation {
+        let schema = serde_json::to_string_pretty(&file.schema())?;
+        println!("{schema}");
+        return Ok(());
+    }
+
     // Load the settings files.
     let settings = Settings::load(&opts.settings_path).wrap_err("Failed to load settings")?;
 
+    // Dry runs only rely on the settings and the command line: they return before creating the
+    // client of the cloud provider, and thus require no credentials.
+    if opts.dry_run {
+        return plan::dry_run(settings, opts).await;
+    }
+
     match &settings.cloud_provider {
         CloudProvider::Aws => {
             // Create the client for the cloud provider.
@@ -161,6 +335,34 @@ async fn main() -> eyre::Result<()> {
             // Execute the command.
             run(settings, client, opts).await
         }
+        CloudProvider::DigitalOcean => {
+            // Create the client for the cloud provider.
+            let token = settings
+                .load_token()
+                .wrap_err("Failed to load cloud provider's token")?;
+            let client = DigitalOceanClient::new(token, settings.clone());
+
+            // Execute the command.
+            run(settings, client, opts).await
+        }
+        CloudProvider::Gcp => {
+            // Create the client for the cloud provider.
+            let project_id = settings
+                .gcp_project_id
+                .clone()
+                .ok_or_else(|| eyre::eyre!("Missing 'gcp_project_id' in the settings file"))?;
+            let service_account_file =
+                settings.gcp_service_account_file.clone().ok_or_else(|| {
+                    eyre::eyre!("Missing 'gcp_service_account_file' in the settings file")
+                })?;
+            let token = GcpClient::access_token(service_account_file)
+                .await
+                .wrap_err("Failed to obtain GCP access token")?;
+            let client = GcpClient::new(token, project_id, settings.clone());
+
+            // Execute the command.
+            run(settings, client, opts).await
+        }
         CloudProvider::Local => {
             // Create the local client for running benchmarks locally.
             let client = LocalClient::new();
@@ -171,6 +373,123 @@ async fn main() -> eyre::Result<()> {
     }
 }
 
+/// Parse the faults to inject from the command line.
+fn parse_faults(faults: &str) -> Result<FaultsType, String> {
+    faults.parse()
+}
+
+fn parse_load_profile(profile: &str) -> Result<LoadProfile, String> {
+    profile.parse()
+}
+
+fn parse_load_mode(mode: &str) -> Result<LoadMode, String> {
+    mode.parse()
+}
+
+/// Override the settings with the benchmark options of the command line.
+fn benchmark_settings(
+    mut settings: Settings,
+    committee: usize,
+    faults: Option<FaultsType>,
+    duration: Option<u64>,
+) -> eyre::Result<Settings> {
+    settings
+        .validate_committee(committee)
+        .wrap_err("Invalid benchmark parameters")?;
+    if let Some(faults) = faults {
+        settings.faults = faults;
+    }
+    if let Some(duration) = duration {
+        settings.benchmark_duration = Duration::from_secs(duration);
+    }
+    Ok(settings)
+}
+
+/// Make the parameters of the benchmarks specified on the command line.
+fn benchmark_parameters(
+    settings: &Settings,
+    committee: usize,
+    loads: Vec<usize>,
+    load_profiles: Vec<LoadProfile>,
+    load_mode: LoadMode,
+    specs: Vec<String>,
+    warmup: u64,
+) -> eyre::Result<Vec<BenchmarkParameters>> {
+    let node_parameters = match &settings.node_parameters_path {
+        Some(path) => NodeParameters::load(path).wrap_err("Failed to load node's parameters")?,
+        None => NodeParameters::default(),
+    };
+    let client_parameters = match &settings.client_parameters_path {
+        Some(path) => {
+            ClientParameters::load(path).wrap_err("Failed to load client's parameters")?
+        }
+        None => ClientParameters::default(),
+    };
+
+    let mut set_of_benchmark_parameters = if load_profiles.is_empty() {
+        BenchmarkParameters::new_from_loads(
+            settings.clone(),
+            node_parameters,
+            client_parameters,
+            committee,
+            loads,
+            specs,
+        )
+    } else {
+        BenchmarkParameters::new_from_profiles(
+            settings.clone(),
+            node_parameters,
+            client_parameters,
+            committee,
+            load_profiles,
+            specs,
+        )
+    };
+    for parameters in &mut set_of_benchmark_parameters {
+        parameters.load_mode = load_mode;
+        parameters.warmup = Duration::from_secs(warmup);
+    }
+    Ok(set_of_benchmark_parameters)
+}
+
+/// Print a summary of the specified measurements collection, or of all the collections of the
+/// specified directory merged together.
+fn summarize(path: PathBuf, format: ExportFormat) -> eyre::Result<()> {
+    let measurements = if path.is_dir() {
+        let collections = MeasurementsCollection::load_dir(&path)?;
+        eyre::ensure!(
+            !collections.is_empty(),
+            "No measurements found in {}",
+            path.display()
+        );
+        MeasurementsCollection::merge(collections)
+    } else {
+        MeasurementsCollection::load(path)?
+    };
+    match format {
+        ExportFormat::Text => measurements.display_summary(),
+        _ => measurements
+            .export(format, std::io::stdout())
+            .wrap_err("Failed to export measurements")?,
+    }
+    Ok(())
+}
+
+/// Compare the candidate measurements against the baseline ones. Exits with a non-zero code if
+/// any metric regressed.
+fn compare(baseline: PathBuf, candidate: PathBuf, threshold: f64) -> eyre::Result<()> {
+    let baseline =
+        RunMetrics::load(baseline).wrap_err("Failed to load the baseline measurements")?;
+    let candidate =
+        RunMetrics::load(candidate).wrap_err("Failed to load the candidate measurements")?;
+    let comparison = Comparison::new(&baseline, &candidate, threshold);
+    comparison.display();
+    if comparison.exit_code() != 0 {
+        std::process::exit(comparison.exit_code());
+    }
+    Ok(())
+}
+
 async fn run<C: ServerProviderClient>(
     settings: Settings,
     client: C,
@@ -187,11 +506,26 @@ async fn run<C: ServerProviderClient>(
             TestbedAction::Status => testbed.status(),
 
             // Deploy the specified number of instances on the testbed.
-            TestbedAction::Deploy { instances, region } => testbed
-                .deploy(instances, region)
+            TestbedAction::Deploy {
+                instances,
+                region,
+                prune_extra,
+            } => testbed
+                .deploy(instances, region, prune_extra)
                 .await
                 .wrap_err("Failed to deploy testbed")?,
 
+            // Deploy the instances required to run the specified committee.
+            TestbedAction::Provision { committee } => {
+                settings
+                    .validate_committee(committee)
+                    .wrap_err("Failed to provision testbed")?;
+                testbed
+                    .provision(committee)
+                    .await
+                    .wrap_err("Failed to provision testbed")?
+            }
+
             // Start the specified number of instances on an existing testbed.
             TestbedAction::Start { instances } => testbed
                 .start(instances)
@@ -201,99 +535,151 @@ async fn run<C: ServerProviderClient>(
             // Stop an existing testbed.
             TestbedAction::Stop => testbed.stop().await.wrap_err("Failed to stop testbed")?,
 
-            // Destroy the testbed and terminal all instances.
-            TestbedAction::Destroy => testbed
-                .destroy()
-                .await
-                .wrap_err("Failed to destroy testbed")?,
+            // Destroy the testbed and terminal all instances (or only those of a run).
+            TestbedAction::Destroy { run } => match run {
+                Some(run) => testbed.destroy_run(&run).await,
+                None => testbed.destroy().await,
+            }
+            .wrap_err("Failed to destroy testbed")?,
         },
 
         // Run benchmarks.
         Operation::Benchmark {
             committee,
             loads,
+            load_profiles,
+            load_mode,
+            specs,
             skip_testbed_update,
             skip_testbed_configuration,
+            repeat,
+            faults,
+            warmup,
+            duration,
+            soak,
+            snapshot_interval,
+            resume,
         } => {
+            let settings = benchmark_settings(settings, committee, faults, duration.or(soak))?;
+
             // Create the appropriate executor based on cloud provider.
             let executor = match &settings.cloud_provider {
                 CloudProvider::Local => {
                     // For local execution, use direct command execution
                     let working_dir = settings.working_dir.clone();
-                    Executor::local(LocalCommandExecutor::new(working_dir))
+                    let local_executor = LocalCommandExecutor::new(working_dir)
+                        .with_timeout(settings.local_timeout)
+                        .with_retry_policy(settings.retry_policy.clone());
+                    Executor::local(local_executor)
                 }
                 _ => {
                     // For cloud providers, use SSH
                     let username = testbed.username();
-                    let private_key_file = settings.ssh_private_key_file.clone();
-                    let ssh_manager = SshConnectionManager::new(username.into(), private_key_file)
+                    let private_key = SshPrivateKey::load(
+                        &settings.ssh_private_key_file,
+                        settings.ssh_private_key_passphrase.clone(),
+                    )
+                    .wrap_err("Failed to load the ssh private key")?;
+                    let ssh_manager = SshConnectionManager::new(username.into(), private_key)
                         .with_timeout(settings.ssh_timeout)
-                        .with_retries(settings.ssh_retries);
+                        .with_jump_host(settings.ssh_jump_host)
+                        .with_retries(settings.ssh_retries)
+                        .with_pool(
+                            settings.ssh_pool_idle_timeout,
+                            settings.ssh_pool_max_per_host,
+                        )
+                        .with_retry_policy(settings.retry_policy.clone());
                     Executor::ssh(ssh_manager)
                 }
             };
 
             let mut instances = testbed.instances();
 
-            // For local execution, auto-create instances if none exist
-            if instances.is_empty() && matches!(settings.cloud_provider, CloudProvider::Local) {
-                display::action("No instances found, creating local instances automatically");
-                let needed_instances = committee
-                    + settings.dedicated_clients
-                    + if settings.monitoring { 1 } else { 0 };
-                // Create a few extra instances to have some buffer
-                let instances_to_create = (needed_instances + 2).max(4);
+            // For local execution, reconcile the testbed with the instances needed to run the
+            // committee (reusing the existing ones rather than creating new ones each run).
+            if matches!(settings.cloud_provider, CloudProvider::Local) {
+                let regions = settings.regions.len().max(1);
+                let target = settings.required_instances(committee).div_ceil(regions);
                 testbed
-                    .deploy(instances_to_create, None)
+                    .ensure(target, None)
                     .await
-                    .wrap_err("Failed to auto-create local instances")?;
+                    .wrap_err("Failed to create local instances")?;
                 instances = testbed.instances();
-                display::done();
             }
 
+            // Deploy the instances with the specs overriding the ones of the settings (if needed).
+            for specs in &specs {
+                testbed
+                    .provision_with_specs(committee, specs)
+                    .await
+                    .wrap_err("Failed to provision testbed")?;
+                let provisioned: Vec<_> = testbed
+                    .instances_with_specs(specs)
+                    .into_iter()
+                    .filter(|x| !instances.contains(x))
+                    .collect();
+                instances.extend(provisioned);
+            }
+
+            // Freshly deployed instances may still be booting their ssh daemon.
+            let active: Vec<_> = instances
+                .iter()
+                .filter(|x| x.is_active())
+                .cloned()
+                .collect();
+            testbed
+                .wait_until_reachable(&active, &executor, settings.reachability_timeout)
+                .await
+                .wrap_err("Testbed is not ready")?;
+
             let setup_commands = testbed
                 .setup_commands()
                 .await
                 .wrap_err("Failed to load testbed setup commands")?;
 
             let protocol_commands = Protocol::new(&settings);
-            let node_parameters = match &settings.node_parameters_path {
-                Some(path) => {
-                    NodeParameters::load(path).wrap_err("Failed to load node's parameters")?
-                }
-                None => NodeParameters::default(),
-            };
-            let client_parameters = match &settings.client_parameters_path {
-                Some(path) => {
-                    ClientParameters::load(path).wrap_err("Failed to load client's parameters")?
-                }
-                None => ClientParameters::default(),
-            };
-
-            let set_of_benchmark_parameters = BenchmarkParameters::new_from_loads(
-                settings.clone(),
-                node_parameters,
-                client_parameters,
+            let set_of_benchmark_parameters = benchmark_parameters(
+                &settings,
                 committee,
                 loads,
-            );
+                load_profiles,
+                load_mode,
+                specs,
+                warmup,
+            )?;
 
-            Orchestrator::new(
+            let orchestrator = Orchestrator::new(
                 settings,
                 instances,
                 setup_commands,
                 protocol_commands,
                 executor,
-            )
-            .skip_testbed_update(skip_testbed_update)
-            .skip_testbed_configuration(skip_testbed_configuration)
-            .run_benchmarks(set_of_benchmark_parameters)
-            .await
-            .wrap_err("Failed to run benchmarks")?;
+            );
+
+            orchestrator
+                .skip_testbed_update(skip_testbed_update)
+                .skip_testbed_configuration(skip_testbed_configuration)
+                .with_repeat(repeat)
+                .with_soak_snapshots(soak.map(|_| Duration::from_secs(snapshot_interval)))
+                .with_resume(resume)
+                .with_seed(opts.seed)
+                .run_benchmarks(set_of_benchmark_parameters)
+                .await
+                .wrap_err("Failed to run benchmarks")?;
         }
 
         // Print a summary of the specified measurements collection.
-        Operation::Summarize { path } => MeasurementsCollection::load(path)?.display_summary(),
+        Operation::Summarize { path, format } => summarize(path, format)?,
+
+        // Compare two sets of measurements.
+        Operation::Compare {
+            baseline,
+            candidate,
+            threshold,
+        } => compare(baseline, candidate, threshold)?,
+
+        // Handled before loading the settings.
+        Operation::Schema { .. } => unreachable!("Schemas are printed without settings"),
     }
     Ok(())
 }
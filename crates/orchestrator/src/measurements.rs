@@ -2,19 +2,224 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::Debug,
     fs,
-    io::BufRead,
+    io::{self, BufRead, Write},
     path::{Path, PathBuf},
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use prettytable::{row, Table};
 use prometheus_parse::Scrape;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-use crate::{benchmark::BenchmarkParameters, display, protocol::ProtocolMetrics};
+use crate::{
+    benchmark::BenchmarkParameters,
+    display,
+    error::MonitorResult,
+    protocol::ProtocolMetrics,
+    remote_write::{self, TimeSeries},
+};
+
+/// The output format of an exported collection of measurements.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A human-readable summary.
+    #[default]
+    Text,
+    /// The summary of each workload, one row per workload.
+    Csv,
+    /// The summary of each workload, as a json document.
+    Json,
+}
+
+/// The summary of a collection of measurements, exported to feed dashboards.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MeasurementsSummary {
+    /// The committee size.
+    pub nodes: usize,
+    /// The load submitted to the system (tx/s).
+    pub load: usize,
+    /// The duration of the benchmark (in seconds).
+    pub duration_s: u64,
+    /// The summary of each workload, sorted by workload.
+    pub workloads: Vec<WorkloadSummary>,
+}
+
+/// The throughput and latency of a workload. The latency percentiles are `None` if no
+/// transaction was recorded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WorkloadSummary {
+    pub workload: String,
+    pub tps: u64,
+    pub latency_avg_ms: f64,
+    pub latency_p50_ms: Option<f64>,
+    pub latency_p95_ms: Option<f64>,
+    pub latency_p99_ms: Option<f64>,
+}
+
+/// The average latency of each stage of the commit pipeline, to locate the bottleneck of the
+/// end-to-end latency.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+pub struct LatencyBreakdown {
+    /// The time between the proposal of a block and its reception by a quorum of nodes.
+    pub dissemination: Option<Duration>,
+    /// The time between the proposal of a leader block and its commit.
+    pub commit: Option<Duration>,
+}
+
+/// The progress of a node as reported by its own metrics, to cross-check the throughput
+/// measured by the load generators.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct NodeMetrics {
+    /// Duration since the beginning of the benchmark.
+    pub timestamp: Duration,
+    /// The number of leaders committed by the node.
+    pub committed_leaders: u64,
+    /// The number of leaders the node could not decide yet.
+    pub undecided_leaders: u64,
+    /// The number of transactions submitted to the node.
+    pub submitted_transactions: u64,
+    /// The round of the last block proposed by the node.
+    pub round: u64,
+}
+
+/// The relative difference between the throughput measured by the load generators and the rate
+/// at which the nodes receive transactions above which the run is reported as saturated.
+pub const THROUGHPUT_DIVERGENCE_THRESHOLD: f64 = 0.2;
+
+/// Nodes crashing or recovering during the benchmark, to correlate latency spikes with faults.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FaultEvent {
+    /// The time of the event since the beginning of the benchmark.
+    pub elapsed: Duration,
+    /// The indices of the nodes crashed by the event.
+    pub crashed: Vec<usize>,
+    /// The indices of the nodes recovered by the event.
+    pub recovered: Vec<usize>,
+}
+
+/// The throughput and latency measured while the load profile targeted a given load.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoadPoint {
+    /// The load (tx/s) targeted by the load profile.
+    pub target_load: usize,
+    /// The throughput (tx/s) summed across nodes and workloads.
+    pub tps: f64,
+    /// The average latency of the transactions finalized at this load.
+    pub latency: Duration,
+}
+
+/// The throughput and latency of a run merged into a collection of measurements.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct MergedRun {
+    /// The load (tx/s) submitted during the run.
+    pub load: usize,
+    /// The throughput (tx/s) summed across workloads.
+    pub tps: u64,
+    /// The average latency across workloads.
+    pub latency: Duration,
+}
+
+/// The throughput and latency of the runs merged at a given load, averaged across runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SaturationPoint {
+    /// The load (tx/s) submitted during the runs.
+    pub load: usize,
+    /// The number of runs at this load.
+    pub runs: usize,
+    /// The average throughput (tx/s).
+    pub tps: f64,
+    /// The sample variance of the throughput across runs (zero for a single run).
+    pub tps_variance: f64,
+    /// The average latency (in ms).
+    pub latency_ms: f64,
+    /// The sample variance of the latency across runs (in ms²).
+    pub latency_variance: f64,
+}
+
+/// The mean and sample variance of the provided samples. The variance is zero if there are
+/// fewer than two samples.
+fn mean_and_variance(samples: &[f64]) -> (f64, f64) {
+    let n = samples.len();
+    if n == 0 {
+        return (0.0, 0.0);
+    }
+    let mean = samples.iter().sum::<f64>() / n as f64;
+    if n == 1 {
+        return (mean, 0.0);
+    }
+    let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+    (mean, variance)
+}
+
+/// The latency percentiles of the finalized transactions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LatencyDistribution {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub p999: Duration,
+}
+
+/// A uniform random sample of bounded size of the latencies of the finalized transactions
+/// (reservoir sampling). It allows computing percentiles without retaining every latency.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct LatencySamples {
+    /// The maximum number of samples retained.
+    capacity: usize,
+    /// The number of latencies offered to the reservoir.
+    seen: u64,
+    /// The retained latencies.
+    samples: Vec<Duration>,
+}
+
+impl LatencySamples {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Offer a latency to the reservoir. Every latency offered so far has the same probability
+    /// of being retained.
+    pub fn add(&mut self, latency: Duration) {
+        self.seen += 1;
+        if self.samples.len() < self.capacity {
+            self.samples.push(latency);
+        } else {
+            let index = rand::thread_rng().gen_range(0..self.seen);
+            if let Some(sample) = self.samples.get_mut(index as usize) {
+                *sample = latency;
+            }
+        }
+    }
+
+    /// Compute the specified percentiles (expressed in [0, 100]) using the nearest-rank method.
+    /// Returns `None` if no latency was retained.
+    pub fn percentiles(&self, ps: &[f64]) -> Option<Vec<Duration>> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        let n = sorted.len() as f64;
+        let percentiles = ps
+            .iter()
+            .map(|p| {
+                // Subtract a small epsilon to avoid rounding up exact ranks (e.g., p99.9).
+                let rank = (p.clamp(0.0, 100.0) / 100.0 * n - 1e-9).ceil() as usize;
+                sorted[rank.clamp(1, sorted.len()) - 1]
+            })
+            .collect();
+        Some(percentiles)
+    }
+}
 
 /// The identifier of prometheus latency buckets.
 type BucketId = String;
@@ -34,9 +239,71 @@ pub struct Measurement {
     count: usize,
     /// Sum of the squares of the latencies of all finalized transactions
     squared_sum: f64,
+    /// The load (tx/s) targeted by the load profile of the benchmark at the time of the
+    /// measurement, if the benchmark follows a load profile.
+    #[serde(default)]
+    target_load: Option<usize>,
 }
 
 impl Measurement {
+    /// Estimate the latencies of the transactions finalized since the previous measurement. The
+    /// latencies are spread evenly within the bucket of each transaction.
+    fn new_latencies(&self, previous: Option<&Self>) -> Vec<Duration> {
+        let mut buckets: Vec<(f64, usize, usize)> = self
+            .buckets
+            .iter()
+            .filter_map(|(id, count)| {
+                let before = previous
+                    .and_then(|x| x.buckets.get(id))
+                    .copied()
+                    .unwrap_or_default();
+                Some((id.parse::<f64>().ok()?, *count, before))
+            })
+            .collect();
+        buckets.sort_by(|(a, ..), (b, ..)| a.total_cmp(b));
+
+        let mut latencies = Vec::new();
+        let (mut lower_bound, mut lower_count, mut lower_before) = (0.0, 0, 0);
+        for (upper_bound, count, before) in buckets {
+            let new = count
+                .saturating_sub(lower_count)
+                .saturating_sub(before.saturating_sub(lower_before));
+            for i in 0..new {
+                let latency = if upper_bound.is_infinite() {
+                    // Nothing is known about the latencies above the last finite bucket.
+                    lower_bound
+                } else {
+                    let fraction = (i as f64 + 0.5) / new as f64;
+                    lower_bound + (upper_bound - lower_bound) * fraction
+                };
+                latencies.push(Duration::from_secs_f64(latency));
+            }
+            (lower_bound, lower_count, lower_before) = (upper_bound, count, before);
+        }
+        latencies
+    }
+
+    /// The difference between this (cumulative) measurement and an earlier one, that is, the
+    /// transactions finalized in between.
+    fn since(&self, earlier: &Self) -> Self {
+        let buckets = self
+            .buckets
+            .iter()
+            .map(|(id, count)| {
+                let before = earlier.buckets.get(id).copied().unwrap_or_default();
+                (id.clone(), count.saturating_sub(before))
+            })
+            .collect();
+        Self {
+            timestamp: self.timestamp,
+            buckets,
+            sum: self.sum.saturating_sub(earlier.sum),
+            count: self.count.saturating_sub(earlier.count),
+            squared_sum: (self.squared_sum - earlier.squared_sum).max(0.0),
+            target_load: self.target_load,
+        }
+    }
+
     /// Make new measurements from the text exposed by prometheus.
     /// Every measurement is identified by a unique label.
     pub fn from_prometheus<M: ProtocolMetrics>(text: &str) -> HashMap<Label, Self> {
@@ -109,6 +376,113 @@ impl Measurement {
         measurements
     }
 
+    /// Extract the time between the start of the node and its first commit from the text
+    /// exposed by prometheus. Returns `None` if the node did not commit yet.
+    pub fn time_to_first_commit<M: ProtocolMetrics>(text: &str) -> Option<Duration> {
+        let br = std::io::BufReader::new(text.as_bytes());
+        let parsed = Scrape::parse(br.lines()).unwrap();
+
+        parsed
+            .samples
+            .iter()
+            .find(|x| x.metric == M::TIME_TO_FIRST_COMMIT)
+            .and_then(|x| match x.value {
+                prometheus_parse::Value::Gauge(value) | prometheus_parse::Value::Untyped(value) => {
+                    (value > 0.0).then(|| Duration::from_secs_f64(value))
+                }
+                _ => panic!("Unexpected scraped value"),
+            })
+    }
+
+    /// Extract the memory used by the node from the text exposed by prometheus (in bytes).
+    /// Returns `None` if the metric is not exposed.
+    pub fn memory_usage<M: ProtocolMetrics>(text: &str) -> Option<u64> {
+        let br = std::io::BufReader::new(text.as_bytes());
+        let parsed = Scrape::parse(br.lines()).unwrap();
+
+        parsed
+            .samples
+            .iter()
+            .find(|x| x.metric == M::MEMORY_USAGE)
+            .map(|x| match x.value {
+                prometheus_parse::Value::Gauge(value) | prometheus_parse::Value::Untyped(value) => {
+                    value as u64
+                }
+                _ => panic!("Unexpected scraped value"),
+            })
+    }
+
+    /// Extract the average dissemination and commit latency of the node from the text exposed by
+    /// prometheus. Returns `None` if the node did not report any of them yet.
+    pub fn latency_breakdown<M: ProtocolMetrics>(text: &str) -> Option<LatencyBreakdown> {
+        let br = std::io::BufReader::new(text.as_bytes());
+        let parsed = Scrape::parse(br.lines()).unwrap();
+
+        let value = |metric: String| {
+            parsed
+                .samples
+                .iter()
+                .find(|x| x.metric == metric)
+                .map(|x| match x.value {
+                    prometheus_parse::Value::Counter(value)
+                    | prometheus_parse::Value::Untyped(value) => value,
+                    _ => panic!("Unexpected scraped value"),
+                })
+                .unwrap_or_default()
+        };
+        let average = |histogram: &str| {
+            let count = value(format!("{histogram}_count"));
+            let sum = value(format!("{histogram}_sum"));
+            (count > 0.0).then(|| Duration::from_secs_f64(sum / count))
+        };
+
+        let breakdown = LatencyBreakdown {
+            dissemination: average(M::DISSEMINATION_LATENCY),
+            commit: average(M::COMMIT_LATENCY),
+        };
+        (breakdown != LatencyBreakdown::default()).then_some(breakdown)
+    }
+
+    /// Extract the progress of the node (committed leaders, undecided leaders, submitted
+    /// transactions, and current round) from the text exposed by prometheus. Returns `None` if
+    /// the node does not expose any of them.
+    pub fn node_metrics<M: ProtocolMetrics>(text: &str) -> Option<NodeMetrics> {
+        let br = std::io::BufReader::new(text.as_bytes());
+        let parsed = Scrape::parse(br.lines()).unwrap();
+
+        let value = |sample: &prometheus_parse::Sample| match sample.value {
+            prometheus_parse::Value::Counter(value)
+            | prometheus_parse::Value::Gauge(value)
+            | prometheus_parse::Value::Untyped(value) => value as u64,
+            _ => panic!("Unexpected scraped value"),
+        };
+
+        let mut metrics = NodeMetrics::default();
+        let mut reported = false;
+        for sample in &parsed.samples {
+            match &sample.metric {
+                // Skipped leaders are counted along with the committed ones.
+                x if x == M::COMMITTED_LEADERS => {
+                    if sample.labels.values().any(|x| x.ends_with("commit")) {
+                        metrics.committed_leaders += value(sample);
+                    }
+                }
+                x if x == M::UNDECIDED_LEADERS => metrics.undecided_leaders = value(sample),
+                x if x == M::SUBMITTED_TRANSACTIONS => {
+                    metrics.submitted_transactions = value(sample)
+                }
+                x if x == M::CURRENT_ROUND => metrics.round = value(sample),
+                x if x == M::BENCHMARK_DURATION => {
+                    metrics.timestamp = Duration::from_secs(value(sample));
+                    continue;
+                }
+                _ => continue,
+            }
+            reported = true;
+        }
+        reported.then_some(metrics)
+    }
+
     /// Compute the average latency.
     pub fn average_latency(&self) -> Duration {
         self.sum.checked_div(self.count as u32).unwrap_or_default()
@@ -149,6 +523,25 @@ pub struct MeasurementsCollection {
     pub parameters: BenchmarkParameters,
     /// The data collected by each scraper.
     pub data: HashMap<Label, HashMap<ScraperId, Vec<Measurement>>>,
+    /// The time between the start of each node and its first commit.
+    #[serde(default)]
+    pub time_to_first_commit: BTreeMap<ScraperId, Duration>,
+    /// The average dissemination and commit latency reported by each node.
+    #[serde(default)]
+    pub latency_breakdown: BTreeMap<ScraperId, LatencyBreakdown>,
+    /// A bounded sample of the latencies of the transactions finalized during the run.
+    #[serde(default)]
+    pub latency_samples: LatencySamples,
+    /// The nodes crashed and recovered during the run.
+    #[serde(default)]
+    pub fault_events: Vec<FaultEvent>,
+    /// The progress reported by each node over time.
+    #[serde(default)]
+    pub node_metrics: BTreeMap<ScraperId, Vec<NodeMetrics>>,
+    /// The runs merged into this collection (see [`Self::merge`]), empty if the collection
+    /// holds a single run.
+    #[serde(default)]
+    pub merged_runs: Vec<MergedRun>,
 }
 
 impl MeasurementsCollection {
@@ -157,9 +550,16 @@ impl MeasurementsCollection {
         // Remove the access token from the parameters.
         parameters.settings.repository.remove_access_token();
 
+        let latency_samples = LatencySamples::new(parameters.settings.latency_samples);
         Self {
             parameters,
             data: HashMap::new(),
+            time_to_first_commit: BTreeMap::new(),
+            latency_breakdown: BTreeMap::new(),
+            latency_samples,
+            fault_events: Vec::new(),
+            node_metrics: BTreeMap::new(),
+            merged_runs: Vec::new(),
         }
     }
 
@@ -170,14 +570,258 @@ impl MeasurementsCollection {
         Ok(measurements)
     }
 
-    /// Add a new measurement to the collection.
-    pub fn add(&mut self, scraper_id: ScraperId, label: String, measurement: Measurement) {
+    /// Load all the collections of measurements (`measurements-*.json`) of the specified
+    /// directory, sorted by file name. The results of repeated runs are skipped.
+    pub fn load_dir<P: AsRef<Path>>(path: P) -> Result<Vec<Self>, std::io::Error> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            let name = file
+                .file_name()
+                .and_then(|x| x.to_str())
+                .unwrap_or_default();
+            if name.starts_with("measurements-")
+                && name.ends_with(".json")
+                && !name.ends_with("-repeated.json")
+            {
+                files.push(file);
+            }
+        }
+        files.sort();
+        files.into_iter().map(Self::load).collect()
+    }
+
+    /// Merge the measurements of several runs (typically of different loads) into a single
+    /// collection. The scrapers of each run are renumbered so that their series remain distinct,
+    /// and the throughput and latency of each run are recorded to build the saturation curve
+    /// (see [`Self::saturation_curve`]). The merged collection keeps the parameters of the first
+    /// run. Panics if no collection is provided.
+    pub fn merge(collections: Vec<MeasurementsCollection>) -> MeasurementsCollection {
+        let parameters = collections
+            .first()
+            .expect("No measurements to merge")
+            .parameters
+            .clone();
+        let mut merged = Self::new(parameters);
+        for collection in collections {
+            if collection.merged_runs.is_empty() {
+                merged.merged_runs.push(collection.merged_run());
+            } else {
+                merged.merged_runs.extend(&collection.merged_runs);
+            }
+
+            let offset = merged.next_scraper_id();
+            for (label, data) in collection.data {
+                let series = merged.data.entry(label).or_default();
+                series.extend(data.into_iter().map(|(id, x)| (offset + id, x)));
+            }
+            merged.time_to_first_commit.extend(
+                collection
+                    .time_to_first_commit
+                    .into_iter()
+                    .map(|(id, x)| (offset + id, x)),
+            );
+            merged.latency_breakdown.extend(
+                collection
+                    .latency_breakdown
+                    .into_iter()
+                    .map(|(id, x)| (offset + id, x)),
+            );
+            merged.node_metrics.extend(
+                collection
+                    .node_metrics
+                    .into_iter()
+                    .map(|(id, x)| (offset + id, x)),
+            );
+            merged.fault_events.extend(collection.fault_events);
+            for latency in collection.latency_samples.samples {
+                merged.latency_samples.add(latency);
+            }
+        }
+        merged
+    }
+
+    /// The lowest scraper id not used by any measurement of the collection.
+    fn next_scraper_id(&self) -> ScraperId {
         self.data
+            .values()
+            .flat_map(|data| data.keys())
+            .chain(self.time_to_first_commit.keys())
+            .chain(self.latency_breakdown.keys())
+            .chain(self.node_metrics.keys())
+            .max()
+            .map_or(0, |id| id + 1)
+    }
+
+    /// The throughput and latency of the (single) run of this collection.
+    fn merged_run(&self) -> MergedRun {
+        let labels: Vec<_> = self.labels().collect();
+        let latency = labels
+            .iter()
+            .map(|label| self.aggregate_average_latency(label))
+            .sum::<Duration>()
+            .checked_div(labels.len() as u32)
+            .unwrap_or_default();
+        MergedRun {
+            load: self.parameters.load,
+            tps: labels.iter().map(|label| self.aggregate_tps(label)).sum(),
+            latency,
+        }
+    }
+
+    /// The throughput and latency of the merged runs at each load, sorted by load. The runs
+    /// sharing the same load are averaged. Returns an empty curve if the collection does not
+    /// merge several runs.
+    pub fn saturation_curve(&self) -> Vec<SaturationPoint> {
+        let mut runs: BTreeMap<usize, Vec<&MergedRun>> = BTreeMap::new();
+        for run in &self.merged_runs {
+            runs.entry(run.load).or_default().push(run);
+        }
+        runs.into_iter()
+            .map(|(load, runs)| {
+                let tps: Vec<_> = runs.iter().map(|x| x.tps as f64).collect();
+                let latencies: Vec<_> = runs
+                    .iter()
+                    .map(|x| x.latency.as_micros() as f64 / 1_000.0)
+                    .collect();
+                let (tps, tps_variance) = mean_and_variance(&tps);
+                let (latency_ms, latency_variance) = mean_and_variance(&latencies);
+                SaturationPoint {
+                    load,
+                    runs: runs.len(),
+                    tps,
+                    tps_variance,
+                    latency_ms,
+                    latency_variance,
+                }
+            })
+            .collect()
+    }
+
+    /// Add a new measurement to the collection, tagged with the load targeted by the load
+    /// profile at the time of the measurement (if any). The latencies of the transactions
+    /// finalized during the warm-up are not sampled.
+    pub fn add(&mut self, scraper_id: ScraperId, label: String, mut measurement: Measurement) {
+        if let Some(profile) = &self.parameters.load_profile {
+            measurement.target_load = Some(profile.load_at(measurement.timestamp));
+        }
+        let series = self
+            .data
             .entry(label)
             .or_default()
+            .entry(scraper_id)
+            .or_default();
+        if measurement.timestamp > self.parameters.warmup {
+            for latency in measurement.new_latencies(series.last()) {
+                self.latency_samples.add(latency);
+            }
+        }
+        series.push(measurement);
+    }
+
+    /// The transactions of a series of measurements finalized after the warm-up, that is, the
+    /// difference between its last measurement and its last measurement taken during the
+    /// warm-up (if any).
+    fn steady_state(&self, series: &[Measurement]) -> Option<Measurement> {
+        let last = series.last()?;
+        let warmup = self.parameters.warmup;
+        match series.iter().rev().find(|x| x.timestamp <= warmup) {
+            Some(baseline) if !warmup.is_zero() => Some(last.since(baseline)),
+            _ => Some(last.clone()),
+        }
+    }
+
+    /// Record the time to first commit of a node. Only the first report of each node is kept.
+    pub fn add_time_to_first_commit(&mut self, scraper_id: ScraperId, duration: Duration) {
+        self.time_to_first_commit
+            .entry(scraper_id)
+            .or_insert(duration);
+    }
+
+    /// Record the latency breakdown of a node. The nodes report averages since their start, so
+    /// the latest report of each node replaces the previous one.
+    pub fn add_latency_breakdown(&mut self, scraper_id: ScraperId, breakdown: LatencyBreakdown) {
+        self.latency_breakdown.insert(scraper_id, breakdown);
+    }
+
+    /// Record nodes crashing or recovering during the benchmark.
+    pub fn add_fault_event(&mut self, event: FaultEvent) {
+        self.fault_events.push(event);
+    }
+
+    /// Record the progress reported by a node.
+    pub fn add_node_metrics(&mut self, scraper_id: ScraperId, metrics: NodeMetrics) {
+        self.node_metrics
             .entry(scraper_id)
             .or_default()
-            .push(measurement);
+            .push(metrics);
+    }
+
+    /// The rate (per second) at which the specified counter of each node increased between its
+    /// first and last report. Nodes that reported only once are ignored.
+    fn node_rates(&self, counter: fn(&NodeMetrics) -> u64) -> Vec<f64> {
+        self.node_metrics
+            .values()
+            .filter_map(|series| {
+                let (first, last) = (series.first()?, series.last()?);
+                let elapsed = last.timestamp.checked_sub(first.timestamp)?.as_secs_f64();
+                let increase = counter(last).saturating_sub(counter(first));
+                (elapsed > 0.0).then(|| increase as f64 / elapsed)
+            })
+            .collect()
+    }
+
+    /// The rate at which the nodes commit leaders (leaders/s), averaged across nodes.
+    pub fn node_commit_rate(&self) -> f64 {
+        let rates = self.node_rates(|x| x.committed_leaders);
+        if rates.is_empty() {
+            return 0.0;
+        }
+        rates.iter().sum::<f64>() / rates.len() as f64
+    }
+
+    /// The rate at which the nodes receive transactions (tx/s), summed across nodes.
+    pub fn node_submitted_tps(&self) -> f64 {
+        self.node_rates(|x| x.submitted_transactions).iter().sum()
+    }
+
+    /// The highest round reported by the nodes.
+    pub fn highest_round(&self) -> u64 {
+        self.node_metrics
+            .values()
+            .filter_map(|series| series.last())
+            .map(|x| x.round)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The relative difference between the throughput measured by the load generators (across
+    /// all workloads) and the rate at which the nodes receive transactions. A large divergence
+    /// indicates that the load generators or the nodes are saturated. Returns `None` if the
+    /// nodes did not report any submitted transaction.
+    pub fn throughput_divergence(&self) -> Option<f64> {
+        let submitted = self.node_submitted_tps();
+        if submitted == 0.0 {
+            return None;
+        }
+        let tps: u64 = self.labels().map(|label| self.aggregate_tps(label)).sum();
+        Some((submitted - tps as f64).abs() / submitted)
+    }
+
+    /// Aggregate the latency breakdown of all nodes by averaging each stage over the nodes that
+    /// reported it.
+    pub fn aggregate_latency_breakdown(&self) -> LatencyBreakdown {
+        let average = |stage: fn(&LatencyBreakdown) -> Option<Duration>| {
+            let latencies: Vec<_> = self.latency_breakdown.values().filter_map(stage).collect();
+            latencies
+                .iter()
+                .sum::<Duration>()
+                .checked_div(latencies.len() as u32)
+        };
+        LatencyBreakdown {
+            dissemination: average(|x| x.dissemination),
+            commit: average(|x| x.commit),
+        }
     }
 
     /// Get all measurements associated with the specified label.
@@ -215,6 +859,18 @@ impl MeasurementsCollection {
             .unwrap_or_default()
     }
 
+    /// The total number of finalized transactions and the sum of their latencies across all
+    /// workloads, as of the last measurement of each scraper.
+    pub fn totals(&self) -> (usize, Duration) {
+        self.data
+            .values()
+            .flat_map(|data| data.values())
+            .filter_map(|x| x.last())
+            .fold((0, Duration::default()), |(count, sum), x| {
+                (count + x.count, sum + x.sum)
+            })
+    }
+
     /// Aggregate the tps of multiple data points.
     /// TPS is computed as: total_transactions / actual_processing_time
     /// where actual_processing_time is the time from first transaction submission to last confirmation,
@@ -255,10 +911,14 @@ impl MeasurementsCollection {
         (max_count as f64 / processing_time.as_secs_f64()) as u64
     }
 
-    /// Aggregate the average latency of multiple data points by taking the average.
+    /// Aggregate the average latency of multiple data points (after the warm-up) by taking the
+    /// average.
     pub fn aggregate_average_latency(&self, label: &Label) -> Duration {
         let all_measurements = self.all_measurements(label);
-        let last_data_points: Vec<_> = all_measurements.iter().filter_map(|x| x.last()).collect();
+        let last_data_points: Vec<_> = all_measurements
+            .iter()
+            .filter_map(|x| self.steady_state(x))
+            .collect();
         last_data_points
             .iter()
             .map(|x| x.average_latency())
@@ -272,6 +932,161 @@ impl MeasurementsCollection {
         self.max_result(label, |x| x.stdev_latency())
     }
 
+    /// Compute the latency percentiles (expressed in [0, 100]) across all workloads. The
+    /// percentiles are linearly interpolated within the prometheus latency buckets of the last
+    /// measurement of each scraper (excluding the transactions finalized during the warm-up),
+    /// and keyed by their (rounded) value. Returns an empty map if no transaction was recorded.
+    pub fn percentiles(&self, ps: &[f64]) -> BTreeMap<u64, Duration> {
+        let last_data_points: Vec<_> = self
+            .data
+            .values()
+            .flat_map(|data| data.values())
+            .filter_map(|x| self.steady_state(x))
+            .collect();
+        Self::merged_percentiles(last_data_points.iter(), ps)
+    }
+
+    /// Compute a latency percentile (expressed in [0, 100]) across all workloads from the sampled
+    /// latencies, or from the latency buckets if no latency was sampled. Returns zero if no
+    /// transaction was recorded.
+    pub fn percentile(&self, p: f64) -> Duration {
+        match self.latency_samples.percentiles(&[p]) {
+            Some(percentiles) => percentiles[0],
+            None => self
+                .percentiles(&[p])
+                .into_values()
+                .next()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Compute the p50, p90, p95, p99, and p99.9 latency across all workloads. Returns `None` if
+    /// no transaction was recorded.
+    pub fn latency_distribution(&self) -> Option<LatencyDistribution> {
+        let ps = [50.0, 90.0, 95.0, 99.0, 99.9];
+        let percentiles = match self.latency_samples.percentiles(&ps) {
+            Some(percentiles) => percentiles,
+            None if self.percentiles(&ps).is_empty() => return None,
+            None => ps.iter().map(|p| self.percentile(*p)).collect(),
+        };
+        Some(LatencyDistribution {
+            p50: percentiles[0],
+            p90: percentiles[1],
+            p95: percentiles[2],
+            p99: percentiles[3],
+            p999: percentiles[4],
+        })
+    }
+
+    /// Compute the latency percentiles of a single workload (excluding the warm-up).
+    pub fn label_percentiles(&self, label: &Label, ps: &[f64]) -> BTreeMap<u64, Duration> {
+        let last_data_points: Vec<_> = self
+            .data
+            .get(label)
+            .into_iter()
+            .flat_map(|data| data.values())
+            .filter_map(|x| self.steady_state(x))
+            .collect();
+        Self::merged_percentiles(last_data_points.iter(), ps)
+    }
+
+    /// Compute the latency percentiles of the union of the specified measurements.
+    fn merged_percentiles<'a>(
+        measurements: impl Iterator<Item = &'a Measurement>,
+        ps: &[f64],
+    ) -> BTreeMap<u64, Duration> {
+        // Merge the (cumulative) latency buckets of all scrapers.
+        let mut buckets: Vec<(f64, usize)> = Vec::new();
+        for measurement in measurements {
+            for (bucket_id, count) in &measurement.buckets {
+                let Ok(upper_bound) = bucket_id.parse::<f64>() else {
+                    continue;
+                };
+                match buckets.iter_mut().find(|(bound, _)| *bound == upper_bound) {
+                    Some((_, total)) => *total += count,
+                    None => buckets.push((upper_bound, *count)),
+                }
+            }
+        }
+        buckets.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        let total = buckets.last().map(|(_, count)| *count).unwrap_or_default();
+        if total == 0 {
+            return BTreeMap::new();
+        }
+
+        let mut percentiles = BTreeMap::new();
+        for p in ps {
+            let rank = p.clamp(0.0, 100.0) / 100.0 * total as f64;
+            let (mut lower_bound, mut lower_count) = (0.0, 0);
+            for (upper_bound, count) in &buckets {
+                if *count as f64 >= rank {
+                    let latency = if upper_bound.is_infinite() {
+                        // Nothing is known about the latencies above the last finite bucket.
+                        lower_bound
+                    } else if *count == lower_count {
+                        *upper_bound
+                    } else {
+                        let fraction = (rank - lower_count as f64) / (count - lower_count) as f64;
+                        lower_bound + (upper_bound - lower_bound) * fraction.max(0.0)
+                    };
+                    percentiles.insert(p.round() as u64, Duration::from_secs_f64(latency));
+                    break;
+                }
+                (lower_bound, lower_count) = (*upper_bound, *count);
+            }
+        }
+        percentiles
+    }
+
+    /// The throughput and latency at each load targeted by the load profile, sorted by load. The
+    /// transactions finalized between two measurements are attributed to the load targeted at
+    /// the time of the former. Returns an empty curve if the benchmark has no load profile.
+    pub fn load_curve(&self) -> Vec<LoadPoint> {
+        // The finalized transactions and the sum of their latencies at each load, along with the
+        // time spent at each load by each series of measurements.
+        let mut totals: BTreeMap<usize, (usize, Duration)> = BTreeMap::new();
+        let mut durations: BTreeMap<usize, Vec<Duration>> = BTreeMap::new();
+        for series in self.data.values().flat_map(|data| data.values()) {
+            let mut elapsed: BTreeMap<usize, Duration> = BTreeMap::new();
+            for window in series.windows(2) {
+                let (previous, current) = (&window[0], &window[1]);
+                let Some(load) = previous.target_load else {
+                    continue;
+                };
+                let (count, sum) = totals.entry(load).or_default();
+                *count += current.count.saturating_sub(previous.count);
+                *sum += current.sum.saturating_sub(previous.sum);
+                *elapsed.entry(load).or_default() +=
+                    current.timestamp.saturating_sub(previous.timestamp);
+            }
+            for (load, duration) in elapsed {
+                durations.entry(load).or_default().push(duration);
+            }
+        }
+
+        totals
+            .into_iter()
+            .map(|(target_load, (count, sum))| {
+                // The throughput sums the transactions finalized by all series over the average
+                // time the series spent at that load.
+                let duration = durations
+                    .get(&target_load)
+                    .and_then(|x| x.iter().sum::<Duration>().checked_div(x.len() as u32))
+                    .unwrap_or_default();
+                LoadPoint {
+                    target_load,
+                    tps: if duration.is_zero() {
+                        0.0
+                    } else {
+                        count as f64 / duration.as_secs_f64()
+                    },
+                    latency: sum.checked_div(count as u32).unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+
     /// Save the collection of measurements as a json file.
     pub fn save<P: AsRef<Path>>(&self, path: P) {
         let json = serde_json::to_string_pretty(self).expect("Cannot serialize metrics");
@@ -280,8 +1095,132 @@ impl MeasurementsCollection {
         fs::write(file, json).unwrap();
     }
 
-    /// Display a summary of the measurements.
-    pub fn display_summary(&self) {
+    /// Summarize the throughput and latency of each workload.
+    pub fn summary(&self) -> MeasurementsSummary {
+        let to_millis = |x: Duration| x.as_secs_f64() * 1_000.0;
+        let mut labels: Vec<_> = self.labels().collect();
+        labels.sort();
+        let workloads = labels
+            .into_iter()
+            .map(|label| {
+                let percentiles = self.label_percentiles(label, &[50.0, 95.0, 99.0]);
+                let percentile = |p| percentiles.get(&p).copied().map(to_millis);
+                WorkloadSummary {
+                    workload: label.clone(),
+                    tps: self.aggregate_tps(label),
+                    latency_avg_ms: to_millis(self.aggregate_average_latency(label)),
+                    latency_p50_ms: percentile(50),
+                    latency_p95_ms: percentile(95),
+                    latency_p99_ms: percentile(99),
+                }
+            })
+            .collect();
+
+        MeasurementsSummary {
+            nodes: self.parameters.nodes,
+            load: self.parameters.load,
+            duration_s: self.benchmark_duration().as_secs(),
+            workloads,
+        }
+    }
+
+    /// The series pushed to a prometheus remote-write endpoint: the sum and count of the
+    /// latencies of each workload as measured by each scraper, along with the throughput and
+    /// average latency of each workload at the end of the benchmark. The benchmark started at
+    /// the specified time (in ms since the unix epoch).
+    pub fn remote_write_series(&self, start_ms: i64) -> Vec<TimeSeries> {
+        let benchmark = format!("{:?}", self.parameters);
+        let end_ms = start_ms + self.benchmark_duration().as_millis() as i64;
+        let mut labels: Vec<_> = self.labels().collect();
+        labels.sort();
+
+        let mut series = Vec::new();
+        for label in labels {
+            let mut scrapers: Vec<_> = self.data[label].iter().collect();
+            scrapers.sort_by_key(|(scraper_id, _)| **scraper_id);
+            for (scraper_id, measurements) in scrapers {
+                let tags = vec![
+                    ("benchmark".into(), benchmark.clone()),
+                    ("node".into(), scraper_id.to_string()),
+                    ("workload".into(), label.clone()),
+                ];
+                let mut sum = TimeSeries::new("benchmark_latency_s_sum", tags.clone());
+                let mut count = TimeSeries::new("benchmark_latency_s_count", tags);
+                for measurement in measurements {
+                    let timestamp_ms = start_ms + measurement.timestamp.as_millis() as i64;
+                    sum.add(measurement.sum.as_secs_f64(), timestamp_ms);
+                    count.add(measurement.count as f64, timestamp_ms);
+                }
+                series.extend([sum, count]);
+            }
+
+            let tags = vec![
+                ("benchmark".into(), benchmark.clone()),
+                ("workload".into(), label.clone()),
+            ];
+            let mut tps = TimeSeries::new("benchmark_tps", tags.clone());
+            tps.add(self.aggregate_tps(label) as f64, end_ms);
+            let mut latency = TimeSeries::new("benchmark_latency_avg_s", tags);
+            let average_latency = self.aggregate_average_latency(label);
+            latency.add(average_latency.as_secs_f64(), end_ms);
+            series.extend([tps, latency]);
+        }
+        series
+    }
+
+    /// Push the measurements to the specified prometheus remote-write endpoint. The benchmark
+    /// is assumed to have just completed: its measurements are timestamped backwards from now.
+    pub async fn push_remote_write(&self, endpoint: &str) -> MonitorResult<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let start = now.saturating_sub(self.benchmark_duration());
+        let series = self.remote_write_series(start.as_millis() as i64);
+        remote_write::push(endpoint, &series).await
+    }
+
+    /// Serialize the summary of the measurements as a json document.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.summary()).expect("Cannot serialize summary")
+    }
+
+    /// Serialize the summary of the measurements as csv, one row per workload. Missing latency
+    /// percentiles are left empty.
+    pub fn to_csv(&self) -> String {
+        let summary = self.summary();
+        let mut csv = String::from(
+            "workload,nodes,load_tx_s,duration_s,tps,latency_avg_ms,\
+            latency_p50_ms,latency_p95_ms,latency_p99_ms\n",
+        );
+        let optional = |x: Option<f64>| x.map(|x| x.to_string()).unwrap_or_default();
+        for workload in summary.workloads {
+            csv.push_str(&format!(
+                "\"{}\",{},{},{},{},{},{},{},{}\n",
+                workload.workload.replace('"', "\"\""),
+                summary.nodes,
+                summary.load,
+                summary.duration_s,
+                workload.tps,
+                workload.latency_avg_ms,
+                optional(workload.latency_p50_ms),
+                optional(workload.latency_p95_ms),
+                optional(workload.latency_p99_ms),
+            ));
+        }
+        csv
+    }
+
+    /// Export the measurements in the specified format.
+    pub fn export(&self, format: ExportFormat, mut writer: impl Write) -> io::Result<()> {
+        match format {
+            ExportFormat::Text => self.summary_table().print(&mut writer).map(|_| ()),
+            ExportFormat::Csv => write!(writer, "{}", self.to_csv()),
+            ExportFormat::Json => writeln!(writer, "{}", self.to_json()),
+        }
+    }
+
+    /// Make a table summarizing the measurements.
+    fn summary_table(&self) -> Table {
         let mut table = Table::new();
         table.set_format(display::default_table_format());
 
@@ -292,8 +1231,13 @@ impl MeasurementsCollection {
         table.add_row(row![bH2->""]);
         table.add_row(row![b->"Nodes:", self.parameters.nodes]);
         table.add_row(row![b->"Faults:", self.parameters.settings.faults]);
+        table.add_row(row![b->"Specs:", self.parameters.specs()]);
         table.add_row(row![b->"Load:", format!("{} tx/s", self.parameters.load)]);
         table.add_row(row![b->"Duration:", format!("{} s", duration.as_secs())]);
+        if !self.parameters.warmup.is_zero() {
+            let warmup = self.parameters.warmup.as_secs();
+            table.add_row(row![b->"Warm-up:", format!("{warmup} s (excluded from latency)")]);
+        }
 
         let mut labels: Vec<_> = self.labels().collect();
         labels.sort();
@@ -309,6 +1253,256 @@ impl MeasurementsCollection {
             table.add_row(row![b->"Latency (stdev):", format!("{} ms", stdev_latency.as_millis())]);
         }
 
+        let saturation = self.saturation_curve();
+        if !saturation.is_empty() {
+            table.add_row(row![bH2->""]);
+            for point in saturation {
+                table.add_row(row![
+                    b->format!("Load {} tx/s ({} runs):", point.load, point.runs),
+                    format!(
+                        "{:.0} tx/s (variance {:.0}), {:.0} ms (variance {:.0} ms²)",
+                        point.tps, point.tps_variance, point.latency_ms, point.latency_variance
+                    )
+                ]);
+            }
+        }
+
+        let curve = self.load_curve();
+        if !curve.is_empty() {
+            table.add_row(row![bH2->""]);
+            for point in curve {
+                table.add_row(row![
+                    b->format!("Target load {} tx/s:", point.target_load),
+                    format!("{:.0} tx/s, {} ms", point.tps, point.latency.as_millis())
+                ]);
+            }
+        }
+
+        if let Some(distribution) = self.latency_distribution() {
+            table.add_row(row![bH2->""]);
+            for (p, latency) in [
+                ("p50", distribution.p50),
+                ("p90", distribution.p90),
+                ("p95", distribution.p95),
+                ("p99", distribution.p99),
+                ("p99.9", distribution.p999),
+            ] {
+                table.add_row(row![
+                    b->format!("Latency ({p}):"),
+                    format!("{} ms", latency.as_millis())
+                ]);
+            }
+        }
+
+        let breakdown = self.aggregate_latency_breakdown();
+        if breakdown != LatencyBreakdown::default() {
+            table.add_row(row![bH2->""]);
+            for (stage, latency) in [
+                ("Dissemination", breakdown.dissemination),
+                ("Commit", breakdown.commit),
+            ] {
+                if let Some(latency) = latency {
+                    table.add_row(row![
+                        b->format!("{stage} latency (avg):"),
+                        format!("{} ms", latency.as_millis())
+                    ]);
+                }
+            }
+        }
+
+        if !self.fault_events.is_empty() {
+            table.add_row(row![bH2->""]);
+            let nodes = |indices: &[usize]| {
+                let indices: Vec<_> = indices.iter().map(|x| x.to_string()).collect();
+                indices.join(", ")
+            };
+            for event in &self.fault_events {
+                let mut changes = Vec::new();
+                if !event.crashed.is_empty() {
+                    changes.push(format!("crashed nodes {}", nodes(&event.crashed)));
+                }
+                if !event.recovered.is_empty() {
+                    changes.push(format!("recovered nodes {}", nodes(&event.recovered)));
+                }
+                table.add_row(row![
+                    b->format!("Faults ({}s):", event.elapsed.as_secs()),
+                    changes.join(", ")
+                ]);
+            }
+        }
+
+        if !self.node_metrics.is_empty() {
+            table.add_row(row![bH2->""]);
+            table.add_row(row![
+                b->"Commit rate (nodes):",
+                format!("{:.1} leaders/s", self.node_commit_rate())
+            ]);
+            table.add_row(row![
+                b->"Submitted (nodes):",
+                format!("{:.0} tx/s", self.node_submitted_tps())
+            ]);
+            table.add_row(row![b->"Highest round:", self.highest_round()]);
+        }
+
+        if !self.time_to_first_commit.is_empty() {
+            table.add_row(row![bH2->""]);
+            for (node, duration) in &self.time_to_first_commit {
+                table.add_row(row![
+                    b->format!("Time to first commit (node {node}):"),
+                    format!("{} ms", duration.as_millis())
+                ]);
+            }
+        }
+        table
+    }
+
+    /// Display a summary of the measurements.
+    pub fn display_summary(&self) {
+        display::newline();
+        self.summary_table().printstd();
+        display::newline();
+    }
+}
+
+/// The mean of a metric across repeated runs along with the margin of its 95% confidence
+/// interval, that is, the true mean lies in `mean ± margin` with 95% confidence.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ConfidenceInterval {
+    /// The sample mean.
+    pub mean: f64,
+    /// The half-width of the confidence interval.
+    pub margin: f64,
+}
+
+impl ConfidenceInterval {
+    /// Two-sided 95% critical values of the Student's t-distribution, indexed by the number of
+    /// degrees of freedom minus one.
+    const T_VALUES: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    /// The critical value of the normal distribution, used when there are more degrees of
+    /// freedom than listed in `T_VALUES`.
+    const Z_VALUE: f64 = 1.960;
+
+    /// Compute the 95% confidence interval of the mean of the provided samples. The margin is
+    /// zero if there are fewer than two samples.
+    pub fn from_samples(samples: &[f64]) -> Self {
+        let n = samples.len();
+        if n == 0 {
+            return Self::default();
+        }
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        if n == 1 {
+            return Self { mean, margin: 0.0 };
+        }
+
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        let standard_error = (variance / n as f64).sqrt();
+        let critical_value = Self::T_VALUES.get(n - 2).copied().unwrap_or(Self::Z_VALUE);
+        Self {
+            mean,
+            margin: critical_value * standard_error,
+        }
+    }
+}
+
+/// The measurements collected by repeating the same benchmark multiple times.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct RepeatedMeasurements {
+    /// The measurements of each run.
+    pub runs: Vec<MeasurementsCollection>,
+}
+
+impl RepeatedMeasurements {
+    /// Create a new (empty) set of repeated runs.
+    pub fn new() -> Self {
+        Self { runs: Vec::new() }
+    }
+
+    /// Add the measurements of a new run.
+    pub fn add(&mut self, run: MeasurementsCollection) {
+        self.runs.push(run);
+    }
+
+    /// Get all labels measured in at least one run.
+    pub fn labels(&self) -> Vec<&Label> {
+        let mut labels: Vec<_> = self.runs.iter().flat_map(|run| run.labels()).collect();
+        labels.sort();
+        labels.dedup();
+        labels
+    }
+
+    /// The confidence interval of the tps across runs.
+    pub fn tps(&self, label: &Label) -> ConfidenceInterval {
+        let samples: Vec<_> = self
+            .runs
+            .iter()
+            .map(|run| run.aggregate_tps(label) as f64)
+            .collect();
+        ConfidenceInterval::from_samples(&samples)
+    }
+
+    /// The confidence interval of the average latency (in seconds) across runs.
+    pub fn average_latency(&self, label: &Label) -> ConfidenceInterval {
+        let samples: Vec<_> = self
+            .runs
+            .iter()
+            .map(|run| run.aggregate_average_latency(label).as_secs_f64())
+            .collect();
+        ConfidenceInterval::from_samples(&samples)
+    }
+
+    /// Save all runs as a single json file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) {
+        let Some(first) = self.runs.first() else {
+            return;
+        };
+        let json = serde_json::to_string_pretty(self).expect("Cannot serialize metrics");
+        let mut file = PathBuf::from(path.as_ref());
+        file.push(format!("measurements-{:?}-repeated.json", first.parameters));
+        fs::write(file, json).unwrap();
+    }
+
+    /// Display a summary of the repeated runs.
+    pub fn display_summary(&self) {
+        let Some(first) = self.runs.first() else {
+            return;
+        };
+
+        let mut table = Table::new();
+        table.set_format(display::default_table_format());
+
+        table.set_titles(row![bH2->"Repeated Benchmark Summary (95% CI)"]);
+        table.add_row(row![b->"Benchmark type:", first.parameters.node_parameters]);
+        table.add_row(row![bH2->""]);
+        table.add_row(row![b->"Nodes:", first.parameters.nodes]);
+        table.add_row(row![b->"Faults:", first.parameters.settings.faults]);
+        table.add_row(row![b->"Specs:", first.parameters.specs()]);
+        table.add_row(row![b->"Load:", format!("{} tx/s", first.parameters.load)]);
+        table.add_row(row![b->"Runs:", self.runs.len()]);
+
+        for label in self.labels() {
+            let tps = self.tps(label);
+            let latency = self.average_latency(label);
+
+            table.add_row(row![bH2->""]);
+            table.add_row(row![b->"Workload:", label]);
+            table.add_row(row![
+                b->"TPS:",
+                format!("{:.0} ± {:.0} tx/s", tps.mean, tps.margin)
+            ]);
+            table.add_row(row![
+                b->"Latency (avg):",
+                format!(
+                    "{:.0} ± {:.0} ms",
+                    latency.mean * 1_000.0,
+                    latency.margin * 1_000.0
+                )
+            ]);
+        }
+
         display::newline();
         table.printstd();
         display::newline();
@@ -319,8 +1513,22 @@ impl MeasurementsCollection {
 mod test {
     use std::{collections::HashMap, time::Duration};
 
-    use super::{BenchmarkParameters, Measurement, MeasurementsCollection};
-    use crate::protocol::test_protocol_metrics::TestProtocolMetrics;
+    use super::{
+        BenchmarkParameters,
+        ConfidenceInterval,
+        ExportFormat,
+        FaultEvent,
+        LatencyBreakdown,
+        LatencyDistribution,
+        LatencySamples,
+        LoadPoint,
+        Measurement,
+        MeasurementsCollection,
+        MeasurementsSummary,
+        NodeMetrics,
+        RepeatedMeasurements,
+    };
+    use crate::{benchmark::LoadProfile, protocol::test_protocol_metrics::TestProtocolMetrics};
 
     #[test]
     fn average_latency() {
@@ -330,6 +1538,7 @@ mod test {
             sum: Duration::from_secs(2),
             count: 100,
             squared_sum: 0.0,
+            target_load: None,
         };
 
         assert_eq!(data.average_latency(), Duration::from_millis(20));
@@ -343,6 +1552,7 @@ mod test {
             sum: Duration::from_secs(50),
             count: 100,
             squared_sum: 75.0,
+            target_load: None,
         };
 
         // squared_sum / count
@@ -354,6 +1564,477 @@ mod test {
         assert_eq!((stdev.as_secs_f64() * 10.0).round(), 7.0);
     }
 
+    #[test]
+    fn confidence_interval() {
+        let interval = ConfidenceInterval::from_samples(&[10.0, 12.0, 14.0]);
+        assert_eq!(interval.mean, 12.0);
+        // stdev = 2, standard error = 2 / sqrt(3), t(df = 2) = 4.303
+        assert_eq!((interval.margin * 1_000.0).round(), 4_969.0);
+
+        // A single run has no spread.
+        let interval = ConfidenceInterval::from_samples(&[10.0]);
+        assert_eq!(interval.mean, 10.0);
+        assert_eq!(interval.margin, 0.0);
+
+        // No runs at all.
+        assert_eq!(
+            ConfidenceInterval::from_samples(&[]),
+            ConfidenceInterval::default()
+        );
+
+        // Identical runs have no spread either.
+        let interval = ConfidenceInterval::from_samples(&[5.0; 50]);
+        assert_eq!(interval.mean, 5.0);
+        assert_eq!(interval.margin, 0.0);
+    }
+
+    #[test]
+    fn repeated_average_latency() {
+        let label = "shared".to_string();
+        let mut repeated = RepeatedMeasurements::new();
+        for latency in [100, 200, 300, 400] {
+            let mut run = MeasurementsCollection::new(BenchmarkParameters::new_for_tests());
+            let measurement = Measurement {
+                timestamp: Duration::from_secs(10),
+                buckets: HashMap::new(),
+                sum: Duration::from_millis(latency * 10),
+                count: 10,
+                squared_sum: 0.0,
+                target_load: None,
+            };
+            run.add(0, label.clone(), measurement);
+            repeated.add(run);
+        }
+
+        assert_eq!(repeated.labels(), vec![&label]);
+        let interval = repeated.average_latency(&label);
+        assert_eq!((interval.mean * 1_000.0).round(), 250.0);
+        // stdev = 0.129, standard error = 0.0645, t(df = 3) = 3.182
+        assert_eq!((interval.margin * 1_000.0).round(), 205.0);
+    }
+
+    #[test]
+    fn percentiles() {
+        let buckets = [
+            ("0.1", 10),
+            ("0.2", 50),
+            ("0.5", 90),
+            ("1", 100),
+            ("inf", 100),
+        ];
+        let measurement = Measurement {
+            timestamp: Duration::from_secs(10),
+            buckets: buckets
+                .iter()
+                .map(|(id, count)| (id.to_string(), *count))
+                .collect(),
+            sum: Duration::from_secs(30),
+            count: 100,
+            squared_sum: 0.0,
+            target_load: None,
+        };
+
+        // The buckets of all scrapers are merged.
+        let mut aggregator = MeasurementsCollection::new(BenchmarkParameters::new_for_tests());
+        aggregator.add(0, "shared".into(), measurement.clone());
+        aggregator.add(1, "shared".into(), measurement);
+
+        let percentiles = aggregator.percentiles(&[30.0, 50.0, 90.0, 99.0]);
+        let millis: Vec<_> = percentiles
+            .iter()
+            .map(|(p, latency)| (*p, (latency.as_secs_f64() * 1_000.0).round() as u64))
+            .collect();
+        assert_eq!(millis, [(30, 150), (50, 200), (90, 500), (99, 950)]);
+    }
+
+    #[test]
+    fn warmup_excluded() {
+        // The transactions finalized during the cold start are slow (up to 1s) while the ones
+        // finalized afterwards are fast (up to 100ms).
+        let measurement = |timestamp: u64, fast: usize, slow: usize| {
+            let buckets = [("0.1", fast), ("1", fast + slow), ("inf", fast + slow)];
+            Measurement {
+                timestamp: Duration::from_secs(timestamp),
+                buckets: buckets
+                    .iter()
+                    .map(|(id, count)| (id.to_string(), *count))
+                    .collect(),
+                sum: Duration::from_millis(50 * fast as u64 + 500 * slow as u64),
+                count: fast + slow,
+                squared_sum: 0.0,
+                target_load: None,
+            }
+        };
+        let points = [
+            measurement(5, 0, 50),
+            measurement(10, 0, 100),
+            measurement(20, 900, 100),
+        ];
+
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        let mut cold = MeasurementsCollection::new(parameters.clone());
+        parameters.warmup = Duration::from_secs(10);
+        let mut warm = MeasurementsCollection::new(parameters);
+        warm.latency_samples = LatencySamples::new(1_000);
+        for point in points {
+            cold.add(0, "shared".into(), point.clone());
+            warm.add(0, "shared".into(), point);
+        }
+
+        // The cold-start transactions skew the tail latency unless excluded.
+        assert!(cold.percentiles(&[99.0])[&99] > Duration::from_millis(500));
+        assert!(warm.percentiles(&[99.0])[&99] <= Duration::from_millis(100));
+        assert!(warm.percentile(99.0) <= Duration::from_millis(100));
+        let average = warm.aggregate_average_latency(&"shared".into());
+        assert_eq!(average, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn percentiles_empty() {
+        let aggregator = MeasurementsCollection::new(BenchmarkParameters::new_for_tests());
+        assert!(aggregator.percentiles(&[50.0, 99.0]).is_empty());
+        assert_eq!(aggregator.latency_distribution(), None);
+        assert_eq!(aggregator.percentile(99.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn latency_distribution() {
+        // The latencies 1ms, 2ms, ..., 1000ms.
+        let mut aggregator = MeasurementsCollection::new(BenchmarkParameters::new_for_tests());
+        aggregator.latency_samples = LatencySamples::new(1_000);
+        for i in 1..=1_000 {
+            aggregator.latency_samples.add(Duration::from_millis(i));
+        }
+
+        assert_eq!(aggregator.percentile(75.0), Duration::from_millis(750));
+        assert_eq!(
+            aggregator.latency_distribution(),
+            Some(LatencyDistribution {
+                p50: Duration::from_millis(500),
+                p90: Duration::from_millis(900),
+                p95: Duration::from_millis(950),
+                p99: Duration::from_millis(990),
+                p999: Duration::from_millis(999),
+            })
+        );
+    }
+
+    #[test]
+    fn latency_reservoir() {
+        // Only a bounded (uniform) sample of the latencies is retained.
+        let mut samples = LatencySamples::new(100);
+        for i in 1..=10_000 {
+            samples.add(Duration::from_millis(i));
+        }
+        assert_eq!(samples.samples.len(), 100);
+        assert_eq!(samples.seen, 10_000);
+        let median = samples.percentiles(&[50.0]).unwrap()[0];
+        assert!(median > Duration::from_secs(3) && median < Duration::from_secs(7));
+    }
+
+    #[test]
+    fn sampled_latencies() {
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings.latency_samples = 1_000;
+        let mut aggregator = MeasurementsCollection::new(parameters);
+
+        // The latencies are estimated from the buckets of the successive measurements.
+        let mut buckets = [
+            ("0.1", 10),
+            ("0.2", 50),
+            ("0.5", 90),
+            ("1", 100),
+            ("inf", 100),
+        ];
+        for timestamp in [10, 20] {
+            let measurement = Measurement {
+                timestamp: Duration::from_secs(timestamp),
+                buckets: buckets
+                    .iter()
+                    .map(|(id, count)| (id.to_string(), *count))
+                    .collect(),
+                sum: Duration::from_secs(30),
+                count: buckets[4].1,
+                squared_sum: 0.0,
+                target_load: None,
+            };
+            aggregator.add(0, "shared".into(), measurement);
+            buckets[4].1 += 10;
+        }
+        assert_eq!(aggregator.latency_samples.samples.len(), 110);
+
+        let distribution = aggregator.latency_distribution().unwrap();
+        assert_eq!(distribution.p50.as_millis(), 233);
+        assert_eq!(distribution.p999, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn load_curve() {
+        let profile: LoadProfile = "ramp:100-300/20@10".parse().unwrap();
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.load_profile = Some(profile.clone());
+        let mut collection = MeasurementsCollection::new(parameters);
+        for scraper_id in 0..2 {
+            let (mut count, mut sum) = (0, Duration::ZERO);
+            for secs in (0..=30).step_by(5) {
+                let timestamp = Duration::from_secs(secs);
+                let measurement = Measurement {
+                    timestamp,
+                    buckets: HashMap::new(),
+                    sum,
+                    count,
+                    squared_sum: 0.0,
+                    target_load: None,
+                };
+                collection.add(scraper_id, "shared".into(), measurement);
+
+                // Each node finalizes half of the load, with a latency of 1ms per tx/s of load.
+                let load = profile.load_at(timestamp);
+                let finalized = load / 2 * 5;
+                count += finalized;
+                sum += Duration::from_millis(load as u64) * finalized as u32;
+            }
+        }
+
+        let tags: Vec<_> = collection.data["shared"][&0]
+            .iter()
+            .map(|x| x.target_load)
+            .collect();
+        let expected = [100, 100, 200, 200, 300, 300, 300].map(Some);
+        assert_eq!(tags, expected);
+
+        let expected: Vec<_> = [100, 200, 300]
+            .into_iter()
+            .map(|load| LoadPoint {
+                target_load: load,
+                tps: load as f64,
+                latency: Duration::from_millis(load as u64),
+            })
+            .collect();
+        assert_eq!(collection.load_curve(), expected);
+
+        // Benchmarks without load profile have no load curve.
+        assert!(test_collection().load_curve().is_empty());
+    }
+
+    #[test]
+    fn merge() {
+        // A run at the specified load in which both nodes finalize 1000 transactions with the
+        // specified latency.
+        let run = |load: usize, latency_ms: u64| {
+            let mut parameters = BenchmarkParameters::new_for_tests();
+            parameters.load = load;
+            let mut collection = MeasurementsCollection::new(parameters);
+            for scraper_id in 0..2 {
+                let measurement = Measurement {
+                    timestamp: Duration::from_secs(10),
+                    buckets: HashMap::new(),
+                    sum: Duration::from_millis(latency_ms) * 1000,
+                    count: 1000,
+                    squared_sum: 0.0,
+                    target_load: None,
+                };
+                collection.add(scraper_id, "shared".into(), measurement);
+            }
+            collection
+        };
+        let runs = vec![run(100, 10), run(200, 50), run(100, 30)];
+        let tps: Vec<_> = runs
+            .iter()
+            .map(|x| x.aggregate_tps(&"shared".into()))
+            .collect();
+
+        let merged = MeasurementsCollection::merge(runs);
+        assert_eq!(merged.parameters.load, 100);
+        assert_eq!(merged.data["shared"].len(), 6);
+        assert_eq!(merged.totals(), (6000, Duration::from_secs(180)));
+        assert_eq!(merged.merged_runs.len(), 3);
+
+        // The runs at the same load are averaged.
+        let curve = merged.saturation_curve();
+        assert_eq!(curve.len(), 2);
+        assert_eq!((curve[0].load, curve[0].runs), (100, 2));
+        assert_eq!(curve[0].tps, (tps[0] + tps[2]) as f64 / 2.0);
+        assert_eq!(curve[0].latency_ms, 20.0);
+        assert_eq!(curve[0].latency_variance, 200.0);
+        assert_eq!((curve[1].load, curve[1].runs), (200, 1));
+        assert_eq!(curve[1].tps, tps[1] as f64);
+        assert_eq!(curve[1].latency_ms, 50.0);
+        assert_eq!(curve[1].latency_variance, 0.0);
+
+        // Merging merged collections keeps the original runs.
+        let remerged = MeasurementsCollection::merge(vec![merged, run(300, 20)]);
+        assert_eq!(remerged.merged_runs.len(), 4);
+        assert_eq!(remerged.totals().0, 8000);
+        assert_eq!(remerged.saturation_curve().len(), 3);
+
+        // Single runs have no saturation curve.
+        assert!(test_collection().saturation_curve().is_empty());
+    }
+
+    fn test_collection() -> MeasurementsCollection {
+        let mut collection = MeasurementsCollection::new(BenchmarkParameters::new_for_tests());
+        for (scraper_id, timestamp) in [(0, 10), (0, 20), (1, 10)] {
+            let measurement = Measurement {
+                timestamp: Duration::from_secs(timestamp),
+                buckets: HashMap::new(),
+                sum: Duration::from_secs(timestamp / 5),
+                count: 10 * timestamp as usize,
+                squared_sum: 0.0,
+                target_load: None,
+            };
+            collection.add(scraper_id, "shared".into(), measurement);
+        }
+        collection
+    }
+
+    #[test]
+    fn remote_write_series() {
+        let collection = test_collection();
+        let series = collection.remote_write_series(1_000_000);
+        let names: Vec<_> = series.iter().map(|x| x.labels[0].value.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "benchmark_latency_s_sum",
+                "benchmark_latency_s_count",
+                "benchmark_latency_s_sum",
+                "benchmark_latency_s_count",
+                "benchmark_tps",
+                "benchmark_latency_avg_s"
+            ]
+        );
+
+        // The labels are sorted by name.
+        let benchmark = format!("{:?}", collection.parameters);
+        let labels: Vec<_> = series[0]
+            .labels
+            .iter()
+            .map(|x| (x.name.as_str(), x.value.as_str()))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                ("__name__", "benchmark_latency_s_sum"),
+                ("benchmark", benchmark.as_str()),
+                ("node", "0"),
+                ("workload", "shared"),
+            ]
+        );
+        let samples = |i: usize| -> Vec<_> {
+            series[i]
+                .samples
+                .iter()
+                .map(|x| (x.value, x.timestamp))
+                .collect()
+        };
+        assert_eq!(samples(0), [(2.0, 1_010_000), (4.0, 1_020_000)]);
+        assert_eq!(samples(3), [(100.0, 1_010_000)]);
+
+        // The summary of each workload is timestamped at the end of the benchmark.
+        let tps = collection.aggregate_tps(&"shared".into()) as f64;
+        assert_eq!(samples(4), [(tps, 1_020_000)]);
+    }
+
+    #[test]
+    fn export_text() {
+        let mut collection = test_collection();
+        collection.add_fault_event(FaultEvent {
+            elapsed: Duration::from_secs(30),
+            crashed: vec![1, 3],
+            recovered: vec![],
+        });
+        let mut output = Vec::new();
+        collection.export(ExportFormat::Text, &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("Benchmark Summary"));
+        assert!(text.contains("shared"));
+        assert!(text.contains("crashed nodes 1, 3"));
+    }
+
+    /// A test collection with a second workload recording latency buckets.
+    fn test_collection_with_buckets() -> MeasurementsCollection {
+        let mut collection = test_collection();
+        let buckets = [("0.1", 10), ("0.2", 50), ("0.5", 90), ("1", 100)];
+        let measurement = Measurement {
+            timestamp: Duration::from_secs(10),
+            buckets: buckets
+                .iter()
+                .map(|(id, count)| (id.to_string(), *count))
+                .collect(),
+            sum: Duration::from_secs(30),
+            count: 100,
+            squared_sum: 0.0,
+            target_load: None,
+        };
+        collection.add(0, "owned".into(), measurement);
+        collection
+    }
+
+    #[test]
+    fn export_csv() {
+        let collection = test_collection_with_buckets();
+        let mut output = Vec::new();
+        collection.export(ExportFormat::Csv, &mut output).unwrap();
+
+        let text = String::from_utf8(output).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(
+            lines.next(),
+            Some(
+                "workload,nodes,load_tx_s,duration_s,tps,latency_avg_ms,\
+                latency_p50_ms,latency_p95_ms,latency_p99_ms"
+            )
+        );
+        let rows: Vec<_> = lines.map(|x| x.split(',').collect::<Vec<_>>()).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][..4], ["\"owned\"", "4", "500", "20"]);
+        assert_eq!(rows[0][5], "300");
+        assert_eq!(rows[0][6..].len(), 3);
+        assert_eq!(rows[1][..4], ["\"shared\"", "4", "500", "20"]);
+        assert_eq!(rows[1][5..], ["20", "", "", ""]);
+    }
+
+    #[test]
+    fn export_json() {
+        let collection = test_collection_with_buckets();
+        let mut output = Vec::new();
+        collection.export(ExportFormat::Json, &mut output).unwrap();
+
+        // The json document parses back into the same summary.
+        let loaded: MeasurementsSummary = serde_json::from_slice(&output).unwrap();
+        let summary = collection.summary();
+        assert_eq!(loaded, summary);
+        assert_eq!((loaded.nodes, loaded.load, loaded.duration_s), (4, 500, 20));
+
+        let owned = &loaded.workloads[0];
+        assert_eq!(owned.workload, "owned");
+        assert_eq!(owned.tps, collection.aggregate_tps(&"owned".to_string()));
+        assert_eq!(owned.latency_avg_ms, 300.0);
+        let millis = |x: Option<f64>| x.map(|x| x.round() as u64);
+        assert_eq!(millis(owned.latency_p50_ms), Some(200));
+        assert_eq!(millis(owned.latency_p95_ms), Some(750));
+        assert_eq!(millis(owned.latency_p99_ms), Some(950));
+
+        let shared = &loaded.workloads[1];
+        assert_eq!(shared.workload, "shared");
+        assert_eq!(shared.latency_avg_ms, 20.0);
+        assert_eq!(shared.latency_p50_ms, None);
+    }
+
+    #[test]
+    fn export_empty() {
+        let collection = MeasurementsCollection::new(BenchmarkParameters::new_for_tests());
+
+        let summary: MeasurementsSummary = serde_json::from_str(&collection.to_json()).unwrap();
+        assert!(summary.workloads.is_empty());
+        assert_eq!(summary.duration_s, 0);
+
+        let csv = collection.to_csv();
+        assert_eq!(csv.lines().count(), 1);
+        assert!(csv.starts_with("workload,"));
+    }
+
     #[test]
     fn prometheus_parse() {
         let report = r#"
@@ -462,6 +2143,157 @@ mod test {
         assert_eq!(shared_workload_data_points.len(), 1);
     }
 
+    #[test]
+    fn time_to_first_commit() {
+        let report = |value| {
+            format!(
+                r#"
+                # HELP benchmark_duration Duration of the benchmark
+                # TYPE benchmark_duration counter
+                benchmark_duration 30
+                # HELP time_to_first_commit Time between the start of the node and its first commit in seconds
+                # TYPE time_to_first_commit gauge
+                time_to_first_commit {value}
+            "#
+            )
+        };
+
+        let parse = |text: &str| Measurement::time_to_first_commit::<TestProtocolMetrics>(text);
+        assert_eq!(parse(&report(0.0)), None);
+        assert_eq!(parse(&report(1.5)), Some(Duration::from_millis(1_500)));
+
+        let mut aggregator = MeasurementsCollection::new(BenchmarkParameters::new_for_tests());
+        aggregator.add_time_to_first_commit(0, Duration::from_millis(1_500));
+        aggregator.add_time_to_first_commit(1, Duration::from_millis(3_000));
+        aggregator.add_time_to_first_commit(0, Duration::from_millis(2_000));
+        assert_eq!(
+            aggregator.time_to_first_commit,
+            [
+                (0, Duration::from_millis(1_500)),
+                (1, Duration::from_millis(3_000))
+            ]
+            .into_iter()
+            .collect()
+        );
+    }
+
+    #[test]
+    fn latency_breakdown() {
+        let report = r#"
+            # HELP benchmark_duration Duration of the benchmark
+            # TYPE benchmark_duration counter
+            benchmark_duration 30
+            # HELP commit_latency_seconds Buckets measuring the time between the creation of a leader block and its commit in seconds
+            # TYPE commit_latency_seconds histogram
+            commit_latency_seconds_bucket{le="0.5"} 2
+            commit_latency_seconds_bucket{le="1"} 4
+            commit_latency_seconds_bucket{le="+Inf"} 4
+            commit_latency_seconds_sum 2.4
+            commit_latency_seconds_count 4
+            # HELP dissemination_latency_seconds Buckets measuring the time between the proposal of a block and its reception by a quorum of authorities in seconds
+            # TYPE dissemination_latency_seconds histogram
+            dissemination_latency_seconds_bucket{le="0.1"} 8
+            dissemination_latency_seconds_bucket{le="0.25"} 10
+            dissemination_latency_seconds_bucket{le="+Inf"} 10
+            dissemination_latency_seconds_sum 1.5
+            dissemination_latency_seconds_count 10
+        "#;
+
+        let parse = |text: &str| Measurement::latency_breakdown::<TestProtocolMetrics>(text);
+        let breakdown = parse(report).unwrap();
+        assert_eq!(breakdown.dissemination, Some(Duration::from_millis(150)));
+        assert_eq!(breakdown.commit, Some(Duration::from_millis(600)));
+
+        // Nodes that did not disseminate or commit anything yet do not report a breakdown.
+        let report = r#"
+            # HELP dissemination_latency_seconds Buckets measuring the time between the proposal of a block and its reception by a quorum of authorities in seconds
+            # TYPE dissemination_latency_seconds histogram
+            dissemination_latency_seconds_bucket{le="+Inf"} 0
+            dissemination_latency_seconds_sum 0
+            dissemination_latency_seconds_count 0
+        "#;
+        assert_eq!(parse(report), None);
+
+        let mut aggregator = MeasurementsCollection::new(BenchmarkParameters::new_for_tests());
+        aggregator.add_latency_breakdown(0, breakdown);
+        aggregator.add_latency_breakdown(
+            1,
+            LatencyBreakdown {
+                dissemination: Some(Duration::from_millis(250)),
+                commit: None,
+            },
+        );
+        assert_eq!(
+            aggregator.aggregate_latency_breakdown(),
+            LatencyBreakdown {
+                dissemination: Some(Duration::from_millis(200)),
+                commit: Some(Duration::from_millis(600)),
+            }
+        );
+    }
+
+    #[test]
+    fn node_metrics() {
+        let report = |duration, leaders, submitted| {
+            format!(
+                r#"
+                # HELP benchmark_duration Duration of the benchmark
+                # TYPE benchmark_duration counter
+                benchmark_duration {duration}
+                # HELP committed_leaders_total Total number of (direct or indirect) committed leaders per authority
+                # TYPE committed_leaders_total counter
+                committed_leaders_total{{authority="0",commit_type="direct-commit"}} {leaders}
+                committed_leaders_total{{authority="1",commit_type="direct-commit"}} {leaders}
+                committed_leaders_total{{authority="2",commit_type="indirect-commit"}} 2
+                committed_leaders_total{{authority="3",commit_type="direct-skip"}} 5
+                # HELP current_round The round of the last block proposed by the authority
+                # TYPE current_round gauge
+                current_round {duration}0
+                # HELP submitted_transactions Total number of submitted transactions
+                # TYPE submitted_transactions counter
+                submitted_transactions {submitted}
+                # HELP undecided_leaders Number of leaders above the last decided leader that could not be decided yet
+                # TYPE undecided_leaders gauge
+                undecided_leaders 3
+            "#
+            )
+        };
+
+        let parse = |text: &str| Measurement::node_metrics::<TestProtocolMetrics>(text);
+        let first = parse(&report(10, 40, 10_000)).unwrap();
+        assert_eq!(
+            first,
+            NodeMetrics {
+                timestamp: Duration::from_secs(10),
+                committed_leaders: 82,
+                undecided_leaders: 3,
+                submitted_transactions: 10_000,
+                round: 100,
+            }
+        );
+        let last = parse(&report(20, 90, 20_000)).unwrap();
+
+        // Nodes that do not expose their progress do not report anything.
+        let report = r#"
+            # HELP benchmark_duration Duration of the benchmark
+            # TYPE benchmark_duration counter
+            benchmark_duration 30
+        "#;
+        assert_eq!(parse(report), None);
+
+        let mut aggregator = MeasurementsCollection::new(BenchmarkParameters::new_for_tests());
+        assert_eq!(aggregator.throughput_divergence(), None);
+        for node in 0..2 {
+            aggregator.add_node_metrics(node, first);
+            aggregator.add_node_metrics(node, last);
+        }
+        assert_eq!(aggregator.node_commit_rate(), 10.0);
+        assert_eq!(aggregator.node_submitted_tps(), 2_000.0);
+        assert_eq!(aggregator.highest_round(), 200);
+        // The load generators did not report any transaction.
+        assert_eq!(aggregator.throughput_divergence(), Some(1.0));
+    }
+
     #[test]
     fn prometheus_parse_large() {
         let report = r#"
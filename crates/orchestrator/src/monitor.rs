@@ -1,14 +1,19 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{fs, net::SocketAddr, path::PathBuf};
+use std::{fs, net::IpAddr, path::PathBuf, time::Duration};
+
+use mysticeti_core::{
+    config::{NodeParameters, NodePublicConfig},
+    metrics::{COMMITTED_LEADERS_LABELS, COMMITTED_LEADERS_TOTAL, COMMIT_LATENCY_S, LATENCY_S},
+    prometheus::METRICS_ROUTE,
+};
+use serde_json::json;
 
 use crate::{
-    benchmark::BenchmarkParameters,
     client::Instance,
     error::{MonitorError, MonitorResult},
     executor::Executor,
-    protocol::ProtocolMetrics,
     ssh::CommandContext,
 };
 
@@ -17,6 +22,10 @@ pub struct Monitor {
     clients: Vec<Instance>,
     nodes: Vec<Instance>,
     executor: Executor,
+    /// The parameters of the validators, used to derive their metrics port.
+    node_parameters: NodeParameters,
+    /// The interval at which prometheus scrapes its targets.
+    scrape_interval: Duration,
 }
 
 impl Monitor {
@@ -32,9 +41,23 @@ impl Monitor {
             clients,
             nodes,
             executor,
+            node_parameters: NodeParameters::default(),
+            scrape_interval: Prometheus::DEFAULT_SCRAPE_INTERVAL,
         }
     }
 
+    /// Set the parameters of the validators.
+    pub fn with_node_parameters(mut self, node_parameters: NodeParameters) -> Self {
+        self.node_parameters = node_parameters;
+        self
+    }
+
+    /// Set the interval at which prometheus scrapes its targets.
+    pub fn with_scrape_interval(mut self, scrape_interval: Duration) -> Self {
+        self.scrape_interval = scrape_interval;
+        self
+    }
+
     /// Dependencies to install.
     pub fn dependencies() -> Vec<String> {
         let mut commands: Vec<String> = Vec::new();
@@ -44,20 +67,18 @@ impl Monitor {
         commands
     }
 
+    /// Start prometheus and grafana on the dedicated motoring machine.
+    pub async fn start(&self) -> MonitorResult<()> {
+        self.start_prometheus().await?;
+        self.start_grafana().await?;
+        self.provision_dashboard().await
+    }
+
     /// Start a prometheus instance on the dedicated motoring machine.
-    pub async fn start_prometheus<P: ProtocolMetrics>(
-        &self,
-        protocol_commands: &P,
-        parameters: &BenchmarkParameters,
-    ) -> MonitorResult<()> {
-        // Configure and reload prometheus.
+    pub async fn start_prometheus(&self) -> MonitorResult<()> {
+        // Upload the configuration and reload prometheus.
         let instance = [self.instance.clone()];
-        let commands = Prometheus::setup_commands(
-            self.nodes.clone(),
-            self.clients.clone(),
-            protocol_commands,
-            parameters,
-        );
+        let commands = Prometheus::setup_commands(&self.prometheus_config(&self.nodes));
         self.executor
             .execute(instance, commands, CommandContext::default())
             .await?;
@@ -77,10 +98,155 @@ impl Monitor {
         Ok(())
     }
 
+    /// Generate the prometheus configuration scraping the specified validators. It contains
+    /// one target per validator, plus the node exporters of the validators and the clients.
+    /// NOTE: The configuration file is a yaml file so spaces are important.
+    pub fn prometheus_config(&self, instances: &[Instance]) -> String {
+        let ips = instances.iter().map(|x| IpAddr::V4(x.main_ip)).collect();
+        let node_parameters = Some(self.node_parameters.clone());
+        let node_config = NodePublicConfig::new_for_benchmarks(ips, node_parameters);
+        let validators = node_config.all_metric_addresses().map(|x| x.to_string());
+
+        let exporter = |x: &Instance| format!("{}:{}", x.main_ip, NodeExporter::DEFAULT_PORT);
+        let validators_exporters = instances.iter().map(exporter);
+        let clients_exporters = self.clients.iter().map(exporter);
+
+        let interval = self.scrape_interval.as_secs().max(1);
+        let mut config = vec![
+            "global:".to_string(),
+            format!("  scrape_interval: {interval}s"),
+            format!("  evaluation_interval: {interval}s"),
+            "scrape_configs:".to_string(),
+        ];
+        config.extend(Prometheus::scrape_configuration(
+            "validators",
+            Some(METRICS_ROUTE),
+            validators,
+        ));
+        config.extend(Prometheus::scrape_configuration(
+            "validators-node-exporter",
+            None,
+            validators_exporters,
+        ));
+        config.extend(Prometheus::scrape_configuration(
+            "clients-node-exporter",
+            None,
+            clients_exporters,
+        ));
+        config.join("\n")
+    }
+
+    /// Upload the mysticeti dashboard to grafana (once it is up).
+    pub async fn provision_dashboard(&self) -> MonitorResult<()> {
+        let instance = std::iter::once(self.instance.clone());
+        let commands = Grafana::dashboard_commands(&Self::grafana_dashboard());
+        self.executor
+            .execute(instance, commands, CommandContext::default())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Generate the grafana dashboard displaying the leaders committed by each authority (by
+    /// commit type), the commit latency, and the throughput of the testbed.
+    pub fn grafana_dashboard() -> serde_json::Value {
+        let [authority, commit_type] = COMMITTED_LEADERS_LABELS;
+        let panels = [
+            (
+                "Committed leaders",
+                "ops",
+                vec![(
+                    format!(
+                        "sum by ({authority}, {commit_type}) \
+                        (rate({COMMITTED_LEADERS_TOTAL}[1m]))"
+                    ),
+                    format!("{{{{{authority}}}}} {{{{{commit_type}}}}}"),
+                )],
+            ),
+            (
+                "Commit latency",
+                "s",
+                [0.5, 0.95]
+                    .into_iter()
+                    .map(|quantile| {
+                        (
+                            format!(
+                                "histogram_quantile({quantile}, \
+                                sum by (le) (rate({COMMIT_LATENCY_S}_bucket[1m])))"
+                            ),
+                            format!("p{}", quantile * 100.0),
+                        )
+                    })
+                    .collect(),
+            ),
+            (
+                "Throughput",
+                "ops",
+                vec![(
+                    format!("sum(rate({LATENCY_S}_count[1m]))"),
+                    "transactions".to_string(),
+                )],
+            ),
+        ];
+
+        let datasource = json!({ "type": "prometheus", "uid": Grafana::DATASOURCE_UID });
+        let panels: Vec<_> = panels
+            .into_iter()
+            .enumerate()
+            .map(|(i, (title, unit, queries))| {
+                let targets: Vec<_> = queries
+                    .into_iter()
+                    .zip('A'..)
+                    .map(|((expr, legend), id)| {
+                        json!({
+                            "refId": id.to_string(),
+                            "datasource": datasource,
+                            "expr": expr,
+                            "legendFormat": legend,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "id": i + 1,
+                    "type": "timeseries",
+                    "title": title,
+                    "datasource": datasource,
+                    "gridPos": { "h": 8, "w": 12, "x": 12 * (i % 2), "y": 8 * (i / 2) },
+                    "fieldConfig": { "defaults": { "unit": unit }, "overrides": [] },
+                    "targets": targets,
+                })
+            })
+            .collect();
+
+        json!({
+            "uid": Grafana::DASHBOARD_UID,
+            "title": "Mysticeti",
+            "timezone": "browser",
+            "refresh": "5s",
+            "time": { "from": "now-15m", "to": "now" },
+            "schemaVersion": 36,
+            "panels": panels,
+        })
+    }
+
     /// The public address of the grafana instance.
     pub fn grafana_address(&self) -> String {
         format!("http://{}:{}", self.instance.main_ip, Grafana::DEFAULT_PORT)
     }
+
+    /// The public url of the mysticeti dashboard (see [`Self::provision_dashboard`]).
+    pub fn dashboard_url(&self) -> String {
+        format!("{}/d/{}", self.grafana_address(), Grafana::DASHBOARD_UID)
+    }
+
+    /// The public address of the prometheus instance.
+    pub fn prometheus_address(&self) -> String {
+        format!(
+            "http://{}:{}",
+            self.instance.main_ip,
+            Prometheus::DEFAULT_PORT
+        )
+    }
 }
 
 /// Generate the commands to setup prometheus on the given instances.
@@ -91,6 +257,8 @@ impl Prometheus {
     const DEFAULT_PROMETHEUS_CONFIG_PATH: &'static str = "/etc/prometheus/prometheus.yml";
     /// The default prometheus port.
     pub const DEFAULT_PORT: u16 = 9090;
+    /// The default interval at which prometheus scrapes its targets.
+    pub const DEFAULT_SCRAPE_INTERVAL: Duration = Duration::from_secs(5);
 
     /// The commands to install prometheus.
     pub fn install_commands() -> Vec<&'static str> {
@@ -100,76 +268,34 @@ impl Prometheus {
         ]
     }
 
-    /// Generate the commands to update the prometheus configuration and restart prometheus.
-    pub fn setup_commands<I, P>(
-        nodes: I,
-        _clients: I,
-        protocol: &P,
-        parameters: &BenchmarkParameters,
-    ) -> String
-    where
-        I: IntoIterator<Item = Instance>,
-        P: ProtocolMetrics,
-    {
-        // Generate the prometheus configuration.
-        let mut config = vec![Self::global_configuration()];
-
-        let nodes_metrics_path = protocol.nodes_metrics_path(nodes, parameters);
-        for (i, (_, nodes_metrics_path)) in nodes_metrics_path.into_iter().enumerate() {
-            let id = format!("node-{i}");
-            let scrape_config = Self::scrape_configuration(&id, &nodes_metrics_path);
-            config.push(scrape_config);
-        }
-
-        // NOTE: Hack to avoid clients metrics.
-        // let clients_metrics_path = protocol.clients_metrics_path(clients, parameters);
-        // for (i, (_, client_metrics_path)) in clients_metrics_path.into_iter().enumerate() {
-        //     let id = format!("client-{i}");
-        //     let scrape_config = Self::scrape_configuration(&id, &client_metrics_path);
-        //     config.push(scrape_config);
-        // }
-
-        // Make the command to configure and restart prometheus.
+    /// Generate the commands to upload the prometheus configuration and restart prometheus.
+    pub fn setup_commands(config: &str) -> String {
         format!(
-            "sudo echo \"{}\" > {} && sudo service prometheus restart",
-            config.join("\n"),
+            "sudo echo \"{config}\" > {} && sudo service prometheus restart",
             Self::DEFAULT_PROMETHEUS_CONFIG_PATH
         )
     }
 
-    /// Generate the global prometheus configuration.
+    /// Generate the lines of a scrape job collecting metrics from the given targets. Jobs
+    /// without targets are omitted.
     /// NOTE: The configuration file is a yaml file so spaces are important.
-    fn global_configuration() -> String {
-        [
-            "global:",
-            "  scrape_interval: 5s",
-            "  evaluation_interval: 5s",
-            "scrape_configs:",
-        ]
-        .join("\n")
-    }
-
-    /// Generate the prometheus configuration from the given metrics path.
-    /// NOTE: The configuration file is a yaml file so spaces are important.
-    fn scrape_configuration(id: &str, nodes_metrics_path: &str) -> String {
-        let parts: Vec<_> = nodes_metrics_path.split('/').collect();
-        let address = parts[0].parse::<SocketAddr>().unwrap();
-        let ip = address.ip();
-        let port = address.port();
-        let path = parts[1];
+    fn scrape_configuration<I>(job: &str, metrics_path: Option<&str>, targets: I) -> Vec<String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let targets: Vec<_> = targets.into_iter().collect();
+        if targets.is_empty() {
+            return Vec::new();
+        }
 
-        [
-            &format!("  - job_name: instance-{id}"),
-            &format!("    metrics_path: /{path}"),
-            "    static_configs:",
-            "      - targets:",
-            &format!("        - {ip}:{port}"),
-            &format!("  - job_name: instance-node-exporter-{id}"),
-            "    static_configs:",
-            "      - targets:",
-            &format!("        - {ip}:9200"),
-        ]
-        .join("\n")
+        let mut lines = vec![format!("  - job_name: {job}")];
+        if let Some(path) = metrics_path {
+            lines.push(format!("    metrics_path: {path}"));
+        }
+        lines.push("    static_configs:".into());
+        lines.push("      - targets:".into());
+        lines.extend(targets.iter().map(|x| format!("        - {x}")));
+        lines
     }
 }
 
@@ -178,8 +304,14 @@ pub struct Grafana;
 impl Grafana {
     /// The path to the datasources directory.
     const DATASOURCES_PATH: &'static str = "/etc/grafana/provisioning/datasources";
+    /// The path where the dashboard is stored before being uploaded to grafana.
+    const DASHBOARD_PATH: &'static str = "/etc/grafana/testbed-dashboard.json";
     /// The default grafana port.
     pub const DEFAULT_PORT: u16 = 3000;
+    /// The uid of the prometheus datasource.
+    pub const DATASOURCE_UID: &'static str = "Fixed-UID-testbed";
+    /// The uid of the mysticeti dashboard.
+    pub const DASHBOARD_UID: &'static str = "mysticeti-testbed";
 
     /// The commands to install grafana.
     pub fn install_commands() -> Vec<&'static str> {
@@ -212,6 +344,23 @@ impl Grafana {
         .join(" && ")
     }
 
+    /// Generate the commands to wait for grafana to be up and upload the specified dashboard
+    /// through its http api (using the default credentials).
+    pub fn dashboard_commands(dashboard: &serde_json::Value) -> String {
+        let address = format!("http://localhost:{}", Self::DEFAULT_PORT);
+        let request = json!({ "dashboard": dashboard, "overwrite": true });
+        [
+            format!("echo '{request}' > {}", Self::DASHBOARD_PATH),
+            format!("for i in $(seq 60); do curl -sf {address}/api/health && break; sleep 1; done"),
+            format!(
+                "curl -sf -X POST -H 'Content-Type: application/json' -u admin:admin \
+                {address}/api/dashboards/db -d @{}",
+                Self::DASHBOARD_PATH
+            ),
+        ]
+        .join(" && ")
+    }
+
     /// Generate the content of the datasource file for the given instance.
     /// NOTE: The datasource file is a yaml file so spaces are important.
     fn datasource() -> String {
@@ -227,7 +376,7 @@ impl Grafana {
             "    orgId: 1",
             &format!("    url: http://localhost:{}", Prometheus::DEFAULT_PORT),
             "    editable: true",
-            "    uid: Fixed-UID-testbed",
+            &format!("    uid: {}", Self::DATASOURCE_UID),
         ]
         .join("\n")
     }
@@ -370,3 +519,139 @@ impl NodeExporter {
         .join("\n")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::Duration,
+    };
+
+    use mysticeti_core::{
+        config::{NodeParameters, NodePublicConfig},
+        metrics::{COMMITTED_LEADERS_LABELS, COMMITTED_LEADERS_TOTAL, COMMIT_LATENCY_S, LATENCY_S},
+        prometheus::METRICS_ROUTE,
+    };
+
+    use super::{Grafana, Monitor};
+    use crate::{client::Instance, executor::Executor, local_executor::LocalCommandExecutor};
+
+    #[test]
+    fn prometheus_config() {
+        let instance = |i: usize| Instance::new_for_test(format!("instance-{i}"));
+        let nodes: Vec<_> = (0..4).map(instance).collect();
+        let clients: Vec<_> = (4..6).map(instance).collect();
+        let executor = Executor::local(LocalCommandExecutor::new("/tmp".into()));
+        let monitor = Monitor::new(instance(6), clients, nodes.clone(), executor)
+            .with_scrape_interval(Duration::from_secs(10));
+
+        let config = monitor.prometheus_config(&nodes);
+        let config: serde_yaml::Value = serde_yaml::from_str(&config).unwrap();
+        assert_eq!(config["global"]["scrape_interval"], "10s");
+
+        let jobs = config["scrape_configs"].as_sequence().unwrap();
+        let targets = |job: &str| {
+            let job = jobs.iter().find(|x| x["job_name"] == job).unwrap();
+            job["static_configs"][0]["targets"]
+                .as_sequence()
+                .unwrap()
+                .len()
+        };
+        assert_eq!(targets("validators"), 4);
+        assert_eq!(targets("validators-node-exporter"), 4);
+        assert_eq!(targets("clients-node-exporter"), 2);
+
+        let validators = jobs.iter().find(|x| x["job_name"] == "validators").unwrap();
+        assert_eq!(validators["metrics_path"], METRICS_ROUTE);
+    }
+
+    #[test]
+    fn prometheus_config_lists_every_node() {
+        let nodes: Vec<_> = (0..4)
+            .map(|i| {
+                let mut instance = Instance::new_for_test(format!("node-{i}"));
+                instance.main_ip = Ipv4Addr::new(10, 0, 0, i + 1);
+                instance
+            })
+            .collect();
+        let executor = Executor::local(LocalCommandExecutor::new("/tmp".into()));
+        let node_parameters = NodeParameters::default();
+        let monitor = Monitor::new(nodes[0].clone(), vec![], nodes.clone(), executor)
+            .with_node_parameters(node_parameters.clone());
+
+        let config = monitor.prometheus_config(&nodes);
+        let config: serde_yaml::Value = serde_yaml::from_str(&config).unwrap();
+        let jobs = config["scrape_configs"].as_sequence().unwrap();
+        let validators = jobs.iter().find(|x| x["job_name"] == "validators").unwrap();
+        let targets: Vec<_> = validators["static_configs"][0]["targets"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .map(|x| x.as_str().unwrap().to_string())
+            .collect();
+
+        // Each node is scraped at its own metrics endpoint, in the order of the nodes.
+        let ips = nodes.iter().map(|x| IpAddr::V4(x.main_ip)).collect();
+        let expected: Vec<_> = NodePublicConfig::new_for_benchmarks(ips, Some(node_parameters))
+            .all_metric_addresses()
+            .map(|x| x.to_string())
+            .collect();
+        assert_eq!(targets, expected);
+        for (node, target) in nodes.iter().zip(&targets) {
+            assert!(target.starts_with(&format!("{}:", node.main_ip)));
+        }
+
+        // The dashboard is served by the monitoring instance.
+        assert_eq!(
+            monitor.dashboard_url(),
+            format!("http://10.0.0.1:3000/d/{}", Grafana::DASHBOARD_UID)
+        );
+    }
+
+    #[test]
+    fn grafana_dashboard() {
+        let dashboard = Monitor::grafana_dashboard();
+        assert!(dashboard["title"].is_string());
+        assert_eq!(dashboard["uid"], Grafana::DASHBOARD_UID);
+
+        // Every panel is well-formed and queries the prometheus datasource.
+        let panels = dashboard["panels"].as_array().unwrap();
+        assert_eq!(panels.len(), 3);
+        let mut expressions = Vec::new();
+        for (i, panel) in panels.iter().enumerate() {
+            assert_eq!(panel["id"], i + 1);
+            assert!(panel["type"].is_string());
+            assert!(panel["title"].is_string());
+            for key in ["h", "w", "x", "y"] {
+                assert!(panel["gridPos"][key].is_u64());
+            }
+            assert_eq!(panel["datasource"]["type"], "prometheus");
+            assert_eq!(panel["datasource"]["uid"], Grafana::DATASOURCE_UID);
+
+            let targets = panel["targets"].as_array().unwrap();
+            assert!(!targets.is_empty());
+            for target in targets {
+                assert!(target["refId"].is_string());
+                assert_eq!(target["datasource"]["uid"], Grafana::DATASOURCE_UID);
+                expressions.push(target["expr"].as_str().unwrap().to_string());
+            }
+        }
+
+        // The panels query the metrics (and labels) exported by the validators.
+        let [authority, commit_type] = COMMITTED_LEADERS_LABELS;
+        let by_commit_type = format!("by ({authority}, {commit_type})");
+        assert!(expressions
+            .iter()
+            .any(|x| x.contains(COMMITTED_LEADERS_TOTAL) && x.contains(&by_commit_type)));
+        let latency = format!("{COMMIT_LATENCY_S}_bucket");
+        assert_eq!(
+            expressions.iter().filter(|x| x.contains(&latency)).count(),
+            2
+        );
+        let throughput = format!("{LATENCY_S}_count");
+        assert!(expressions.iter().any(|x| x.contains(&throughput)));
+
+        // The dashboard can be uploaded with a single-quoted shell string.
+        assert!(!dashboard.to_string().contains('\''));
+    }
+}
@@ -1,30 +1,64 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{
-    collections::{HashMap, VecDeque},
-    fs,
-    path::PathBuf,
-};
+use std::{collections::HashMap, fs, ops::Deref, path::PathBuf, time::Duration};
 
+use futures::future::try_join_all;
+use mysticeti_core::{commit_sink::read_commit_sequence, config::NodePrivateConfig};
 use tokio::time::{self, Instant};
 
 use crate::{
+    assignment::{take_region_balanced, take_with_specs},
     benchmark::BenchmarkParameters,
+    checkpoint::Checkpoint,
     client::Instance,
     display,
     ensure,
-    error::{TestbedError, TestbedResult},
+    error::{CloudProviderError, TestbedError, TestbedResult},
     executor::Executor,
-    faults::CrashRecoverySchedule,
-    logs::LogsAnalyzer,
-    measurements::{Measurement, MeasurementsCollection},
+    faults::{CrashRecoverySchedule, FaultsType, NetworkLatency, NetworkPartition},
+    logs::{
+        check_commit_sequences,
+        print_findings,
+        scan_for_errors,
+        CollectedLogs,
+        LogFinding,
+        LogsAnalyzer,
+    },
+    measurements::{
+        FaultEvent,
+        Measurement,
+        MeasurementsCollection,
+        RepeatedMeasurements,
+        THROUGHPUT_DIVERGENCE_THRESHOLD,
+    },
     monitor::Monitor,
     protocol::{ProtocolCommands, ProtocolMetrics},
-    settings::{CloudProvider, Settings},
+    settings::{CloudProvider, InstanceRole, Settings},
+    soak::{DriftThresholds, SoakReport},
     ssh::{CommandContext, CommandStatus},
 };
 
+/// The instances selected to run a benchmark: the load generators, the nodes, the hot spares, and
+/// the monitoring instance (if any).
+type SelectedInstances = (
+    Vec<Instance>,
+    Vec<Instance>,
+    Vec<Instance>,
+    Option<Instance>,
+);
+
+/// A step of the ordered teardown of a benchmark run.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TeardownStep {
+    /// Kill the load generators to stop submitting new transactions.
+    StopClients,
+    /// Wait for the in-flight transactions to commit.
+    Cooldown(Duration),
+    /// Kill the nodes (without deleting the log files).
+    StopNodes,
+}
+
 /// An orchestrator to deploy nodes and run benchmarks on a testbed.
 pub struct Orchestrator<P> {
     /// The testbed's settings.
@@ -44,6 +78,16 @@ pub struct Orchestrator<P> {
     /// Skip the testbed configuration. Setting this value to true is dangerous and may
     /// lead to unexpected behavior.
     skip_testbed_configuration: bool,
+    /// The number of times to run each benchmark.
+    repeat: usize,
+    /// The interval between the measurement snapshots of a soak test (if any).
+    snapshot_interval: Option<Duration>,
+    /// Walk through the benchmarks without running anything on the instances.
+    dry_run: bool,
+    /// Skip the benchmarks completed by a previous (interrupted) run of the orchestrator.
+    resume: bool,
+    /// The seed of the randomized benchmark decisions (e.g., the nodes to crash).
+    seed: u64,
 }
 
 impl<P> Orchestrator<P> {
@@ -63,6 +107,11 @@ impl<P> Orchestrator<P> {
             executor,
             skip_testbed_update: false,
             skip_testbed_configuration: false,
+            repeat: 1,
+            snapshot_interval: None,
+            dry_run: false,
+            resume: false,
+            seed: 0,
         }
     }
 
@@ -85,69 +134,127 @@ impl<P> Orchestrator<P> {
         self
     }
 
+    /// Set the number of times to run each benchmark. Repeated runs are summarized with
+    /// confidence intervals.
+    pub fn with_repeat(mut self, repeat: usize) -> Self {
+        self.repeat = repeat.max(1);
+        self
+    }
+
+    /// Run the benchmarks as soak tests, taking a measurement snapshot at the specified interval
+    /// to detect drifts (throughput decline, latency growth, memory creep) over time.
+    pub fn with_soak_snapshots(mut self, snapshot_interval: Option<Duration>) -> Self {
+        self.snapshot_interval = snapshot_interval;
+        self
+    }
+
+    /// Walk through the benchmarks without running anything. The commands that would run are
+    /// displayed instead (the executor should be a dry-run executor), and the benchmarks
+    /// complete immediately without collecting any measurement.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Skip the benchmarks that a previous run of the orchestrator already completed with the
+    /// exact same parameters, e.g., to resume a sweep of loads interrupted midway.
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Set the seed of the randomized benchmark decisions. Two runs with the same seed and
+    /// settings crash the same nodes.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// The directory holding the measurements (and checkpoints) of the benchmarks.
+    fn results_directory(&self) -> PathBuf {
+        let commit = &self.settings.repository.commit;
+        self.settings.results_dir.join(format!("results-{commit}"))
+    }
+
+    /// Return the benchmarks left to run, that is, all of them unless resuming a previous run of
+    /// the orchestrator, in which case the benchmarks it completed are skipped.
+    fn pending_benchmarks(
+        &self,
+        checkpoint: &Checkpoint,
+        set_of_parameters: Vec<BenchmarkParameters>,
+    ) -> Vec<BenchmarkParameters> {
+        if !self.resume {
+            return set_of_parameters;
+        }
+        set_of_parameters
+            .into_iter()
+            .filter(|parameters| {
+                let completed = checkpoint.is_completed(parameters);
+                if completed {
+                    display::config("Skipping completed benchmark", parameters);
+                }
+                !completed
+            })
+            .collect()
+    }
+
     /// Returns the instances of the testbed on which to run the benchmarks.
     ///
-    /// This function returns two vectors of instances; the first contains the instances on which to
-    /// run the load generators and the second contains the instances on which to run the nodes.
-    /// Additionally returns an optional monitoring instance.
+    /// This function returns three vectors of instances; the first contains the instances on which
+    /// to run the load generators, the second contains the instances on which to run the nodes,
+    /// and the third contains the hot spares (excluded from the committee). Additionally returns
+    /// an optional monitoring instance.
     pub fn select_instances(
         &self,
         parameters: &BenchmarkParameters,
-    ) -> TestbedResult<(Vec<Instance>, Vec<Instance>, Option<Instance>)> {
-        // Ensure there are enough active instances.
-        let available_instances: Vec<_> = self.instances.iter().filter(|x| x.is_active()).collect();
-        let minimum_instances = parameters.nodes
-            + self.settings.dedicated_clients
-            + if self.settings.monitoring { 1 } else { 0 };
-        ensure!(
-            available_instances.len() >= minimum_instances,
-            TestbedError::InsufficientCapacity(minimum_instances - available_instances.len())
-        );
-
-        // Sort the instances by region. This step ensures that the instances are selected as
-        // equally as possible from all regions.
-        let mut instances_by_regions = HashMap::new();
-        for instance in available_instances {
-            instances_by_regions
-                .entry(&instance.region)
-                .or_insert_with(VecDeque::new)
-                .push_back(instance);
-        }
+    ) -> TestbedResult<SelectedInstances> {
+        // Ensure there are enough active instances (of each specs).
+        let mut pool: Vec<_> = self
+            .instances
+            .iter()
+            .filter(|x| x.is_active())
+            .cloned()
+            .collect();
+        let missing: usize = parameters
+            .required_instances_by_specs()
+            .into_iter()
+            .map(|(specs, required)| {
+                let available = pool.iter().filter(|x| x.has_specs(&specs)).count();
+                required.saturating_sub(available)
+            })
+            .sum();
+        ensure!(missing == 0, TestbedError::InsufficientCapacity(missing));
+        let regions = &self.settings.regions;
 
         // Select the instance to host the monitoring stack.
         let mut monitoring_instance = None;
         if self.settings.monitoring {
-            let region = &self.settings.regions[0];
-            monitoring_instance = instances_by_regions
-                .get_mut(region)
-                .map(|instances| instances.pop_front().unwrap().clone());
-        }
-
-        // Select the instances to host exclusively load generators.
-        let mut client_instances = Vec::new();
-        for region in self.settings.regions.iter().cycle() {
-            if client_instances.len() == self.settings.dedicated_clients {
-                break;
-            }
-            if let Some(regional_instances) = instances_by_regions.get_mut(region) {
-                if let Some(instance) = regional_instances.pop_front() {
-                    client_instances.push(instance.clone());
-                }
-            }
+            let region = &regions[0];
+            let specs = parameters.specs_for(InstanceRole::Monitoring);
+            monitoring_instance = take_with_specs(&mut pool, specs, |pool| {
+                pool.iter()
+                    .position(|x| &x.region == region)
+                    .map(|index| pool.remove(index))
+            });
         }
 
-        // Select the instances to host the nodes.
-        let mut nodes_instances = Vec::new();
-        for region in self.settings.regions.iter().cycle() {
-            if nodes_instances.len() == parameters.nodes {
-                break;
-            }
-            if let Some(regional_instances) = instances_by_regions.get_mut(region) {
-                if let Some(instance) = regional_instances.pop_front() {
-                    nodes_instances.push(instance.clone());
-                }
-            }
-        }
+        // Select the instances to host exclusively load generators. They are selected as
+        // equally as possible from all regions.
+        let specs = parameters.specs_for(InstanceRole::Client);
+        let mut client_instances = take_with_specs(&mut pool, specs, |pool| {
+            take_region_balanced(pool, regions, self.settings.dedicated_clients)
+        });
+
+        // Select the instances to host the nodes and, among the remaining ones, the hot spares.
+        let specs = parameters.specs_for(InstanceRole::Validator);
+        let (nodes_instances, spare_instances) = take_with_specs(&mut pool, specs, |pool| {
+            let nodes = self
+                .settings
+                .assignment
+                .assign(pool, regions, parameters.nodes)?;
+            let spares = take_region_balanced(pool, regions, self.settings.spare_instances);
+            Ok::<_, TestbedError>((nodes, spares))
+        })?;
 
         // Spawn a load generate collocated with each node if there are no instances dedicated
         // to excursively run load generators.
@@ -155,7 +262,73 @@ impl<P> Orchestrator<P> {
             client_instances.clone_from(&nodes_instances);
         }
 
-        Ok((client_instances, nodes_instances, monitoring_instance))
+        Ok((
+            client_instances,
+            nodes_instances,
+            spare_instances,
+            monitoring_instance,
+        ))
+    }
+
+    /// The steps to stop a benchmark run: the load generators are stopped first, then the
+    /// nodes are given some time to commit the in-flight transactions before being stopped.
+    pub fn teardown_steps(&self, parameters: &BenchmarkParameters) -> Vec<TeardownStep> {
+        let mut steps = Vec::new();
+        if parameters.load != 0 {
+            steps.push(TeardownStep::StopClients);
+            if !parameters.settings.drain_cooldown.is_zero() {
+                steps.push(TeardownStep::Cooldown(parameters.settings.drain_cooldown));
+            }
+        }
+        steps.push(TeardownStep::StopNodes);
+        steps
+    }
+
+    /// Specialize the context booting the i-th node with its log file and environment.
+    fn node_context(&self, i: usize, context: CommandContext) -> CommandContext {
+        let mut context = context.with_log_file(self.node_log_file(i));
+        if let Some(rust_log) = self.settings.rust_log_overrides.get(&i) {
+            context = context.with_env("RUST_LOG", rust_log);
+        }
+        context
+    }
+
+    /// The log file of the i-th node. Local instances share the same home directory, so each
+    /// node logs to its own file.
+    fn node_log_file(&self, i: usize) -> PathBuf {
+        if matches!(self.settings.cloud_provider, CloudProvider::Local) {
+            format!("~/node-{i}.log").into()
+        } else {
+            "~/node.log".into()
+        }
+    }
+
+    /// Return the spot instances (among the specified ones) that are no longer reachable.
+    async fn unreachable_spot_instances(&self, instances: &[Instance]) -> Vec<Instance> {
+        let mut unreachable = Vec::new();
+        for instance in instances.iter().filter(|x| x.is_spot()) {
+            let probe = self
+                .executor
+                .execute([instance.clone()], "true", CommandContext::default())
+                .await;
+            if probe.is_err() {
+                unreachable.push(instance.clone());
+            }
+        }
+        unreachable
+    }
+
+    /// The commit sink file of the i-th node.
+    fn node_commit_sequence_file(&self, i: usize) -> PathBuf {
+        self.node_storage_dir(i)
+            .join(NodePrivateConfig::COMMITTED_LEADERS_FILENAME)
+    }
+
+    /// The storage directory of the node with the specified index.
+    fn node_storage_dir(&self, i: usize) -> PathBuf {
+        self.settings
+            .working_dir
+            .join(NodePrivateConfig::default_storage_path(i as u64))
     }
 }
 
@@ -251,18 +424,39 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         Ok(())
     }
 
+    /// Assign a distinct range of ports to the nodes if they all run on the local machine (where
+    /// they would otherwise collide). The ports are written in the configuration of the nodes
+    /// and stay bound until the nodes boot.
+    fn with_local_ports(
+        &self,
+        mut parameters: BenchmarkParameters,
+    ) -> TestbedResult<BenchmarkParameters> {
+        let (_, nodes, _, _) = self.select_instances(&parameters)?;
+        if let Some(ranges) = self.executor.reserve_ports(&nodes)? {
+            let ports = ranges
+                .iter()
+                .map(|x| (x.consensus(), x.metrics()))
+                .collect();
+            parameters.node_parameters = parameters.node_parameters.with_benchmark_ports(ports);
+        }
+        Ok(parameters)
+    }
+
     /// Configure the instances with the appropriate configuration files.
     pub async fn configure(&self, parameters: &BenchmarkParameters) -> TestbedResult<()> {
         display::config("Configuring instances", "");
 
         // Select instances to configure.
-        let (clients, nodes, _) = self.select_instances(parameters)?;
+        let (clients, nodes, spares, _) = self.select_instances(parameters)?;
         for (i, node) in nodes.iter().enumerate() {
             display::config(format!("  - node {i}"), &node.ssh_address());
         }
         for (i, client) in clients.iter().enumerate() {
             display::config(format!("  - client {i}"), &client.ssh_address());
         }
+        for (i, spare) in spares.iter().enumerate() {
+            display::config(format!("  - spare {i}"), &spare.ssh_address());
+        }
 
         // Generate the genesis configuration file and the keystore allowing access to gas objects.
         let command = self
@@ -272,41 +466,30 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
 
         let id = "configure";
         let repo_name = self.settings.repository_name();
-        
-        // For local execution, only run on the first instance since all instances are the same machine
+
         let mut instances = nodes;
         if parameters.settings.dedicated_clients != 0 {
             instances.extend(clients);
         };
-        
-        // Kill any existing configure session first (only need to do this once for local execution)
-        let kill_command = format!("(tmux kill-session -t {id} || true)");
-        let kill_context = CommandContext::default();
-        if matches!(self.settings.cloud_provider, CloudProvider::Local) {
-            // For local execution, only kill once since all instances are the same machine
-            if let Some(first_instance) = instances.first() {
-                self.executor
-                    .execute(std::iter::once(first_instance.clone()), kill_command, kill_context)
-                    .await?;
-            }
-        } else {
-            // For cloud execution, kill on all instances
-            self.executor
-                .execute(instances.iter().cloned(), kill_command, kill_context)
-                .await?;
-        }
-        
+        // Spares need the configuration files to take over the identity of crashed nodes.
+        instances.extend(spares);
+
+        // For local execution, only run on the first instance since all instances are the same
+        // machine.
+        let instances_to_use: Vec<_> =
+            if matches!(self.settings.cloud_provider, CloudProvider::Local) {
+                instances.into_iter().take(1).collect()
+            } else {
+                instances
+            };
+
+        // Kill any existing configure session first.
+        self.executor.kill(instances_to_use.clone(), id).await?;
+
         let context = CommandContext::new()
             .run_background(id.into())
             .with_log_file(format!("~/{id}.log").into())
             .with_execute_from_path(repo_name.into());
-        
-        // For local execution, only execute on first instance to avoid duplicate tmux sessions
-        let instances_to_use: Vec<_> = if matches!(self.settings.cloud_provider, CloudProvider::Local) {
-            instances.into_iter().take(1).collect()
-        } else {
-            instances
-        };
 
         self.executor
             .execute(instances_to_use.clone(), command, context)
@@ -330,6 +513,11 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         if delete_logs {
             command.push("(rm -rf ~/*log* || true)".into());
         }
+        let local = matches!(self.executor, Executor::Local(_));
+        if self.settings.faults.is_network_fault() && !local {
+            command.push(NetworkLatency::clear_command());
+            command.push(NetworkPartition::clear_command());
+        }
         let command = command.join(" ; ");
 
         // Execute the deletion on all machines.
@@ -341,24 +529,117 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         Ok(())
     }
 
+    /// Stop the load generators and nodes of a benchmark run (without deleting the log files).
+    pub async fn teardown(&self, parameters: &BenchmarkParameters) -> TestbedResult<()> {
+        for step in self.teardown_steps(parameters) {
+            match step {
+                TeardownStep::StopClients => {
+                    display::action("Stopping load generators");
+                    let (clients, _, _, _) = self.select_instances(parameters)?;
+                    self.executor.kill(clients, "client").await?;
+                    display::done();
+                }
+                TeardownStep::Cooldown(duration) => {
+                    display::action(format!(
+                        "Waiting {}s for in-flight transactions",
+                        duration.as_secs()
+                    ));
+                    if !self.dry_run {
+                        time::sleep(duration).await;
+                    }
+                    display::done();
+                }
+                TeardownStep::StopNodes => self.cleanup(false).await?,
+            }
+        }
+        Ok(())
+    }
+
     /// Reload prometheus and grafana.
     pub async fn start_monitoring(&self, parameters: &BenchmarkParameters) -> TestbedResult<()> {
-        let (clients, nodes, instance) = self.select_instances(parameters)?;
+        let (clients, nodes, _, instance) = self.select_instances(parameters)?;
         if let Some(instance) = instance {
             display::action("Configuring monitoring instance");
 
-            let monitor = Monitor::new(instance, clients, nodes, self.executor.clone());
-            let commands = &self.protocol_commands;
-            monitor.start_prometheus(commands, parameters).await?;
-            monitor.start_grafana().await?;
+            let monitor = Monitor::new(instance, clients, nodes, self.executor.clone())
+                .with_node_parameters(parameters.node_parameters.deref().clone())
+                .with_scrape_interval(self.settings.scrape_interval);
+            monitor.start().await?;
 
             display::done();
             display::config("Grafana address", monitor.grafana_address());
+            display::config("Dashboard", monitor.dashboard_url());
+            display::config("Prometheus address", monitor.prometheus_address());
             display::newline();
         }
         Ok(())
     }
 
+    /// The commands booting one node per specified instance, along with their context.
+    fn node_targets(
+        &self,
+        instances: Vec<Instance>,
+        parameters: &BenchmarkParameters,
+    ) -> Vec<(Instance, String, CommandContext)> {
+        let repo = self.settings.repository_name();
+        let context = CommandContext::new()
+            .run_background("node".into())
+            .with_execute_from_path(repo.into());
+
+        self.protocol_commands
+            .node_command(instances, parameters)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (instance, command))| {
+                (instance, command, self.node_context(i, context.clone()))
+            })
+            .collect()
+    }
+
+    /// The context running the load generators.
+    fn client_context(&self) -> CommandContext {
+        let repo = self.settings.repository_name();
+        CommandContext::new()
+            .run_background("client".into())
+            .with_log_file("~/client.log".into())
+            .with_execute_from_path(repo.into())
+    }
+
+    /// Render the node and client commands that a benchmark with the specified parameters
+    /// would run, without touching any instance. The output starts with the parameters
+    /// (prefixed by '#') followed by one command per line, prefixed by the id of its instance.
+    pub fn dry_run(&self, parameters: &BenchmarkParameters) -> TestbedResult<Vec<String>> {
+        let (clients, nodes, _, _) = self.select_instances(parameters)?;
+
+        let mut lines = vec![
+            format!("# benchmark: {parameters}"),
+            format!(
+                "# node parameters: {}",
+                serde_json::to_string(&parameters.node_parameters).unwrap()
+            ),
+            format!(
+                "# client parameters: {}",
+                serde_json::to_string(&parameters.client_parameters).unwrap()
+            ),
+        ];
+
+        let render = |instance: &Instance, command: String, context: &CommandContext| {
+            let command = context.for_instance(instance).apply(command);
+            format!("{} {command}", instance.id)
+        };
+        for (instance, command, context) in self.node_targets(nodes, parameters) {
+            lines.push(render(&instance, command, &context));
+        }
+        if parameters.load != 0 {
+            let context = self.client_context();
+            let targets = self.protocol_commands.client_command(clients, parameters);
+            for (instance, command) in targets {
+                lines.push(render(&instance, command, &context));
+            }
+        }
+        Ok(lines)
+    }
+
     /// Boot a node on the specified instances.
     async fn boot_nodes(
         &self,
@@ -366,45 +647,82 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         parameters: &BenchmarkParameters,
     ) -> TestbedResult<()> {
         // Run one node per instance.
+        let targets = self.node_targets(instances, parameters);
+        self.boot_node_targets(targets, parameters).await
+    }
+
+    /// Restart the nodes of the committee running on the specified instances. Unlike
+    /// [`Self::boot_nodes`], each node keeps the identity of its position in the committee and
+    /// thus resumes from its own storage.
+    async fn recover_nodes(
+        &self,
+        nodes: &[Instance],
+        recovered: &[Instance],
+        parameters: &BenchmarkParameters,
+    ) -> TestbedResult<()> {
         let targets = self
-            .protocol_commands
-            .node_command(instances.clone(), parameters);
+            .node_targets(nodes.to_vec(), parameters)
+            .into_iter()
+            .filter(|(instance, ..)| recovered.contains(instance))
+            .collect();
+        self.boot_node_targets(targets, parameters).await
+    }
 
-        let repo = self.settings.repository_name();
-        
-        // For local execution, use unique session names per instance to avoid conflicts
-        // For cloud execution, use the same session name since instances are separate machines
-        let is_local = matches!(self.settings.cloud_provider, CloudProvider::Local);
-        
-        if is_local {
-            // For local execution, execute each node with a unique session name
-            for (i, (instance, command)) in targets.into_iter().enumerate() {
-                let session_name = format!("node-{}", i);
-                let log_file = format!("~/node-{}.log", i);
-                let context = CommandContext::new()
-                    .run_background(session_name)
-                    .with_log_file(log_file.into())
-                    .with_execute_from_path(repo.clone().into());
-                
-                self.executor
-                    .execute_per_instance(std::iter::once((instance, command)), context)
-                    .await?;
-            }
-        } else {
-            // For cloud execution, use the same session name for all
-            let context = CommandContext::new()
-                .run_background("node".into())
-                .with_log_file("~/node.log".into())
-                .with_execute_from_path(repo.into());
+    /// The commands booting the specified spares, each taking over the identity of the crashed
+    /// node it replaces. The protocol assigns identities by position in the committee, so the
+    /// spares are substituted to the crashed nodes in the list of nodes.
+    fn promotion_targets(
+        &self,
+        nodes: &[Instance],
+        promoted: &[(Instance, Instance)],
+        parameters: &BenchmarkParameters,
+    ) -> Vec<(Instance, String, CommandContext)> {
+        let spares: Vec<_> = promoted.iter().map(|(_, spare)| spare).collect();
+        let nodes = nodes
+            .iter()
+            .map(|node| {
+                promoted
+                    .iter()
+                    .find(|(crashed, _)| crashed == node)
+                    .map_or(node, |(_, spare)| spare)
+                    .clone()
+            })
+            .collect();
+        self.node_targets(nodes, parameters)
+            .into_iter()
+            .filter(|(instance, ..)| spares.contains(&instance))
+            .collect()
+    }
+
+    /// Boot the specified spares in place of the crashed nodes they replace.
+    async fn promote_spares(
+        &self,
+        nodes: &[Instance],
+        promoted: &[(Instance, Instance)],
+        parameters: &BenchmarkParameters,
+    ) -> TestbedResult<()> {
+        let targets = self.promotion_targets(nodes, promoted, parameters);
+        self.boot_node_targets(targets, parameters).await
+    }
+
+    /// Run the specified node commands and wait until the nodes are reachable.
+    async fn boot_node_targets(
+        &self,
+        targets: Vec<(Instance, String, CommandContext)>,
+        parameters: &BenchmarkParameters,
+    ) -> TestbedResult<()> {
+        let instances: Vec<_> = targets.iter().map(|(x, ..)| x.clone()).collect();
+        self.executor.release_ports(&instances);
+        let boots = targets.into_iter().map(|(instance, command, context)| {
             self.executor
-                .execute_per_instance(targets, context)
-                .await?;
-        }
+                .execute_per_instance(std::iter::once((instance, command)), context)
+        });
+        try_join_all(boots).await?;
 
         // Wait until all nodes are reachable.
         let commands = self
             .protocol_commands
-            .nodes_metrics_command(instances.clone(), parameters);
+            .nodes_metrics_command(instances, parameters);
         self.executor.wait_for_success(commands).await;
 
         Ok(())
@@ -415,7 +733,7 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         display::action("\nDeploying validators");
 
         // Select the instances to run.
-        let (_, nodes, _) = self.select_instances(parameters)?;
+        let (_, nodes, _, _) = self.select_instances(parameters)?;
 
         // Boot one node per instance.
         self.boot_nodes(nodes, parameters).await?;
@@ -424,6 +742,76 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         Ok(())
     }
 
+    /// Inject the network faults (if any) between the validators: latency, degraded links,
+    /// or partitions.
+    pub async fn inject_network_faults(
+        &self,
+        parameters: &BenchmarkParameters,
+    ) -> TestbedResult<()> {
+        if !self.settings.faults.is_network_fault() {
+            return Ok(());
+        }
+        if matches!(self.executor, Executor::Local(_)) {
+            display::warn("Network faults are not supported locally, ignoring them");
+            return Ok(());
+        }
+
+        display::action(format!("Injecting {}", self.settings.faults));
+        let (_, nodes, _, _) = self.select_instances(parameters)?;
+        let targets = match &self.settings.faults {
+            FaultsType::Latency { millis, jitter } => {
+                let command = NetworkLatency::inject_command(*millis, *jitter);
+                nodes.into_iter().map(|x| (x, command.clone())).collect()
+            }
+            FaultsType::Degrade {
+                targets,
+                delay_ms,
+                loss_percent,
+                rate_mbit,
+            } => {
+                let command = NetworkLatency::degrade_command(*delay_ms, *loss_percent, *rate_mbit);
+                targets
+                    .iter()
+                    .filter_map(|i| nodes.get(*i))
+                    .map(|x| (x.clone(), command.clone()))
+                    .collect()
+            }
+            FaultsType::Partition { groups } => {
+                NetworkPartition::partition_commands(&nodes, groups)
+            }
+            _ => Vec::new(),
+        };
+        self.executor
+            .execute_per_instance(targets, CommandContext::default())
+            .await?;
+        display::done();
+        Ok(())
+    }
+
+    /// Remove the network faults injected between the validators.
+    pub async fn remove_network_faults(
+        &self,
+        parameters: &BenchmarkParameters,
+    ) -> TestbedResult<()> {
+        let local = matches!(self.executor, Executor::Local(_));
+        if !self.settings.faults.is_network_fault() || local {
+            return Ok(());
+        }
+
+        display::action("Removing injected network faults");
+        let (_, nodes, _, _) = self.select_instances(parameters)?;
+        let command = [
+            NetworkLatency::clear_command(),
+            NetworkPartition::clear_command(),
+        ]
+        .join(" ; ");
+        self.executor
+            .execute(nodes, command, CommandContext::default())
+            .await?;
+        display::done();
+        Ok(())
+    }
+
     /// Deploy the load generators.
     pub async fn run_clients(&self, parameters: &BenchmarkParameters) -> TestbedResult<()> {
         if parameters.load == 0 {
@@ -434,20 +822,14 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         display::action("Setting up load generators");
 
         // Select the instances to run.
-        let (clients, _, _) = self.select_instances(parameters)?;
+        let (clients, _, _, _) = self.select_instances(parameters)?;
 
         // Deploy the load generators.
         let targets = self
             .protocol_commands
             .client_command(clients.clone(), parameters);
-
-        let repo = self.settings.repository_name();
-        let context = CommandContext::new()
-            .run_background("client".into())
-            .with_log_file("~/client.log".into())
-            .with_execute_from_path(repo.into());
         self.executor
-            .execute_per_instance(targets, context)
+            .execute_per_instance(targets, self.client_context())
             .await?;
 
         // Wait until all load generators are reachable.
@@ -467,11 +849,11 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
     ) -> TestbedResult<MeasurementsCollection> {
         display::action(format!(
             "Scraping metrics (at least {}s)",
-            self.settings.benchmark_duration.as_secs()
+            parameters.total_duration().as_secs()
         ));
 
         // Select the instances to run.
-        let (clients, nodes, _) = self.select_instances(parameters)?;
+        let (clients, nodes, spares, _) = self.select_instances(parameters)?;
         let mut killed_nodes: Vec<Instance> = Vec::new();
 
         // Regularly scrape the client metrics.
@@ -484,10 +866,20 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         metrics_interval.tick().await; // The first tick returns immediately.
 
         let faults_type = parameters.settings.faults.clone();
-        let mut faults_schedule = CrashRecoverySchedule::new(faults_type, nodes.clone());
+        let mut faults_schedule = CrashRecoverySchedule::new(faults_type, nodes.clone())
+            .with_spares(spares)
+            .with_seed(self.seed);
         let mut faults_interval = time::interval(self.settings.faults.crash_interval());
         faults_interval.tick().await; // The first tick returns immediately.
 
+        let mut soak_report = SoakReport::new();
+        let mut memory = None;
+        let snapshot_period = self
+            .snapshot_interval
+            .unwrap_or(self.settings.scrape_interval);
+        let mut snapshot_interval = time::interval(snapshot_period);
+        snapshot_interval.tick().await; // The first tick returns immediately.
+
         let start = Instant::now();
         loop {
             tokio::select! {
@@ -499,55 +891,108 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
                     let mut instances = metrics_commands.clone();
                     instances.retain(|(instance, _)| !killed_nodes.contains(instance));
 
-                    let stdio = self
+                    let targets: Vec<_> = instances.iter().map(|(x, _)| x.clone()).collect();
+                    let stdio = match self
                         .executor
                         .execute_per_instance(instances, CommandContext::default())
-                        .await?;
+                        .await
+                    {
+                        Ok(stdio) => stdio,
+                        Err(e) => {
+                            // The cloud provider may reclaim spot instances at any time.
+                            let lost = self.unreachable_spot_instances(&targets).await;
+                            if lost.is_empty() {
+                                return Err(e.into());
+                            }
+                            for instance in &lost {
+                                let id = instance.id.clone();
+                                let error = CloudProviderError::SpotInterrupted(id);
+                                display::warn(format!("{error}; excluding it from the benchmark"));
+                            }
+                            killed_nodes.extend(lost);
+                            continue;
+                        }
+                    };
 
+                    memory = None;
                     for (i, (stdout, _stderr)) in stdio.iter().enumerate() {
                         for (label, measurement) in Measurement::from_prometheus::<P>(stdout) {
                             aggregator.add(i, label, measurement);
                         }
+                        if let Some(duration) = Measurement::time_to_first_commit::<P>(stdout) {
+                            aggregator.add_time_to_first_commit(i, duration);
+                        }
+                        if let Some(breakdown) = Measurement::latency_breakdown::<P>(stdout) {
+                            aggregator.add_latency_breakdown(i, breakdown);
+                        }
+                        if let Some(metrics) = Measurement::node_metrics::<P>(stdout) {
+                            aggregator.add_node_metrics(i, metrics);
+                        }
+                        memory = memory.max(Measurement::memory_usage::<P>(stdout));
                     }
 
-                    let results_directory = &self.settings.results_dir;
-                    let commit = &self.settings.repository.commit;
-                    let path: PathBuf = results_directory.join(&format!("results-{commit}"));
+                    let path = self.results_directory();
                     fs::create_dir_all(&path).expect("Failed to create log directory");
                     aggregator.save(path);
 
-                    let benchmark_duration = parameters.settings.benchmark_duration.as_secs();
-                    if elapsed > benchmark_duration {
+                    if elapsed > parameters.total_duration().as_secs() {
                         break;
                     }
                 },
 
+                // Take a measurement snapshot of the soak test.
+                _ = snapshot_interval.tick(), if self.snapshot_interval.is_some() => {
+                    if let Some(snapshot) = soak_report.snapshot(&aggregator, memory) {
+                        display::newline();
+                        display::config(
+                            "Soak snapshot",
+                            format!(
+                                "{:.0} tx/s, {} ms",
+                                snapshot.tps,
+                                snapshot.average_latency.as_millis()
+                            ),
+                        );
+                    }
+                }
+
                 // Kill and recover nodes according to the input schedule.
-                _ = faults_interval.tick() => {
+                now = faults_interval.tick() => {
+                    let elapsed = now.duration_since(start);
+                    faults_schedule.observe_elapsed(elapsed);
+                    if faults_schedule.awaits_round() {
+                        if let Some(round) = self.highest_round(&nodes, &killed_nodes).await? {
+                            faults_schedule.observe_round(round);
+                        }
+                    }
                     let action = faults_schedule.update();
                     if !action.kill.is_empty() {
                         killed_nodes.extend(action.kill.clone());
-                        // For local execution, we need to find the index of each instance in the original nodes list
-                        // to determine the correct session name. For now, kill all node-* sessions.
-                        if matches!(self.settings.cloud_provider, CloudProvider::Local) {
-                            // Kill all node sessions (node-0, node-1, etc.) - this is safe since we're killing specific instances
-                            for i in 0..action.kill.len() {
-                                let session_name = format!("node-{}", i);
-                                // Try to kill each session - some might not exist, which is fine
-                                let _ = self.executor.kill(action.kill.iter().take(1).cloned(), &session_name).await;
-                            }
-                            // Also kill the generic "node" session in case it exists
-                            let _ = self.executor.kill(action.kill.clone(), "node").await;
-                        } else {
-                            self.executor.kill(action.kill.clone(), "node").await?;
-                        }
+                        self.executor.kill(action.kill.clone(), "node").await?;
                     }
                     if !action.boot.is_empty() {
                         // Monitor not yet supported for this
                         killed_nodes.retain(|instance| !action.boot.contains(instance));
-                        self.boot_nodes(action.boot.clone(), parameters).await?;
+                        self.recover_nodes(&nodes, &action.boot, parameters).await?;
                     }
                     if !action.kill.is_empty() || !action.boot.is_empty() {
+                        let indices = |instances: &[Instance]| -> Vec<usize> {
+                            instances
+                                .iter()
+                                .filter_map(|x| nodes.iter().position(|node| node == x))
+                                .collect()
+                        };
+                        let event = FaultEvent {
+                            elapsed,
+                            crashed: indices(&action.kill),
+                            recovered: indices(&action.boot),
+                        };
+                        aggregator.add_fault_event(event);
+                    }
+                    if !action.promote.is_empty() {
+                        self.promote_spares(&nodes, &action.promote, parameters)
+                            .await?;
+                    }
+                    if !action.is_no_op() {
                         display::newline();
                         display::config("Testbed update", action);
                     }
@@ -556,16 +1001,68 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         }
 
         display::done();
+        if self.snapshot_interval.is_some() {
+            let path = self.results_directory();
+            soak_report.save(path, &aggregator);
+            soak_report.display_summary(&DriftThresholds::default());
+        }
+        if let Some(endpoint) = &self.settings.remote_write_endpoint {
+            // Pushing the measurements is best-effort: they are saved locally regardless.
+            if let Err(e) = aggregator.push_remote_write(endpoint.as_str()).await {
+                display::warn(e);
+            }
+        }
+        if let Some(divergence) = aggregator.throughput_divergence() {
+            if divergence > THROUGHPUT_DIVERGENCE_THRESHOLD {
+                display::warn(format!(
+                    "The throughput measured by the load generators diverges from the \
+                    transactions received by the nodes ({:.0} tx/s) by {:.0}%: the load \
+                    generators or the nodes may be saturated",
+                    aggregator.node_submitted_tps(),
+                    divergence * 100.0
+                ));
+            }
+        }
         Ok(aggregator)
     }
 
-    /// Download the log files from the nodes and clients.
+    /// Return the highest round reached by the nodes that are still alive, as reported by
+    /// their logs.
+    async fn highest_round(
+        &self,
+        nodes: &[Instance],
+        killed_nodes: &[Instance],
+    ) -> TestbedResult<Option<u64>> {
+        let targets = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, instance)| !killed_nodes.contains(instance))
+            .map(|(i, instance)| {
+                let log_file = self.node_log_file(i);
+                let command = format!(
+                    "(grep 'Reached round' {} | tail -n 1 || true)",
+                    log_file.display()
+                );
+                (instance.clone(), command)
+            });
+        let stdio = self
+            .executor
+            .execute_per_instance(targets, CommandContext::default())
+            .await?;
+        Ok(stdio
+            .iter()
+            .filter_map(|(stdout, _)| LogsAnalyzer::highest_round(stdout))
+            .max())
+    }
+
+    /// Download the log files from the nodes and clients, and scan them for the log patterns of
+    /// the settings.
     pub async fn download_logs(
         &self,
         parameters: &BenchmarkParameters,
-    ) -> TestbedResult<LogsAnalyzer> {
+    ) -> TestbedResult<(LogsAnalyzer, Vec<LogFinding>)> {
         // Select the instances to run.
-        let (clients, nodes, _) = self.select_instances(parameters)?;
+        let (clients, nodes, _, _) = self.select_instances(parameters)?;
 
         // Create a log sub-directory for this run.
         let commit = &self.settings.repository.commit;
@@ -578,17 +1075,19 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         .collect();
         fs::create_dir_all(&path).expect("Failed to create log directory");
 
-        // NOTE: Our ssh library does not seem to be able to transfers files in parallel reliably.
+        let concurrency = self.settings.download_concurrency;
+        let patterns = &self.settings.log_patterns;
         let mut log_parsers = Vec::new();
+        let mut findings = Vec::new();
 
         // Download the clients log files.
         display::action("Downloading clients logs");
-        for (i, instance) in clients.iter().enumerate() {
-            display::status(format!("{}/{}", i + 1, clients.len()));
-
-            let connection = self.executor.connect(instance.ssh_address()).await?;
-            let client_log_content = connection.download("client.log")?;
-
+        let targets = clients.iter().map(|x| (x.clone(), "client.log"));
+        let logs = CollectedLogs::download(&self.executor, targets, concurrency).await;
+        for (i, content) in logs.contents.iter().enumerate() {
+            let Some(client_log_content) = content else {
+                continue;
+            };
             let client_log_file = [path.clone(), format!("client-{i}.log").into()]
                 .iter()
                 .collect::<PathBuf>();
@@ -596,33 +1095,159 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
                 .expect("Cannot write log file");
 
             let mut log_parser = LogsAnalyzer::default();
-            log_parser.set_client_errors(&client_log_content);
+            log_parser.set_client_errors(client_log_content);
             log_parsers.push(log_parser)
         }
         display::done();
+        logs.print_failures();
+        let client_logs: HashMap<_, _> = clients
+            .iter()
+            .zip(logs.contents)
+            .filter_map(|(instance, content)| Some((instance.clone(), content?)))
+            .collect();
+        findings.extend(scan_for_errors(&client_logs, patterns));
 
         display::action("Downloading nodes logs");
-        for (i, instance) in nodes.iter().enumerate() {
-            display::status(format!("{}/{}", i + 1, nodes.len()));
-
-            let connection = self.executor.connect(instance.ssh_address()).await?;
-            let node_log_content = connection.download("node.log")?;
-
+        let targets = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x.clone(), self.node_log_file(i)));
+        let logs = CollectedLogs::download(&self.executor, targets, concurrency).await;
+        let mut panicked = Vec::new();
+        for (i, content) in logs.contents.iter().enumerate() {
+            let Some(node_log_content) = content else {
+                continue;
+            };
             let node_log_file = [path.clone(), format!("node-{i}.log").into()]
                 .iter()
                 .collect::<PathBuf>();
             fs::write(&node_log_file, node_log_content.as_bytes()).expect("Cannot write log file");
 
             let mut log_parser = LogsAnalyzer::default();
-            log_parser.set_node_errors(&node_log_content);
+            log_parser.set_node_errors(node_log_content);
+            if log_parser.node_panic {
+                panicked.push(i);
+            }
             log_parsers.push(log_parser)
         }
         display::done();
+        logs.print_failures();
+        let node_logs: HashMap<_, _> = nodes
+            .iter()
+            .zip(logs.contents)
+            .filter_map(|(instance, content)| Some((instance.clone(), content?)))
+            .collect();
+        findings.extend(scan_for_errors(&node_logs, patterns));
+
+        // Keep the storage of the nodes that panicked for post-mortem analysis.
+        if !panicked.is_empty() {
+            display::action("Downloading the storage of panicked nodes");
+            let targets = panicked.into_iter().map(|i| {
+                let local = path.join(format!("storage-{i}"));
+                (nodes[i].clone(), self.node_storage_dir(i), local)
+            });
+            match self
+                .executor
+                .download_dir_per_instance(targets, concurrency)
+                .await
+            {
+                Ok(()) => display::done(),
+                Err(e) => display::warn(format!("Failed to download the storage: {e}")),
+            }
+        }
 
-        Ok(log_parsers
-            .into_iter()
-            .max()
-            .expect("At least one log parser"))
+        let analyzer = log_parsers.into_iter().max().unwrap_or_default();
+        Ok((analyzer, findings))
+    }
+
+    /// Download the sequences of committed leaders written by the commit sink of the nodes and
+    /// check that all nodes committed the same sequence (up to the slowest node). When nodes
+    /// are instructed to equivocate, only the correct nodes are checked and any divergence
+    /// between them is a safety violation.
+    pub async fn collect_commit_sequences(
+        &self,
+        parameters: &BenchmarkParameters,
+    ) -> TestbedResult<()> {
+        display::action("Collecting commit sequences");
+
+        // Select the instances to run.
+        let (_, nodes, _, _) = self.select_instances(parameters)?;
+
+        // Save the sequences with the log files of this run.
+        let commit = &self.settings.repository.commit;
+        let path: PathBuf = [
+            &self.settings.logs_dir,
+            &format!("logs-{commit}").into(),
+            &format!("logs-{parameters:?}").into(),
+        ]
+        .iter()
+        .collect();
+        fs::create_dir_all(&path).expect("Failed to create log directory");
+
+        let targets = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x.clone(), self.node_commit_sequence_file(i)));
+        let contents = self
+            .executor
+            .download_per_instance(targets, self.settings.download_concurrency)
+            .await?;
+
+        let equivocating = self.settings.faults.equivocating_nodes();
+        let mut sequences = Vec::new();
+        for (i, content) in contents.iter().enumerate().skip(equivocating) {
+            let file = path.join(format!("commits-{i}.txt"));
+            fs::write(&file, content.as_bytes()).expect("Cannot write commit sequence");
+            match read_commit_sequence(&file) {
+                Ok(sequence) => sequences.push((i, sequence)),
+                Err(e) => display::warn(format!("Malformed commit sequence of node {i}: {e}")),
+            }
+        }
+        display::done();
+
+        let divergences = check_commit_sequences(&sequences);
+        for divergence in &divergences {
+            display::error(divergence);
+        }
+        if equivocating != 0 {
+            if let Some(divergence) = divergences.first() {
+                return Err(TestbedError::SafetyViolation(divergence.to_string()));
+            }
+        }
+        let length = sequences
+            .iter()
+            .map(|(_, x)| x.len())
+            .min()
+            .unwrap_or_default();
+        display::config("Common committed leaders", length);
+        Ok(())
+    }
+
+    /// Deploy the validators and the load generators, and wait for the benchmark to terminate.
+    /// Returns `None` if the benchmark duration is zero (the validators are left running).
+    async fn run_benchmark(
+        &self,
+        parameters: &BenchmarkParameters,
+    ) -> TestbedResult<Option<MeasurementsCollection>> {
+        // Display the commands of the validators and load generators instead of deploying them.
+        if self.dry_run {
+            for line in self.dry_run(parameters)? {
+                display::dry_run(line);
+            }
+            return Ok(Some(MeasurementsCollection::new(parameters.clone())));
+        }
+
+        // Deploy the validators.
+        self.run_nodes(parameters).await?;
+        if parameters.settings.benchmark_duration.as_secs() == 0 {
+            return Ok(None);
+        }
+
+        // Deploy the load generators.
+        self.run_clients(parameters).await?;
+
+        // Wait for the benchmark to terminate. Then save the results.
+        self.run(parameters).await.map(Some)
     }
 
     /// Run all the benchmarks specified by the benchmark generator.
@@ -632,6 +1257,9 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
     ) -> TestbedResult<()> {
         display::header("Preparing testbed");
         display::config("Commit", format!("'{}'", &self.settings.repository.commit));
+        let mut checkpoint =
+            Checkpoint::load(self.results_directory()).expect("Failed to load the checkpoints");
+        let set_of_parameters = self.pending_benchmarks(&checkpoint, set_of_parameters);
         display::newline();
 
         // Cleanup the testbed (in case the previous run was not completed).
@@ -672,7 +1300,13 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
             
             // Don't use log file redirection for build command to avoid shell syntax issues
             let build_context = CommandContext::default();
-            self.executor.execute(active, build_command, build_context).await?;
+
+            // Show the progress of the build (which may take a while) as it goes.
+            self.executor
+                .execute_streaming(active, build_command, build_context, |_, line| {
+                    display::status(line.trim())
+                })
+                .await?;
             display::done();
         }
 
@@ -680,42 +1314,86 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         let mut i = 1;
         let mut latest_committee_size = 0;
         for parameters in set_of_parameters {
-            display::header(format!("Starting benchmark {i}"));
-            display::config("Node Parameters", &parameters.node_parameters);
-            display::config("Benchmark Parameters", &parameters);
-            display::newline();
+            let parameters = self.with_local_ports(parameters)?;
+            let mut repeated = RepeatedMeasurements::new();
+            for repetition in 1..=self.repeat {
+                if self.repeat > 1 {
+                    display::header(format!(
+                        "Starting benchmark {i} (run {repetition}/{})",
+                        self.repeat
+                    ));
+                } else {
+                    display::header(format!("Starting benchmark {i}"));
+                }
+                display::config("Node Parameters", &parameters.node_parameters);
+                display::config("Benchmark Parameters", &parameters);
+                display::newline();
+
+                // Cleanup the testbed (in case the previous run was not completed).
+                self.cleanup(true).await?;
+                // Start the instance monitoring tools.
+                self.start_monitoring(&parameters).await?;
+
+                // Configure all instances (if needed).
+                if !self.skip_testbed_configuration && latest_committee_size != parameters.nodes {
+                    self.configure(&parameters).await?;
+                    latest_committee_size = parameters.nodes;
+                }
 
-            // Cleanup the testbed (in case the previous run was not completed).
-            self.cleanup(true).await?;
-            // Start the instance monitoring tools.
-            self.start_monitoring(&parameters).await?;
+                // Inject the network faults (if any) before the benchmark starts. They are
+                // removed even if the injection or the benchmark fails; the cleanup of the
+                // next run removes the ones left behind by an aborted run.
+                let result = match self.inject_network_faults(&parameters).await {
+                    Ok(()) => self.run_benchmark(&parameters).await,
+                    Err(e) => Err(e),
+                };
+                self.remove_network_faults(&parameters).await?;
+                let Some(aggregator) = result? else {
+                    return Ok(());
+                };
+                if self.dry_run {
+                    // Nothing ran, so there is nothing to measure or collect.
+                    self.teardown(&parameters).await?;
+                    continue;
+                }
+                aggregator.display_summary();
+                repeated.add(aggregator);
+
+                // Drain the load generators, then kill the nodes (without deleting the log files).
+                self.teardown(&parameters).await?;
+
+                // Download the log files.
+                if self.settings.log_processing {
+                    let (error_counter, findings) = self.download_logs(&parameters).await?;
+                    error_counter.print_summary();
+                    print_findings(&findings);
+                    if let Some(finding) = findings.iter().find(|x| x.critical) {
+                        return Err(TestbedError::CriticalLogFinding(finding.to_string()));
+                    }
+                }
 
-            // Configure all instances (if needed).
-            if !self.skip_testbed_configuration && latest_committee_size != parameters.nodes {
-                self.configure(&parameters).await?;
-                latest_committee_size = parameters.nodes;
+                // Collect the commit sequences of the nodes (if enabled).
+                if parameters.node_parameters.enable_commit_sink
+                    || self.settings.faults.equivocating_nodes() != 0
+                {
+                    self.collect_commit_sequences(&parameters).await?;
+                }
             }
 
-            // Deploy the validators.
-            self.run_nodes(&parameters).await?;
-            if parameters.settings.benchmark_duration.as_secs() == 0 {
-                return Ok(());
+            // Summarize the repeated runs of this benchmark.
+            if self.repeat > 1 && !self.dry_run {
+                let path = self.results_directory();
+                fs::create_dir_all(&path).expect("Failed to create log directory");
+                repeated.save(path);
+                repeated.display_summary();
             }
 
-            // Deploy the load generators.
-            self.run_clients(&parameters).await?;
-
-            // Wait for the benchmark to terminate. Then save the results and print a summary.
-            let aggregator = self.run(&parameters).await?;
-            aggregator.display_summary();
-
-            // Kill the nodes and clients (without deleting the log files).
-            self.cleanup(false).await?;
-
-            // Download the log files.
-            if self.settings.log_processing {
-                let error_counter = self.download_logs(&parameters).await?;
-                error_counter.print_summary();
+            // Record the completion of the benchmark right away, in case the orchestrator is
+            // interrupted during the next ones.
+            if !self.dry_run {
+                checkpoint
+                    .record(&parameters)
+                    .expect("Failed to record the checkpoint");
             }
 
             i += 1;
@@ -725,3 +1403,342 @@ impl<P: ProtocolCommands + ProtocolMetrics> Orchestrator<P> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        net::{IpAddr, TcpListener},
+        ops::Deref,
+        time::Duration,
+    };
+
+    use mysticeti_core::config::NodePublicConfig;
+
+    use super::{Orchestrator, TeardownStep};
+    use crate::{
+        benchmark::BenchmarkParameters,
+        checkpoint::Checkpoint,
+        client::Instance,
+        executor::Executor,
+        local_executor::LocalCommandExecutor,
+        protocol::{mysticeti::MysticetiProtocol, ProtocolMetrics},
+        settings::Settings,
+        ssh::CommandContext,
+    };
+
+    /// The test settings, with a single region.
+    fn test_settings() -> Settings {
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["region".into()];
+        settings
+    }
+
+    /// Make the specified number of test instances in the region of the test settings.
+    fn test_instances(n: usize) -> Vec<Instance> {
+        (0..n)
+            .map(|i| {
+                let mut instance = Instance::new_for_test(i.to_string());
+                instance.region = "region".into();
+                instance
+            })
+            .collect()
+    }
+
+    /// Make an orchestrator running the specified protocol on the specified instances. Its
+    /// commands are executed locally.
+    fn test_orchestrator<P>(
+        settings: &Settings,
+        instances: Vec<Instance>,
+        protocol: P,
+    ) -> Orchestrator<P> {
+        let executor = Executor::local(LocalCommandExecutor::new("/tmp".into()));
+        Orchestrator::new(settings.clone(), instances, Vec::new(), protocol, executor)
+    }
+
+    #[test]
+    fn spares_excluded_from_committee() {
+        let mut settings = test_settings();
+        settings.spare_instances = 2;
+        let orchestrator = test_orchestrator(&settings, test_instances(6), ());
+
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings = settings;
+        let (clients, nodes, spares, monitoring) =
+            orchestrator.select_instances(&parameters).unwrap();
+
+        // The committee is assigned from the first instances.
+        let ids: Vec<_> = nodes.iter().map(|x| x.id.as_str()).collect();
+        assert_eq!(ids, ["0", "1", "2", "3"]);
+        let ids: Vec<_> = spares.iter().map(|x| x.id.as_str()).collect();
+        assert_eq!(ids, ["4", "5"]);
+        assert_eq!(clients, nodes);
+        assert!(monitoring.is_none());
+    }
+
+    #[test]
+    fn promoted_spares_take_over_identity() {
+        let mut settings = test_settings();
+        settings.spare_instances = 2;
+        let protocol = MysticetiProtocol::new(&settings);
+        let orchestrator = test_orchestrator(&settings, test_instances(6), protocol);
+
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings = settings;
+        let (_, nodes, spares, _) = orchestrator.select_instances(&parameters).unwrap();
+
+        // The spares replace the crashed nodes 1 and 3, and boot with their authority indices.
+        let promoted = [
+            (nodes[1].clone(), spares[0].clone()),
+            (nodes[3].clone(), spares[1].clone()),
+        ];
+        let targets = orchestrator.promotion_targets(&nodes, &promoted, &parameters);
+        assert_eq!(targets.len(), 2);
+        for ((_, spare), (authority, (instance, command, _))) in
+            promoted.iter().zip([1, 3].into_iter().zip(targets))
+        {
+            assert_eq!(&instance, spare);
+            assert!(command.contains(&format!("--authority {authority} ")));
+        }
+    }
+
+    #[test]
+    fn local_nodes_use_reserved_ports() {
+        let settings = test_settings();
+        let protocol = MysticetiProtocol::new(&settings);
+        let orchestrator = test_orchestrator(&settings, test_instances(4), protocol);
+
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings = settings;
+        let parameters = orchestrator.with_local_ports(parameters).unwrap();
+        let (_, nodes, _, _) = orchestrator.select_instances(&parameters).unwrap();
+
+        // The nodes advertise the reserved ports, which stay bound until the nodes boot.
+        let ports = parameters.node_parameters.benchmark_ports.clone().unwrap();
+        let ips = nodes.iter().map(|x| IpAddr::V4(x.main_ip)).collect();
+        let node_parameters = Some(parameters.node_parameters.deref().clone());
+        let config = NodePublicConfig::new_for_benchmarks(ips, node_parameters);
+        let network_ports: Vec<_> = config.all_network_addresses().map(|x| x.port()).collect();
+        assert_eq!(network_ports, ports.iter().map(|x| x.0).collect::<Vec<_>>());
+        let metrics_paths = orchestrator
+            .protocol_commands
+            .nodes_metrics_path(nodes.clone(), &parameters);
+        for ((_, path), (_, metrics_port)) in metrics_paths.iter().zip(&ports) {
+            assert_eq!(path, &format!("127.0.0.1:{metrics_port}/metrics"));
+        }
+        assert!(TcpListener::bind(("127.0.0.1", ports[0].0)).is_err());
+        orchestrator.executor.release_ports(&nodes);
+        assert!(TcpListener::bind(("127.0.0.1", ports[0].0)).is_ok());
+    }
+
+    #[test]
+    fn instance_specs_override() {
+        let mut settings = test_settings();
+        settings.specs = "small".into();
+        let mut instances = test_instances(8);
+        for (i, instance) in instances.iter_mut().enumerate() {
+            instance.specs = if i % 2 == 0 { "small" } else { "large" }.into();
+        }
+        let orchestrator = test_orchestrator(&settings, instances, ());
+
+        // Each benchmark only runs on the instances with its own specs.
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings = settings;
+        let (_, nodes, _, _) = orchestrator.select_instances(&parameters).unwrap();
+        assert!(nodes.iter().all(|x| x.specs == "small"));
+
+        parameters.instance_specs = Some("large".into());
+        let (_, nodes, _, _) = orchestrator.select_instances(&parameters).unwrap();
+        assert!(nodes.iter().all(|x| x.specs == "large"));
+
+        parameters.instance_specs = Some("medium".into());
+        assert!(orchestrator.select_instances(&parameters).is_err());
+    }
+
+    #[test]
+    fn role_specs() {
+        let mut settings = test_settings();
+        settings.validator_specs = Some("large".into());
+        settings.client_specs = Some("small".into());
+        settings.dedicated_clients = 2;
+        let mut instances = test_instances(8);
+        for (i, instance) in instances.iter_mut().enumerate() {
+            instance.specs = if i < 3 { "small" } else { "large" }.into();
+        }
+        let orchestrator = test_orchestrator(&settings, instances, ());
+
+        // The load generators and the nodes run on the instances with the specs of their role.
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings = settings;
+        let (clients, nodes, _, _) = orchestrator.select_instances(&parameters).unwrap();
+        assert_eq!(clients.len(), 2);
+        assert!(clients.iter().all(|x| x.specs == "small"));
+        assert_eq!(nodes.len(), parameters.nodes);
+        assert!(nodes.iter().all(|x| x.specs == "large"));
+
+        // There are not enough instances with the validators' specs to run a larger committee.
+        parameters.nodes = 6;
+        assert!(orchestrator.select_instances(&parameters).is_err());
+    }
+
+    #[test]
+    fn not_enough_instances_for_spares() {
+        let mut settings = test_settings();
+        settings.spare_instances = 1;
+        let orchestrator = test_orchestrator(&settings, test_instances(4), ());
+
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings = settings;
+        assert!(orchestrator.select_instances(&parameters).is_err());
+    }
+
+    #[test]
+    fn teardown_order() {
+        let mut settings = test_settings();
+        settings.drain_cooldown = Duration::from_secs(5);
+        let orchestrator = test_orchestrator(&settings, Vec::new(), ());
+
+        // Clients are stopped before the nodes, with the cooldown in between.
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings = settings;
+        assert_eq!(
+            orchestrator.teardown_steps(&parameters),
+            [
+                TeardownStep::StopClients,
+                TeardownStep::Cooldown(Duration::from_secs(5)),
+                TeardownStep::StopNodes,
+            ]
+        );
+
+        // There is nothing to drain without load generators.
+        parameters.load = 0;
+        assert_eq!(
+            orchestrator.teardown_steps(&parameters),
+            [TeardownStep::StopNodes]
+        );
+
+        // The cooldown can be disabled.
+        parameters.load = 100;
+        parameters.settings.drain_cooldown = Duration::ZERO;
+        assert_eq!(
+            orchestrator.teardown_steps(&parameters),
+            [TeardownStep::StopClients, TeardownStep::StopNodes]
+        );
+    }
+
+    #[test]
+    fn rust_log_override() {
+        let mut settings = test_settings();
+        settings.rust_log_overrides.insert(3, "debug".into());
+        let orchestrator = test_orchestrator(&settings, Vec::new(), ());
+
+        // Only the targeted node receives the override.
+        for i in 0..4 {
+            let context = orchestrator.node_context(i, CommandContext::new());
+            let command = context.apply("run");
+            if i == 3 {
+                assert_eq!(context.env, [("RUST_LOG".into(), "debug".into())]);
+                assert!(command.starts_with("export RUST_LOG=debug && run"));
+            } else {
+                assert!(context.env.is_empty());
+                assert!(!command.contains("RUST_LOG"));
+            }
+        }
+    }
+
+    #[test]
+    fn dry_run() {
+        let settings = test_settings();
+        let protocol = MysticetiProtocol::new(&settings);
+        let orchestrator = test_orchestrator(&settings, test_instances(4), protocol);
+
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings = settings;
+        let lines = orchestrator.dry_run(&parameters).unwrap();
+
+        // The parameters are followed by one command per node.
+        let (header, commands): (Vec<_>, Vec<_>) = lines.iter().partition(|x| x.starts_with('#'));
+        assert_eq!(header.len(), 3);
+        assert_eq!(commands.len(), parameters.nodes);
+        for (i, command) in commands.iter().enumerate() {
+            let (id, command) = command.split_once(' ').unwrap();
+            assert_eq!(id, i.to_string());
+            assert!(command.contains(&format!("--authority {i}")));
+        }
+
+        // The output is stable across calls.
+        assert_eq!(orchestrator.dry_run(&parameters).unwrap(), lines);
+    }
+
+    #[tokio::test]
+    async fn dry_run_benchmarks() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut settings = test_settings();
+        settings.results_dir = directory.path().join("results");
+        settings.logs_dir = directory.path().join("logs");
+        settings.drain_cooldown = Duration::from_secs(3600);
+        let protocol = MysticetiProtocol::new(&settings);
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings = settings.clone();
+        let mut orchestrator = Orchestrator::new(
+            settings,
+            test_instances(4),
+            Vec::new(),
+            protocol,
+            Executor::dry_run(),
+        )
+        .with_repeat(2)
+        .with_dry_run(true);
+
+        // The benchmarks complete immediately without running or collecting anything.
+        let run = orchestrator.run_benchmarks(vec![parameters]);
+        tokio::time::timeout(Duration::from_secs(60), run)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::fs::read_dir(directory.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn resume_benchmarks() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut settings = test_settings();
+        settings.results_dir = directory.path().into();
+        let orchestrator = test_orchestrator(&settings, Vec::new(), ());
+        let sweep: Vec<_> = [100, 200, 300]
+            .into_iter()
+            .map(|load| {
+                let mut parameters = BenchmarkParameters::new_for_tests();
+                parameters.settings = settings.clone();
+                parameters.load = load;
+                parameters
+            })
+            .collect();
+
+        // A first run completes the first two loads before being interrupted.
+        let mut checkpoint = Checkpoint::load(orchestrator.results_directory()).unwrap();
+        for parameters in &sweep[..2] {
+            checkpoint.record(parameters).unwrap();
+        }
+
+        // A second run runs all loads again, unless it resumes the first one.
+        let checkpoint = Checkpoint::load(orchestrator.results_directory()).unwrap();
+        let pending = orchestrator.pending_benchmarks(&checkpoint, sweep.clone());
+        assert_eq!(pending.len(), 3);
+
+        let orchestrator = orchestrator.with_resume(true);
+        let pending = orchestrator.pending_benchmarks(&checkpoint, sweep.clone());
+        let loads: Vec<_> = pending.iter().map(|x| x.load).collect();
+        assert_eq!(loads, [300]);
+
+        // Changing the committee size invalidates the checkpoints.
+        let resized: Vec<_> = sweep
+            .into_iter()
+            .map(|mut parameters| {
+                parameters.nodes = 7;
+                parameters
+            })
+            .collect();
+        let pending = orchestrator.pending_benchmarks(&checkpoint, resized);
+        assert_eq!(pending.len(), 3);
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use crate::{client::Instance, display};
+
+/// The approximate round-trip time between two instances of the same region (in milliseconds).
+const INTRA_REGION_RTT: u64 = 1;
+
+/// The approximate round-trip times between regions (in milliseconds). Regions are identified by
+/// their AWS names; pairs missing from the table are reported as unknown.
+const REGION_RTTS: &[(&str, &str, u64)] = &[
+    ("us-east-1", "us-west-1", 62),
+    ("us-east-1", "us-west-2", 67),
+    ("us-east-1", "ca-central-1", 15),
+    ("us-east-1", "sa-east-1", 115),
+    ("us-east-1", "eu-west-1", 68),
+    ("us-east-1", "eu-central-1", 90),
+    ("us-east-1", "eu-north-1", 110),
+    ("us-east-1", "ap-south-1", 185),
+    ("us-east-1", "ap-northeast-1", 145),
+    ("us-east-1", "ap-southeast-1", 215),
+    ("us-east-1", "ap-southeast-2", 198),
+    ("us-west-1", "us-west-2", 22),
+    ("us-west-1", "ca-central-1", 78),
+    ("us-west-1", "sa-east-1", 175),
+    ("us-west-1", "eu-west-1", 130),
+    ("us-west-1", "eu-central-1", 148),
+    ("us-west-1", "eu-north-1", 170),
+    ("us-west-1", "ap-south-1", 230),
+    ("us-west-1", "ap-northeast-1", 105),
+    ("us-west-1", "ap-southeast-1", 170),
+    ("us-west-1", "ap-southeast-2", 140),
+    ("us-west-2", "ca-central-1", 60),
+    ("us-west-2", "sa-east-1", 178),
+    ("us-west-2", "eu-west-1", 120),
+    ("us-west-2", "eu-central-1", 140),
+    ("us-west-2", "eu-north-1", 160),
+    ("us-west-2", "ap-south-1", 220),
+    ("us-west-2", "ap-northeast-1", 98),
+    ("us-west-2", "ap-southeast-1", 165),
+    ("us-west-2", "ap-southeast-2", 140),
+    ("ca-central-1", "sa-east-1", 125),
+    ("ca-central-1", "eu-west-1", 75),
+    ("ca-central-1", "eu-central-1", 92),
+    ("ca-central-1", "eu-north-1", 105),
+    ("ca-central-1", "ap-south-1", 195),
+    ("ca-central-1", "ap-northeast-1", 145),
+    ("ca-central-1", "ap-southeast-1", 210),
+    ("ca-central-1", "ap-southeast-2", 200),
+    ("sa-east-1", "eu-west-1", 180),
+    ("sa-east-1", "eu-central-1", 205),
+    ("sa-east-1", "eu-north-1", 215),
+    ("sa-east-1", "ap-south-1", 300),
+    ("sa-east-1", "ap-northeast-1", 255),
+    ("sa-east-1", "ap-southeast-1", 325),
+    ("sa-east-1", "ap-southeast-2", 310),
+    ("eu-west-1", "eu-central-1", 25),
+    ("eu-west-1", "eu-north-1", 40),
+    ("eu-west-1", "ap-south-1", 125),
+    ("eu-west-1", "ap-northeast-1", 205),
+    ("eu-west-1", "ap-southeast-1", 175),
+    ("eu-west-1", "ap-southeast-2", 255),
+    ("eu-central-1", "eu-north-1", 22),
+    ("eu-central-1", "ap-south-1", 110),
+    ("eu-central-1", "ap-northeast-1", 225),
+    ("eu-central-1", "ap-southeast-1", 160),
+    ("eu-central-1", "ap-southeast-2", 250),
+    ("eu-north-1", "ap-south-1", 130),
+    ("eu-north-1", "ap-northeast-1", 245),
+    ("eu-north-1", "ap-southeast-1", 180),
+    ("eu-north-1", "ap-southeast-2", 270),
+    ("ap-south-1", "ap-northeast-1", 130),
+    ("ap-south-1", "ap-southeast-1", 60),
+    ("ap-south-1", "ap-southeast-2", 150),
+    ("ap-northeast-1", "ap-southeast-1", 70),
+    ("ap-northeast-1", "ap-southeast-2", 105),
+    ("ap-southeast-1", "ap-southeast-2", 92),
+];
+
+/// Return the expected round-trip time between two regions (in any order), if known.
+pub fn region_rtt(a: &str, b: &str) -> Option<Duration> {
+    if a == b {
+        return Some(Duration::from_millis(INTRA_REGION_RTT));
+    }
+    REGION_RTTS
+        .iter()
+        .find(|(x, y, _)| (*x == a && *y == b) || (*x == b && *y == a))
+        .map(|(_, _, rtt)| Duration::from_millis(*rtt))
+}
+
+/// The statistics of the expected round-trip times between the instances of the testbed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+}
+
+/// The distribution of the instances across regions, along with the round-trip times expected
+/// between them. Helps validating that a benchmark is actually geo-distributed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlacementSummary {
+    /// The number of instances in each region.
+    pub regions: Vec<(String, usize)>,
+    /// The expected round-trip times between each pair of instances (only counting the pairs
+    /// whose round-trip time is known), or `None` if there are no such pairs.
+    pub latency: Option<LatencyStats>,
+    /// The pairs of regions whose round-trip time is unknown.
+    pub unknown: Vec<(String, String)>,
+}
+
+impl PlacementSummary {
+    /// Group the specified instances by region (in the order of the specified regions) and
+    /// compute the round-trip times expected between them.
+    pub fn new<'a, I>(regions: &[String], instances: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Instance> + Clone,
+    {
+        let regions: Vec<_> = regions
+            .iter()
+            .map(|region| {
+                let count = instances
+                    .clone()
+                    .into_iter()
+                    .filter(|x| &x.region == region)
+                    .count();
+                (region.clone(), count)
+            })
+            .filter(|(_, count)| *count != 0)
+            .collect();
+
+        // Weight the round-trip time between each pair of regions by the number of pairs of
+        // instances it applies to.
+        let (mut min, mut max, mut total, mut pairs) = (None, None, Duration::ZERO, 0u32);
+        let mut unknown = Vec::new();
+        for (i, (a, count_a)) in regions.iter().enumerate() {
+            for (b, count_b) in &regions[i..] {
+                let weight = if a == b {
+                    count_a * (count_a - 1) / 2
+                } else {
+                    count_a * count_b
+                };
+                if weight == 0 {
+                    continue;
+                }
+                let Some(rtt) = region_rtt(a, b) else {
+                    unknown.push((a.clone(), b.clone()));
+                    continue;
+                };
+                min = Some(min.map_or(rtt, |x: Duration| x.min(rtt)));
+                max = Some(max.map_or(rtt, |x: Duration| x.max(rtt)));
+                total += rtt * weight as u32;
+                pairs += weight as u32;
+            }
+        }
+        let latency = min.zip(max).map(|(min, max)| LatencyStats {
+            min,
+            max,
+            mean: total / pairs,
+        });
+
+        Self {
+            regions,
+            latency,
+            unknown,
+        }
+    }
+
+    /// Print the summary.
+    pub fn print(&self) {
+        let regions: Vec<_> = self
+            .regions
+            .iter()
+            .map(|(region, count)| format!("{region} ({count})"))
+            .collect();
+        display::config("Placement", regions.join(", "));
+        if let Some(LatencyStats { min, max, mean }) = &self.latency {
+            display::config(
+                "Expected RTT",
+                format!("min {min:?}, mean {mean:?}, max {max:?}"),
+            );
+        }
+        if !self.unknown.is_empty() {
+            let pairs: Vec<_> = self
+                .unknown
+                .iter()
+                .map(|(a, b)| format!("{a} <-> {b}"))
+                .collect();
+            display::warn(format!("Unknown RTT between {}", pairs.join(", ")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{region_rtt, LatencyStats, PlacementSummary};
+    use crate::client::Instance;
+
+    #[test]
+    fn rtt_lookup() {
+        let rtt = region_rtt("us-east-1", "eu-west-1");
+        assert_eq!(rtt, Some(Duration::from_millis(68)));
+        assert_eq!(region_rtt("eu-west-1", "us-east-1"), rtt);
+        assert_eq!(
+            region_rtt("eu-west-1", "eu-west-1"),
+            Some(Duration::from_millis(1))
+        );
+        assert_eq!(region_rtt("us-east-1", "mars-north-1"), None);
+    }
+
+    #[test]
+    fn group_by_region() {
+        let regions = ["us-east-1", "eu-west-1", "ap-south-1", "mars-north-1"];
+        let regions: Vec<_> = regions.iter().map(|x| x.to_string()).collect();
+        let instances: Vec<_> = ["us-east-1", "us-east-1", "eu-west-1", "mars-north-1"]
+            .iter()
+            .enumerate()
+            .map(|(i, region)| {
+                let mut instance = Instance::new_for_test(i.to_string());
+                instance.region = region.to_string();
+                instance
+            })
+            .collect();
+
+        let summary = PlacementSummary::new(&regions, &instances);
+        let expected = [("us-east-1", 2), ("eu-west-1", 1), ("mars-north-1", 1)];
+        let expected: Vec<_> = expected.iter().map(|(x, n)| (x.to_string(), *n)).collect();
+        assert_eq!(summary.regions, expected);
+
+        // One pair of instances within us-east-1 and two pairs between us-east-1 and eu-west-1.
+        let latency = LatencyStats {
+            min: Duration::from_millis(1),
+            max: Duration::from_millis(68),
+            mean: Duration::from_millis(1 + 2 * 68) / 3,
+        };
+        assert_eq!(summary.latency, Some(latency));
+        assert_eq!(summary.unknown.len(), 2);
+        assert!(summary
+            .unknown
+            .iter()
+            .all(|(a, b)| a == "mars-north-1" || b == "mars-north-1"));
+    }
+}
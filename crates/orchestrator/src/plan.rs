@@ -0,0 +1,278 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Dry runs of the orchestrator. They are planned from the settings and the command line alone,
+//! without creating the client of the cloud provider: the changes to the testbed are described
+//! regardless of its current instances, and the benchmarks walk through placeholder instances
+//! (displaying the commands they would run instead of running them).
+
+use std::{net::Ipv4Addr, time::Duration};
+
+use eyre::Context;
+
+use crate::{
+    benchmark_parameters,
+    benchmark_settings,
+    client::{Instance, InstanceStatus},
+    compare,
+    display,
+    executor::Executor,
+    orchestrator::Orchestrator,
+    settings::Settings,
+    summarize,
+    Operation,
+    Opts,
+    Protocol,
+    TestbedAction,
+};
+
+/// Display what the operation specified on the command line would do, without doing it.
+pub async fn dry_run(settings: Settings, opts: Opts) -> eyre::Result<()> {
+    match opts.operation {
+        Operation::Testbed { action } => {
+            for line in testbed_changes(&settings, action)? {
+                display::dry_run(line);
+            }
+        }
+
+        Operation::Benchmark {
+            committee,
+            loads,
+            load_profiles,
+            load_mode,
+            specs,
+            skip_testbed_update,
+            skip_testbed_configuration,
+            repeat,
+            faults,
+            warmup,
+            duration,
+            soak,
+            snapshot_interval,
+            resume,
+        } => {
+            let settings = benchmark_settings(settings, committee, faults, duration.or(soak))?;
+            let instances = placeholder_instances(&settings, committee, &specs);
+            let protocol_commands = Protocol::new(&settings);
+            let set_of_benchmark_parameters = benchmark_parameters(
+                &settings,
+                committee,
+                loads,
+                load_profiles,
+                load_mode,
+                specs,
+                warmup,
+            )?;
+
+            Orchestrator::new(
+                settings,
+                instances,
+                Vec::new(),
+                protocol_commands,
+                Executor::dry_run(),
+            )
+            .skip_testbed_update(skip_testbed_update)
+            .skip_testbed_configuration(skip_testbed_configuration)
+            .with_repeat(repeat)
+            .with_soak_snapshots(soak.map(|_| Duration::from_secs(snapshot_interval)))
+            .with_dry_run(true)
+            .with_resume(resume)
+            .with_seed(opts.seed)
+            .run_benchmarks(set_of_benchmark_parameters)
+            .await
+            .wrap_err("Failed to run benchmarks")?;
+        }
+        // These operations only read local files.
+        Operation::Summarize { path, format } => summarize(path, format)?,
+        Operation::Compare {
+            baseline,
+            candidate,
+            threshold,
+        } => compare(baseline, candidate, threshold)?,
+
+        // Handled before loading the settings.
+        Operation::Schema { .. } => unreachable!("Schemas are printed without settings"),
+    }
+    Ok(())
+}
+
+/// Describe the changes the specified action would make to the testbed. The current instances
+/// of the testbed are unknown, so the changes are described as targets rather than as a list of
+/// instances to create or delete.
+fn testbed_changes(settings: &Settings, action: TestbedAction) -> eyre::Result<Vec<String>> {
+    let regions = settings.regions.join(", ");
+    let changes = match action {
+        TestbedAction::Status => Vec::new(),
+        TestbedAction::Deploy {
+            instances,
+            region,
+            prune_extra,
+        } => {
+            let specs = &settings.specs;
+            let regions = region.unwrap_or(regions);
+            let mut changes = vec![format!(
+                "Create up to {instances} {specs} instance(s) in each of [{regions}]"
+            )];
+            if prune_extra {
+                changes.push(format!(
+                    "Delete the {specs} instances beyond {instances} in each of [{regions}]"
+                ));
+            }
+            changes
+        }
+        TestbedAction::Provision { committee } => {
+            settings
+                .validate_committee(committee)
+                .wrap_err("Failed to provision testbed")?;
+            settings
+                .required_instances_by_specs(committee)
+                .into_iter()
+                .map(|(specs, required)| {
+                    let quantity = required.div_ceil(settings.regions.len().max(1));
+                    format!("Create up to {quantity} {specs} instance(s) in each of [{regions}]")
+                })
+                .collect()
+        }
+        TestbedAction::Start { instances } => {
+            vec![format!(
+                "Start {instances} instance(s) in each of [{regions}]"
+            )]
+        }
+        TestbedAction::Stop => vec!["Stop all active instances".into()],
+        TestbedAction::Destroy { run } => match run {
+            Some(run) => vec![format!("Delete all instances of run '{run}'")],
+            None => vec!["Delete all instances".into()],
+        },
+    };
+    Ok(changes)
+}
+
+/// Make placeholders for the instances a benchmark would run on: enough active instances of
+/// each specs (spread across the regions of the settings) to run the committee along with its
+/// spares, load generators, and monitoring instance.
+fn placeholder_instances(settings: &Settings, committee: usize, specs: &[String]) -> Vec<Instance> {
+    let required = if specs.is_empty() {
+        settings.required_instances_by_specs(committee)
+    } else {
+        let required = settings.required_instances(committee);
+        specs.iter().map(|x| (x.clone(), required)).collect()
+    };
+
+    let mut instances = Vec::new();
+    for (specs, quantity) in required {
+        let regions = settings.regions.iter().cycle().take(quantity);
+        for region in regions {
+            instances.push(Instance {
+                id: format!("instance-{}", instances.len()),
+                region: region.clone(),
+                main_ip: Ipv4Addr::UNSPECIFIED,
+                tags: Vec::new(),
+                specs: specs.clone(),
+                status: InstanceStatus::Active,
+            });
+        }
+    }
+    instances
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+
+    use super::{dry_run, placeholder_instances, testbed_changes};
+    use crate::{
+        settings::{CloudProvider, Settings},
+        Opts,
+        TestbedAction,
+    };
+
+    #[test]
+    fn placeholders() {
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["a".into(), "b".into()];
+        settings.dedicated_clients = 1;
+        settings.spare_instances = 1;
+
+        // The instances are spread across the regions.
+        let instances = placeholder_instances(&settings, 4, &[]);
+        assert_eq!(instances.len(), 6);
+        assert_eq!(instances.iter().filter(|x| x.region == "a").count(), 3);
+        assert!(instances.iter().all(|x| x.is_active()));
+
+        // The specs of the command line override the ones of the settings.
+        let specs = ["large".to_string(), "small".to_string()];
+        let instances = placeholder_instances(&settings, 4, &specs);
+        assert_eq!(instances.len(), 12);
+        assert_eq!(instances.iter().filter(|x| x.has_specs("large")).count(), 6);
+    }
+
+    #[test]
+    fn testbed() {
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["a".into(), "b".into()];
+        settings.specs = "large".into();
+        settings.spare_instances = 1;
+
+        let action = TestbedAction::Provision { committee: 4 };
+        let changes = testbed_changes(&settings, action).unwrap();
+        assert_eq!(
+            changes,
+            ["Create up to 3 large instance(s) in each of [a, b]"]
+        );
+
+        let action = TestbedAction::Deploy {
+            instances: 2,
+            region: Some("a".into()),
+            prune_extra: true,
+        };
+        let changes = testbed_changes(&settings, action).unwrap();
+        assert_eq!(
+            changes,
+            [
+                "Create up to 2 large instance(s) in each of [a]",
+                "Delete the large instances beyond 2 in each of [a]"
+            ]
+        );
+
+        // The committee must fit in the maximum number of instances.
+        settings.max_instances = Some(4);
+        let action = TestbedAction::Provision { committee: 4 };
+        assert!(testbed_changes(&settings, action).is_err());
+    }
+
+    #[tokio::test]
+    async fn testbed_without_credentials() {
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["us-east-1".into()];
+
+        // Neither the token nor the private key files exist, and the project of GCP is missing.
+        for cloud_provider in [CloudProvider::DigitalOcean, CloudProvider::Gcp] {
+            settings.cloud_provider = cloud_provider;
+            for action in ["status", "deploy --instances 2", "stop", "destroy"] {
+                let args = ["orchestrator", "--dry-run", "testbed"]
+                    .into_iter()
+                    .chain(action.split(' '));
+                let opts = Opts::try_parse_from(args).unwrap();
+                dry_run(settings.clone(), opts).await.unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn benchmark_without_credentials() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut settings = Settings::new_for_test();
+        settings.results_dir = directory.path().join("results");
+        settings.logs_dir = directory.path().join("logs");
+        settings.regions = vec!["us-east-1".into(), "eu-north-1".into()];
+
+        // Neither the token nor the private key files exist, and the project of GCP is missing.
+        for cloud_provider in [CloudProvider::Aws, CloudProvider::Gcp] {
+            settings.cloud_provider = cloud_provider;
+            let args = ["orchestrator", "--dry-run", "benchmark", "--committee", "4"];
+            let opts = Opts::try_parse_from(args).unwrap();
+            dry_run(settings.clone(), opts).await.unwrap();
+        }
+        assert_eq!(std::fs::read_dir(directory.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A pool of connections keyed by the address of the host they connect to. Connections are
+/// returned to the pool once released and reused by the next request to the same host, unless
+/// they remained idle for longer than the idle timeout.
+pub struct ConnectionPool<T> {
+    /// The duration after which idle connections are closed rather than reused.
+    idle_timeout: Duration,
+    /// The maximum number of connections open at once with each host.
+    max_per_host: usize,
+    /// The connections of each host.
+    hosts: Mutex<HashMap<SocketAddr, Host<T>>>,
+}
+
+/// The connections with a single host.
+struct Host<T> {
+    /// The connections not currently in use, along with the time they were released.
+    idle: Vec<(T, Instant)>,
+    /// Bounds the number of connections open at once with the host.
+    permits: Arc<Semaphore>,
+}
+
+impl<T> ConnectionPool<T> {
+    /// Create a new pool closing the connections idle for longer than the specified timeout and
+    /// opening at most the specified number of connections with each host.
+    pub fn new(idle_timeout: Duration, max_per_host: usize) -> Self {
+        Self {
+            idle_timeout,
+            max_per_host: max_per_host.max(1),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get a connection with the specified host, reusing an idle one if possible and opening a
+    /// new one otherwise. Waits until a connection is released if the host already has the
+    /// maximum number of connections open.
+    pub async fn get<F, Fut, E>(
+        self: &Arc<Self>,
+        address: SocketAddr,
+        connect: F,
+    ) -> Result<PooledConnection<T>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let permits = self.host(address, |host| host.permits.clone());
+        let permit = permits
+            .acquire_owned()
+            .await
+            .expect("Connection pool semaphore closed");
+
+        let idle = self.host(address, |host| {
+            // Close the expired connections (dropping them) and reuse the most recent one.
+            host.idle
+                .retain(|(_, released)| released.elapsed() < self.idle_timeout);
+            host.idle.pop().map(|(connection, _)| connection)
+        });
+        let connection = match idle {
+            Some(connection) => connection,
+            None => connect().await?,
+        };
+
+        Ok(PooledConnection {
+            pool: self.clone(),
+            address,
+            connection: Some(connection),
+            _permit: permit,
+        })
+    }
+
+    /// Access the connections of the specified host.
+    fn host<R>(&self, address: SocketAddr, f: impl FnOnce(&mut Host<T>) -> R) -> R {
+        let mut hosts = self.hosts.lock().unwrap();
+        let host = hosts.entry(address).or_insert_with(|| Host {
+            idle: Vec::new(),
+            permits: Arc::new(Semaphore::new(self.max_per_host)),
+        });
+        f(host)
+    }
+}
+
+/// A connection drawn from a pool, returned to the pool when dropped.
+pub struct PooledConnection<T> {
+    /// The pool to which the connection is returned.
+    pool: Arc<ConnectionPool<T>>,
+    /// The address of the host.
+    address: SocketAddr,
+    /// The connection (only taken when the guard is dropped or the connection discarded).
+    connection: Option<T>,
+    /// Counts the connection towards the maximum number of connections of the host.
+    _permit: OwnedSemaphorePermit,
+}
+
+impl<T> PooledConnection<T> {
+    /// Close the connection instead of returning it to the pool (e.g., because it failed).
+    pub fn discard(mut self) {
+        self.connection = None;
+    }
+}
+
+impl<T> Deref for PooledConnection<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.connection
+            .as_ref()
+            .expect("Connection already released")
+    }
+}
+
+impl<T> DerefMut for PooledConnection<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.connection
+            .as_mut()
+            .expect("Connection already released")
+    }
+}
+
+impl<T> Drop for PooledConnection<T> {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            let released = (connection, Instant::now());
+            self.pool
+                .host(self.address, |host| host.idle.push(released));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use super::ConnectionPool;
+
+    /// A connection with a mock host, recording the commands it executed.
+    struct MockSession {
+        id: usize,
+        executed: usize,
+    }
+
+    impl MockSession {
+        fn execute(&mut self) {
+            self.executed += 1;
+        }
+    }
+
+    /// Open a new mock session, counting the sessions opened so far.
+    async fn connect(opened: &AtomicUsize) -> Result<MockSession, ()> {
+        let id = opened.fetch_add(1, Ordering::SeqCst);
+        Ok(MockSession { id, executed: 0 })
+    }
+
+    #[tokio::test]
+    async fn reuse_sessions() {
+        let pool = Arc::new(ConnectionPool::new(Duration::from_secs(60), 4));
+        let address: SocketAddr = "127.0.0.1:22".parse().unwrap();
+        let opened = AtomicUsize::new(0);
+
+        // Sequential commands to the same host share a single session.
+        for _ in 0..10 {
+            let mut session = pool.get(address, || connect(&opened)).await.unwrap();
+            session.execute();
+        }
+        assert_eq!(opened.load(Ordering::SeqCst), 1);
+        let session = pool.get(address, || connect(&opened)).await.unwrap();
+        assert_eq!((session.id, session.executed), (0, 10));
+        drop(session);
+
+        // Other hosts get their own sessions.
+        let other: SocketAddr = "127.0.0.2:22".parse().unwrap();
+        let session = pool.get(other, || connect(&opened)).await.unwrap();
+        assert_eq!(session.id, 1);
+
+        // Discarded sessions are not reused.
+        let session = pool.get(address, || connect(&opened)).await.unwrap();
+        session.discard();
+        let session = pool.get(address, || connect(&opened)).await.unwrap();
+        assert_eq!(session.id, 2);
+    }
+
+    #[tokio::test]
+    async fn evict_idle_sessions() {
+        let pool = Arc::new(ConnectionPool::new(Duration::from_millis(50), 4));
+        let address: SocketAddr = "127.0.0.1:22".parse().unwrap();
+        let opened = AtomicUsize::new(0);
+
+        drop(pool.get(address, || connect(&opened)).await.unwrap());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let session = pool.get(address, || connect(&opened)).await.unwrap();
+        assert_eq!(session.id, 1);
+    }
+
+    #[tokio::test]
+    async fn max_sessions_per_host() {
+        let pool = Arc::new(ConnectionPool::new(Duration::from_secs(60), 2));
+        let address: SocketAddr = "127.0.0.1:22".parse().unwrap();
+        let opened = AtomicUsize::new(0);
+
+        let first = pool.get(address, || connect(&opened)).await.unwrap();
+        let _second = pool.get(address, || connect(&opened)).await.unwrap();
+
+        // A third session waits until one of the others is released.
+        let third = pool.get(address, || connect(&opened));
+        let timeout = Duration::from_millis(50);
+        assert!(tokio::time::timeout(timeout, third).await.is_err());
+
+        drop(first);
+        let third = pool.get(address, || connect(&opened)).await.unwrap();
+        assert_eq!(third.id, 0);
+        assert_eq!(opened.load(Ordering::SeqCst), 2);
+    }
+}
@@ -82,6 +82,25 @@ pub trait ProtocolMetrics {
     /// The name of the metric reporting the square of the sum of the end-to-end latency of all
     /// finalized transactions.
     const LATENCY_SQUARED_SUM: &'static str;
+    /// The name of the metric reporting the time between the start of a node and its first
+    /// commit (in seconds).
+    const TIME_TO_FIRST_COMMIT: &'static str;
+    /// The name of the metric reporting the memory used by the node (in bytes).
+    const MEMORY_USAGE: &'static str;
+    /// The name of the histogram reporting the time between the proposal of a block and its
+    /// reception by a quorum of nodes (in seconds).
+    const DISSEMINATION_LATENCY: &'static str;
+    /// The name of the histogram reporting the time between the proposal of a leader block and
+    /// its commit (in seconds).
+    const COMMIT_LATENCY: &'static str;
+    /// The name of the counter reporting the leaders decided by the node (by commit type).
+    const COMMITTED_LEADERS: &'static str;
+    /// The name of the gauge reporting the number of leaders the node could not decide yet.
+    const UNDECIDED_LEADERS: &'static str;
+    /// The name of the counter reporting the transactions submitted to the node.
+    const SUBMITTED_TRANSACTIONS: &'static str;
+    /// The name of the gauge reporting the round of the last block proposed by the node.
+    const CURRENT_ROUND: &'static str;
 
     /// The network path where the nodes expose prometheus metrics.
     fn nodes_metrics_path<I>(
@@ -145,6 +164,14 @@ pub mod test_protocol_metrics {
         const LATENCY_BUCKETS: &'static str = "latency_s";
         const LATENCY_SUM: &'static str = "latency_s_sum";
         const LATENCY_SQUARED_SUM: &'static str = "latency_squared_s";
+        const TIME_TO_FIRST_COMMIT: &'static str = "time_to_first_commit";
+        const MEMORY_USAGE: &'static str = "global_in_memory_blocks_bytes";
+        const DISSEMINATION_LATENCY: &'static str = "dissemination_latency_seconds";
+        const COMMIT_LATENCY: &'static str = "commit_latency_seconds";
+        const COMMITTED_LEADERS: &'static str = "committed_leaders_total";
+        const UNDECIDED_LEADERS: &'static str = "undecided_leaders";
+        const SUBMITTED_TRANSACTIONS: &'static str = "submitted_transactions";
+        const CURRENT_ROUND: &'static str = "current_round";
 
         fn nodes_metrics_path<I>(
             &self,
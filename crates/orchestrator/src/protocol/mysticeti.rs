@@ -12,12 +12,13 @@ use mysticeti_core::{
     config::{self, ClientParameters, NodeParameters},
     types::AuthorityIndex,
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::{ProtocolCommands, ProtocolMetrics, ProtocolParameters, BINARY_PATH};
 use crate::{benchmark::BenchmarkParameters, client::Instance, settings::Settings};
 
-#[derive(Clone, Serialize, Deserialize, Default)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(transparent)]
 pub struct MysticetiNodeParameters(NodeParameters);
 
@@ -29,6 +30,20 @@ impl Deref for MysticetiNodeParameters {
     }
 }
 
+impl MysticetiNodeParameters {
+    /// Set the network and metrics ports of each node, in the order of the authority index.
+    pub fn with_benchmark_ports(mut self, ports: Vec<(u16, u16)>) -> Self {
+        self.0.benchmark_ports = Some(ports);
+        self
+    }
+
+    /// Make the nodes with the lowest indices equivocate at every round.
+    pub fn with_equivocating_nodes(mut self, nodes: usize) -> Self {
+        self.0.equivocating_authorities = (0..nodes as AuthorityIndex).collect();
+        self
+    }
+}
+
 impl Debug for MysticetiNodeParameters {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.consensus_only {
@@ -51,7 +66,7 @@ impl Display for MysticetiNodeParameters {
 
 impl ProtocolParameters for MysticetiNodeParameters {}
 
-#[derive(Serialize, Deserialize, Clone, Default)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Default)]
 #[serde(transparent)]
 pub struct MysticetiClientParameters(ClientParameters);
 
@@ -99,7 +114,16 @@ impl ProtocolCommands for MysticetiProtocol {
             .collect::<Vec<_>>()
             .join(" ");
 
-        let node_parameters = parameters.node_parameters.clone();
+        let equivocating = parameters.settings.faults.equivocating_nodes();
+        let mut node_parameters = parameters
+            .node_parameters
+            .clone()
+            .with_equivocating_nodes(equivocating);
+        // Record the committed sequences to check that the correct nodes agree on them despite
+        // the equivocating nodes.
+        if equivocating != 0 {
+            node_parameters.0.enable_commit_sink = true;
+        }
         let node_parameters_string = serde_yaml::to_string(&node_parameters).unwrap();
         let node_parameters_path = self.working_dir.join("node-parameters.yaml");
         let upload_node_parameters = format!(
@@ -109,6 +133,14 @@ impl ProtocolCommands for MysticetiProtocol {
 
         let mut client_parameters = parameters.client_parameters.clone();
         client_parameters.0.load = parameters.load / parameters.nodes;
+        client_parameters.0.target_commit_tps = parameters.load_mode.target_commit_tps();
+        if let Some(profile) = &parameters.load_profile {
+            client_parameters.0.load_schedule = profile
+                .schedule(parameters.settings.benchmark_duration)
+                .into_iter()
+                .map(|(time, load)| (time, load / parameters.nodes))
+                .collect();
+        }
         let client_parameters_string = serde_yaml::to_string(&client_parameters).unwrap();
         let client_parameters_path = self.working_dir.join("client-parameters.yaml");
         let upload_client_parameters = format!(
@@ -139,11 +171,12 @@ impl ProtocolCommands for MysticetiProtocol {
     fn node_command<I>(
         &self,
         instances: I,
-        _parameters: &BenchmarkParameters,
+        parameters: &BenchmarkParameters,
     ) -> Vec<(Instance, String)>
     where
         I: IntoIterator<Item = Instance>,
     {
+        let silent = parameters.settings.faults.silent_nodes();
         instances
             .into_iter()
             .enumerate()
@@ -156,7 +189,7 @@ impl ProtocolCommands for MysticetiProtocol {
                     .join(format!("private-config-{authority}.yaml"));
                 let client_parameters_path = self.working_dir.join("client-parameters.yaml");
 
-                let run = [
+                let mut run = [
                     &format!("./{BINARY_PATH}/mysticeti"),
                     "run",
                     &format!("--authority {authority}"),
@@ -169,6 +202,9 @@ impl ProtocolCommands for MysticetiProtocol {
                     ),
                 ]
                 .join(" ");
+                if i < silent {
+                    run.push_str(" --silent");
+                }
 
                 let command = ["source $HOME/.cargo/env", &run].join(" && ");
                 (instance, command)
@@ -195,6 +231,14 @@ impl ProtocolMetrics for MysticetiProtocol {
     const LATENCY_BUCKETS: &'static str = "latency_s";
     const LATENCY_SUM: &'static str = "latency_s_sum";
     const LATENCY_SQUARED_SUM: &'static str = mysticeti_core::metrics::LATENCY_SQUARED_S;
+    const TIME_TO_FIRST_COMMIT: &'static str = mysticeti_core::metrics::TIME_TO_FIRST_COMMIT;
+    const MEMORY_USAGE: &'static str = mysticeti_core::metrics::GLOBAL_IN_MEMORY_BLOCKS_BYTES;
+    const DISSEMINATION_LATENCY: &'static str = mysticeti_core::metrics::DISSEMINATION_LATENCY_S;
+    const COMMIT_LATENCY: &'static str = mysticeti_core::metrics::COMMIT_LATENCY_S;
+    const COMMITTED_LEADERS: &'static str = mysticeti_core::metrics::COMMITTED_LEADERS_TOTAL;
+    const UNDECIDED_LEADERS: &'static str = mysticeti_core::metrics::UNDECIDED_LEADERS;
+    const SUBMITTED_TRANSACTIONS: &'static str = mysticeti_core::metrics::SUBMITTED_TRANSACTIONS;
+    const CURRENT_ROUND: &'static str = mysticeti_core::metrics::CURRENT_ROUND;
 
     fn nodes_metrics_path<I>(
         &self,
@@ -239,3 +283,79 @@ impl MysticetiProtocol {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::MysticetiProtocol;
+    use crate::{
+        benchmark::BenchmarkParameters,
+        client::Instance,
+        faults::FaultsType,
+        protocol::{ProtocolCommands, ProtocolMetrics},
+    };
+
+    #[tokio::test]
+    async fn equivocating_nodes_in_node_parameters() {
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings.faults = FaultsType::Equivocate { faults: 1 };
+        let protocol = MysticetiProtocol::new(&parameters.settings);
+        let instances: Vec<_> = (0..parameters.nodes)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+
+        let genesis = protocol
+            .genesis_command(instances.iter(), &parameters)
+            .await;
+        assert!(genesis.contains("equivocating_authorities:\n- 0\n"));
+        // The fault is set in the node parameters shared by all nodes.
+        let commands = protocol.node_command(instances.clone(), &parameters);
+        assert!(commands.iter().all(|(_, x)| !x.contains("--equivocate")));
+
+        // No node equivocates without the fault.
+        parameters.settings.faults = FaultsType::default();
+        let genesis = protocol
+            .genesis_command(instances.iter(), &parameters)
+            .await;
+        assert!(genesis.contains("equivocating_authorities: []"));
+    }
+
+    #[test]
+    fn silent_flag_on_targeted_nodes() {
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        parameters.settings.faults = FaultsType::Silent { faults: 1 };
+        let protocol = MysticetiProtocol::new(&parameters.settings);
+        let instances = (0..parameters.nodes).map(|i| Instance::new_for_test(i.to_string()));
+
+        let commands = protocol.node_command(instances, &parameters);
+        let flagged: Vec<_> = commands
+            .iter()
+            .map(|(_, command)| command.contains("--silent"))
+            .collect();
+        assert_eq!(flagged, vec![true, false, false, false]);
+        assert!(commands.iter().all(|(_, x)| !x.contains("--equivocate")));
+    }
+
+    #[test]
+    fn configurable_metrics_port() {
+        let mut parameters = BenchmarkParameters::new_for_tests();
+        let protocol = MysticetiProtocol::new(&parameters.settings);
+        let instances = || (0..parameters.nodes).map(|i| Instance::new_for_test(i.to_string()));
+
+        // By default, the metrics port is offset from the network port by the committee size.
+        let paths = protocol.nodes_metrics_path(instances(), &parameters);
+        assert_eq!(paths[0].1, "127.0.0.1:1504/metrics");
+
+        parameters.node_parameters.0.metrics_port_offset = Some(1000);
+        let paths = protocol.nodes_metrics_path(instances(), &parameters);
+        let ports: Vec<_> = paths.iter().map(|(_, path)| path.as_str()).collect();
+        assert_eq!(
+            ports,
+            vec![
+                "127.0.0.1:2500/metrics",
+                "127.0.0.1:2501/metrics",
+                "127.0.0.1:2502/metrics",
+                "127.0.0.1:2503/metrics"
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A minimal client of the prometheus remote-write protocol (version 1.0). Time series are
+//! encoded as a protobuf `WriteRequest`, compressed with snappy (block format), and posted to
+//! the remote-write endpoint.
+
+use prost::Message;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+
+use crate::error::{MonitorError, MonitorResult};
+
+/// The header specifying the version of the remote-write protocol.
+const REMOTE_WRITE_VERSION_HEADER: &str = "X-Prometheus-Remote-Write-Version";
+/// The version of the remote-write protocol.
+const REMOTE_WRITE_VERSION: &str = "0.1.0";
+
+/// A time series pushed to the remote-write endpoint.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TimeSeries {
+    /// The labels of the series (including `__name__`), sorted by name.
+    #[prost(message, repeated, tag = "1")]
+    pub labels: Vec<Label>,
+    /// The samples of the series, sorted by timestamp.
+    #[prost(message, repeated, tag = "2")]
+    pub samples: Vec<Sample>,
+}
+
+/// A label of a time series.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Label {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub value: String,
+}
+
+/// A sample of a time series.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Sample {
+    #[prost(double, tag = "1")]
+    pub value: f64,
+    /// The time of the sample (in ms since the unix epoch).
+    #[prost(int64, tag = "2")]
+    pub timestamp: i64,
+}
+
+/// The request posted to the remote-write endpoint.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub timeseries: Vec<TimeSeries>,
+}
+
+impl TimeSeries {
+    /// Create a new (empty) time series of the specified metric. The labels are sorted by name
+    /// as required by the protocol.
+    pub fn new(name: &str, labels: Vec<(String, String)>) -> Self {
+        let mut labels: Vec<_> = labels
+            .into_iter()
+            .chain([("__name__".into(), name.into())])
+            .map(|(name, value)| Label { name, value })
+            .collect();
+        labels.sort_by(|a, b| (&a.name, &a.value).cmp(&(&b.name, &b.value)));
+        Self {
+            labels,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Add a sample to the series.
+    pub fn add(&mut self, value: f64, timestamp_ms: i64) {
+        self.samples.push(Sample {
+            value,
+            timestamp: timestamp_ms,
+        });
+    }
+}
+
+/// Encode the specified series as a protobuf `WriteRequest` message.
+pub fn encode_write_request(series: &[TimeSeries]) -> Vec<u8> {
+    let request = WriteRequest {
+        timeseries: series.to_vec(),
+    };
+    request.encode_to_vec()
+}
+
+/// Push the specified series to the remote-write endpoint.
+pub async fn push(endpoint: &str, series: &[TimeSeries]) -> MonitorResult<()> {
+    let error = |message: String| MonitorError::RemoteWrite {
+        endpoint: endpoint.into(),
+        message,
+    };
+    let body = snap::raw::Encoder::new()
+        .compress_vec(&encode_write_request(series))
+        .map_err(|e| error(e.to_string()))?;
+    let response = reqwest::Client::new()
+        .post(endpoint)
+        .header(CONTENT_TYPE, "application/x-protobuf")
+        .header(CONTENT_ENCODING, "snappy")
+        .header(REMOTE_WRITE_VERSION_HEADER, REMOTE_WRITE_VERSION)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| error(e.to_string()))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_default();
+        return Err(error(format!("{status}: {message}")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use mockito::{Matcher, Server};
+    use prost::Message;
+
+    use super::{encode_write_request, push, Label, Sample, TimeSeries, WriteRequest};
+
+    fn sample_series() -> TimeSeries {
+        let mut series = TimeSeries::new("up", vec![("job".into(), "a".into())]);
+        series.add(1.0, 1000);
+        series
+    }
+
+    #[test]
+    fn encode() {
+        let series = sample_series();
+        let label = |name: &str, value: &str| Label {
+            name: name.into(),
+            value: value.into(),
+        };
+        assert_eq!(series.labels, [label("__name__", "up"), label("job", "a")]);
+        assert_eq!(
+            series.samples,
+            [Sample {
+                value: 1.0,
+                timestamp: 1000
+            }]
+        );
+
+        let bytes = encode_write_request(&[series.clone()]);
+        let request = WriteRequest::decode(bytes.as_slice()).unwrap();
+        assert_eq!(request.timeseries, [series]);
+        assert!(encode_write_request(&[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn push_series() {
+        let mut server = Server::new_async().await;
+        let body = snap::raw::Encoder::new()
+            .compress_vec(&encode_write_request(&[sample_series()]))
+            .unwrap();
+        let mock = server
+            .mock("POST", "/api/v1/write")
+            .match_header("content-type", "application/x-protobuf")
+            .match_header("content-encoding", "snappy")
+            .match_header("x-prometheus-remote-write-version", "0.1.0")
+            .match_body(Matcher::from(body))
+            .with_status(204)
+            .create_async()
+            .await;
+
+        let endpoint = format!("{}/api/v1/write", server.url());
+        push(&endpoint, &[sample_series()]).await.unwrap();
+        mock.assert_async().await;
+
+        // Rejected requests are reported.
+        server
+            .mock("POST", "/api/v1/write")
+            .with_status(400)
+            .with_body("out of order sample")
+            .create_async()
+            .await;
+        let error = push(&endpoint, &[sample_series()]).await.unwrap_err();
+        assert!(error.to_string().contains("out of order sample"));
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{future::Future, time::Duration};
+
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DurationMilliSeconds};
+
+/// How to retry a failed connection or command: the delay between two attempts starts at
+/// `base_delay` and is multiplied by `multiplier` after every attempt (up to `max_delay`). Each
+/// delay is randomly extended by up to `jitter` (a fraction of the delay) so that concurrent
+/// retries do not all happen at the same time. The retries stop once the total delay would
+/// exceed `max_elapsed`.
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// The delay before the first retry (in milliseconds).
+    #[serde_as(as = "DurationMilliSeconds")]
+    pub base_delay: Duration,
+    /// The maximum delay between two attempts (in milliseconds).
+    #[serde_as(as = "DurationMilliSeconds")]
+    pub max_delay: Duration,
+    /// The factor by which the delay grows after every attempt.
+    pub multiplier: f64,
+    /// The maximum random extension of each delay, as a fraction of the delay.
+    pub jitter: f64,
+    /// The maximum total delay spent retrying (in milliseconds).
+    #[serde_as(as = "DurationMilliSeconds")]
+    pub max_elapsed: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_elapsed: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delays between successive attempts.
+    pub fn backoff(&self) -> Backoff {
+        Backoff {
+            policy: self.clone(),
+            next: self.base_delay,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Run the operation until it succeeds, retrying it at most `retries` times (and within the
+    /// retry window). Returns the last error if all attempts fail.
+    pub async fn retry<T, E, F, R>(&self, retries: usize, operation: F) -> Result<T, E>
+    where
+        F: FnMut() -> R,
+        R: Future<Output = Result<T, E>>,
+    {
+        self.retry_if(retries, |_| true, operation).await
+    }
+
+    /// Same as [`Self::retry`], but only retry the errors for which `retryable` holds; any other
+    /// error is returned right away.
+    pub async fn retry_if<T, E, F, R, P>(
+        &self,
+        retries: usize,
+        retryable: P,
+        mut operation: F,
+    ) -> Result<T, E>
+    where
+        F: FnMut() -> R,
+        R: Future<Output = Result<T, E>>,
+        P: Fn(&E) -> bool,
+    {
+        let mut backoff = self.backoff();
+        let mut attempts = 0;
+        loop {
+            let error = match operation().await {
+                Ok(x) => return Ok(x),
+                Err(e) if retryable(&e) => e,
+                Err(e) => return Err(e),
+            };
+            attempts += 1;
+            match backoff.next_delay() {
+                Some(delay) if attempts <= retries => tokio::time::sleep(delay).await,
+                _ => return Err(error),
+            }
+        }
+    }
+
+    /// The blocking version of [`Self::retry`], for operations running on a blocking thread.
+    pub fn retry_blocking<T, E, F>(&self, retries: usize, mut operation: F) -> Result<T, E>
+    where
+        F: FnMut() -> Result<T, E>,
+    {
+        let mut backoff = self.backoff();
+        let mut attempts = 0;
+        loop {
+            let error = match operation() {
+                Ok(x) => return Ok(x),
+                Err(e) => e,
+            };
+            attempts += 1;
+            match backoff.next_delay() {
+                Some(delay) if attempts <= retries => std::thread::sleep(delay),
+                _ => return Err(error),
+            }
+        }
+    }
+}
+
+/// The sequence of delays of a retry policy.
+pub struct Backoff {
+    policy: RetryPolicy,
+    /// The delay before the next attempt (without jitter).
+    next: Duration,
+    /// The total delay returned so far.
+    elapsed: Duration,
+}
+
+impl Backoff {
+    /// The delay before the next attempt, or `None` if it would exceed the retry window.
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        let jitter = if self.policy.jitter > 0.0 {
+            rand::thread_rng().gen_range(0.0..self.policy.jitter)
+        } else {
+            0.0
+        };
+        let delay = self.next.mul_f64(1.0 + jitter).min(self.policy.max_delay);
+        if self.elapsed + delay > self.policy.max_elapsed {
+            return None;
+        }
+
+        self.elapsed += delay;
+        self.next = self
+            .next
+            .mul_f64(self.policy.multiplier)
+            .min(self.policy.max_delay);
+        Some(delay)
+    }
+
+    /// The delay before the next attempt, or the maximum delay once the retry window is
+    /// exhausted. This is useful to poll for a condition indefinitely.
+    pub fn next_delay_or_max(&mut self) -> Duration {
+        self.next_delay().unwrap_or(self.policy.max_delay)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::RetryPolicy;
+
+    fn policy(max_elapsed: Duration) -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1_000),
+            multiplier: 2.0,
+            jitter: 0.0,
+            max_elapsed,
+        }
+    }
+
+    #[test]
+    fn delays_follow_multiplier() {
+        let mut backoff = policy(Duration::from_secs(3)).backoff();
+        let delays: Vec<_> = std::iter::from_fn(|| backoff.next_delay())
+            .map(|x| x.as_millis())
+            .collect();
+        // The next delay (1s) would exceed the retry window (3s).
+        assert_eq!(delays, vec![100, 200, 400, 800, 1_000]);
+
+        let mut backoff = policy(Duration::from_secs(10)).backoff();
+        let delays: Vec<_> = std::iter::from_fn(|| backoff.next_delay())
+            .map(|x| x.as_millis())
+            .collect();
+        let mut expected = vec![100, 200, 400, 800];
+        expected.extend([1_000; 8]);
+        assert_eq!(delays, expected);
+        assert_eq!(backoff.next_delay_or_max(), Duration::from_millis(1_000));
+    }
+
+    #[test]
+    fn jitter_within_bounds() {
+        let mut policy = policy(Duration::from_secs(3_600));
+        policy.jitter = 0.5;
+        let mut backoff = policy.backoff();
+        let mut expected = Duration::from_millis(100);
+        for _ in 0..20 {
+            let delay = backoff.next_delay().unwrap();
+            assert!(delay >= expected.min(policy.max_delay), "{delay:?}");
+            assert!(
+                delay <= expected.mul_f64(1.5).min(policy.max_delay),
+                "{delay:?}"
+            );
+            expected = expected.mul_f64(policy.multiplier);
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_returns_last_error() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(4),
+            ..policy(Duration::from_secs(1))
+        };
+        let attempts = AtomicUsize::new(0);
+        let operation = || async {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            if attempt == 3 {
+                Ok(attempt)
+            } else {
+                Err(attempt)
+            }
+        };
+
+        // The operation succeeds at the third attempt.
+        assert_eq!(policy.retry(5, operation).await, Ok(3));
+
+        // Only the specified number of retries are attempted.
+        attempts.store(10, Ordering::SeqCst);
+        assert_eq!(policy.retry(2, operation).await, Err(13));
+        attempts.store(10, Ordering::SeqCst);
+        assert_eq!(
+            policy.retry_blocking(0, || Err::<(), _>(attempts.load(Ordering::SeqCst))),
+            Err(10)
+        );
+
+        // The retries stop once the retry window is exhausted (after 1 + 2 + 4 + 4 ms).
+        let policy = RetryPolicy {
+            max_elapsed: Duration::from_millis(12),
+            ..policy
+        };
+        attempts.store(10, Ordering::SeqCst);
+        assert_eq!(policy.retry(100, operation).await, Err(15));
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use schemars::{schema::RootSchema, schema_for};
+
+use crate::{settings::Settings, ClientParameters, NodeParameters};
+
+/// The configuration files read by the orchestrator.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigFile {
+    /// The testbed settings file.
+    Settings,
+    /// The file overriding the default node parameters.
+    NodeParameters,
+    /// The file overriding the default client parameters.
+    ClientParameters,
+}
+
+impl ConfigFile {
+    /// The JSON schema of the file, listing all fields along with their default values (if any).
+    /// Editors use it to autocomplete and validate the file.
+    pub fn schema(&self) -> RootSchema {
+        match self {
+            Self::Settings => schema_for!(Settings),
+            Self::NodeParameters => schema_for!(NodeParameters),
+            Self::ClientParameters => schema_for!(ClientParameters),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use jsonschema::JSONSchema;
+
+    use super::ConfigFile;
+
+    /// Check that the specified asset file is valid according to the schema.
+    fn validate_asset(file: ConfigFile, asset: &str) {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "assets", asset]
+            .iter()
+            .collect();
+        let content = fs::read_to_string(path).unwrap();
+        let instance: serde_json::Value = serde_yaml::from_str(&content).unwrap();
+
+        let schema = serde_json::to_value(file.schema()).unwrap();
+        let compiled = JSONSchema::compile(&schema).unwrap();
+        let result = compiled.validate(&instance);
+        if let Err(errors) = result {
+            let errors: Vec<_> = errors.map(|x| x.to_string()).collect();
+            panic!("Invalid {asset}: {errors:?}");
+        }
+    }
+
+    #[test]
+    fn validate_settings_files() {
+        validate_asset(ConfigFile::Settings, "settings-template.yml");
+        validate_asset(ConfigFile::Settings, "settings-local.yml");
+    }
+
+    #[test]
+    fn validate_parameters_files() {
+        validate_asset(ConfigFile::NodeParameters, "node-parameters.yml");
+        validate_asset(ConfigFile::ClientParameters, "client-parameters.yml");
+    }
+
+    #[test]
+    fn schema_lists_defaults() {
+        let schema = serde_json::to_value(ConfigFile::Settings.schema()).unwrap();
+        let properties = &schema["properties"];
+        assert_eq!(properties["monitoring"]["default"], true);
+        assert_eq!(properties["benchmark_duration"]["default"], 0);
+        assert!(properties["max_instances"]["default"].is_null());
+
+        // Fields without default values must be specified.
+        let required = schema["required"].as_array().unwrap();
+        assert!(required.contains(&"testbed_id".into()));
+        assert!(!required.contains(&"monitoring".into()));
+
+        // An invalid value is rejected.
+        let compiled = JSONSchema::compile(&schema).unwrap();
+        let invalid = serde_json::json!({ "testbed_id": 1 });
+        assert!(!compiled.is_valid(&invalid));
+    }
+}
@@ -2,26 +2,36 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
+    collections::BTreeMap,
     env,
     fmt::Display,
     fs,
+    net::SocketAddr,
     path::{Path, PathBuf},
     time::Duration,
 };
 
 use reqwest::Url;
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject},
+    JsonSchema,
+};
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr, DurationSeconds};
 
 use crate::{
+    assignment::AssignmentStrategy,
     client::Instance,
     error::{SettingsError, SettingsResult},
     faults::FaultsType,
+    logs::LogPattern,
+    retry::RetryPolicy,
 };
 
 /// The git repository holding the codebase.
 #[serde_as]
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub struct Repository {
     /// The url of the repository.
     #[serde_as(as = "DisplayFromStr")]
@@ -54,6 +64,17 @@ impl Repository {
     }
 }
 
+/// The role of an instance in a benchmark, determining the specs it is deployed with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstanceRole {
+    /// Hosts a node of the committee (or a hot spare).
+    Validator,
+    /// Hosts only load generators.
+    Client,
+    /// Hosts the monitoring stack.
+    Monitoring,
+}
+
 /// The list of supported cloud providers.
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub enum CloudProvider {
@@ -62,33 +83,90 @@ pub enum CloudProvider {
     Aws,
     #[serde(alias = "vultr")]
     Vultr,
+    #[serde(alias = "gcp")]
+    Gcp,
     #[serde(alias = "local")]
     Local,
+    #[serde(alias = "digitalocean")]
+    DigitalOcean,
+}
+
+impl CloudProvider {
+    /// All the names accepted for the cloud providers (including their aliases).
+    const NAMES: [&'static str; 10] = [
+        "Aws",
+        "aws",
+        "Vultr",
+        "vultr",
+        "Gcp",
+        "gcp",
+        "Local",
+        "local",
+        "DigitalOcean",
+        "digitalocean",
+    ];
+}
+
+// The derived schema ignores the serde aliases, and would reject the lowercase names used by
+// most settings files.
+impl JsonSchema for CloudProvider {
+    fn schema_name() -> String {
+        "CloudProvider".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(Self::NAMES.iter().map(|x| (*x).into()).collect()),
+            ..Default::default()
+        }
+        .into()
+    }
 }
 
 /// The testbed settings. Those are topically specified in a file.
 #[serde_as]
-#[derive(Serialize, Deserialize, Clone, Default)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Default)]
 pub struct Settings {
     /// The testbed unique id. This allows multiple users to run concurrent testbeds on the
     /// same cloud provider's account without interference with each others.
     pub testbed_id: String,
+    /// The id of the benchmark run. If specified, the instances created by the orchestrator are
+    /// tagged with it and only the instances carrying it are used, so that multiple runs can
+    /// share the same testbed without interfering with each other.
+    pub run_id: Option<String>,
     /// The cloud provider hosting the testbed.
     pub cloud_provider: CloudProvider,
     /// The path to the secret token for authentication with the cloud provider.
     #[serde(skip_serializing)]
     pub token_file: PathBuf,
+    /// The GCP project hosting the testbed (only used with the GCP cloud provider).
+    pub gcp_project_id: Option<String>,
+    /// The path to the GCP service-account JSON key file (only used with the GCP cloud provider).
+    #[serde(skip_serializing)]
+    pub gcp_service_account_file: Option<PathBuf>,
     /// The ssh private key to access the instances.
     #[serde(skip_serializing)]
     pub ssh_private_key_file: PathBuf,
+    /// The passphrase of the ssh private key (if encrypted). Defaults to the value of the
+    /// `SSH_KEY_PASSPHRASE` environment variable.
+    #[serde(skip_serializing)]
+    pub ssh_private_key_passphrase: Option<String>,
     /// The corresponding ssh public key registered on the instances. If not specified. the
     /// public key defaults the same path as the private key with an added extension 'pub'.
     pub ssh_public_key_file: Option<PathBuf>,
+    /// The address of the bastion (jump host) through which all ssh connections to the
+    /// instances are tunnelled, e.g., when the instances live in a private subnet.
+    pub ssh_jump_host: Option<SocketAddr>,
     /// The list of cloud provider regions to deploy the testbed.
     pub regions: Vec<String>,
     /// The specs of the instances to deploy. Those are dependent on the cloud provider, e.g.,
     /// specifying 't3.medium' creates instances with 2 vCPU and 4GBo of ram on AWS.
     pub specs: String,
+    /// The specs of the instances hosting the nodes (and their hot spares), overriding `specs`.
+    pub validator_specs: Option<String>,
+    /// The specs of the instances dedicated to the load generators, overriding `specs`.
+    pub client_specs: Option<String>,
     /// The details of the git reposit to deploy.
     pub repository: Repository,
     /// The path to the node's configuration file. If not specified, the orchestrator uses the
@@ -102,6 +180,11 @@ pub struct Settings {
     #[serde(default = "defaults::default_benchmark_duration")]
     #[serde_as(as = "DurationSeconds")]
     pub benchmark_duration: Duration,
+    /// The time to wait between stopping the load generators and stopping the nodes at the end
+    /// of a benchmark, letting in-flight transactions commit.
+    #[serde(default = "defaults::default_drain_cooldown")]
+    #[serde_as(as = "DurationSeconds")]
+    pub drain_cooldown: Duration,
     /// The default faults type to apply to the testbed's nodes.
     #[serde(default = "defaults::default_faults_type")]
     pub faults: FaultsType,
@@ -117,6 +200,18 @@ pub struct Settings {
     /// Whether to use NVMe drives for data storage (if available).
     #[serde(default = "defaults::default_use_nvme")]
     pub nvme: bool,
+    /// Whether to request spot instances. Instances are created with on-demand capacity if the
+    /// spot request cannot be fulfilled within `spot_request_timeout`. Only supported on AWS.
+    #[serde(default = "defaults::default_use_spot_instances")]
+    pub use_spot_instances: bool,
+    /// The maximum hourly price (in USD) to pay for a spot instance. Defaults to the on-demand
+    /// price of the instance type.
+    #[serde(default = "defaults::default_max_spot_price")]
+    pub max_spot_price: Option<String>,
+    /// The time to wait for a spot request to be fulfilled (in seconds).
+    #[serde(default = "defaults::default_spot_request_timeout")]
+    #[serde_as(as = "DurationSeconds")]
+    pub spot_request_timeout: Duration,
     /// The interval between measurements collection.
     #[serde(default = "defaults::default_scrape_interval")]
     #[serde_as(as = "DurationSeconds")]
@@ -128,9 +223,27 @@ pub struct Settings {
     /// to zero, the orchestrator runs a load generate collocated with each node.
     #[serde(default = "defaults::default_dedicated_clients")]
     pub dedicated_clients: usize,
+    /// Number of instances provisioned on top of the committee and kept idle as hot spares.
+    /// Spares are never part of the initial committee; a fault may later promote them to
+    /// replace crashed nodes.
+    #[serde(default = "defaults::default_spare_instances")]
+    pub spare_instances: usize,
+    /// The maximum number of instances the testbed may deploy (e.g., the instance quota of the
+    /// cloud provider's account). If not specified, the number of instances is not bounded.
+    #[serde(default = "defaults::default_max_instances")]
+    pub max_instances: Option<usize>,
+    /// The strategy assigning the authorities of the committee to the instances.
+    #[serde(default = "defaults::default_assignment")]
+    pub assignment: AssignmentStrategy,
     /// Whether to start a grafana and prometheus instance on a dedicate machine.
     #[serde(default = "defaults::default_monitoring")]
     pub monitoring: bool,
+    /// The prometheus remote-write endpoint (e.g., `http://host:9090/api/v1/write`) to which
+    /// the measurements of each benchmark are pushed once it completes, for long-term storage.
+    /// Nothing is pushed if not specified.
+    #[serde(default)]
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub remote_write_endpoint: Option<Url>,
     /// The timeout duration for ssh commands (in seconds).
     #[serde(default = "defaults::default_ssh_timeout")]
     #[serde_as(as = "DurationSeconds")]
@@ -138,17 +251,68 @@ pub struct Settings {
     /// The number of times the orchestrator should retry an ssh command.
     #[serde(default = "defaults::default_ssh_retries")]
     pub ssh_retries: usize,
+    /// The duration after which idle ssh sessions are closed rather than reused (in seconds).
+    #[serde(default = "defaults::default_ssh_pool_idle_timeout")]
+    #[serde_as(as = "DurationSeconds")]
+    pub ssh_pool_idle_timeout: Duration,
+    /// The maximum number of ssh sessions open at once with each instance.
+    #[serde(default = "defaults::default_ssh_pool_max_per_host")]
+    pub ssh_pool_max_per_host: usize,
+    /// The time to wait for all instances to be reachable before running benchmarks on them
+    /// (in seconds).
+    #[serde(default = "defaults::default_reachability_timeout")]
+    #[serde_as(as = "DurationSeconds")]
+    pub reachability_timeout: Duration,
+    /// The delays between the retries of the ssh connections and commands, between the attempts
+    /// at running a command until it succeeds (both over ssh and locally), and between the
+    /// retries of the requests to the cloud provider.
+    #[serde(default = "defaults::default_retry_policy")]
+    pub retry_policy: RetryPolicy,
+    /// The number of times the orchestrator should retry a request to the cloud provider that
+    /// failed transiently (e.g., because of rate limiting).
+    #[serde(default = "defaults::default_cloud_retries")]
+    pub cloud_retries: usize,
+    /// The timeout duration for commands executed on the local machine (in seconds). Commands
+    /// running longer are killed. This value is only used by the local cloud provider and
+    /// should leave enough time to compile the codebase.
+    #[serde(default = "defaults::default_local_timeout")]
+    #[serde_as(as = "DurationSeconds")]
+    pub local_timeout: Duration,
+    /// The `RUST_LOG` value of specific nodes, indexed by authority. This is useful to debug
+    /// a single node; the other nodes keep their default log level.
+    #[serde(default = "defaults::default_rust_log_overrides")]
+    pub rust_log_overrides: BTreeMap<usize, String>,
+    /// The maximum number of files downloaded from the instances at the same time.
+    #[serde(default = "defaults::default_download_concurrency")]
+    pub download_concurrency: usize,
+    /// The patterns to look for in the logs after every benchmark run. A match of a critical
+    /// pattern fails the run.
+    #[serde(default = "defaults::default_log_patterns")]
+    pub log_patterns: Vec<LogPattern>,
+    /// The maximum number of transaction latencies retained per benchmark run to compute the
+    /// latency percentiles. Beyond this number, the latencies are sampled (reservoir sampling).
+    #[serde(default = "defaults::default_latency_samples")]
+    pub latency_samples: usize,
 }
 
 mod defaults {
-    use std::{path::PathBuf, time::Duration};
+    use std::{collections::BTreeMap, path::PathBuf, time::Duration};
 
-    use crate::faults::FaultsType;
+    use crate::{
+        assignment::AssignmentStrategy,
+        faults::FaultsType,
+        logs::LogPattern,
+        retry::RetryPolicy,
+    };
 
     pub fn default_benchmark_duration() -> Duration {
         Duration::from_secs(0)
     }
 
+    pub fn default_drain_cooldown() -> Duration {
+        Duration::from_secs(10)
+    }
+
     pub fn default_faults_type() -> FaultsType {
         FaultsType::default()
     }
@@ -169,6 +333,18 @@ mod defaults {
         true
     }
 
+    pub fn default_use_spot_instances() -> bool {
+        false
+    }
+
+    pub fn default_max_spot_price() -> Option<String> {
+        None
+    }
+
+    pub fn default_spot_request_timeout() -> Duration {
+        Duration::from_secs(60)
+    }
+
     pub fn default_scrape_interval() -> Duration {
         Duration::from_secs(15)
     }
@@ -181,6 +357,18 @@ mod defaults {
         0
     }
 
+    pub fn default_spare_instances() -> usize {
+        0
+    }
+
+    pub fn default_max_instances() -> Option<usize> {
+        None
+    }
+
+    pub fn default_assignment() -> AssignmentStrategy {
+        AssignmentStrategy::default()
+    }
+
     pub fn default_monitoring() -> bool {
         true
     }
@@ -192,6 +380,46 @@ mod defaults {
     pub fn default_ssh_retries() -> usize {
         3
     }
+
+    pub fn default_ssh_pool_idle_timeout() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    pub fn default_ssh_pool_max_per_host() -> usize {
+        8
+    }
+
+    pub fn default_reachability_timeout() -> Duration {
+        Duration::from_secs(300)
+    }
+
+    pub fn default_retry_policy() -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    pub fn default_cloud_retries() -> usize {
+        5
+    }
+
+    pub fn default_local_timeout() -> Duration {
+        Duration::from_secs(3600)
+    }
+
+    pub fn default_rust_log_overrides() -> BTreeMap<usize, String> {
+        BTreeMap::new()
+    }
+
+    pub fn default_download_concurrency() -> usize {
+        10
+    }
+
+    pub fn default_log_patterns() -> Vec<LogPattern> {
+        LogPattern::defaults()
+    }
+
+    pub fn default_latency_samples() -> usize {
+        100_000
+    }
 }
 
 impl Settings {
@@ -203,7 +431,11 @@ impl Settings {
         let reader = || -> Result<Self, Box<dyn std::error::Error>> {
             let data = fs::read(path.clone())?;
             let data = Self::resolve_env(&path, std::str::from_utf8(&data)?)?;
-            let settings: Settings = serde_yaml::from_slice(data.as_bytes())?;
+            let extension = path.as_ref().extension().and_then(|x| x.to_str());
+            let settings: Settings = match extension {
+                Some("json") => serde_json::from_str(&data)?,
+                _ => serde_yaml::from_str(&data)?,
+            };
 
             fs::create_dir_all(&settings.results_dir)?;
             fs::create_dir_all(&settings.logs_dir)?;
@@ -211,10 +443,142 @@ impl Settings {
             Ok(settings)
         };
 
-        reader().map_err(|e| SettingsError::InvalidSettings {
+        let settings = reader().map_err(|e| SettingsError::InvalidSettings {
             file: path.to_string(),
             message: e.to_string(),
-        })
+        })?;
+        settings
+            .validate()
+            .map_err(|errors| SettingsError::InvalidSettings {
+                file: path.to_string(),
+                message: errors
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            })?;
+        Ok(settings)
+    }
+
+    /// Check that the settings can be used to deploy a testbed. Returns all the errors found,
+    /// each naming the offending field and suggesting a fix.
+    pub fn validate(&self) -> Result<(), Vec<SettingsError>> {
+        let invalid = |field, message: String, suggestion: &str| SettingsError::InvalidField {
+            field,
+            message,
+            suggestion: suggestion.to_string(),
+        };
+        let mut errors = Vec::new();
+        let local = matches!(self.cloud_provider, CloudProvider::Local);
+
+        // The local provider runs its commands directly, without ssh.
+        if !local {
+            if let Err(e) = fs::File::open(&self.ssh_private_key_file) {
+                errors.push(invalid(
+                    "ssh_private_key_file",
+                    format!("cannot read '{}': {e}", self.ssh_private_key_file.display()),
+                    "point it to an existing private key or generate one with `ssh-keygen`",
+                ));
+            }
+            if self.regions.is_empty() {
+                errors.push(invalid(
+                    "regions",
+                    "no region specified".into(),
+                    "list at least one region of the cloud provider",
+                ));
+            }
+        }
+
+        // The load generators, spares, and monitoring instance must leave room for the nodes.
+        if let Some(max_instances) = self.max_instances {
+            let reserved = self.required_instances(0);
+            if reserved >= max_instances {
+                errors.push(invalid(
+                    "dedicated_clients",
+                    format!(
+                        "the {reserved} instance(s) reserved for load generators, spares, and \
+                        monitoring leave no room for the nodes within {max_instances} instances"
+                    ),
+                    "reduce `dedicated_clients` or `spare_instances`, or raise `max_instances`",
+                ));
+            }
+        }
+
+        if local {
+            if self.working_dir.as_os_str().is_empty() {
+                errors.push(invalid(
+                    "working_dir",
+                    "no working directory specified".into(),
+                    "set it to a local directory, e.g., `~/working_dir`",
+                ));
+            } else if let Err(e) = Self::check_writable(&self.working_dir) {
+                errors.push(invalid(
+                    "working_dir",
+                    format!("cannot write to '{}': {e}", self.working_dir.display()),
+                    "choose a directory the current user can create and write to",
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check that the testbed can host a committee of the specified size along with its load
+    /// generators, spares, and monitoring instance. The committee size is only known once the
+    /// command is parsed, so this check complements `validate`.
+    pub fn validate_committee(&self, committee: usize) -> SettingsResult<()> {
+        let required = self.required_instances(committee);
+        match self.max_instances {
+            Some(max_instances) if required > max_instances => Err(SettingsError::InvalidField {
+                field: "max_instances",
+                message: format!(
+                    "a committee of {committee} node(s) requires {required} instances (including \
+                    load generators, spares, and monitoring) but at most {max_instances} are allowed"
+                ),
+                suggestion: "reduce the committee size, `dedicated_clients`, or \
+                    `spare_instances`, or raise `max_instances`"
+                    .into(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the specified local directory exists (or can be created) and is writable. This
+    /// check leaves the filesystem untouched: the directory is only created when the benchmark
+    /// sets up the instances, so it is enough for its nearest existing ancestor to be a writable
+    /// directory.
+    fn check_writable(directory: &Path) -> std::io::Result<()> {
+        let directory = match directory.strip_prefix("~") {
+            Ok(relative) => {
+                let home = env::var("HOME")
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::NotFound, e))?;
+                Path::new(&home).join(relative)
+            }
+            Err(_) => directory.to_path_buf(),
+        };
+        let directory = env::current_dir()?.join(directory);
+        let ancestor = directory
+            .ancestors()
+            .find(|x| x.exists())
+            .unwrap_or(Path::new("/"));
+        let metadata = fs::metadata(ancestor)?;
+        if !metadata.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("'{}' is not a directory", ancestor.display()),
+            ));
+        }
+        if metadata.permissions().readonly() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("'{}' is read-only", ancestor.display()),
+            ));
+        }
+        Ok(())
     }
 
     // Resolves ${ENV} into it's value for each env variable.
@@ -275,11 +639,71 @@ impl Settings {
         }
     }
 
-    /// Check whether the input instance matches the criteria described in the settings.
+    /// Check whether the input instance matches the criteria described in the settings (with the
+    /// specs of any role).
     pub fn filter_instances(&self, instance: &Instance) -> bool {
+        [
+            InstanceRole::Validator,
+            InstanceRole::Client,
+            InstanceRole::Monitoring,
+        ]
+        .into_iter()
+        .any(|role| self.filter_instances_with_specs(instance, self.specs_for(role)))
+    }
+
+    /// The specs of the instances with the specified role.
+    pub fn specs_for(&self, role: InstanceRole) -> &str {
+        let specs = match role {
+            InstanceRole::Validator => &self.validator_specs,
+            InstanceRole::Client => &self.client_specs,
+            InstanceRole::Monitoring => &None,
+        };
+        specs.as_deref().unwrap_or(&self.specs)
+    }
+
+    /// Check whether the input instance runs in one of the regions of the settings, has the
+    /// specified specs (overriding the specs of the settings), and belongs to the current run.
+    pub fn filter_instances_with_specs(&self, instance: &Instance, specs: &str) -> bool {
         self.regions.contains(&instance.region)
-            && instance.specs.to_lowercase().replace('.', "")
-                == self.specs.to_lowercase().replace('.', "")
+            && instance.has_specs(specs)
+            && instance.has_tags(&self.run_tags())
+    }
+
+    /// The tags marking the instances of the current benchmark run (if any).
+    pub fn run_tags(&self) -> Vec<String> {
+        self.run_id.iter().cloned().collect()
+    }
+
+    /// The number of active instances required to run a committee of the specified size, that is,
+    /// the nodes, their hot spares, the dedicated load generators, and the monitoring instance.
+    pub fn required_instances(&self, committee: usize) -> usize {
+        committee
+            + self.spare_instances
+            + self.dedicated_clients
+            + if self.monitoring { 1 } else { 0 }
+    }
+
+    /// The number of active instances of each specs required to run a committee of the
+    /// specified size, deploying each role with its own specs.
+    pub fn required_instances_by_specs(&self, committee: usize) -> Vec<(String, usize)> {
+        let roles = [
+            (InstanceRole::Validator, committee + self.spare_instances),
+            (InstanceRole::Client, self.dedicated_clients),
+            (
+                InstanceRole::Monitoring,
+                if self.monitoring { 1 } else { 0 },
+            ),
+        ];
+
+        let mut required: Vec<(String, usize)> = Vec::new();
+        for (role, quantity) in roles.into_iter().filter(|(_, x)| *x != 0) {
+            let specs = self.specs_for(role);
+            match required.iter_mut().find(|(x, _)| x == specs) {
+                Some((_, total)) => *total += quantity,
+                None => required.push((specs.into(), quantity)),
+            }
+        }
+        required
     }
 
     /// The number of regions specified in the settings.
@@ -310,9 +734,28 @@ impl Settings {
 
 #[cfg(test)]
 mod test {
+    use std::fs;
+
     use reqwest::Url;
 
-    use crate::settings::Settings;
+    use crate::{
+        error::SettingsError,
+        settings::{CloudProvider, Settings},
+    };
+
+    /// The fields named by the errors of the settings validation.
+    fn invalid_fields(settings: &Settings) -> Vec<&'static str> {
+        match settings.validate() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .iter()
+                .map(|x| match x {
+                    SettingsError::InvalidField { field, .. } => *field,
+                    _ => panic!("Unexpected error: {x}"),
+                })
+                .collect(),
+        }
+    }
 
     #[test]
     fn load_ssh_public_key() {
@@ -321,6 +764,142 @@ mod test {
         assert_eq!(public_key, "This is a fake public key for tests");
     }
 
+    #[test]
+    fn load_json_and_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        let results_dir = dir.path().join("results");
+        let logs_dir = dir.path().join("logs");
+        let private_key_file = dir.path().join("id_ed25519");
+        fs::write(&private_key_file, "This is a fake private key for tests").unwrap();
+
+        let yaml = format!(
+            "testbed_id: testbed\n\
+            cloud_provider: aws\n\
+            token_file: /path/to/token/file\n\
+            ssh_private_key_file: {}\n\
+            regions:\n  - us-east-1\n  - eu-north-1\n\
+            specs: m5d.8xlarge\n\
+            repository:\n  url: https://example.com/author/repo\n  commit: main\n\
+            results_dir: {}\n\
+            logs_dir: {}\n\
+            benchmark_duration: 300\n\
+            rust_log_overrides:\n  3: debug\n",
+            private_key_file.display(),
+            results_dir.display(),
+            logs_dir.display()
+        );
+        let json = format!(
+            r#"{{
+                "testbed_id": "testbed",
+                "cloud_provider": "aws",
+                "token_file": "/path/to/token/file",
+                "ssh_private_key_file": "{}",
+                "regions": ["us-east-1", "eu-north-1"],
+                "specs": "m5d.8xlarge",
+                "repository": {{"url": "https://example.com/author/repo", "commit": "main"}},
+                "results_dir": "{}",
+                "logs_dir": "{}",
+                "benchmark_duration": 300,
+                "rust_log_overrides": {{"3": "debug"}}
+            }}"#,
+            private_key_file.display(),
+            results_dir.display(),
+            logs_dir.display()
+        );
+
+        let mut loaded = Vec::new();
+        for (file, content) in [
+            ("settings.yaml", &yaml),
+            ("settings.yml", &yaml),
+            ("settings.json", &json),
+        ] {
+            let path = dir.path().join(file);
+            fs::write(&path, content).unwrap();
+            loaded.push(Settings::load(path.display().to_string()).unwrap());
+        }
+
+        let json_settings = loaded.pop().unwrap();
+        assert_eq!(json_settings.benchmark_duration.as_secs(), 300);
+        assert_eq!(json_settings.rust_log_overrides[&3], "debug");
+        for settings in loaded {
+            assert_eq!(
+                serde_json::to_value(&settings).unwrap(),
+                serde_json::to_value(&json_settings).unwrap()
+            );
+            assert_eq!(settings.token_file, json_settings.token_file);
+            assert_eq!(
+                settings.ssh_private_key_file,
+                json_settings.ssh_private_key_file
+            );
+        }
+    }
+
+    #[test]
+    fn validate_missing_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["us-east-1".into()];
+        assert_eq!(invalid_fields(&settings), vec!["ssh_private_key_file"]);
+        let error = settings.validate().unwrap_err().remove(0).to_string();
+        assert!(error.contains("/path/to/private/key/file"), "{error}");
+        assert!(error.contains("ssh-keygen"), "{error}");
+
+        settings.ssh_private_key_file = dir.path().join("id_ed25519");
+        fs::write(&settings.ssh_private_key_file, "private key").unwrap();
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_empty_regions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut settings = Settings::new_for_test();
+        settings.ssh_private_key_file = dir.path().join("id_ed25519");
+        fs::write(&settings.ssh_private_key_file, "private key").unwrap();
+        settings.dedicated_clients = 2;
+        settings.max_instances = Some(2);
+        assert_eq!(
+            invalid_fields(&settings),
+            vec!["regions", "dedicated_clients"]
+        );
+
+        // The local provider needs neither ssh keys nor regions, but a writable directory.
+        settings.cloud_provider = CloudProvider::Local;
+        settings.ssh_private_key_file = "/path/to/private/key/file".into();
+        settings.max_instances = Some(4);
+        settings.working_dir = dir.path().join("working_dir");
+        assert!(settings.validate().is_ok());
+        // Validating the settings does not create the working directory.
+        assert!(!settings.working_dir.exists());
+        settings.working_dir = dir.path().join("id_ed25519").join("working_dir");
+        assert_eq!(invalid_fields(&settings), vec!["working_dir"]);
+        settings.working_dir = "".into();
+        assert_eq!(invalid_fields(&settings), vec!["working_dir"]);
+    }
+
+    #[test]
+    fn validate_committee() {
+        let mut settings = Settings::new_for_test();
+        settings.dedicated_clients = 1;
+        settings.monitoring = true;
+        assert!(settings.validate_committee(100).is_ok());
+
+        // The committee, the load generator, and the monitoring instance need 6 instances.
+        settings.max_instances = Some(6);
+        assert!(settings.validate_committee(4).is_ok());
+        let error = settings.validate_committee(5).unwrap_err();
+        assert!(matches!(
+            error,
+            SettingsError::InvalidField {
+                field: "max_instances",
+                ..
+            }
+        ));
+        assert!(
+            error.to_string().contains("requires 7 instances"),
+            "{error}"
+        );
+    }
+
     #[test]
     fn repository_name() {
         let mut settings = Settings::new_for_test();
@@ -0,0 +1,284 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use prettytable::{row, Table};
+use serde::{Deserialize, Serialize};
+
+use crate::{display, measurements::MeasurementsCollection};
+
+/// The measurements collected over one snapshot interval of a soak test.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SoakSnapshot {
+    /// Duration since the beginning of the benchmark (at the end of the interval).
+    pub elapsed: Duration,
+    /// The throughput over the interval (tx/s).
+    pub tps: f64,
+    /// The average latency of the transactions finalized during the interval.
+    pub average_latency: Duration,
+    /// The largest memory usage reported by the nodes at the end of the interval (in bytes).
+    pub memory: Option<u64>,
+}
+
+/// The maximum drift tolerated over the whole soak test, expressed as a fraction of the
+/// initial value of each metric.
+#[derive(Clone, Copy, Debug)]
+pub struct DriftThresholds {
+    pub throughput_decline: f64,
+    pub latency_growth: f64,
+    pub memory_growth: f64,
+}
+
+impl Default for DriftThresholds {
+    fn default() -> Self {
+        Self {
+            throughput_decline: 0.1,
+            latency_growth: 0.2,
+            memory_growth: 0.2,
+        }
+    }
+}
+
+/// A regression detected over the soak test, along with the measured drift.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Regression {
+    ThroughputDecline(f64),
+    LatencyGrowth(f64),
+    MemoryCreep(f64),
+}
+
+impl Display for Regression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ThroughputDecline(x) => write!(f, "Throughput declined by {:.1}%", -x * 100.0),
+            Self::LatencyGrowth(x) => write!(f, "Latency grew by {:.1}%", x * 100.0),
+            Self::MemoryCreep(x) => write!(f, "Memory usage grew by {:.1}%", x * 100.0),
+        }
+    }
+}
+
+/// The periodic snapshots of a soak test. Each snapshot only covers the transactions finalized
+/// since the previous one, so slow degradations are not hidden by the cumulative measurements.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SoakReport {
+    snapshots: Vec<SoakSnapshot>,
+    /// The totals (elapsed time, transaction count, latency sum) at the previous snapshot.
+    #[serde(skip)]
+    checkpoint: (Duration, usize, Duration),
+}
+
+impl SoakReport {
+    /// Create a new (empty) soak report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a report from existing snapshots.
+    #[cfg(test)]
+    pub fn from_snapshots(snapshots: Vec<SoakSnapshot>) -> Self {
+        Self {
+            snapshots,
+            ..Self::default()
+        }
+    }
+
+    /// Take a snapshot of the measurements collected since the previous snapshot. Returns `None`
+    /// if no new measurement was collected in the meantime.
+    pub fn snapshot(
+        &mut self,
+        collection: &MeasurementsCollection,
+        memory: Option<u64>,
+    ) -> Option<&SoakSnapshot> {
+        let elapsed = collection.benchmark_duration();
+        let (count, sum) = collection.totals();
+        let (last_elapsed, last_count, last_sum) = self.checkpoint;
+        let interval = elapsed.checked_sub(last_elapsed).filter(|x| !x.is_zero())?;
+
+        let transactions = count.saturating_sub(last_count);
+        let average_latency = sum
+            .saturating_sub(last_sum)
+            .checked_div(transactions as u32)
+            .unwrap_or_default();
+        self.checkpoint = (elapsed, count, sum);
+        self.snapshots.push(SoakSnapshot {
+            elapsed,
+            tps: transactions as f64 / interval.as_secs_f64(),
+            average_latency,
+            memory,
+        });
+        self.snapshots.last()
+    }
+
+    /// The relative change of the throughput over the soak test (negative if it declines).
+    pub fn throughput_drift(&self) -> Option<f64> {
+        self.drift(|x| Some(x.tps))
+    }
+
+    /// The relative change of the average latency over the soak test.
+    pub fn latency_drift(&self) -> Option<f64> {
+        self.drift(|x| Some(x.average_latency.as_secs_f64()))
+    }
+
+    /// The relative change of the memory usage over the soak test.
+    pub fn memory_drift(&self) -> Option<f64> {
+        self.drift(|x| x.memory.map(|x| x as f64))
+    }
+
+    /// Compute the relative drift of a metric by fitting a line (least squares) through the
+    /// snapshots, and comparing its value at the last snapshot with its value at the first one.
+    /// Fitting a line rather than comparing the first and last snapshots makes the drift robust
+    /// to the noise of individual snapshots.
+    fn drift(&self, metric: impl Fn(&SoakSnapshot) -> Option<f64>) -> Option<f64> {
+        let points: Vec<_> = self
+            .snapshots
+            .iter()
+            .filter_map(|x| metric(x).map(|y| (x.elapsed.as_secs_f64(), y)))
+            .collect();
+        if points.len() < 2 {
+            return None;
+        }
+
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let covariance: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        if variance == 0.0 {
+            return None;
+        }
+
+        let slope = covariance / variance;
+        let (first, last) = (points[0].0, points[points.len() - 1].0);
+        let initial = mean_y + slope * (first - mean_x);
+        if initial <= 0.0 {
+            return None;
+        }
+        Some(slope * (last - first) / initial)
+    }
+
+    /// The regressions exceeding the specified thresholds.
+    pub fn regressions(&self, thresholds: &DriftThresholds) -> Vec<Regression> {
+        let mut regressions = Vec::new();
+        if let Some(drift) = self.throughput_drift() {
+            if -drift > thresholds.throughput_decline {
+                regressions.push(Regression::ThroughputDecline(drift));
+            }
+        }
+        if let Some(drift) = self.latency_drift() {
+            if drift > thresholds.latency_growth {
+                regressions.push(Regression::LatencyGrowth(drift));
+            }
+        }
+        if let Some(drift) = self.memory_drift() {
+            if drift > thresholds.memory_growth {
+                regressions.push(Regression::MemoryCreep(drift));
+            }
+        }
+        regressions
+    }
+
+    /// Save the snapshots as a json file.
+    pub fn save<P: AsRef<Path>>(&self, path: P, collection: &MeasurementsCollection) {
+        let json = serde_json::to_string_pretty(self).expect("Cannot serialize snapshots");
+        let mut file = PathBuf::from(path.as_ref());
+        file.push(format!("soak-{:?}.json", collection.parameters));
+        fs::write(file, json).unwrap();
+    }
+
+    /// Display the drift of each metric and the detected regressions.
+    pub fn display_summary(&self, thresholds: &DriftThresholds) {
+        let format_drift = |drift: Option<f64>| {
+            drift
+                .map(|x| format!("{:+.1}%", x * 100.0))
+                .unwrap_or_else(|| "n/a".into())
+        };
+
+        let mut table = Table::new();
+        table.set_format(display::default_table_format());
+
+        table.set_titles(row![bH2->"Soak Test Summary"]);
+        table.add_row(row![b->"Snapshots:", self.snapshots.len()]);
+        table.add_row(row![b->"Throughput drift:", format_drift(self.throughput_drift())]);
+        table.add_row(row![b->"Latency drift:", format_drift(self.latency_drift())]);
+        table.add_row(row![b->"Memory drift:", format_drift(self.memory_drift())]);
+
+        display::newline();
+        table.printstd();
+        display::newline();
+
+        for regression in self.regressions(thresholds) {
+            display::warn(format!("Regression detected: {regression}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{DriftThresholds, Regression, SoakReport, SoakSnapshot};
+
+    fn snapshot(elapsed: u64, tps: f64, latency_ms: u64, memory: u64) -> SoakSnapshot {
+        SoakSnapshot {
+            elapsed: Duration::from_secs(elapsed),
+            tps,
+            average_latency: Duration::from_millis(latency_ms),
+            memory: Some(memory),
+        }
+    }
+
+    #[test]
+    fn declining_throughput() {
+        // The throughput declines linearly from 1000 tx/s to 700 tx/s (with some noise) while
+        // the latency and memory remain stable.
+        let noise = [0.0, 15.0, -10.0, 5.0, -15.0, 10.0, 0.0];
+        let snapshots = (0..7)
+            .map(|i| {
+                snapshot(
+                    60 * (i + 1),
+                    1000.0 - 50.0 * i as f64 + noise[i as usize],
+                    500,
+                    1 << 20,
+                )
+            })
+            .collect();
+        let report = SoakReport::from_snapshots(snapshots);
+
+        let drift = report.throughput_drift().unwrap();
+        assert!((drift + 0.3).abs() < 0.01, "{drift}");
+        assert_eq!(report.latency_drift(), Some(0.0));
+        assert_eq!(report.memory_drift(), Some(0.0));
+
+        let regressions = report.regressions(&DriftThresholds::default());
+        assert_eq!(regressions.len(), 1);
+        assert!(matches!(regressions[0], Regression::ThroughputDecline(_)));
+    }
+
+    #[test]
+    fn stable_throughput() {
+        let noise = [0.0, 15.0, -10.0, 5.0, -15.0, 10.0, 0.0];
+        let snapshots = (0..7)
+            .map(|i| snapshot(60 * (i + 1), 1000.0 + noise[i as usize], 500, 1 << 20))
+            .collect();
+        let report = SoakReport::from_snapshots(snapshots);
+
+        assert!(report.throughput_drift().unwrap().abs() < 0.01);
+        assert!(report.regressions(&DriftThresholds::default()).is_empty());
+    }
+
+    #[test]
+    fn not_enough_snapshots() {
+        let report = SoakReport::from_snapshots(vec![snapshot(60, 1000.0, 500, 1 << 20)]);
+        assert_eq!(report.throughput_drift(), None);
+        assert!(report.regressions(&DriftThresholds::default()).is_empty());
+    }
+}
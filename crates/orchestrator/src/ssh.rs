@@ -2,22 +2,39 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    io::Read,
-    net::SocketAddr,
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener},
     path::{Path, PathBuf},
+    sync::Arc,
+    thread,
     time::Duration,
 };
 
 use futures::future::try_join_all;
-use ssh2::{Channel, Session};
-use tokio::{net::TcpStream, runtime::Handle, task::JoinHandle, time::sleep};
+use ssh2::{Channel, ErrorCode, Session, Sftp};
+use tokio::{net::TcpStream, runtime::Handle, sync::mpsc, task::JoinHandle, time::sleep};
 
 use crate::{
     client::Instance,
     ensure,
     error::{SshError, SshResult},
+    pool::{ConnectionPool, PooledConnection},
+    retry::RetryPolicy,
+    ssh_key::SshPrivateKey,
 };
 
+/// A line printed by a command, forwarded as soon as the command prints it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputLine {
+    Stdout(String),
+    Stderr(String),
+}
+
+/// The channel receiving the output of streamed commands, along with the id of the instance
+/// running them.
+pub type OutputSender = mpsc::UnboundedSender<(String, OutputLine)>;
+
 #[derive(PartialEq, Eq)]
 /// The status of a ssh command running in the background.
 pub enum CommandStatus {
@@ -26,10 +43,11 @@ pub enum CommandStatus {
 }
 
 impl CommandStatus {
-    /// Return whether a background command is still running. Returns `Terminated` if the
-    /// command is not running in the background.
-    pub fn status(command_id: &str, text: &str) -> Self {
-        if text.contains(command_id) {
+    /// Return whether a background command is still running given the output of `tmux ls`.
+    /// Returns `Terminated` if the command is not running in the background.
+    pub fn status(session_name: &str, text: &str) -> Self {
+        let prefix = format!("{session_name}:");
+        if text.lines().any(|line| line.starts_with(&prefix)) {
             Self::Running
         } else {
             Self::Terminated
@@ -37,6 +55,21 @@ impl CommandStatus {
     }
 }
 
+/// Return the name of the tmux session running the specified background command on an
+/// instance. Sessions are named after the instance so that multiple instances sharing the same
+/// host do not collide.
+pub fn session_name(command_id: &str, instance: &Instance) -> String {
+    // Tmux does not allow periods and colons in session names.
+    format!("{command_id}-{}", instance.id).replace(['.', ':'], "_")
+}
+
+/// The command killing the tmux session running the specified background command on an
+/// instance. The session name is matched exactly, not as a prefix.
+pub fn kill_session_command(command_id: &str, instance: &Instance) -> String {
+    let session = session_name(command_id, instance);
+    format!("(tmux kill-session -t ={session} || true)")
+}
+
 /// The command to execute on all specified remote machines.
 #[derive(Clone, Default)]
 pub struct CommandContext {
@@ -47,6 +80,8 @@ pub struct CommandContext {
     pub path: Option<PathBuf>,
     /// The log file to redirect all stdout and stderr.
     pub log_file: Option<PathBuf>,
+    /// The environment variables to set before running the command.
+    pub env: Vec<(String, String)>,
 }
 
 impl CommandContext {
@@ -56,6 +91,7 @@ impl CommandContext {
             background: None,
             path: None,
             log_file: None,
+            env: Vec::new(),
         }
     }
 
@@ -77,9 +113,34 @@ impl CommandContext {
         self
     }
 
+    /// Set an environment variable for the command.
+    pub fn with_env<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Specialize the context for the specified instance, naming its background session after
+    /// the instance.
+    pub fn for_instance(&self, instance: &Instance) -> Self {
+        let mut context = self.clone();
+        context.background = self
+            .background
+            .as_ref()
+            .map(|id| session_name(id, instance));
+        context
+    }
+
     /// Apply the context to a base command.
     pub fn apply<S: Into<String>>(&self, base_command: S) -> String {
         let mut str = base_command.into();
+        if !self.env.is_empty() {
+            let variables: Vec<_> = self
+                .env
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect();
+            str = format!("export {} && {str}", variables.join(" "));
+        }
         if let Some(log_file) = &self.log_file {
             str = format!("{str} |& tee {}", log_file.as_path().display());
         }
@@ -97,51 +158,97 @@ impl CommandContext {
 pub struct SshConnectionManager {
     /// The ssh username.
     username: String,
-    /// The ssh primate key to connect to the instances.
-    private_key_file: PathBuf,
+    /// The ssh private key to connect to the instances.
+    private_key: SshPrivateKey,
     /// The timeout value of the connection.
     timeout: Option<Duration>,
     /// The number of retries before giving up to execute the command.
     retries: usize,
+    /// The delays between the retries.
+    retry_policy: RetryPolicy,
+    /// The authenticated sessions kept open for reuse (shared by all clones of the manager).
+    pool: Arc<ConnectionPool<SshConnection>>,
+    /// The bastion through which all connections are tunnelled (if any).
+    jump_host: Option<SocketAddr>,
 }
 
 impl SshConnectionManager {
-    /// Delay before re-attempting an ssh execution.
+    /// Delay between two checks of the status of a command.
     const RETRY_DELAY: Duration = Duration::from_secs(5);
+    /// Default duration after which idle sessions are closed.
+    const DEFAULT_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+    /// Default maximum number of sessions open at once with each host.
+    const DEFAULT_POOL_MAX_PER_HOST: usize = 8;
 
-    /// Create a new ssh manager from the instances username and private keys.
-    pub fn new(username: String, private_key_file: PathBuf) -> Self {
+    /// Create a new ssh manager from the instances username and private key.
+    pub fn new(username: String, private_key: SshPrivateKey) -> Self {
         Self {
             username,
-            private_key_file,
+            private_key,
             timeout: None,
             retries: 0,
+            retry_policy: RetryPolicy::default(),
+            pool: Arc::new(ConnectionPool::new(
+                Self::DEFAULT_POOL_IDLE_TIMEOUT,
+                Self::DEFAULT_POOL_MAX_PER_HOST,
+            )),
+            jump_host: None,
         }
     }
 
+    /// Set the duration after which idle sessions are closed and the maximum number of sessions
+    /// open at once with each host.
+    pub fn with_pool(mut self, idle_timeout: Duration, max_per_host: usize) -> Self {
+        self.pool = Arc::new(ConnectionPool::new(idle_timeout, max_per_host));
+        self
+    }
+
     /// Set a timeout duration for the connections.
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
         self.timeout = Some(timeout);
         self
     }
 
+    /// Tunnel all connections through the specified jump host (if any).
+    pub fn with_jump_host(mut self, jump_host: Option<SocketAddr>) -> Self {
+        self.jump_host = jump_host;
+        self
+    }
+
     /// Set the maximum number of times to retries to establish a connection and execute commands.
     pub fn with_retries(mut self, retries: usize) -> Self {
         self.retries = retries;
         self
     }
 
-    /// Create a new ssh connection with the provided host.
-    pub async fn connect(&self, address: SocketAddr) -> SshResult<SshConnection> {
-        let mut error = None;
-        for _ in 0..self.retries + 1 {
-            match SshConnection::new(address, &self.username, self.private_key_file.clone()).await {
-                Ok(x) => return Ok(x.with_timeout(&self.timeout).with_retries(self.retries)),
-                Err(e) => error = Some(e),
-            }
-            sleep(Self::RETRY_DELAY).await;
-        }
-        Err(error.unwrap())
+    /// Set the delays between the retries.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Get an ssh connection with the provided host, reusing an idle session if possible. The
+    /// session returns to the pool once the connection is dropped.
+    pub async fn connect(&self, address: SocketAddr) -> SshResult<PooledConnection<SshConnection>> {
+        self.pool
+            .get(address, || async {
+                let connection = self
+                    .retry_policy
+                    .retry(self.retries, || {
+                        SshConnection::new(
+                            address,
+                            &self.username,
+                            &self.private_key,
+                            self.jump_host,
+                        )
+                    })
+                    .await?;
+                Ok(connection
+                    .with_timeout(&self.timeout)
+                    .with_retries(self.retries)
+                    .with_retry_policy(self.retry_policy.clone()))
+            })
+            .await
     }
 
     /// Execute the specified ssh command on all provided instances.
@@ -197,9 +304,10 @@ impl SshConnectionManager {
 
                 tokio::spawn(async move {
                     let connection = ssh_manager.connect(instance.ssh_address()).await?;
+                    let command = context.for_instance(&instance).apply(command);
                     // SshConnection::execute is a blocking call, needs to go to blocking pool
                     Handle::current()
-                        .spawn_blocking(move || connection.execute(context.apply(command)))
+                        .spawn_blocking(move || release(connection, |x| x.execute(command)))
                         .await
                         .unwrap()
                 })
@@ -207,6 +315,49 @@ impl SshConnectionManager {
             .collect::<Vec<_>>()
     }
 
+    /// Execute the specified ssh command on all provided instances, forwarding their output to
+    /// the sender line by line as it is printed.
+    pub async fn execute_streaming<I, S>(
+        &self,
+        instances: I,
+        command: S,
+        context: CommandContext,
+        sender: OutputSender,
+    ) -> SshResult<Vec<(String, String)>>
+    where
+        I: IntoIterator<Item = Instance>,
+        S: Into<String> + Clone + Send + 'static,
+    {
+        let handles = instances
+            .into_iter()
+            .map(|instance| {
+                let ssh_manager = self.clone();
+                let context = context.clone();
+                let command = command.clone();
+                let sender = sender.clone();
+
+                tokio::spawn(async move {
+                    let connection = ssh_manager.connect(instance.ssh_address()).await?;
+                    let command = context.for_instance(&instance).apply(command);
+                    Handle::current()
+                        .spawn_blocking(move || {
+                            release(connection, |x| {
+                                x.execute_streaming(command, instance.id, sender)
+                            })
+                        })
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        try_join_all(handles)
+            .await
+            .unwrap()
+            .into_iter()
+            .collect::<SshResult<_>>()
+    }
+
     /// Wait until a command running in the background returns or started.
     pub async fn wait_for_command<I>(
         &self,
@@ -217,6 +368,7 @@ impl SshConnectionManager {
     where
         I: IntoIterator<Item = Instance> + Clone,
     {
+        let instances: Vec<_> = instances.into_iter().collect();
         loop {
             sleep(Self::RETRY_DELAY).await;
 
@@ -229,7 +381,10 @@ impl SshConnectionManager {
                 .await?;
             if result
                 .iter()
-                .all(|(stdout, _)| CommandStatus::status(command_id, stdout) == status)
+                .zip(&instances)
+                .all(|((stdout, _), instance)| {
+                    CommandStatus::status(&session_name(command_id, instance), stdout) == status
+                })
             {
                 break;
             }
@@ -242,8 +397,9 @@ impl SshConnectionManager {
         I: IntoIterator<Item = (Instance, S)> + Clone,
         S: Into<String> + Send + 'static + Clone,
     {
+        let mut backoff = self.retry_policy.backoff();
         loop {
-            sleep(Self::RETRY_DELAY).await;
+            sleep(backoff.next_delay_or_max()).await;
 
             if self
                 .execute_per_instance(instances.clone(), CommandContext::default())
@@ -260,14 +416,29 @@ impl SshConnectionManager {
     where
         I: IntoIterator<Item = Instance>,
     {
-        let ssh_command = format!("(tmux kill-session -t {command_id} || true)");
-        let targets = instances.into_iter().map(|x| (x, ssh_command.clone()));
+        let targets = instances.into_iter().map(|x| {
+            let ssh_command = kill_session_command(command_id, &x);
+            (x, ssh_command)
+        });
         self.execute_per_instance(targets, CommandContext::default())
             .await?;
         Ok(())
     }
 }
 
+/// Run the specified operation over a pooled connection, closing the connection rather than
+/// returning it to the pool if the session failed.
+fn release<T>(
+    connection: PooledConnection<SshConnection>,
+    operation: impl FnOnce(&SshConnection) -> SshResult<T>,
+) -> SshResult<T> {
+    let result = operation(&connection);
+    if result.as_ref().is_err_and(|e| e.is_connection_failure()) {
+        connection.discard();
+    }
+    result
+}
+
 /// Representation of an ssh connection.
 pub struct SshConnection {
     /// The ssh session.
@@ -276,37 +447,33 @@ pub struct SshConnection {
     address: SocketAddr,
     /// The number of retries before giving up to execute the command.
     retries: usize,
+    /// The delays between the retries.
+    retry_policy: RetryPolicy,
 }
 
 impl SshConnection {
     /// Default duration before timing out the ssh connection.
     const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 
-    /// Create a new ssh connection with a specific host.
-    pub async fn new<P: AsRef<Path>>(
+    /// Create a new ssh connection with a specific host, tunnelled through the jump host (if
+    /// specified).
+    pub async fn new(
         address: SocketAddr,
         username: &str,
-        private_key_file: P,
+        private_key: &SshPrivateKey,
+        jump_host: Option<SocketAddr>,
     ) -> SshResult<Self> {
-        let tcp = TcpStream::connect(address)
-            .await
-            .map_err(|error| SshError::ConnectionError { address, error })?;
-
-        let mut session =
-            Session::new().map_err(|error| SshError::SessionError { address, error })?;
-        session.set_timeout(Self::DEFAULT_TIMEOUT.as_millis() as u32);
-        session.set_tcp_stream(tcp);
-        session
-            .handshake()
-            .map_err(|error| SshError::SessionError { address, error })?;
-        session
-            .userauth_pubkey_file(username, None, private_key_file.as_ref(), None)
-            .map_err(|error| SshError::SessionError { address, error })?;
+        let transport = TcpTransport {
+            username,
+            private_key,
+        };
+        let session = open_session(&transport, address, jump_host).await?;
 
         Ok(Self {
             session,
             address,
             retries: 0,
+            retry_policy: RetryPolicy::default(),
         })
     }
 
@@ -327,6 +494,12 @@ impl SshConnection {
         self
     }
 
+    /// Set the delays between the retries.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
     /// Make a useful session error from the lower level error message.
     fn make_session_error(&self, error: ssh2::Error) -> SshError {
         SshError::SessionError {
@@ -345,21 +518,13 @@ impl SshConnection {
 
     /// Execute a ssh command on the remote machine.
     pub fn execute(&self, command: String) -> SshResult<(String, String)> {
-        let mut error = None;
-        for _ in 0..self.retries + 1 {
-            let channel = match self.session.channel_session() {
-                Ok(x) => x,
-                Err(e) => {
-                    error = Some(self.make_session_error(e));
-                    continue;
-                }
-            };
-            match self.execute_impl(channel, command.clone()) {
-                r @ Ok(..) => return r,
-                Err(e) => error = Some(e),
-            }
-        }
-        Err(error.unwrap())
+        self.retry_policy.retry_blocking(self.retries, || {
+            let channel = self
+                .session
+                .channel_session()
+                .map_err(|e| self.make_session_error(e))?;
+            self.execute_impl(channel, command.clone())
+        })
     }
 
     /// Execute an ssh command on the remote machine and return both stdout and stderr.
@@ -379,6 +544,38 @@ impl SshConnection {
             .read_to_string(&mut stderr)
             .map_err(|e| self.make_connection_error(e))?;
 
+        self.close(channel, &stderr)?;
+        Ok((stdout, stderr))
+    }
+
+    /// Execute a ssh command on the remote machine, forwarding each line of its stdout to the
+    /// sender as soon as it is printed (followed by the lines of its stderr). The command is not
+    /// retried since part of its output may already have been forwarded.
+    pub fn execute_streaming(
+        &self,
+        command: String,
+        id: String,
+        sender: OutputSender,
+    ) -> SshResult<(String, String)> {
+        let mut channel = self
+            .session
+            .channel_session()
+            .map_err(|e| self.make_session_error(e))?;
+        channel
+            .exec(&command)
+            .map_err(|e| self.make_session_error(e))?;
+
+        let stdout = forward_lines(&mut channel, &id, &sender, OutputLine::Stdout)
+            .map_err(|e| self.make_connection_error(e))?;
+        let stderr = forward_lines(channel.stderr(), &id, &sender, OutputLine::Stderr)
+            .map_err(|e| self.make_connection_error(e))?;
+
+        self.close(channel, &stderr)?;
+        Ok((stdout, stderr))
+    }
+
+    /// Close the channel of a command and check its exit status.
+    fn close(&self, mut channel: Channel, stderr: &str) -> SshResult<()> {
         channel.close().map_err(|e| self.make_session_error(e))?;
         channel
             .wait_close()
@@ -393,34 +590,333 @@ impl SshConnection {
             SshError::NonZeroExitCode {
                 address: self.address,
                 code: exit_status,
-                message: stderr.clone()
+                message: stderr.into()
             }
         );
-
-        Ok((stdout, stderr))
+        Ok(())
     }
 
     /// Download a file from the remote machines through scp.
     pub fn download<P: AsRef<Path>>(&self, path: P) -> SshResult<String> {
-        let mut error = None;
-        for _ in 0..self.retries + 1 {
-            let (mut channel, _stats) = match self.session.scp_recv(path.as_ref()) {
-                Ok(x) => x,
-                Err(e) => {
-                    error = Some(self.make_session_error(e));
-                    continue;
-                }
-            };
+        self.retry_policy.retry_blocking(self.retries, || {
+            let (mut channel, _stats) = self
+                .session
+                .scp_recv(path.as_ref())
+                .map_err(|e| self.make_session_error(e))?;
 
             let mut content = String::new();
-            match channel
+            channel
                 .read_to_string(&mut content)
-                .map_err(|e| self.make_connection_error(e))
-            {
-                Ok(..) => return Ok(content),
-                Err(e) => error = Some(e),
+                .map_err(|e| self.make_connection_error(e))?;
+            Ok(content)
+        })
+    }
+
+    /// Download a whole directory (and its sub-directories) from the remote machine through
+    /// sftp, preserving its structure under the local directory.
+    pub fn download_dir(&self, remote: &Path, local: &Path) -> SshResult<()> {
+        // The sftp status code of missing files.
+        const NO_SUCH_FILE: i32 = 2;
+
+        let sftp = self
+            .session
+            .sftp()
+            .map_err(|e| self.make_session_error(e))?;
+        let missing = || SshError::MissingDirectory {
+            address: self.address,
+            path: remote.to_path_buf(),
+        };
+        let stat = sftp.stat(remote).map_err(|e| match e.code() {
+            ErrorCode::SFTP(NO_SUCH_FILE) => missing(),
+            _ => self.make_session_error(e),
+        })?;
+        ensure!(stat.is_dir(), missing());
+        self.download_tree(&sftp, remote, local)
+    }
+
+    /// Recursively download the content of a remote directory.
+    fn download_tree(&self, sftp: &Sftp, remote: &Path, local: &Path) -> SshResult<()> {
+        fs::create_dir_all(local).map_err(|e| self.make_connection_error(e))?;
+        let entries = sftp
+            .readdir(remote)
+            .map_err(|e| self.make_session_error(e))?;
+        for (path, stat) in entries {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            let target = local.join(name);
+            if stat.is_dir() {
+                self.download_tree(sftp, &path, &target)?;
+            } else {
+                let mut source = sftp.open(&path).map_err(|e| self.make_session_error(e))?;
+                let mut file =
+                    fs::File::create(&target).map_err(|e| self.make_connection_error(e))?;
+                io::copy(&mut source, &mut file).map_err(|e| self.make_connection_error(e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Establishes authenticated ssh sessions. Abstracts the network away so that the route of the
+/// connections can be tested.
+pub trait SshTransport {
+    type Session;
+
+    /// Open an authenticated session with the specified host.
+    async fn connect(&self, address: SocketAddr) -> SshResult<Self::Session>;
+
+    /// Open an authenticated session with the specified host, tunnelled through an existing
+    /// session with a jump host.
+    async fn connect_through(
+        &self,
+        jump: &Self::Session,
+        address: SocketAddr,
+    ) -> SshResult<Self::Session>;
+}
+
+/// Open a session with the specified host. If a jump host is specified, the session with the
+/// jump host is established first and the session with the host is tunnelled through it.
+pub async fn open_session<T: SshTransport>(
+    transport: &T,
+    address: SocketAddr,
+    jump_host: Option<SocketAddr>,
+) -> SshResult<T::Session> {
+    match jump_host {
+        Some(jump_host) => {
+            let jump = transport.connect(jump_host).await?;
+            transport.connect_through(&jump, address).await
+        }
+        None => transport.connect(address).await,
+    }
+}
+
+/// Establishes ssh sessions over tcp, authenticating with a private key.
+struct TcpTransport<'a> {
+    username: &'a str,
+    private_key: &'a SshPrivateKey,
+}
+
+impl TcpTransport<'_> {
+    /// Run the ssh handshake over the specified stream and authenticate.
+    fn authenticate(&self, tcp: TcpStream, address: SocketAddr) -> SshResult<Session> {
+        let mut session =
+            Session::new().map_err(|error| SshError::SessionError { address, error })?;
+        session.set_timeout(SshConnection::DEFAULT_TIMEOUT.as_millis() as u32);
+        session.set_tcp_stream(tcp);
+        session
+            .handshake()
+            .map_err(|error| SshError::SessionError { address, error })?;
+        session
+            .userauth_pubkey_file(
+                self.username,
+                None,
+                self.private_key.path(),
+                self.private_key.passphrase(),
+            )
+            .map_err(|error| SshError::SessionError { address, error })?;
+        Ok(session)
+    }
+}
+
+impl SshTransport for TcpTransport<'_> {
+    type Session = Session;
+
+    async fn connect(&self, address: SocketAddr) -> SshResult<Session> {
+        let tcp = TcpStream::connect(address)
+            .await
+            .map_err(|error| SshError::ConnectionError { address, error })?;
+        self.authenticate(tcp, address)
+    }
+
+    async fn connect_through(&self, jump: &Session, address: SocketAddr) -> SshResult<Session> {
+        let channel = jump
+            .channel_direct_tcpip(&address.ip().to_string(), address.port(), None)
+            .map_err(|error| SshError::SessionError { address, error })?;
+        let local = tunnel(jump.clone(), channel)
+            .map_err(|error| SshError::ConnectionError { address, error })?;
+        let tcp = TcpStream::connect(local)
+            .await
+            .map_err(|error| SshError::ConnectionError { address, error })?;
+        self.authenticate(tcp, address)
+    }
+}
+
+/// The delay between two polls of an idle tunnel.
+const TUNNEL_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Expose a channel opened through a jump host on a local port, so that a new ssh session can
+/// run over it. Only the first connection to the local port is forwarded, until either end
+/// closes. Returns the local address.
+fn tunnel(jump: Session, channel: Channel) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let local = listener.local_addr()?;
+    thread::spawn(move || {
+        if let Ok((stream, _)) = listener.accept() {
+            // The tunnelled session fails on its own if the tunnel breaks.
+            let _ = forward_bytes(jump, channel, stream);
+        }
+    });
+    Ok(local)
+}
+
+/// Copy the bytes between a local stream and a channel in both directions until either end
+/// closes. Polls both ends from a single thread since a channel cannot be read and written
+/// concurrently.
+fn forward_bytes(
+    jump: Session,
+    mut channel: Channel,
+    mut stream: std::net::TcpStream,
+) -> io::Result<()> {
+    jump.set_blocking(false);
+    stream.set_nonblocking(true)?;
+    let mut buffer = [0u8; 16 * 1024];
+    loop {
+        let mut idle = true;
+        match stream.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(n) => {
+                write_all_nonblocking(&mut channel, &buffer[..n])?;
+                idle = false;
             }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => (),
+            Err(e) => return Err(e),
         }
-        Err(error.unwrap())
+        match channel.read(&mut buffer) {
+            Ok(0) if channel.eof() => return Ok(()),
+            Ok(0) => (),
+            Ok(n) => {
+                write_all_nonblocking(&mut stream, &buffer[..n])?;
+                idle = false;
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => (),
+            Err(e) => return Err(e),
+        }
+        if idle {
+            thread::sleep(TUNNEL_POLL_INTERVAL);
+        }
+    }
+}
+
+/// Write all the data to a non-blocking writer, waiting while the writer is not ready.
+fn write_all_nonblocking<W: Write>(writer: &mut W, mut data: &[u8]) -> io::Result<()> {
+    while !data.is_empty() {
+        match writer.write(data) {
+            Ok(n) => data = &data[n..],
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(TUNNEL_POLL_INTERVAL),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Forward each line read from the reader to the sender, and return everything read.
+fn forward_lines<R: Read>(
+    reader: R,
+    id: &str,
+    sender: &OutputSender,
+    make_line: fn(String) -> OutputLine,
+) -> std::io::Result<String> {
+    let mut output = String::new();
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        output.push_str(&line);
+        output.push('\n');
+        // The output is still collected if nobody listens to the stream anymore.
+        let _ = sender.send((id.into(), make_line(line)));
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{net::SocketAddr, sync::Mutex};
+
+    use super::{open_session, SshTransport};
+    use crate::error::{SshError, SshResult};
+
+    /// A step taken to establish a session.
+    #[derive(Debug, PartialEq, Eq)]
+    enum Step {
+        Connect(SocketAddr),
+        Tunnel {
+            jump: SocketAddr,
+            target: SocketAddr,
+        },
+    }
+
+    /// A transport recording the steps taken to establish the sessions. Sessions are represented
+    /// by the address of the host they are established with.
+    #[derive(Default)]
+    struct MockTransport {
+        steps: Mutex<Vec<Step>>,
+        unreachable: Option<SocketAddr>,
+    }
+
+    impl MockTransport {
+        fn check_reachable(&self, address: SocketAddr) -> SshResult<()> {
+            if self.unreachable == Some(address) {
+                let error = std::io::ErrorKind::ConnectionRefused.into();
+                return Err(SshError::ConnectionError { address, error });
+            }
+            Ok(())
+        }
+    }
+
+    impl SshTransport for MockTransport {
+        type Session = SocketAddr;
+
+        async fn connect(&self, address: SocketAddr) -> SshResult<SocketAddr> {
+            self.steps.lock().unwrap().push(Step::Connect(address));
+            self.check_reachable(address)?;
+            Ok(address)
+        }
+
+        async fn connect_through(
+            &self,
+            jump: &SocketAddr,
+            address: SocketAddr,
+        ) -> SshResult<SocketAddr> {
+            let step = Step::Tunnel {
+                jump: *jump,
+                target: address,
+            };
+            self.steps.lock().unwrap().push(step);
+            self.check_reachable(address)?;
+            Ok(address)
+        }
+    }
+
+    #[tokio::test]
+    async fn route_through_jump_host() {
+        let jump: SocketAddr = "10.0.0.1:22".parse().unwrap();
+        let target: SocketAddr = "192.168.1.5:22".parse().unwrap();
+
+        // The session with the bastion is established first, then the target is reached
+        // through it.
+        let transport = MockTransport::default();
+        let session = open_session(&transport, target, Some(jump)).await.unwrap();
+        assert_eq!(session, target);
+        let steps = transport.steps.into_inner().unwrap();
+        assert_eq!(steps, [Step::Connect(jump), Step::Tunnel { jump, target }]);
+
+        // Without a jump host, the target is reached directly.
+        let transport = MockTransport::default();
+        open_session(&transport, target, None).await.unwrap();
+        let steps = transport.steps.into_inner().unwrap();
+        assert_eq!(steps, [Step::Connect(target)]);
+
+        // The target is never contacted if the bastion is unreachable.
+        let transport = MockTransport {
+            unreachable: Some(jump),
+            ..Default::default()
+        };
+        let result = open_session(&transport, target, Some(jump)).await;
+        assert!(matches!(
+            result,
+            Err(SshError::ConnectionError { address, .. }) if address == jump
+        ));
+        let steps = transport.steps.into_inner().unwrap();
+        assert_eq!(steps, [Step::Connect(jump)]);
     }
 }
@@ -0,0 +1,387 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    env,
+    fmt::{Debug, Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::{
+    ensure,
+    error::{SshError, SshResult},
+};
+
+/// The algorithms of the ssh private keys supported by the orchestrator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SshKeyType {
+    Rsa,
+    Ed25519,
+    Ecdsa,
+}
+
+impl Display for SshKeyType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rsa => write!(f, "rsa"),
+            Self::Ed25519 => write!(f, "ed25519"),
+            Self::Ecdsa => write!(f, "ecdsa"),
+        }
+    }
+}
+
+/// An ssh private key file, along with its passphrase (if the key is encrypted).
+#[derive(Clone)]
+pub struct SshPrivateKey {
+    /// The path to the private key file.
+    path: PathBuf,
+    /// The algorithm of the key, detected from the file.
+    key_type: SshKeyType,
+    /// The passphrase decrypting the key (only set for encrypted keys).
+    passphrase: Option<String>,
+}
+
+impl SshPrivateKey {
+    /// The environment variable holding the passphrase of encrypted keys, used when no
+    /// passphrase is specified in the settings.
+    pub const PASSPHRASE_ENV: &'static str = "SSH_KEY_PASSPHRASE";
+
+    /// Load a private key file in the OpenSSH or PEM format, detecting its algorithm and whether
+    /// it is encrypted. The passphrase of encrypted keys defaults to the value of
+    /// [`Self::PASSPHRASE_ENV`].
+    pub fn load<P: AsRef<Path>>(path: P, passphrase: Option<String>) -> SshResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let contents = fs::read_to_string(&path).map_err(|e| SshError::InvalidKey {
+            path: path.clone(),
+            message: e.to_string(),
+        })?;
+        let (key_type, encrypted) = match detect_key(&contents) {
+            Ok(detected) => detected,
+            Err(KeyFormatError::Unsupported(key_type)) => {
+                return Err(SshError::UnsupportedKey { path, key_type })
+            }
+            Err(KeyFormatError::Malformed(message)) => {
+                return Err(SshError::InvalidKey {
+                    path,
+                    message: message.into(),
+                })
+            }
+        };
+
+        let passphrase = if encrypted {
+            passphrase.or_else(|| env::var(Self::PASSPHRASE_ENV).ok())
+        } else {
+            None
+        };
+        ensure!(
+            !encrypted || passphrase.is_some(),
+            SshError::MissingPassphrase { path }
+        );
+        Ok(Self {
+            path,
+            key_type,
+            passphrase,
+        })
+    }
+
+    /// The path to the private key file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The passphrase decrypting the key, if the key is encrypted.
+    pub fn passphrase(&self) -> Option<&str> {
+        self.passphrase.as_deref()
+    }
+}
+
+impl Debug for SshPrivateKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Never print the passphrase.
+        f.debug_struct("SshPrivateKey")
+            .field("path", &self.path)
+            .field("key_type", &self.key_type)
+            .field("encrypted", &self.passphrase.is_some())
+            .finish()
+    }
+}
+
+/// The reason why the type of a private key could not be detected.
+#[derive(Debug, PartialEq, Eq)]
+enum KeyFormatError {
+    /// The key is well-formed but uses an unsupported algorithm or format.
+    Unsupported(String),
+    /// The file is not a private key.
+    Malformed(&'static str),
+}
+
+/// The magic bytes starting the body of OpenSSH private keys.
+const OPENSSH_MAGIC: &[u8] = b"openssh-key-v1\0";
+
+/// The DER encoding of the object identifiers of the algorithms of PKCS#8 keys.
+const PKCS8_OIDS: &[(&[u8], SshKeyType)] = &[
+    // rsaEncryption (1.2.840.113549.1.1.1).
+    (
+        &[
+            0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01,
+        ],
+        SshKeyType::Rsa,
+    ),
+    // id-ecPublicKey (1.2.840.10045.2.1).
+    (
+        &[0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01],
+        SshKeyType::Ecdsa,
+    ),
+    // id-Ed25519 (1.3.101.112).
+    (&[0x06, 0x03, 0x2b, 0x65, 0x70], SshKeyType::Ed25519),
+];
+
+/// Detect the algorithm of a private key and whether it is encrypted.
+fn detect_key(contents: &str) -> Result<(SshKeyType, bool), KeyFormatError> {
+    if contents.starts_with("PuTTY-User-Key-File") {
+        return Err(KeyFormatError::Unsupported("putty".into()));
+    }
+    let (label, headers, body) = parse_pem(contents).ok_or(KeyFormatError::Malformed(
+        "not a PEM or OpenSSH private key",
+    ))?;
+    let encrypted = headers.iter().any(|x| x.contains("ENCRYPTED"));
+    match label {
+        "OPENSSH PRIVATE KEY" => detect_openssh_key(&decode_body(&body)?),
+        "RSA PRIVATE KEY" => Ok((SshKeyType::Rsa, encrypted)),
+        "EC PRIVATE KEY" => Ok((SshKeyType::Ecdsa, encrypted)),
+        "PRIVATE KEY" => {
+            let der = decode_body(&body)?;
+            PKCS8_OIDS
+                .iter()
+                .find(|(oid, _)| der.windows(oid.len()).any(|x| x == *oid))
+                .map(|(_, key_type)| (*key_type, false))
+                .ok_or(KeyFormatError::Unsupported("pkcs8".into()))
+        }
+        "ENCRYPTED PRIVATE KEY" => Err(KeyFormatError::Unsupported("encrypted pkcs8".into())),
+        "DSA PRIVATE KEY" => Err(KeyFormatError::Unsupported("dsa".into())),
+        other => Err(KeyFormatError::Unsupported(other.to_lowercase())),
+    }
+}
+
+/// Detect the algorithm of an OpenSSH private key (from the type of its first public key) and
+/// whether it is encrypted (from its cipher).
+fn detect_openssh_key(data: &[u8]) -> Result<(SshKeyType, bool), KeyFormatError> {
+    let truncated = || KeyFormatError::Malformed("truncated OpenSSH private key");
+    let mut reader = data
+        .strip_prefix(OPENSSH_MAGIC)
+        .ok_or(KeyFormatError::Malformed("missing OpenSSH magic bytes"))?;
+    let cipher = read_string(&mut reader).ok_or_else(truncated)?;
+    let _kdf = read_string(&mut reader).ok_or_else(truncated)?;
+    let _kdf_options = read_string(&mut reader).ok_or_else(truncated)?;
+    let _keys = read_u32(&mut reader).ok_or_else(truncated)?;
+    let mut public_key = read_string(&mut reader).ok_or_else(truncated)?;
+    let key_type = read_string(&mut public_key).ok_or_else(truncated)?;
+
+    let encrypted = cipher != b"none";
+    match key_type {
+        b"ssh-rsa" => Ok((SshKeyType::Rsa, encrypted)),
+        b"ssh-ed25519" => Ok((SshKeyType::Ed25519, encrypted)),
+        x if x.starts_with(b"ecdsa-sha2-") => Ok((SshKeyType::Ecdsa, encrypted)),
+        other => Err(KeyFormatError::Unsupported(
+            String::from_utf8_lossy(other).into_owned(),
+        )),
+    }
+}
+
+/// Split a PEM file into its label, its headers (e.g., 'Proc-Type: 4,ENCRYPTED'), and its
+/// base64-encoded body.
+fn parse_pem(contents: &str) -> Option<(&str, Vec<&str>, String)> {
+    let mut lines = contents.lines().map(str::trim).skip_while(|x| x.is_empty());
+    let label = lines
+        .next()?
+        .strip_prefix("-----BEGIN ")?
+        .strip_suffix("-----")?;
+    let (mut headers, mut body) = (Vec::new(), String::new());
+    for line in lines {
+        if line.starts_with("-----END ") {
+            return Some((label, headers, body));
+        }
+        if line.contains(':') {
+            headers.push(line);
+        } else {
+            body.push_str(line);
+        }
+    }
+    None
+}
+
+fn decode_body(body: &str) -> Result<Vec<u8>, KeyFormatError> {
+    STANDARD
+        .decode(body)
+        .map_err(|_| KeyFormatError::Malformed("invalid base64 encoding"))
+}
+
+/// Read a big-endian u32 from the ssh wire format.
+fn read_u32(reader: &mut &[u8]) -> Option<u32> {
+    let (value, rest) = reader.split_first_chunk::<4>()?;
+    *reader = rest;
+    Some(u32::from_be_bytes(*value))
+}
+
+/// Read a length-prefixed string from the ssh wire format.
+fn read_string<'a>(reader: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let length = read_u32(reader)? as usize;
+    let data = reader.get(..length)?;
+    *reader = &reader[length..];
+    Some(data)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, path::PathBuf};
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use rand::{thread_rng, RngCore};
+
+    use super::{detect_key, KeyFormatError, SshKeyType, SshPrivateKey};
+    use crate::error::SshError;
+
+    /// Wrap binary key material in a PEM file with the specified label and headers.
+    fn pem(label: &str, headers: &[&str], data: &[u8]) -> String {
+        let body = STANDARD.encode(data);
+        let mut lines = vec![format!("-----BEGIN {label}-----")];
+        lines.extend(headers.iter().map(|x| x.to_string()));
+        lines.extend(
+            body.as_bytes()
+                .chunks(70)
+                .map(|x| String::from_utf8_lossy(x).into()),
+        );
+        lines.push(format!("-----END {label}-----"));
+        lines.join("\n") + "\n"
+    }
+
+    fn random_bytes(length: usize) -> Vec<u8> {
+        let mut bytes = vec![0; length];
+        thread_rng().fill_bytes(&mut bytes);
+        bytes
+    }
+
+    fn put_string(buffer: &mut Vec<u8>, data: &[u8]) {
+        buffer.extend((data.len() as u32).to_be_bytes());
+        buffer.extend(data);
+    }
+
+    /// Generate an OpenSSH private key of the specified type with random key material.
+    fn openssh_key(key_type: &str, cipher: &str) -> String {
+        let mut public_key = Vec::new();
+        put_string(&mut public_key, key_type.as_bytes());
+        put_string(&mut public_key, &random_bytes(32));
+
+        let mut data = b"openssh-key-v1\0".to_vec();
+        put_string(&mut data, cipher.as_bytes());
+        put_string(
+            &mut data,
+            if cipher == "none" { b"none" } else { b"bcrypt" },
+        );
+        put_string(&mut data, &[]);
+        data.extend(1u32.to_be_bytes());
+        put_string(&mut data, &public_key);
+        put_string(&mut data, &random_bytes(64));
+        pem("OPENSSH PRIVATE KEY", &[], &data)
+    }
+
+    /// Generate a PKCS#8 private key with the specified algorithm identifier and random key
+    /// material.
+    fn pkcs8_key(oid: &[u8]) -> String {
+        let mut data = vec![0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, oid.len() as u8];
+        data.extend(oid);
+        data.extend([0x04, 0x22, 0x04, 0x20]);
+        data.extend(random_bytes(32));
+        pem("PRIVATE KEY", &[], &data)
+    }
+
+    #[test]
+    fn detect_openssh_keys() {
+        let keys = [
+            ("ssh-ed25519", SshKeyType::Ed25519),
+            ("ecdsa-sha2-nistp256", SshKeyType::Ecdsa),
+            ("ecdsa-sha2-nistp384", SshKeyType::Ecdsa),
+            ("ecdsa-sha2-nistp521", SshKeyType::Ecdsa),
+            ("ssh-rsa", SshKeyType::Rsa),
+        ];
+        for (name, key_type) in keys {
+            let key = openssh_key(name, "none");
+            assert_eq!(detect_key(&key), Ok((key_type, false)), "{name}");
+            let key = openssh_key(name, "aes256-ctr");
+            assert_eq!(detect_key(&key), Ok((key_type, true)), "{name}");
+        }
+    }
+
+    #[test]
+    fn detect_pem_keys() {
+        let material = random_bytes(64);
+        let key = pem("RSA PRIVATE KEY", &[], &material);
+        assert_eq!(detect_key(&key), Ok((SshKeyType::Rsa, false)));
+        let headers = [
+            "Proc-Type: 4,ENCRYPTED",
+            "DEK-Info: AES-128-CBC,0123456789ABCDEF",
+        ];
+        let key = pem("EC PRIVATE KEY", &headers, &material);
+        assert_eq!(detect_key(&key), Ok((SshKeyType::Ecdsa, true)));
+
+        let ed25519 = pkcs8_key(&[0x06, 0x03, 0x2b, 0x65, 0x70]);
+        assert_eq!(detect_key(&ed25519), Ok((SshKeyType::Ed25519, false)));
+        let ecdsa = pkcs8_key(&[0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01]);
+        assert_eq!(detect_key(&ecdsa), Ok((SshKeyType::Ecdsa, false)));
+    }
+
+    #[test]
+    fn reject_unsupported_keys() {
+        let dss = openssh_key("ssh-dss", "none");
+        let unsupported = |x: &str| Err(KeyFormatError::Unsupported(x.into()));
+        assert_eq!(detect_key(&dss), unsupported("ssh-dss"));
+        let dsa = pem("DSA PRIVATE KEY", &[], &random_bytes(64));
+        assert_eq!(detect_key(&dsa), unsupported("dsa"));
+        assert!(matches!(
+            detect_key("This is a fake private key for tests"),
+            Err(KeyFormatError::Malformed(_))
+        ));
+
+        // The error names the detected type.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("id_dsa");
+        fs::write(&path, dss).unwrap();
+        match SshPrivateKey::load(&path, None) {
+            Err(SshError::UnsupportedKey { key_type, .. }) => assert_eq!(key_type, "ssh-dss"),
+            other => panic!("Unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn load_encrypted_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path: PathBuf = dir.path().join("id_ecdsa");
+        fs::write(&path, openssh_key("ecdsa-sha2-nistp256", "aes256-ctr")).unwrap();
+
+        // The passphrase comes from the settings or, failing that, from the environment.
+        let key = SshPrivateKey::load(&path, Some("secret".into())).unwrap();
+        assert_eq!(key.key_type, SshKeyType::Ecdsa);
+        assert_eq!(key.passphrase(), Some("secret"));
+
+        env::remove_var(SshPrivateKey::PASSPHRASE_ENV);
+        assert!(matches!(
+            SshPrivateKey::load(&path, None),
+            Err(SshError::MissingPassphrase { .. })
+        ));
+        env::set_var(SshPrivateKey::PASSPHRASE_ENV, "from-env");
+        let key = SshPrivateKey::load(&path, None).unwrap();
+        assert_eq!(key.passphrase(), Some("from-env"));
+        env::remove_var(SshPrivateKey::PASSPHRASE_ENV);
+
+        // Passphrases are ignored for unencrypted keys.
+        let path = dir.path().join("id_ed25519");
+        fs::write(&path, openssh_key("ssh-ed25519", "none")).unwrap();
+        let key = SshPrivateKey::load(&path, Some("secret".into())).unwrap();
+        assert_eq!(key.key_type, SshKeyType::Ed25519);
+        assert_eq!(key.passphrase(), None);
+        assert_eq!(key.path(), path);
+    }
+}
@@ -1,9 +1,9 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::time::Duration;
+use std::{future::Future, time::Duration};
 
-use futures::future::try_join_all;
+use futures::future::{join_all, try_join_all};
 use prettytable::{row, Table};
 use tokio::time::{self, Instant};
 
@@ -11,9 +11,12 @@ use super::client::Instance;
 use crate::{
     client::ServerProviderClient,
     display,
-    error::{TestbedError, TestbedResult},
+    error::{CloudProviderError, TestbedError, TestbedResult},
+    executor::Executor,
+    placement::PlacementSummary,
     settings::{CloudProvider, Settings},
-    ssh::SshConnection,
+    ssh::{CommandContext, SshConnection},
+    ssh_key::SshPrivateKey,
 };
 
 /// Represents a testbed running on a cloud provider.
@@ -27,6 +30,9 @@ pub struct Testbed<C> {
 }
 
 impl<C: ServerProviderClient> Testbed<C> {
+    /// The delay between two attempts at reaching the instances.
+    const REACHABILITY_INTERVAL: Duration = Duration::from_secs(5);
+
     /// Create a new testbed instance with the specified settings and client.
     pub async fn new(settings: Settings, client: C) -> TestbedResult<Self> {
         // Only load SSH key for cloud providers (not needed for local execution)
@@ -37,7 +43,12 @@ impl<C: ServerProviderClient> Testbed<C> {
             // For local execution, register_ssh_public_key is a no-op, but we call it for consistency
             client.register_ssh_public_key(String::new()).await?;
         }
-        let instances = client.list_instances().await?;
+        let (policy, retries) = (&settings.retry_policy, settings.cloud_retries);
+        let instances = policy
+            .retry_if(retries, CloudProviderError::is_transient, || {
+                client.list_instances()
+            })
+            .await?;
 
         Ok(Self {
             settings,
@@ -46,12 +57,23 @@ impl<C: ServerProviderClient> Testbed<C> {
         })
     }
 
+    /// List the instances of the cloud provider, retrying transient failures.
+    async fn list_instances(&self) -> TestbedResult<Vec<Instance>> {
+        let (policy, retries) = (&self.settings.retry_policy, self.settings.cloud_retries);
+        let instances = policy
+            .retry_if(retries, CloudProviderError::is_transient, || {
+                self.client.list_instances()
+            })
+            .await?;
+        Ok(instances)
+    }
+
     /// Return the username to connect to the instances through ssh.
     pub fn username(&self) -> &'static str {
         C::USERNAME
     }
 
-    /// Return the list of instances of the testbed.
+    /// Return the list of instances of the testbed (with the specs of any role).
     pub fn instances(&self) -> Vec<Instance> {
         self.instances
             .iter()
@@ -60,6 +82,15 @@ impl<C: ServerProviderClient> Testbed<C> {
             .collect()
     }
 
+    /// Return the list of instances of the testbed with the specified specs.
+    pub fn instances_with_specs(&self, specs: &str) -> Vec<Instance> {
+        self.instances
+            .iter()
+            .filter(|x| self.settings.filter_instances_with_specs(x, specs))
+            .cloned()
+            .collect()
+    }
+
     /// Return the list of provider-specific instance setup commands.
     pub async fn setup_commands(&self) -> TestbedResult<Vec<String>> {
         self.client
@@ -68,6 +99,14 @@ impl<C: ServerProviderClient> Testbed<C> {
             .map_err(TestbedError::from)
     }
 
+    /// Summarize the distribution of the active instances across regions and the round-trip
+    /// times expected between them.
+    pub fn placement_summary(&self) -> PlacementSummary {
+        let instances = self.instances();
+        let active = instances.iter().filter(|x| x.is_active());
+        PlacementSummary::new(&self.settings.regions, active)
+    }
+
     /// Print the current status of the testbed.
     pub fn status(&self) {
         let filtered = self
@@ -104,7 +143,10 @@ impl<C: ServerProviderClient> Testbed<C> {
                 let private_key_file = self.settings.ssh_private_key_file.display();
                 let username = C::USERNAME;
                 let ip = instance.main_ip;
-                let connect = format!("ssh -i {private_key_file} {username}@{ip}");
+                let mut connect = format!("ssh -i {private_key_file} {username}@{ip}");
+                if let Some(jump_host) = self.settings.ssh_jump_host {
+                    connect = format!("{connect} -J {username}@{jump_host}");
+                }
                 if !instance.is_terminated() {
                     if instance.is_active() {
                         table.add_row(row![bFg->format!("{j}"), connect]);
@@ -126,30 +168,194 @@ impl<C: ServerProviderClient> Testbed<C> {
         display::newline();
         table.printstd();
         display::newline();
+        self.placement_summary().print();
+        display::newline();
     }
 
-    /// Populate the testbed by creating the specified amount of instances per region. The total
-    /// number of instances created is thus the specified amount x the number of regions.
-    pub async fn deploy(&mut self, quantity: usize, region: Option<String>) -> TestbedResult<()> {
-        display::action(format!("Deploying instances ({quantity} per region)"));
+    /// Populate the testbed with the specified amount of instances per region (or only in the
+    /// specified region). Only the shortfall is created, so re-running a deploy (e.g., after a
+    /// partial failure) does not duplicate the existing instances. Extra instances are left
+    /// running unless `prune_extra` is set.
+    pub async fn deploy(
+        &mut self,
+        quantity: usize,
+        region: Option<String>,
+        prune_extra: bool,
+    ) -> TestbedResult<()> {
+        let specs = self.settings.specs.clone();
+        self.deploy_with_specs(quantity, region, &specs, prune_extra)
+            .await
+    }
 
-        let instances = match region {
-            Some(x) => {
-                try_join_all((0..quantity).map(|_| self.client.create_instance(x.clone()))).await?
-            }
-            None => {
-                try_join_all(self.settings.regions.iter().flat_map(|region| {
-                    (0..quantity).map(|_| self.client.create_instance(region.clone()))
-                }))
-                .await?
+    /// Populate the testbed with the specified amount of instances with the specified specs
+    /// (overriding the specs of the settings) per region.
+    pub async fn deploy_with_specs(
+        &mut self,
+        quantity: usize,
+        region: Option<String>,
+        specs: &str,
+        prune_extra: bool,
+    ) -> TestbedResult<()> {
+        let regions = match region {
+            Some(x) => vec![x],
+            None => self.settings.regions.clone(),
+        };
+
+        // Count the existing instances of each region (preferring to keep the active ones).
+        let (mut create, mut delete) = (Vec::new(), Vec::new());
+        for region in regions {
+            let (active, inactive): (Vec<_>, Vec<_>) = self
+                .instances_with_specs(specs)
+                .into_iter()
+                .filter(|x| x.region == region && !x.is_terminated())
+                .partition(|x| x.is_active());
+            let existing: Vec<_> = active.into_iter().chain(inactive).collect();
+
+            create.extend(vec![region; quantity.saturating_sub(existing.len())]);
+            if prune_extra {
+                delete.extend(existing.into_iter().skip(quantity));
             }
+        }
+        if create.is_empty() && delete.is_empty() {
+            return Ok(());
+        }
+
+        display::action(format!(
+            "Deploying {specs} instances ({} created, {} deleted)",
+            create.len(),
+            delete.len()
+        ));
+
+        let instances = self.create_instances(create, specs).await?;
+        try_join_all(delete.into_iter().map(|x| self.client.delete_instance(x))).await?;
+
+        // Wait until the instances are booted (skip for local execution).
+        if cfg!(not(test)) && !matches!(self.settings.cloud_provider, CloudProvider::Local) {
+            self.wait_until_booted(instances.iter()).await?;
+        }
+        self.instances = self.list_instances().await?;
+
+        display::done();
+        Ok(())
+    }
+
+    /// Create one instance with the specified specs in each of the specified regions, retrying
+    /// transient failures. The instances are tagged with the current benchmark run.
+    async fn create_instances(
+        &self,
+        regions: Vec<String>,
+        specs: &str,
+    ) -> TestbedResult<Vec<Instance>> {
+        let (policy, retries) = (&self.settings.retry_policy, self.settings.cloud_retries);
+        let tags = self.settings.run_tags();
+        let tags = &tags;
+        let instances = try_join_all(regions.into_iter().map(|region| {
+            policy.retry_if(retries, CloudProviderError::is_transient, move || {
+                self.client.create_instance(region.clone(), specs, tags)
+            })
+        }))
+        .await?;
+        Ok(instances)
+    }
+
+    /// Deploy enough instances (spread across all regions) to run a committee of the specified
+    /// size along with its hot spares. Each role is deployed with its own specs, and the active
+    /// instances already in the testbed are reused.
+    pub async fn provision(&mut self, committee: usize) -> TestbedResult<()> {
+        for (specs, required) in self.settings.required_instances_by_specs(committee) {
+            self.provision_instances(required, &specs).await?;
+        }
+        Ok(())
+    }
+
+    /// Deploy enough instances with the specified specs (overriding the specs of the settings)
+    /// to run a committee of the specified size along with its hot spares.
+    pub async fn provision_with_specs(
+        &mut self,
+        committee: usize,
+        specs: &str,
+    ) -> TestbedResult<()> {
+        let required = self.settings.required_instances(committee);
+        self.provision_instances(required, specs).await
+    }
+
+    /// Deploy enough instances with the specified specs (spread across all regions) to have the
+    /// specified number of active ones.
+    async fn provision_instances(&mut self, required: usize, specs: &str) -> TestbedResult<()> {
+        let active = self
+            .instances_with_specs(specs)
+            .iter()
+            .filter(|x| x.is_active())
+            .count();
+        if active >= required {
+            return Ok(());
+        }
+
+        let regions = self.settings.regions.len().max(1);
+        let quantity = (required - active).div_ceil(regions);
+        display::action(format!(
+            "Provisioning {specs} instances ({quantity} per region)"
+        ));
+
+        let regions = self.settings.regions.iter();
+        let requests = regions.flat_map(|x| vec![x.clone(); quantity]).collect();
+        let instances = self.create_instances(requests, specs).await?;
+
+        // Wait until the instances are booted (skip for local execution).
+        if cfg!(not(test)) && !matches!(self.settings.cloud_provider, CloudProvider::Local) {
+            self.wait_until_booted(instances.iter()).await?;
+        }
+        self.instances = self.list_instances().await?;
+
+        display::done();
+        Ok(())
+    }
+
+    /// Reconcile the testbed with the specified number of active instances per region (or only
+    /// in the specified region). Stopped instances are started before creating new ones to fill
+    /// the deficit, and the surplus active instances are deleted. Calling it again with the same
+    /// target is thus a no-op.
+    pub async fn ensure(&mut self, target: usize, region: Option<String>) -> TestbedResult<()> {
+        let regions = match region {
+            Some(x) => vec![x],
+            None => self.settings.regions.clone(),
         };
 
+        let (mut start, mut create, mut delete) = (Vec::new(), Vec::new(), Vec::new());
+        for region in regions {
+            let (active, inactive): (Vec<_>, Vec<_>) = self
+                .instances()
+                .into_iter()
+                .filter(|x| x.region == region && !x.is_terminated())
+                .partition(|x| x.is_active());
+
+            let deficit = target.saturating_sub(active.len());
+            let restarted = deficit.min(inactive.len());
+            start.extend(inactive.into_iter().take(restarted));
+            create.extend(vec![region; deficit - restarted]);
+            delete.extend(active.into_iter().skip(target));
+        }
+        if start.is_empty() && create.is_empty() && delete.is_empty() {
+            return Ok(());
+        }
+
+        display::action(format!(
+            "Reconciling testbed ({} started, {} created, {} deleted)",
+            start.len(),
+            create.len(),
+            delete.len()
+        ));
+
+        self.client.start_instances(start.iter()).await?;
+        let created = self.create_instances(create, &self.settings.specs).await?;
+        try_join_all(delete.into_iter().map(|x| self.client.delete_instance(x))).await?;
+
         // Wait until the instances are booted (skip for local execution).
         if cfg!(not(test)) && !matches!(self.settings.cloud_provider, CloudProvider::Local) {
-            self.wait_until_reachable(instances.iter()).await?;
+            self.wait_until_booted(start.iter().chain(created.iter()))
+                .await?;
         }
-        self.instances = self.client.list_instances().await?;
+        self.instances = self.list_instances().await?;
 
         display::done();
         Ok(())
@@ -157,14 +363,31 @@ impl<C: ServerProviderClient> Testbed<C> {
 
     /// Destroy all instances of the testbed.
     pub async fn destroy(&mut self) -> TestbedResult<()> {
-        display::action("Destroying testbed");
+        self.destroy_with_tags(&[]).await
+    }
+
+    /// Destroy the instances of the specified benchmark run, leaving the rest of the testbed
+    /// untouched.
+    pub async fn destroy_run(&mut self, run_id: &str) -> TestbedResult<()> {
+        self.destroy_with_tags(&[run_id.into()]).await
+    }
+
+    /// Destroy the instances of the testbed carrying all the specified tags.
+    async fn destroy_with_tags(&mut self, tags: &[String]) -> TestbedResult<()> {
+        let (targets, remaining) = self
+            .instances
+            .iter()
+            .cloned()
+            .partition::<Vec<_>, _>(|x| x.has_tags(tags));
+        display::action(format!("Destroying testbed ({} instances)", targets.len()));
 
         try_join_all(
-            self.instances
-                .drain(..)
+            targets
+                .into_iter()
                 .map(|instance| self.client.delete_instance(instance)),
         )
         .await?;
+        self.instances = remaining;
 
         display::done();
         Ok(())
@@ -195,9 +418,9 @@ impl<C: ServerProviderClient> Testbed<C> {
 
         // Wait until the instances are started (skip for local execution).
         if cfg!(not(test)) && !matches!(self.settings.cloud_provider, CloudProvider::Local) {
-            self.wait_until_reachable(available.iter()).await?;
+            self.wait_until_booted(available.iter()).await?;
         }
-        self.instances = self.client.list_instances().await?;
+        self.instances = self.list_instances().await?;
 
         display::done();
         Ok(())
@@ -214,7 +437,7 @@ impl<C: ServerProviderClient> Testbed<C> {
 
         // Wait until the instances are stopped.
         loop {
-            let instances = self.client.list_instances().await?;
+            let instances = self.list_instances().await?;
             if instances.iter().all(|x| x.is_inactive()) {
                 self.instances = instances;
                 break;
@@ -225,12 +448,38 @@ impl<C: ServerProviderClient> Testbed<C> {
         Ok(())
     }
 
-    /// Wait until all specified instances are ready to accept ssh connections.
-    async fn wait_until_reachable<'a, I>(&self, instances: I) -> TestbedResult<()>
+    /// Wait until the specified instances respond to a trivial command run through the executor,
+    /// polling them until the timeout elapses. Returns an error listing the instances that never
+    /// became reachable.
+    pub async fn wait_until_reachable(
+        &self,
+        instances: &[Instance],
+        executor: &Executor,
+        timeout: Duration,
+    ) -> TestbedResult<()> {
+        display::action("Waiting for the instances to be reachable");
+        let probe = |instance: Instance| async move {
+            let context = CommandContext::default();
+            executor
+                .execute([instance], "echo ok", context)
+                .await
+                .is_ok()
+        };
+        poll_until_reachable(instances, timeout, Self::REACHABILITY_INTERVAL, probe).await?;
+        display::done();
+        Ok(())
+    }
+
+    /// Wait until all specified (newly booted) instances are ready to accept ssh connections.
+    async fn wait_until_booted<'a, I>(&self, instances: I) -> TestbedResult<()>
     where
         I: Iterator<Item = &'a Instance> + Clone,
     {
         let instances_ids: Vec<_> = instances.map(|x| x.id.clone()).collect();
+        let private_key = SshPrivateKey::load(
+            &self.settings.ssh_private_key_file,
+            self.settings.ssh_private_key_passphrase.clone(),
+        )?;
 
         let mut interval = time::interval(Duration::from_secs(5));
         interval.tick().await; // The first tick returns immediately.
@@ -241,13 +490,13 @@ impl<C: ServerProviderClient> Testbed<C> {
             let elapsed = now.duration_since(start).as_secs_f64().ceil() as u64;
             display::status(format!("{elapsed}s"));
 
-            let instances = self.client.list_instances().await?;
+            let instances = self.list_instances().await?;
             let futures = instances
                 .iter()
                 .filter(|x| instances_ids.contains(&x.id))
                 .map(|instance| {
-                    let private_key_file = self.settings.ssh_private_key_file.clone();
-                    SshConnection::new(instance.ssh_address(), C::USERNAME, private_key_file)
+                    let jump_host = self.settings.ssh_jump_host;
+                    SshConnection::new(instance.ssh_address(), C::USERNAME, &private_key, jump_host)
                 });
             if try_join_all(futures).await.is_ok() {
                 break;
@@ -257,17 +506,106 @@ impl<C: ServerProviderClient> Testbed<C> {
     }
 }
 
+/// Probe the specified instances until all of them respond or the timeout elapses. Only the
+/// instances that did not respond yet are probed again after each interval.
+async fn poll_until_reachable<F, Fut>(
+    instances: &[Instance],
+    timeout: Duration,
+    interval: Duration,
+    probe: F,
+) -> TestbedResult<()>
+where
+    F: Fn(Instance) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let deadline = Instant::now() + timeout;
+    let mut pending = instances.to_vec();
+    loop {
+        let responses = join_all(pending.iter().cloned().map(&probe)).await;
+        let mut responses = responses.into_iter();
+        pending.retain(|_| !responses.next().unwrap_or_default());
+        if pending.is_empty() {
+            return Ok(());
+        }
+        if Instant::now() + interval > deadline {
+            let ids: Vec<_> = pending.iter().map(|x| x.id.as_str()).collect();
+            return Err(TestbedError::UnreachableInstances(ids.join(", ")));
+        }
+        time::sleep(interval).await;
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::{client::test_client::TestClient, settings::Settings, testbed::Testbed};
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+        time::Duration,
+    };
+
+    use super::poll_until_reachable;
+    use crate::{
+        client::{test_client::TestClient, Instance, ServerProviderClient},
+        error::TestbedError,
+        settings::Settings,
+        testbed::Testbed,
+    };
+
+    #[tokio::test]
+    async fn wait_for_late_instances() {
+        let instances: Vec<_> = (0..4)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+
+        // Instance 3 only responds to the third probe, the others respond right away.
+        let attempts = Mutex::new(HashMap::new());
+        let probe = |instance: Instance| {
+            let mut attempts = attempts.lock().unwrap();
+            let count = attempts.entry(instance.id.clone()).or_insert(0);
+            *count += 1;
+            let reachable = instance.id != "3" || *count >= 3;
+            async move { reachable }
+        };
+
+        let interval = Duration::from_millis(10);
+        poll_until_reachable(&instances, Duration::from_secs(5), interval, probe)
+            .await
+            .unwrap();
+        let attempts = attempts.into_inner().unwrap();
+        assert_eq!(attempts["0"], 1);
+        assert_eq!(attempts["3"], 3);
+    }
+
+    #[tokio::test]
+    async fn report_unreachable_instances() {
+        let instances: Vec<_> = (0..4)
+            .map(|i| Instance::new_for_test(i.to_string()))
+            .collect();
+        let probes = AtomicUsize::new(0);
+        let probe = |instance: Instance| {
+            probes.fetch_add(1, Ordering::SeqCst);
+            async move { instance.id != "1" && instance.id != "2" }
+        };
+
+        let (timeout, interval) = (Duration::from_millis(100), Duration::from_millis(10));
+        let result = poll_until_reachable(&instances, timeout, interval, probe).await;
+        match result {
+            Err(TestbedError::UnreachableInstances(ids)) => assert_eq!(ids, "1, 2"),
+            _ => panic!("Unexpected result: {result:?}"),
+        }
+        assert!(probes.load(Ordering::SeqCst) > instances.len());
+    }
 
     #[tokio::test]
     async fn deploy() {
         let settings = Settings::new_for_test();
-        let client = TestClient::new(settings.clone());
+        let client = TestClient::default();
         let mut testbed = Testbed::new(settings, client).await.unwrap();
 
-        testbed.deploy(5, None).await.unwrap();
+        testbed.deploy(5, None, false).await.unwrap();
 
         assert_eq!(
             testbed.instances.len(),
@@ -278,10 +616,152 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn deploy_shortfall() {
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["region".into()];
+        let client = TestClient::default();
+        for _ in 0..2 {
+            let specs = &settings.specs;
+            client.create_instance("region", specs, &[]).await.unwrap();
+        }
+        let mut testbed = Testbed::new(settings, client).await.unwrap();
+
+        // Only the missing instances are created.
+        testbed.deploy(5, None, false).await.unwrap();
+        let instances = testbed.client.list_instances().await.unwrap();
+        assert_eq!(instances.len(), 5);
+        let ids: Vec<_> = instances.iter().map(|x| x.id.as_str()).collect();
+        assert_eq!(ids, ["0", "1", "2", "3", "4"]);
+
+        // Extra instances are left running unless explicitly pruned.
+        testbed.deploy(3, None, false).await.unwrap();
+        assert_eq!(testbed.instances().len(), 5);
+        testbed.deploy(3, None, true).await.unwrap();
+        assert_eq!(testbed.instances().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn provision_spares() {
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["region-a".into(), "region-b".into()];
+        settings.spare_instances = 2;
+        let client = TestClient::default();
+        let mut testbed = Testbed::new(settings, client).await.unwrap();
+
+        testbed.provision(4).await.unwrap();
+        assert_eq!(testbed.instances().len(), 6);
+
+        // Provisioning again reuses the existing instances.
+        testbed.provision(4).await.unwrap();
+        assert_eq!(testbed.instances().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn provision_specs() {
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["region".into()];
+        settings.specs = "small".into();
+        let client = TestClient::default();
+        let mut testbed = Testbed::new(settings, client).await.unwrap();
+        testbed.provision(4).await.unwrap();
+
+        // The instances with the default specs cannot host a benchmark with other specs.
+        testbed.provision_with_specs(4, "large").await.unwrap();
+        let created = testbed.client.list_instances().await.unwrap();
+        assert_eq!(created.len(), 8);
+        assert_eq!(created.iter().filter(|x| x.specs == "large").count(), 4);
+
+        // Each set of specs is selected separately.
+        assert!(testbed.instances().iter().all(|x| x.specs == "small"));
+        assert_eq!(testbed.instances_with_specs("large").len(), 4);
+    }
+
+    #[tokio::test]
+    async fn provision_role_specs() {
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["region".into()];
+        settings.specs = "default".into();
+        settings.validator_specs = Some("large".into());
+        settings.client_specs = Some("small".into());
+        settings.dedicated_clients = 2;
+        settings.spare_instances = 1;
+        settings.monitoring = true;
+        let client = TestClient::default();
+        let mut testbed = Testbed::new(settings, client).await.unwrap();
+
+        testbed.provision(4).await.unwrap();
+        let created = testbed.client.list_instances().await.unwrap();
+        let count = |specs: &str| created.iter().filter(|x| x.specs == specs).count();
+        assert_eq!(created.len(), 8);
+        assert_eq!(count("large"), 5);
+        assert_eq!(count("small"), 2);
+        assert_eq!(count("default"), 1);
+        assert_eq!(testbed.instances(), created);
+
+        // Provisioning again reuses the existing instances.
+        testbed.provision(4).await.unwrap();
+        assert_eq!(testbed.client.list_instances().await.unwrap(), created);
+    }
+
+    #[tokio::test]
+    async fn ensure() {
+        let mut settings = Settings::new_for_test();
+        settings.regions = vec!["region-a".into(), "region-b".into()];
+        let client = TestClient::default();
+        let mut testbed = Testbed::new(settings, client).await.unwrap();
+
+        testbed.ensure(3, None).await.unwrap();
+        let instances = testbed.client.list_instances().await.unwrap();
+        assert_eq!(instances.len(), 6);
+
+        // Ensuring the same target again leaves the testbed untouched.
+        testbed.ensure(3, None).await.unwrap();
+        assert_eq!(testbed.client.list_instances().await.unwrap(), instances);
+
+        // Stopped instances are started rather than replaced.
+        testbed.stop().await.unwrap();
+        testbed.ensure(2, Some("region-a".into())).await.unwrap();
+        let instances = testbed.client.list_instances().await.unwrap();
+        assert_eq!(instances.len(), 6);
+        let active: Vec<_> = instances.iter().filter(|x| x.is_active()).collect();
+        assert_eq!(active.len(), 2);
+        assert!(active.iter().all(|x| x.region == "region-a"));
+
+        // The surplus instances are deleted.
+        testbed.ensure(1, Some("region-a".into())).await.unwrap();
+        let active = testbed.instances().iter().filter(|x| x.is_active()).count();
+        assert_eq!(active, 1);
+        assert_eq!(testbed.instances().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn run_tags() {
+        let settings = Settings::new_for_test();
+        let client = TestClient::default();
+        let mut testbed = Testbed::new(settings, client).await.unwrap();
+        testbed.settings.run_id = Some("run-a".into());
+        testbed.deploy(2, None, false).await.unwrap();
+        testbed.settings.run_id = Some("run-b".into());
+        testbed.deploy(1, None, false).await.unwrap();
+
+        // Only the instances of the current run are used.
+        let regions = testbed.settings.number_of_regions();
+        assert_eq!(testbed.instances().len(), regions);
+        assert!(testbed.instances().iter().all(|x| x.tags == ["run-b"]));
+
+        // Destroying a run leaves the other runs untouched.
+        testbed.destroy_run("run-a").await.unwrap();
+        let remaining = testbed.client.list_instances().await.unwrap();
+        assert_eq!(remaining.len(), regions);
+        testbed.settings.run_id = None;
+        assert_eq!(testbed.instances(), remaining);
+    }
+
     #[tokio::test]
     async fn destroy() {
         let settings = Settings::new_for_test();
-        let client = TestClient::new(settings.clone());
+        let client = TestClient::default();
         let mut testbed = Testbed::new(settings, client).await.unwrap();
 
         testbed.destroy().await.unwrap();
@@ -292,9 +772,9 @@ mod test {
     #[tokio::test]
     async fn start() {
         let settings = Settings::new_for_test();
-        let client = TestClient::new(settings.clone());
+        let client = TestClient::default();
         let mut testbed = Testbed::new(settings, client).await.unwrap();
-        testbed.deploy(5, None).await.unwrap();
+        testbed.deploy(5, None, false).await.unwrap();
         testbed.stop().await.unwrap();
 
         let result = testbed.start(2).await;
@@ -320,9 +800,9 @@ mod test {
     #[tokio::test]
     async fn stop() {
         let settings = Settings::new_for_test();
-        let client = TestClient::new(settings.clone());
+        let client = TestClient::default();
         let mut testbed = Testbed::new(settings, client).await.unwrap();
-        testbed.deploy(5, None).await.unwrap();
+        testbed.deploy(5, None, false).await.unwrap();
         testbed.start(2).await.unwrap();
 
         testbed.stop().await.unwrap();